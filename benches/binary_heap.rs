@@ -2,7 +2,7 @@
 
 extern crate test;
 
-use binary_heap_plus::BinaryHeap;
+use binary_heap_plus::{BinaryHeap, HybridBinaryHeap, LazyBinaryHeap};
 
 use rand::{seq::SliceRandom, thread_rng};
 use test::{black_box, Bencher};
@@ -81,6 +81,22 @@ fn bench_push(b: &mut Bencher) {
     })
 }
 
+#[bench]
+fn bench_push_lazy(b: &mut Bencher) {
+    let mut heap = LazyBinaryHeap::new();
+    let mut rng = thread_rng();
+    let mut vec: Vec<u32> = (0..50_000).collect();
+    vec.shuffle(&mut rng);
+
+    b.iter(|| {
+        for &i in vec.iter() {
+            heap.push(i);
+        }
+        black_box(heap.peek());
+        heap.clear();
+    })
+}
+
 #[bench]
 fn bench_pop(b: &mut Bencher) {
     let mut bheap = BinaryHeap::with_capacity(10_000);
@@ -93,3 +109,135 @@ fn bench_pop(b: &mut Bencher) {
         }
     })
 }
+
+#[bench]
+fn bench_pop_push_large(b: &mut Bencher) {
+    // 64 MiB of data, far larger than the caches.
+    let mut rng = thread_rng();
+    let mut vec: Vec<u64> = (0..8_000_000).collect();
+    vec.shuffle(&mut rng);
+    let refills: Vec<u64> = vec[..1000].to_vec();
+    let mut bheap = BinaryHeap::from(vec);
+
+    b.iter(|| {
+        for &x in refills.iter() {
+            black_box(bheap.pop());
+            bheap.push(x);
+        }
+    })
+}
+
+#[bench]
+fn bench_pop_random_u64(b: &mut Bencher) {
+    let mut rng = thread_rng();
+    let mut vec: Vec<u64> = (0..10_000).collect();
+    vec.shuffle(&mut rng);
+    let bheap = BinaryHeap::from(vec);
+
+    b.iter(|| {
+        let mut bheap = bheap.clone();
+        while let Some(elem) = bheap.pop() {
+            black_box(elem);
+        }
+    })
+}
+
+#[bench]
+fn bench_pop_random_string(b: &mut Bencher) {
+    let mut rng = thread_rng();
+    let mut vec: Vec<String> = (0..10_000).map(|i| format!("key-{:08}", i)).collect();
+    vec.shuffle(&mut rng);
+    let bheap = BinaryHeap::from(vec);
+
+    b.iter(|| {
+        let mut bheap = bheap.clone();
+        while let Some(elem) = bheap.pop() {
+            black_box(elem);
+        }
+    })
+}
+
+#[bench]
+fn bench_pop_random_fat(b: &mut Bencher) {
+    // 256 bytes per element, well over the size for which `pop` switches to
+    // the classic sift-down.
+    #[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+    struct Fat([u64; 32]);
+
+    let mut rng = thread_rng();
+    let mut vec: Vec<Fat> = (0..10_000).map(|i| Fat([i; 32])).collect();
+    vec.shuffle(&mut rng);
+    let bheap = BinaryHeap::from(vec);
+
+    b.iter(|| {
+        let mut bheap = bheap.clone();
+        while let Some(elem) = bheap.pop() {
+            black_box(elem);
+        }
+    })
+}
+
+#[bench]
+fn bench_append_sorted_batch(b: &mut Bencher) {
+    let mut rng = thread_rng();
+    let mut vec: Vec<u32> = (0..100_000).collect();
+    vec.shuffle(&mut rng);
+    let bheap = BinaryHeap::from(vec);
+    let mut batch: Vec<u32> = (0..1_000).map(|i| i * 100).collect();
+    batch.sort_unstable();
+
+    b.iter(|| {
+        let mut bheap = bheap.clone();
+        bheap.append_sorted_ascending(batch.iter().copied());
+        black_box(bheap);
+    })
+}
+
+#[bench]
+fn bench_append_sorted_half(b: &mut Bencher) {
+    let mut rng = thread_rng();
+    let mut vec: Vec<u32> = (0..50_000).map(|i| 2 * i).collect();
+    vec.shuffle(&mut rng);
+    let bheap = BinaryHeap::from(vec);
+    let batch: Vec<u32> = (0..50_000).map(|i| 2 * i + 1).collect();
+
+    b.iter(|| {
+        let mut bheap = bheap.clone();
+        bheap.append_sorted_ascending(batch.iter().copied());
+        black_box(bheap);
+    })
+}
+
+#[bench]
+fn bench_small_push_pop(b: &mut Bencher) {
+    let mut rng = thread_rng();
+    let mut vec: Vec<u32> = (0..12).collect();
+    vec.shuffle(&mut rng);
+    let mut bheap = BinaryHeap::with_capacity(12);
+
+    b.iter(|| {
+        for _ in 0..100 {
+            bheap.extend(vec.iter().copied());
+            while let Some(elem) = bheap.pop() {
+                black_box(elem);
+            }
+        }
+    })
+}
+
+#[bench]
+fn bench_small_push_pop_hybrid(b: &mut Bencher) {
+    let mut rng = thread_rng();
+    let mut vec: Vec<u32> = (0..12).collect();
+    vec.shuffle(&mut rng);
+    let mut heap = HybridBinaryHeap::new();
+
+    b.iter(|| {
+        for _ in 0..100 {
+            heap.extend(vec.iter().copied());
+            while let Some(elem) = heap.pop() {
+                black_box(elem);
+            }
+        }
+    })
+}
@@ -0,0 +1,112 @@
+/* Generated by cbindgen from src/ffi.rs; do not edit. Regenerate with
+ * `cbindgen --config cbindgen.toml --output include/binary_heap_plus.h`. */
+
+#ifndef BINARY_HEAP_PLUS_H
+#define BINARY_HEAP_PLUS_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * A heap of `u64` keys, each carrying an opaque pointer.
+ *
+ * C code only handles pointers to it, created by [`bhp_heap_new`] or
+ * [`bhp_heap_new_min`] and released with [`bhp_heap_free`].
+ *
+ * [`bhp_heap_new`]: fn.bhp_heap_new.html
+ * [`bhp_heap_new_min`]: fn.bhp_heap_new_min.html
+ * [`bhp_heap_free`]: fn.bhp_heap_free.html
+ */
+typedef struct BhpHeap BhpHeap;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Creates an empty heap that pops the greatest key first.
+ *
+ * Release it with [`bhp_heap_free`].
+ *
+ * [`bhp_heap_free`]: fn.bhp_heap_free.html
+ */
+BhpHeap *bhp_heap_new(void);
+
+/**
+ * Creates an empty heap that pops the least key first.
+ *
+ * Release it with [`bhp_heap_free`].
+ *
+ * [`bhp_heap_free`]: fn.bhp_heap_free.html
+ */
+BhpHeap *bhp_heap_new_min(void);
+
+/**
+ * Destroys a heap. The pointers it still carries are not freed.
+ *
+ * # Safety
+ *
+ * `heap` must be null or a heap created by this module that was not freed
+ * yet.
+ */
+void bhp_heap_free(BhpHeap *heap);
+
+/**
+ * Pushes `key` carrying `value`.
+ *
+ * # Safety
+ *
+ * `heap` must be a live heap created by this module, not used by another
+ * thread during the call.
+ */
+void bhp_heap_push(BhpHeap *heap, uint64_t key, void *value);
+
+/**
+ * Removes the entry with the greatest key, or the least for a heap created
+ * by [`bhp_heap_new_min`], writes its key and pointer to the out-pointers
+ * that are not null, and returns `true`. Returns `false` if the heap is
+ * empty.
+ *
+ * # Safety
+ *
+ * `heap` must be a live heap created by this module, not used by another
+ * thread during the call. `key_out` and `value_out` must be null or valid
+ * for writes.
+ *
+ * [`bhp_heap_new_min`]: fn.bhp_heap_new_min.html
+ */
+bool bhp_heap_pop(BhpHeap *heap, uint64_t *key_out, void **value_out);
+
+/**
+ * Writes the key and pointer of the entry [`bhp_heap_pop`] would remove to
+ * the out-pointers that are not null, and returns `true`. Returns `false`
+ * if the heap is empty.
+ *
+ * # Safety
+ *
+ * `heap` must be a live heap created by this module, not modified by
+ * another thread during the call. `key_out` and `value_out` must be null or
+ * valid for writes.
+ *
+ * [`bhp_heap_pop`]: fn.bhp_heap_pop.html
+ */
+bool bhp_heap_peek(const BhpHeap *heap, uint64_t *key_out, void **value_out);
+
+/**
+ * Returns the number of entries in the heap.
+ *
+ * # Safety
+ *
+ * `heap` must be a live heap created by this module, not modified by
+ * another thread during the call.
+ */
+size_t bhp_heap_len(const BhpHeap *heap);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* BINARY_HEAP_PLUS_H */
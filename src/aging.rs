@@ -0,0 +1,193 @@
+//! A priority queue whose entries gain priority while they wait.
+
+use crate::binary_heap::BinaryHeap;
+use compare::Compare;
+use std::cmp::Ordering;
+use std::fmt;
+use std::mem;
+use std::time::{Duration, Instant};
+
+struct AgingEntry<T, P> {
+    value: T,
+    base: P,
+    effective: P,
+    enqueued: Instant,
+    seq: u64,
+}
+
+/// Orders entries by effective priority, then by insertion order.
+#[derive(Clone, Copy, Default, Debug)]
+struct EffectiveOrder;
+
+impl<T, P: Ord> Compare<AgingEntry<T, P>> for EffectiveOrder {
+    fn compare(&self, a: &AgingEntry<T, P>, b: &AgingEntry<T, P>) -> Ordering {
+        a.effective
+            .cmp(&b.effective)
+            .then_with(|| b.seq.cmp(&a.seq))
+    }
+}
+
+/// A max-priority queue in which queued entries age to prevent starvation.
+///
+/// Each entry is pushed with a base priority. Its effective priority is
+/// computed by the aging function from the base priority and the time the
+/// entry has spent in the queue. Effective priorities are recomputed lazily,
+/// when [`pop`] or [`peek`] is called with a `now` later than the previous
+/// refresh by at least the [refresh granularity]. Entries with equal effective
+/// priority are popped in the order they were pushed.
+///
+/// The aging function should be non-decreasing in the waited time; otherwise
+/// aging can make entries *less* likely to be popped.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::AgingHeap;
+/// use std::time::{Duration, Instant};
+///
+/// // Every second spent waiting adds one to the priority.
+/// let mut heap = AgingHeap::new(|p: &u64, waited: Duration| p + waited.as_secs());
+///
+/// let start = Instant::now();
+/// let later = start + Duration::from_secs(10);
+/// heap.push("background", 1, start);
+/// heap.push("urgent", 5, later);
+///
+/// // After waiting ten seconds, the background job has aged to 11 and now
+/// // outranks the freshly pushed urgent job.
+/// assert_eq!(heap.peek(later), Some(&"background"));
+///
+/// heap.push("critical", 20, later);
+/// assert_eq!(heap.pop(later), Some("critical"));
+/// assert_eq!(heap.pop(later), Some("background"));
+/// assert_eq!(heap.pop(later), Some("urgent"));
+/// ```
+///
+/// # Time complexity
+///
+/// | push          | pop / peek without refresh | pop / peek with refresh |
+/// |---------------|----------------------------|-------------------------|
+/// | *O*(log(*n*)) | *O*(log(*n*)) / *O*(1)     | *O*(*n*)                |
+///
+/// [`pop`]: AgingHeap::pop
+/// [`peek`]: AgingHeap::peek
+/// [refresh granularity]: AgingHeap::set_refresh_granularity
+pub struct AgingHeap<T, P, F> {
+    heap: BinaryHeap<AgingEntry<T, P>, EffectiveOrder>,
+    aging: F,
+    granularity: Duration,
+    last_refresh: Option<Instant>,
+    next_seq: u64,
+}
+
+impl<T: fmt::Debug, P: fmt::Debug, F> fmt::Debug for AgingHeap<T, P, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.heap.iter().map(|e| (&e.value, &e.base)))
+            .finish()
+    }
+}
+
+impl<T, P, F> AgingHeap<T, P, F>
+where
+    P: Ord,
+    F: Fn(&P, Duration) -> P,
+{
+    /// Creates an empty `AgingHeap` using `aging` to compute effective
+    /// priorities from a base priority and the time spent waiting.
+    pub fn new(aging: F) -> Self {
+        AgingHeap {
+            heap: BinaryHeap::from_vec(vec![]),
+            aging,
+            granularity: Duration::from_secs(0),
+            last_refresh: None,
+            next_seq: 0,
+        }
+    }
+
+    /// Sets the minimum time between two recomputations of the effective
+    /// priorities.
+    ///
+    /// A coarser granularity trades aging precision for fewer *O*(*n*)
+    /// refreshes when `pop` is called at a high rate. The default is zero,
+    /// which refreshes whenever `now` advances.
+    pub fn set_refresh_granularity(&mut self, granularity: Duration) {
+        self.granularity = granularity;
+    }
+
+    /// Pushes `value` with the given base priority, enqueued at `now`.
+    pub fn push(&mut self, value: T, priority: P, now: Instant) {
+        // Age the new entry to the same instant as the queued ones so that
+        // all effective priorities stay comparable.
+        let waited = self
+            .last_refresh
+            .map_or(Duration::from_secs(0), |t| t.saturating_duration_since(now));
+        let effective = (self.aging)(&priority, waited);
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.heap.push(AgingEntry {
+            value,
+            base: priority,
+            effective,
+            enqueued: now,
+            seq,
+        });
+    }
+
+    /// Removes the entry with the greatest effective priority at `now` and
+    /// returns it, or `None` if the heap is empty.
+    pub fn pop(&mut self, now: Instant) -> Option<T> {
+        self.refresh(now);
+        self.heap.pop().map(|e| e.value)
+    }
+
+    /// Returns the entry with the greatest effective priority at `now`, or
+    /// `None` if the heap is empty.
+    pub fn peek(&mut self, now: Instant) -> Option<&T> {
+        self.refresh(now);
+        self.heap.peek().map(|e| &e.value)
+    }
+
+    fn refresh(&mut self, now: Instant) {
+        let due = match self.last_refresh {
+            Some(last) => now > last && now.duration_since(last) >= self.granularity,
+            None => true,
+        };
+        if !due {
+            return;
+        }
+        self.last_refresh = Some(now);
+
+        let heap = mem::replace(&mut self.heap, BinaryHeap::from_vec(vec![]));
+        let mut entries = heap.into_vec();
+        for e in &mut entries {
+            e.effective = (self.aging)(&e.base, now.saturating_duration_since(e.enqueued));
+        }
+        self.heap = BinaryHeap::from_vec(entries);
+    }
+}
+
+impl<T, P, F> AgingHeap<T, P, F> {
+    /// Returns the number of queued entries.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Returns an iterator visiting all values and their base priorities in
+    /// arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (&T, &P)> {
+        self.heap.iter().map(|e| (&e.value, &e.base))
+    }
+
+    /// Drops all queued entries.
+    pub fn clear(&mut self) {
+        self.heap.clear();
+    }
+}
@@ -0,0 +1,230 @@
+//! Algorithms built on the heap.
+//!
+//! [`replacement_selection`] splits its input into sorted runs for external
+//! sorting, and [`partial_sort`] sorts only the first elements of a slice.
+//!
+//! [`replacement_selection`]: fn.replacement_selection.html
+//! [`partial_sort`]: fn.partial_sort.html
+
+use crate::array::sift_down_slice;
+use crate::binary_heap::{BinaryHeap, FnComparator, MinComparator};
+use compare::Compare;
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::FusedIterator;
+
+struct Tagged<T> {
+    run: usize,
+    item: T,
+}
+
+/// Orders items by earliest run, then greatest item by `C`.
+struct RunOrder<C>(C);
+
+impl<T, C: Compare<T>> Compare<Tagged<T>> for RunOrder<C> {
+    fn compare(&self, a: &Tagged<T>, b: &Tagged<T>) -> Ordering {
+        b.run
+            .cmp(&a.run)
+            .then_with(|| self.0.compare(&a.item, &b.item))
+    }
+}
+
+/// Splits `input` into sorted runs, holding at most `memory` items at once,
+/// with replacement selection.
+///
+/// The returned iterator yields each item with the index of its run, `0`
+/// first. The items of a run come in ascending order, and runs follow each
+/// other, so each run can be written out as it comes, for instance to a
+/// file, and the runs merged afterwards. On random input the runs are about
+/// `2 * memory` items long, twice what sorting chunks of `memory` items
+/// gives, and input that is already sorted comes out as a single run.
+///
+/// The heap holding the items is filled on the first call to `next`.
+///
+/// # Panics
+///
+/// Panics if `memory` is zero.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::algorithms::replacement_selection;
+///
+/// let input = vec![5, 1, 9, 3, 7, 2, 8, 4, 6];
+/// let mut runs: Vec<Vec<i32>> = Vec::new();
+/// for (run, item) in replacement_selection(input, 3) {
+///     if run == runs.len() {
+///         runs.push(Vec::new());
+///     }
+///     runs[run].push(item);
+/// }
+/// assert_eq!(runs, [vec![1, 3, 5, 7, 8, 9], vec![2, 4, 6]]);
+/// ```
+///
+/// # Time complexity
+///
+/// *O*(log(`memory`)) per item.
+pub fn replacement_selection<I>(input: I, memory: usize) -> ReplacementSelection<I::IntoIter>
+where
+    I: IntoIterator,
+    I::Item: Ord,
+{
+    replacement_selection_cmp(input, memory, MinComparator)
+}
+
+/// Like [`replacement_selection`], but sorts the runs so that items that
+/// `cmp` orders as greater come first.
+///
+/// With [`MinComparator`], the default, the runs are in ascending order.
+///
+/// # Panics
+///
+/// Panics if `memory` is zero.
+///
+/// [`replacement_selection`]: fn.replacement_selection.html
+/// [`MinComparator`]: ../struct.MinComparator.html
+pub fn replacement_selection_cmp<I, C>(
+    input: I,
+    memory: usize,
+    cmp: C,
+) -> ReplacementSelection<I::IntoIter, C>
+where
+    I: IntoIterator,
+    C: Compare<I::Item>,
+{
+    assert!(
+        memory > 0,
+        "replacement selection needs memory for one item"
+    );
+    ReplacementSelection {
+        input: input.into_iter(),
+        heap: BinaryHeap::with_capacity_and_cmp(0, RunOrder(cmp)),
+        memory,
+        filled: false,
+    }
+}
+
+/// An iterator splitting its input into sorted runs.
+///
+/// This `struct` is created by [`replacement_selection`]. See its
+/// documentation for more.
+///
+/// [`replacement_selection`]: fn.replacement_selection.html
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct ReplacementSelection<I: Iterator, C = MinComparator> {
+    input: I,
+    heap: BinaryHeap<Tagged<I::Item>, RunOrder<C>>,
+    memory: usize,
+    filled: bool,
+}
+
+impl<I: Iterator, C> fmt::Debug for ReplacementSelection<I, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReplacementSelection")
+            .field("memory", &self.memory)
+            .field("held", &self.heap.len())
+            .finish()
+    }
+}
+
+impl<I: Iterator, C: Compare<I::Item>> Iterator for ReplacementSelection<I, C> {
+    type Item = (usize, I::Item);
+
+    fn next(&mut self) -> Option<(usize, I::Item)> {
+        if !self.filled {
+            self.filled = true;
+            self.heap.reserve_exact(self.memory);
+            let items = self.input.by_ref().take(self.memory);
+            self.heap.extend(items.map(|item| Tagged { run: 0, item }));
+        }
+        let Tagged { run, item } = self.heap.pop()?;
+        if let Some(next) = self.input.next() {
+            // An item less than the one just written would break the order
+            // of the current run, so it waits for the next one.
+            let run = if self.heap.cmp().0.compares_gt(&next, &item) {
+                run + 1
+            } else {
+                run
+            };
+            self.heap.push(Tagged { run, item: next });
+        }
+        Some((run, item))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.input.size_hint();
+        let held = self.heap.len();
+        (
+            lower.saturating_add(held),
+            upper.and_then(|upper| upper.checked_add(held)),
+        )
+    }
+}
+
+impl<I: FusedIterator, C: Compare<I::Item>> FusedIterator for ReplacementSelection<I, C> {}
+
+/// Rearranges `v` so that its first `k` elements are its `k` least, in
+/// ascending order, like `std::partial_sort` in C++.
+///
+/// The other elements are left in unspecified order after them. If `k`
+/// exceeds the length, the whole slice is sorted. The sort is not stable.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::algorithms::partial_sort;
+///
+/// let mut v = [5, 8, 1, 9, 3, 7, 2];
+/// partial_sort(&mut v, 3);
+/// assert_eq!(v[..3], [1, 2, 3]);
+/// ```
+///
+/// # Time complexity
+///
+/// *O*(*n* log(*k*)) for a slice of length *n*.
+pub fn partial_sort<T: Ord>(v: &mut [T], k: usize) {
+    partial_sort_by(v, k, T::cmp);
+}
+
+/// Like [`partial_sort`], but orders the elements with the `compare`
+/// function, as [`slice::sort_by`] does.
+///
+/// # Examples
+///
+/// Keeping the three best scores, best first:
+///
+/// ```
+/// use binary_heap_plus::algorithms::partial_sort_by;
+///
+/// let mut scores = [("a", 12), ("b", 40), ("c", 7), ("d", 33), ("e", 25)];
+/// partial_sort_by(&mut scores, 3, |x, y| y.1.cmp(&x.1));
+/// assert_eq!(scores[..3], [("b", 40), ("d", 33), ("e", 25)]);
+/// ```
+///
+/// [`partial_sort`]: fn.partial_sort.html
+/// [`slice::sort_by`]: https://doc.rust-lang.org/std/primitive.slice.html#method.sort_by
+pub fn partial_sort_by<T, F>(v: &mut [T], k: usize, compare: F)
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    let k = k.min(v.len());
+    if k == 0 {
+        return;
+    }
+    let cmp = FnComparator(compare);
+    // A max-heap of the `k` least elements seen so far, whose top is the
+    // first to give way to a lesser element.
+    for pos in (0..k / 2).rev() {
+        sift_down_slice(&mut v[..k], pos, &cmp);
+    }
+    for i in k..v.len() {
+        if cmp.compares_lt(&v[i], &v[0]) {
+            v.swap(0, i);
+            sift_down_slice(&mut v[..k], 0, &cmp);
+        }
+    }
+    for end in (1..k).rev() {
+        v.swap(0, end);
+        sift_down_slice(&mut v[..end], 0, &cmp);
+    }
+}
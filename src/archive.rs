@@ -0,0 +1,217 @@
+//! Zero-copy archiving of heaps with rkyv.
+//!
+//! Requires the `rkyv` feature.
+
+use crate::binary_heap::{BinaryHeap, HeapOrderError, MaxComparator, MinComparator};
+use compare::Compare;
+use rkyv::bytecheck::{CheckBytes, Verify};
+use rkyv::munge::munge;
+use rkyv::rancor::{Fallible, Source};
+use rkyv::ser::{Allocator, Writer};
+use rkyv::vec::{ArchivedVec, VecResolver};
+use rkyv::{Archive, Archived, Deserialize, Place, Portable, Serialize};
+use std::fmt;
+use std::slice;
+
+/// An archived [`BinaryHeap`].
+///
+/// The elements keep the layout they had in the heap, so the archive can be
+/// read in place, for instance from a memory-mapped file, and
+/// [`peek`](ArchivedBinaryHeap::peek) finds the greatest element without
+/// copying anything. `T` and `C` are the archived element and comparator
+/// types.
+///
+/// Accessing the archive with validation, as [`rkyv::access`] does, also
+/// checks that the elements are in heap order, in *O*(*n*) time. Deserializing
+/// rebuilds the heap, so an archive accessed without validation still yields
+/// a valid heap.
+///
+/// This type is available with the `rkyv` feature.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::{ArchivedBinaryHeap, BinaryHeap, MinComparator};
+/// use rkyv::rancor::Error;
+///
+/// let heap = BinaryHeap::from_vec_cmp(vec![3u32, 1, 4, 1, 5], MinComparator);
+/// let bytes = rkyv::to_bytes::<Error>(&heap).unwrap();
+///
+/// let archived =
+///     rkyv::access::<ArchivedBinaryHeap<rkyv::Archived<u32>, MinComparator>, Error>(&bytes)
+///         .unwrap();
+/// assert_eq!(archived.len(), 5);
+/// assert_eq!(archived.peek().map(|x| x.to_native()), Some(1));
+///
+/// let heap: BinaryHeap<u32, MinComparator> = rkyv::deserialize::<_, Error>(archived).unwrap();
+/// assert_eq!(heap.into_sorted_vec(), [5, 4, 3, 1, 1]);
+/// ```
+///
+/// [`BinaryHeap`]: struct.BinaryHeap.html
+/// [`rkyv::access`]: https://docs.rs/rkyv/0.8/rkyv/fn.access.html
+#[derive(Portable, CheckBytes)]
+#[bytecheck(crate = rkyv::bytecheck, verify)]
+#[repr(C)]
+pub struct ArchivedBinaryHeap<T, C> {
+    data: ArchivedVec<T>,
+    cmp: C,
+}
+
+impl<T, C> ArchivedBinaryHeap<T, C> {
+    /// Returns the greatest element, or `None` if the heap is empty.
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.data.first()
+    }
+
+    /// Returns the number of elements.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns an iterator visiting all elements in heap order, as
+    /// [`BinaryHeap::iter`] does.
+    ///
+    /// [`BinaryHeap::iter`]: struct.BinaryHeap.html#method.iter
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.data.iter()
+    }
+
+    /// Returns the elements in heap order.
+    #[must_use]
+    pub fn as_slice(&self) -> &[T] {
+        self.data.as_slice()
+    }
+
+    /// Returns the archived comparator.
+    #[must_use]
+    pub fn comparator(&self) -> &C {
+        &self.cmp
+    }
+}
+
+impl<T: fmt::Debug, C> fmt::Debug for ArchivedBinaryHeap<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, T, C> IntoIterator for &'a ArchivedBinaryHeap<T, C> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> slice::Iter<'a, T> {
+        self.iter()
+    }
+}
+
+// SAFETY: `verify` only returns `Ok` if the elements are in heap order.
+unsafe impl<T, C, V> Verify<V> for ArchivedBinaryHeap<T, C>
+where
+    C: Compare<T>,
+    V: Fallible + ?Sized,
+    V::Error: Source,
+{
+    fn verify(&self, _: &mut V) -> Result<(), V::Error> {
+        match HeapOrderError::find(self.as_slice(), &self.cmp) {
+            Some(err) => Err(V::Error::new(err)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The resolver for an archived [`BinaryHeap`].
+///
+/// [`BinaryHeap`]: struct.BinaryHeap.html
+pub struct BinaryHeapResolver<R> {
+    data: VecResolver,
+    cmp: R,
+}
+
+/// Archives the elements in their current layout, along with the comparator.
+///
+/// Requires the `rkyv` feature.
+impl<T: Archive, C: Archive> Archive for BinaryHeap<T, C> {
+    type Archived = ArchivedBinaryHeap<T::Archived, C::Archived>;
+    type Resolver = BinaryHeapResolver<C::Resolver>;
+
+    fn resolve(&self, resolver: Self::Resolver, out: Place<Self::Archived>) {
+        munge!(let ArchivedBinaryHeap { data, cmp } = out);
+        ArchivedVec::resolve_from_slice(self.as_slice(), resolver.data, data);
+        self.cmp().resolve(resolver.cmp, cmp);
+    }
+}
+
+impl<T, C, S> Serialize<S> for BinaryHeap<T, C>
+where
+    T: Serialize<S>,
+    C: Serialize<S>,
+    S: Fallible + Allocator + Writer + ?Sized,
+{
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        Ok(BinaryHeapResolver {
+            data: ArchivedVec::<T::Archived>::serialize_from_slice(self.as_slice(), serializer)?,
+            cmp: self.cmp().serialize(serializer)?,
+        })
+    }
+}
+
+/// Deserializes the heap and rebuilds it, in *O*(*n*) time.
+impl<T, C, D> Deserialize<BinaryHeap<T, C>, D> for ArchivedBinaryHeap<Archived<T>, Archived<C>>
+where
+    T: Archive,
+    C: Archive + Compare<T>,
+    ArchivedVec<Archived<T>>: Deserialize<Vec<T>, D>,
+    Archived<C>: Deserialize<C, D>,
+    D: Fallible + ?Sized,
+{
+    fn deserialize(&self, deserializer: &mut D) -> Result<BinaryHeap<T, C>, D::Error> {
+        let data = self.data.deserialize(deserializer)?;
+        let cmp = self.cmp.deserialize(deserializer)?;
+        Ok(BinaryHeap::from_vec_cmp(data, cmp))
+    }
+}
+
+/// Archives the unit comparators as themselves, so that they order archived
+/// elements just as they order the originals.
+macro_rules! archive_as_self {
+    ($($cmp:ident),*) => {$(
+        // SAFETY: the comparator is a `repr(C)` unit struct.
+        unsafe impl Portable for $cmp {}
+
+        // SAFETY: a unit struct has no bytes to check.
+        unsafe impl<V: Fallible + ?Sized> CheckBytes<V> for $cmp {
+            unsafe fn check_bytes(_: *const Self, _: &mut V) -> Result<(), V::Error> {
+                Ok(())
+            }
+        }
+
+        impl Archive for $cmp {
+            type Archived = $cmp;
+            type Resolver = ();
+
+            fn resolve(&self, _: (), _: Place<$cmp>) {}
+        }
+
+        impl<S: Fallible + ?Sized> Serialize<S> for $cmp {
+            fn serialize(&self, _: &mut S) -> Result<(), S::Error> {
+                Ok(())
+            }
+        }
+
+        impl<D: Fallible + ?Sized> Deserialize<$cmp, D> for $cmp {
+            fn deserialize(&self, _: &mut D) -> Result<$cmp, D::Error> {
+                Ok($cmp)
+            }
+        }
+    )*};
+}
+
+archive_as_self!(MaxComparator, MinComparator);
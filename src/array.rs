@@ -0,0 +1,316 @@
+//! A fixed-capacity heap stored inline.
+//!
+//! This module only uses `core` and never allocates, except with the
+//! `safe_only` feature, but the crate as a whole requires `std`, so it cannot
+//! be used from a `no_std` crate.
+
+use compare::Compare;
+use core::fmt;
+#[cfg(not(feature = "safe_only"))]
+use core::mem::MaybeUninit;
+#[cfg(not(feature = "safe_only"))]
+use core::ptr;
+use core::slice;
+
+/// A priority queue with a fixed capacity of `N` elements, stored inline.
+///
+/// `ArrayHeap` never allocates: its elements live in an array inside the
+/// struct, so it can be used in interrupt handlers and other contexts where
+/// allocation is not allowed. Pushing onto a full heap hands the item back
+/// instead of growing.
+///
+/// The crate still links `std`, so `ArrayHeap` is not available to `no_std`
+/// targets.
+///
+/// Like [`BinaryHeap`], the greatest element according to the comparator
+/// `C` is popped first.
+///
+/// With the `safe_only` feature, the elements are stored in a `Vec` with a
+/// capacity of `N`, allocated once by the constructor, since a partially
+/// initialized array cannot be handled without unsafe code.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::{ArrayHeap, MinComparator};
+///
+/// let mut heap: ArrayHeap<u32, MinComparator, 3> = ArrayHeap::new();
+/// assert_eq!(heap.push(30), Ok(()));
+/// assert_eq!(heap.push(10), Ok(()));
+/// assert_eq!(heap.push(20), Ok(()));
+/// assert_eq!(heap.push(40), Err(40));
+///
+/// assert_eq!(heap.pop(), Some(10));
+/// assert_eq!(heap.peek(), Some(&20));
+/// assert_eq!(heap.len(), 2);
+/// ```
+///
+/// [`BinaryHeap`]: struct.BinaryHeap.html
+pub struct ArrayHeap<T, C, const N: usize> {
+    #[cfg(not(feature = "safe_only"))]
+    data: [MaybeUninit<T>; N],
+    #[cfg(feature = "safe_only")]
+    data: Vec<T>,
+    len: usize,
+    cmp: C,
+}
+
+impl<T: fmt::Debug, C, const N: usize> fmt::Debug for ArrayHeap<T, C, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}
+
+impl<T: Clone, C: Clone, const N: usize> Clone for ArrayHeap<T, C, N> {
+    fn clone(&self) -> Self {
+        let mut clone = ArrayHeap::with_cmp(self.cmp.clone());
+        for item in self.as_slice() {
+            // Elements are cloned in heap order, so no sifting is needed.
+            clone.write_last(item.clone());
+        }
+        clone
+    }
+}
+
+impl<T, C: Compare<T> + Default, const N: usize> Default for ArrayHeap<T, C, N> {
+    fn default() -> Self {
+        ArrayHeap::new()
+    }
+}
+
+impl<T, C, const N: usize> Drop for ArrayHeap<T, C, N> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T, C: Compare<T> + Default, const N: usize> ArrayHeap<T, C, N> {
+    /// Creates an empty heap.
+    #[must_use]
+    pub fn new() -> Self {
+        ArrayHeap::with_cmp(C::default())
+    }
+}
+
+impl<T, C, const N: usize> ArrayHeap<T, C, N> {
+    /// Creates an empty heap ordered by `cmp`.
+    pub fn with_cmp(cmp: C) -> Self {
+        ArrayHeap {
+            #[cfg(not(feature = "safe_only"))]
+            // SAFETY: an array of `MaybeUninit` needs no initialization.
+            data: unsafe { MaybeUninit::uninit().assume_init() },
+            #[cfg(feature = "safe_only")]
+            data: Vec::with_capacity(N),
+            len: 0,
+            cmp,
+        }
+    }
+
+    /// Returns the number of elements the heap can hold.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns the number of elements in the heap.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Checks if the heap holds `N` elements, so that [`push`] would fail.
+    ///
+    /// [`push`]: ArrayHeap::push
+    #[must_use]
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// Returns the elements in arbitrary order, as stored in the heap.
+    #[cfg(not(feature = "safe_only"))]
+    #[must_use]
+    pub fn as_slice(&self) -> &[T] {
+        // SAFETY: the first `len` elements are initialized.
+        unsafe { slice::from_raw_parts(self.data.as_ptr() as *const T, self.len) }
+    }
+
+    /// Returns the elements in arbitrary order, as stored in the heap.
+    #[cfg(feature = "safe_only")]
+    #[must_use]
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    #[cfg(not(feature = "safe_only"))]
+    fn as_mut_slice(&mut self) -> &mut [T] {
+        // SAFETY: the first `len` elements are initialized.
+        unsafe { slice::from_raw_parts_mut(self.data.as_mut_ptr() as *mut T, self.len) }
+    }
+
+    #[cfg(feature = "safe_only")]
+    fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.data
+    }
+
+    /// Stores `item` after the elements, which must not fill the heap.
+    #[cfg(not(feature = "safe_only"))]
+    fn write_last(&mut self, item: T) {
+        self.data[self.len] = MaybeUninit::new(item);
+        self.len += 1;
+    }
+
+    /// Stores `item` after the elements, which must not fill the heap.
+    #[cfg(feature = "safe_only")]
+    fn write_last(&mut self, item: T) {
+        self.data.push(item);
+        self.len += 1;
+    }
+
+    /// Removes the last element, which must exist.
+    #[cfg(not(feature = "safe_only"))]
+    fn take_last(&mut self) -> T {
+        self.len -= 1;
+        // SAFETY: the element at the old `len - 1` was initialized and is
+        //  now outside of `len`, so it is read exactly once.
+        unsafe { ptr::read(self.data[self.len].as_ptr()) }
+    }
+
+    /// Removes the last element, which must exist.
+    #[cfg(feature = "safe_only")]
+    fn take_last(&mut self) -> T {
+        self.len -= 1;
+        self.data.pop().unwrap()
+    }
+
+    /// Returns an iterator visiting all elements in arbitrary order.
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.as_slice().iter()
+    }
+
+    /// Drops all elements.
+    #[cfg(not(feature = "safe_only"))]
+    pub fn clear(&mut self) {
+        // `len` is reset first so that a panicking destructor cannot cause a
+        // double drop.
+        let len = self.len;
+        self.len = 0;
+        // SAFETY: the first `len` elements were initialized and are no
+        //  longer reachable.
+        unsafe {
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(
+                self.data.as_mut_ptr() as *mut T,
+                len,
+            ))
+        };
+    }
+
+    /// Drops all elements.
+    #[cfg(feature = "safe_only")]
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.data.clear();
+    }
+}
+
+impl<T, C: Compare<T>, const N: usize> ArrayHeap<T, C, N> {
+    /// Returns the greatest element, or `None` if the heap is empty.
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.as_slice().first()
+    }
+
+    /// Pushes an item onto the heap, or returns it as `Err` if the heap is
+    /// full.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*N*)).
+    pub fn push(&mut self, item: T) -> Result<(), T> {
+        if self.is_full() {
+            return Err(item);
+        }
+        self.write_last(item);
+        self.sift_up(self.len - 1);
+        Ok(())
+    }
+
+    /// Removes the greatest element and returns it, or `None` if the heap is
+    /// empty.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*N*)).
+    pub fn pop(&mut self) -> Option<T> {
+        let last = self.len.checked_sub(1)?;
+        self.as_mut_slice().swap(0, last);
+        let item = self.take_last();
+        self.sift_down(0);
+        Some(item)
+    }
+
+    #[cfg(not(feature = "safe_only"))]
+    fn sift_up(&mut self, pos: usize) {
+        let len = self.len;
+        // SAFETY: the first `len` elements are initialized.
+        let data = unsafe { slice::from_raw_parts_mut(self.data.as_mut_ptr() as *mut T, len) };
+        sift_up_slice(data, pos, &self.cmp);
+    }
+
+    #[cfg(feature = "safe_only")]
+    fn sift_up(&mut self, pos: usize) {
+        sift_up_slice(&mut self.data, pos, &self.cmp);
+    }
+
+    #[cfg(not(feature = "safe_only"))]
+    fn sift_down(&mut self, pos: usize) {
+        let len = self.len;
+        // SAFETY: the first `len` elements are initialized.
+        let data = unsafe { slice::from_raw_parts_mut(self.data.as_mut_ptr() as *mut T, len) };
+        sift_down_slice(data, pos, &self.cmp);
+    }
+
+    #[cfg(feature = "safe_only")]
+    fn sift_down(&mut self, pos: usize) {
+        sift_down_slice(&mut self.data, pos, &self.cmp);
+    }
+}
+
+// These sift by swapping elements instead of moving a hole around, so the
+// slice stays a valid permutation even if the comparator panics.
+
+/// Moves `data[pos]` up until its parent is not less than it.
+pub(crate) fn sift_up_slice<T, C: Compare<T>>(data: &mut [T], mut pos: usize, cmp: &C) {
+    while pos > 0 {
+        let parent = (pos - 1) / 2;
+        if cmp.compares_le(&data[pos], &data[parent]) {
+            break;
+        }
+        data.swap(pos, parent);
+        pos = parent;
+    }
+}
+
+/// Moves `data[pos]` down until neither child is greater than it.
+pub(crate) fn sift_down_slice<T, C: Compare<T>>(data: &mut [T], mut pos: usize, cmp: &C) {
+    let end = data.len();
+    loop {
+        let mut child = 2 * pos + 1;
+        if child >= end {
+            break;
+        }
+        if child + 1 < end && cmp.compares_le(&data[child], &data[child + 1]) {
+            child += 1;
+        }
+        if cmp.compares_ge(&data[pos], &data[child]) {
+            break;
+        }
+        data.swap(pos, child);
+        pos = child;
+    }
+}
@@ -0,0 +1,230 @@
+//! A shared [`BinaryHeap`] whose consumers can await items.
+//!
+//! [`BinaryHeap`]: struct.BinaryHeap.html
+
+use crate::binary_heap::{BinaryHeap, MaxComparator};
+use crate::util;
+use compare::Compare;
+use std::collections::VecDeque;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+
+struct Inner<T, C> {
+    heap: BinaryHeap<T, C>,
+    /// Pending `pop` futures, longest waiting first.
+    waiters: VecDeque<(u64, Waker)>,
+    next_waiter: u64,
+}
+
+impl<T, C: Compare<T>> Inner<T, C> {
+    /// Wakes the longest waiting `pop` future, if any.
+    fn notify_one(&mut self) {
+        if let Some((_, waker)) = self.waiters.pop_front() {
+            waker.wake();
+        }
+    }
+}
+
+/// A thread-safe [`BinaryHeap`] for async code, whose [`pop`] resolves once
+/// an item is available.
+///
+/// [`push`] wakes the `pop` future that has been waiting longest, which then
+/// takes the greatest item. Waiting does not block the executor thread, so
+/// the heap can be shared between tasks of any executor with an `Arc`.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::AsyncHeap;
+/// use futures::executor::block_on;
+/// use futures::join;
+///
+/// let heap = AsyncHeap::new();
+/// let consumer = async {
+///     let first = heap.pop().await;
+///     let second = heap.pop().await;
+///     (first, second)
+/// };
+/// let producer = async {
+///     heap.push(1);
+///     heap.push(3);
+/// };
+/// let ((first, second), ()) = block_on(async { join!(consumer, producer) });
+/// // The consumer waits until the producer has pushed both items.
+/// assert_eq!((first, second), (3, 1));
+/// ```
+///
+/// [`BinaryHeap`]: struct.BinaryHeap.html
+/// [`pop`]: AsyncHeap::pop
+/// [`push`]: AsyncHeap::push
+pub struct AsyncHeap<T, C = MaxComparator> {
+    inner: Mutex<Inner<T, C>>,
+}
+
+impl<T: fmt::Debug, C> fmt::Debug for AsyncHeap<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let inner = util::lock(&self.inner);
+        f.debug_struct("AsyncHeap")
+            .field("heap", &inner.heap)
+            .field("waiters", &inner.waiters.len())
+            .finish()
+    }
+}
+
+impl<T: Ord> Default for AsyncHeap<T> {
+    fn default() -> Self {
+        AsyncHeap::new()
+    }
+}
+
+impl<T: Ord> AsyncHeap<T> {
+    /// Creates an empty max-heap.
+    #[must_use]
+    pub fn new() -> Self {
+        AsyncHeap::from_heap(BinaryHeap::new())
+    }
+}
+
+impl<T, C: Compare<T>> From<BinaryHeap<T, C>> for AsyncHeap<T, C> {
+    fn from(heap: BinaryHeap<T, C>) -> Self {
+        AsyncHeap::from_heap(heap)
+    }
+}
+
+impl<T, C: Compare<T>> AsyncHeap<T, C> {
+    /// Wraps an existing heap.
+    pub fn from_heap(heap: BinaryHeap<T, C>) -> Self {
+        AsyncHeap {
+            inner: Mutex::new(Inner {
+                heap,
+                waiters: VecDeque::new(),
+                next_waiter: 0,
+            }),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner<T, C>> {
+        util::lock(&self.inner)
+    }
+
+    /// Pushes an item onto the heap and wakes the longest waiting [`pop`].
+    ///
+    /// [`pop`]: AsyncHeap::pop
+    pub fn push(&self, item: T) {
+        let mut inner = self.lock();
+        inner.heap.push(item);
+        inner.notify_one();
+    }
+
+    /// Returns a future that removes the greatest item once the heap is not
+    /// empty.
+    ///
+    /// Dropping the future before it completes gives up its place in line;
+    /// an item it was woken for is handed on to the next waiter.
+    pub fn pop(&self) -> Pop<'_, T, C> {
+        Pop {
+            heap: self,
+            waiter: None,
+        }
+    }
+
+    /// Removes the greatest item and returns it, or `None` if the heap is
+    /// empty. Never waits for an item.
+    pub fn try_pop(&self) -> Option<T> {
+        self.lock().heap.pop()
+    }
+
+    /// Returns the number of items in the heap.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.lock().heap.len()
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.lock().heap.is_empty()
+    }
+
+    /// Consumes the wrapper and returns the heap.
+    pub fn into_inner(self) -> BinaryHeap<T, C> {
+        self.inner
+            .into_inner()
+            .unwrap_or_else(|e| e.into_inner())
+            .heap
+    }
+}
+
+/// A future that pops the greatest item from an [`AsyncHeap`].
+///
+/// This `struct` is created by [`AsyncHeap::pop()`]. See its documentation
+/// for more.
+///
+/// [`AsyncHeap`]: struct.AsyncHeap.html
+/// [`AsyncHeap::pop()`]: struct.AsyncHeap.html#method.pop
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Pop<'a, T, C: Compare<T>> {
+    heap: &'a AsyncHeap<T, C>,
+    /// Our id once we have queued up as a waiter.
+    waiter: Option<u64>,
+}
+
+impl<T, C: Compare<T>> fmt::Debug for Pop<'_, T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pop").field("waiter", &self.waiter).finish()
+    }
+}
+
+impl<T, C: Compare<T>> Future for Pop<'_, T, C> {
+    type Output = T;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let heap = self.heap;
+        let mut inner = heap.lock();
+        if let Some(item) = inner.heap.pop() {
+            if let Some(id) = self.waiter.take() {
+                inner.waiters.retain(|(w, _)| *w != id);
+            }
+            return Poll::Ready(item);
+        }
+        match self.waiter {
+            Some(id) => match inner.waiters.iter_mut().find(|(w, _)| *w == id) {
+                Some((_, waker)) => {
+                    if !waker.will_wake(cx.waker()) {
+                        *waker = cx.waker().clone();
+                    }
+                }
+                // Woken, but another consumer took the item: queue up again
+                // at the front, since we have waited longest.
+                None => inner.waiters.push_front((id, cx.waker().clone())),
+            },
+            None => {
+                let id = inner.next_waiter;
+                inner.next_waiter += 1;
+                inner.waiters.push_back((id, cx.waker().clone()));
+                drop(inner);
+                self.waiter = Some(id);
+            }
+        }
+        Poll::Pending
+    }
+}
+
+impl<T, C: Compare<T>> Drop for Pop<'_, T, C> {
+    fn drop(&mut self) {
+        let id = match self.waiter {
+            Some(id) => id,
+            None => return,
+        };
+        let mut inner = self.heap.lock();
+        let queued = inner.waiters.len();
+        inner.waiters.retain(|(w, _)| *w != id);
+        if inner.waiters.len() == queued && !inner.heap.is_empty() {
+            // We were woken for an item we will never take.
+            inner.notify_one();
+        }
+    }
+}
@@ -150,20 +150,60 @@
 
 // use core::ops::{Deref, DerefMut, Place, Placer, InPlace};
 // use core::iter::{FromIterator, FusedIterator};
+#[cfg(feature = "schemars")]
+use std::borrow::Cow;
 use std::cmp::Ordering;
-use std::iter::FromIterator;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::iter::{FromIterator, FusedIterator};
 use std::slice;
 // use std::iter::FusedIterator;
 // use std::vec::Drain;
+#[cfg(feature = "borsh")]
+use borsh::{BorshDeserialize, BorshSerialize};
 use compare::Compare;
 use core::fmt;
-use core::mem::{swap, ManuallyDrop};
+#[cfg(not(feature = "safe_only"))]
+use core::mem::ManuallyDrop;
+use core::mem::{self, swap};
+#[cfg(not(feature = "safe_only"))]
 use core::ptr;
+#[cfg(feature = "futures")]
+use futures_core::stream::{FusedStream, Stream};
+#[cfg(feature = "rand")]
+use rand::Rng;
+#[cfg(feature = "rayon")]
+use rayon::iter::{FromParallelIterator, IntoParallelIterator, ParallelExtend};
+#[cfg(all(feature = "rayon", not(feature = "safe_only")))]
+use rayon::iter::{IndexedParallelIterator, ParallelIterator};
+#[cfg(feature = "rayon")]
+use rayon::slice::ParallelSliceMut;
+
+#[cfg(feature = "zeroize")]
+use crate::wipe;
+#[cfg(feature = "schemars")]
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
 #[cfg(feature = "serde")]
-use serde::{Deserialize, Serialize};
+use serde::de::DeserializeSeed;
+#[cfg(feature = "serde")]
+use serde::de::Error as _;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize};
+#[cfg(feature = "fallible")]
+use std::collections::TryReserveError;
+#[cfg(feature = "serde")]
+use std::marker::PhantomData;
 use std::ops::Deref;
 use std::ops::DerefMut;
+use std::ops::Range;
+#[cfg(feature = "futures")]
+use std::pin::Pin;
+#[cfg(feature = "futures")]
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 use std::vec;
+#[cfg(feature = "zeroize")]
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 // use slice;
 // use vec::{self, Vec};
@@ -274,16 +314,364 @@ use std::vec;
 /// [peek]: BinaryHeap::peek
 /// [peek\_mut]: BinaryHeap::peek_mut
 // #[stable(feature = "rust1", since = "1.0.0")]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct BinaryHeap<T, C = MaxComparator> {
     data: Vec<T>,
     cmp: C,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    growth: GrowthPolicy,
+    /// Called before reallocating while the capacity is frozen.
+    #[cfg(feature = "alloc_guard")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    on_realloc: Option<fn(usize, usize)>,
+}
+
+/// The serialized form of a [`BinaryHeap`], read without trusting its order.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(rename = "BinaryHeap")]
+struct SerdeHeap<T, C> {
+    data: Vec<T>,
+    cmp: C,
+}
+
+/// Deserializes a heap and rebuilds it, so that input whose order was
+/// edited by hand or by an attacker still yields a valid heap.
+///
+/// Rebuilding takes *O*(*n*) time and leaves a valid heap as it is. To reject
+/// such input instead, use [`deserialize_checked`].
+///
+/// [`deserialize_checked`]: fn.deserialize_checked.html
+#[cfg(feature = "serde")]
+impl<'de, T, C> Deserialize<'de> for BinaryHeap<T, C>
+where
+    T: Deserialize<'de>,
+    C: Compare<T> + Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let SerdeHeap { data, cmp } = SerdeHeap::deserialize(deserializer)?;
+        Ok(BinaryHeap::from_vec_cmp(data, cmp))
+    }
+}
+
+/// Deserializes a heap, failing if its elements are not in heap order.
+///
+/// Use it with `#[serde(deserialize_with = "...")]` where a heap should have
+/// been serialized by this crate and anything else is an error. The check
+/// takes *O*(*n*) time, like the rebuild the `Deserialize` implementation
+/// performs.
+///
+/// This function is available with the `serde` feature.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::BinaryHeap;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Frontier {
+///     #[serde(deserialize_with = "binary_heap_plus::deserialize_checked")]
+///     queue: BinaryHeap<u32>,
+/// }
+///
+/// let ok = r#"{ "queue": { "data": [3, 1, 2], "cmp": null } }"#;
+/// assert_eq!(serde_json::from_str::<Frontier>(ok).unwrap().queue.peek(), Some(&3));
+///
+/// let broken = r#"{ "queue": { "data": [1, 3, 2], "cmp": null } }"#;
+/// assert!(serde_json::from_str::<Frontier>(broken).is_err());
+/// ```
+#[cfg(feature = "serde")]
+pub fn deserialize_checked<'de, D, T, C>(deserializer: D) -> Result<BinaryHeap<T, C>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+    C: Compare<T> + Deserialize<'de>,
+{
+    let SerdeHeap { data, cmp }: SerdeHeap<T, C> = SerdeHeap::deserialize(deserializer)?;
+    if let Some(err) = HeapOrderError::find(&data, &cmp) {
+        return Err(D::Error::custom(err));
+    }
+    // The elements were just checked to be in heap order.
+    Ok(BinaryHeap::from_parts(data, cmp, false))
+}
+
+/// Deserializes a heap from a sequence of elements with a comparator given
+/// at deserialization time.
+///
+/// The comparator of a heap ordered by a closure, or by a comparator built
+/// from runtime configuration, can be neither serialized nor created by
+/// `Default`. Serialize such a heap as its elements, for instance with
+/// [`serde_sorted::seq`] or from [`as_slice`], and pass the comparator to
+/// [`HeapSeed::new`] to read it back. The elements may come in any order;
+/// the heap is rebuilt in *O*(*n*).
+///
+/// This type is available with the `serde` feature.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::HeapSeed;
+/// use serde::de::DeserializeSeed;
+///
+/// let modulus = 4;
+/// let by_rest = move |a: &u32, b: &u32| (a % modulus).cmp(&(b % modulus));
+///
+/// let mut de = serde_json::Deserializer::from_str("[4, 7, 9]");
+/// let mut heap = HeapSeed::new(by_rest).deserialize(&mut de).unwrap();
+/// assert_eq!(heap.pop(), Some(7));
+/// ```
+///
+/// [`serde_sorted::seq`]: serde_sorted/seq/index.html
+/// [`as_slice`]: BinaryHeap::as_slice
+#[cfg(feature = "serde")]
+pub struct HeapSeed<T, C> {
+    cmp: C,
+    marker: PhantomData<fn() -> T>,
+}
+
+#[cfg(feature = "serde")]
+impl<T, C: fmt::Debug> fmt::Debug for HeapSeed<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HeapSeed").field("cmp", &self.cmp).finish()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T, C: Compare<T>> HeapSeed<T, C> {
+    /// Creates a seed that deserializes a heap ordered by `cmp`.
+    pub fn new(cmp: C) -> Self {
+        HeapSeed {
+            cmp,
+            marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T, C> DeserializeSeed<'de> for HeapSeed<T, C>
+where
+    T: Deserialize<'de>,
+    C: Compare<T>,
+{
+    type Value = BinaryHeap<T, C>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        let data: Vec<T> = Deserialize::deserialize(deserializer)?;
+        Ok(BinaryHeap::from_vec_cmp(data, self.cmp))
+    }
+}
+
+/// Describes the form the `serde` implementation writes: an object with the
+/// elements in `data`, in any order, and the comparator in `cmp`, which is
+/// `null` for [`MaxComparator`] and [`MinComparator`].
+///
+/// A field serialized as a plain array, with [`serde_sorted::seq`], is
+/// described by `#[schemars(with = "Vec<T>")]` instead.
+///
+/// Requires the `schemars` feature.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::BinaryHeap;
+/// use schemars::{schema_for, JsonSchema};
+///
+/// #[derive(JsonSchema)]
+/// struct Config {
+///     queue: BinaryHeap<u32>,
+///     // Serialized with `serde_sorted::seq`.
+///     #[schemars(with = "Vec<u32>")]
+///     backlog: BinaryHeap<u32>,
+/// }
+///
+/// let schema = schema_for!(Config);
+/// let schema = schema.as_value();
+/// assert_eq!(schema["properties"]["queue"]["$ref"], "#/$defs/BinaryHeap_of_uint32");
+/// assert_eq!(schema["$defs"]["BinaryHeap_of_uint32"]["type"], "object");
+/// assert_eq!(schema["properties"]["backlog"]["type"], "array");
+/// ```
+///
+/// [`MaxComparator`]: struct.MaxComparator.html
+/// [`MinComparator`]: struct.MinComparator.html
+/// [`serde_sorted::seq`]: serde_sorted/seq/index.html
+#[cfg(feature = "schemars")]
+impl<T: JsonSchema, C: JsonSchema> JsonSchema for BinaryHeap<T, C> {
+    fn schema_name() -> Cow<'static, str> {
+        format!("BinaryHeap_of_{}", T::schema_name()).into()
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        format!(
+            "binary_heap_plus::BinaryHeap<{}, {}>",
+            T::schema_id(),
+            C::schema_id()
+        )
+        .into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "object",
+            "properties": {
+                "data": generator.subschema_for::<Vec<T>>(),
+                "cmp": generator.subschema_for::<C>(),
+            },
+            "required": ["data", "cmp"],
+        })
+    }
+}
+
+/// Writes the elements in their current layout, like the `serde`
+/// implementation. The comparator is not written.
+///
+/// Requires the `borsh` feature.
+#[cfg(feature = "borsh")]
+impl<T: BorshSerialize, C> BorshSerialize for BinaryHeap<T, C> {
+    fn serialize<W: borsh::io::Write>(&self, writer: &mut W) -> borsh::io::Result<()> {
+        BorshSerialize::serialize(&self.data, writer)
+    }
+}
+
+/// Reads the elements, takes the comparator from `Default` and rebuilds the
+/// heap, like the `serde` implementation. To reject elements that are not in
+/// heap order instead, use [`borsh_deserialize_checked`].
+///
+/// Requires the `borsh` feature.
+///
+/// [`borsh_deserialize_checked`]: fn.borsh_deserialize_checked.html
+#[cfg(feature = "borsh")]
+impl<T: BorshDeserialize, C: Compare<T> + Default> BorshDeserialize for BinaryHeap<T, C> {
+    fn deserialize_reader<R: borsh::io::Read>(reader: &mut R) -> borsh::io::Result<Self> {
+        let data = Vec::deserialize_reader(reader)?;
+        Ok(BinaryHeap::from_vec_cmp(data, C::default()))
+    }
+}
+
+/// Reads a heap written with borsh, failing with
+/// [`ErrorKind::InvalidData`] if its elements are not in heap order.
+///
+/// This is the borsh counterpart of [`deserialize_checked`].
+///
+/// This function is available with the `borsh` feature.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::{borsh_deserialize_checked, BinaryHeap};
+///
+/// let bytes = borsh::to_vec(&BinaryHeap::from(vec![1u32, 2, 3])).unwrap();
+/// let heap: BinaryHeap<u32> = borsh_deserialize_checked(&mut &bytes[..]).unwrap();
+/// assert_eq!(heap.peek(), Some(&3));
+///
+/// let broken = borsh::to_vec(&vec![1u32, 3, 2]).unwrap();
+/// assert!(borsh_deserialize_checked::<_, u32, binary_heap_plus::MaxComparator>(&mut &broken[..]).is_err());
+/// ```
+///
+/// [`ErrorKind::InvalidData`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.InvalidData
+/// [`deserialize_checked`]: fn.deserialize_checked.html
+#[cfg(feature = "borsh")]
+pub fn borsh_deserialize_checked<R, T, C>(reader: &mut R) -> borsh::io::Result<BinaryHeap<T, C>>
+where
+    R: borsh::io::Read,
+    T: BorshDeserialize,
+    C: Compare<T> + Default,
+{
+    let data = Vec::deserialize_reader(reader)?;
+    let cmp = C::default();
+    if let Some(err) = HeapOrderError::find(&data, &cmp) {
+        return Err(borsh::io::Error::new(
+            borsh::io::ErrorKind::InvalidData,
+            err,
+        ));
+    }
+    // The elements were just checked to be in heap order.
+    Ok(BinaryHeap::from_parts(data, cmp, false))
+}
+
+/// An element found greater than its parent in data that should have been in
+/// heap order.
+#[cfg(any(feature = "serde", feature = "rkyv", feature = "borsh"))]
+#[derive(Debug)]
+pub(crate) struct HeapOrderError {
+    index: usize,
+}
+
+#[cfg(any(feature = "serde", feature = "rkyv", feature = "borsh"))]
+impl HeapOrderError {
+    /// Returns the first element of `data` that is greater than its parent.
+    pub(crate) fn find<T, C: Compare<T>>(data: &[T], cmp: &C) -> Option<Self> {
+        (1..data.len())
+            .find(|&i| cmp.compares_lt(&data[(i - 1) / 2], &data[i]))
+            .map(|index| HeapOrderError { index })
+    }
+}
+
+#[cfg(any(feature = "serde", feature = "rkyv", feature = "borsh"))]
+impl fmt::Display for HeapOrderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "heap property violated: the element at {} is greater than its parent at {}",
+            self.index,
+            (self.index - 1) / 2
+        )
+    }
+}
+
+#[cfg(any(feature = "serde", feature = "rkyv", feature = "borsh"))]
+impl std::error::Error for HeapOrderError {}
+
+/// How [`BinaryHeap::push`] grows the heap's buffer when it is full.
+///
+/// The policy only applies to `push`; [`reserve`], [`extend`] and friends
+/// allocate as they always do. A heap's policy is not serialized: a
+/// deserialized heap uses [`GrowthPolicy::Amortized`].
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::{BinaryHeap, GrowthPolicy};
+///
+/// let mut heap = BinaryHeap::new();
+/// heap.set_growth_policy(GrowthPolicy::Fixed(10));
+/// for x in 0..11 {
+///     heap.push(x);
+/// }
+/// assert_eq!(heap.capacity(), 20);
+/// ```
+///
+/// [`BinaryHeap::push`]: struct.BinaryHeap.html#method.push
+/// [`reserve`]: struct.BinaryHeap.html#method.reserve
+/// [`extend`]: struct.BinaryHeap.html#impl-Extend%3CT%3E
+/// [`GrowthPolicy::Amortized`]: enum.GrowthPolicy.html#variant.Amortized
+#[derive(Clone, Copy, Debug)]
+pub enum GrowthPolicy {
+    /// Let `Vec` choose the new capacity, which currently doubles it. This is
+    /// the default.
+    Amortized,
+    /// Grow by exactly one element, never over-allocating. Every push into a
+    /// full heap reallocates, so pushes take *O*(*n*) time.
+    Exact,
+    /// Grow by the given number of elements, or by one if it is zero.
+    Fixed(usize),
+    /// Grow to the capacity the function returns for the current capacity,
+    /// or by one element if that is not larger.
+    Custom(fn(usize) -> usize),
+}
+
+impl Default for GrowthPolicy {
+    fn default() -> Self {
+        GrowthPolicy::Amortized
+    }
 }
 
 /// For `T` that implements `Ord`, you can use this struct to quickly
 /// set up a max heap.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 #[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "rkyv", repr(C))]
 pub struct MaxComparator;
 
 impl<T: Ord> Compare<T> for MaxComparator {
@@ -295,7 +683,9 @@ impl<T: Ord> Compare<T> for MaxComparator {
 /// For `T` that implements `Ord`, you can use this struct to quickly
 /// set up a min heap.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 #[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "rkyv", repr(C))]
 pub struct MinComparator;
 
 impl<T: Ord> Compare<T> for MinComparator {
@@ -332,6 +722,169 @@ where
     }
 }
 
+/// The comparator defined by closure, reversed so that the least element
+/// according to the closure is popped first
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct MinFnComparator<F>(pub F);
+
+impl<T, F> Compare<T> for MinFnComparator<F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        self.0(b, a)
+    }
+}
+
+/// The comparator ordered by key, reversed so that the element with the
+/// least key is popped first
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct MinKeyComparator<F>(pub F);
+
+impl<K: Ord, T, F> Compare<T> for MinKeyComparator<F>
+where
+    F: Fn(&T) -> K,
+{
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        self.0(b).cmp(&self.0(a))
+    }
+}
+
+/// The comparator ordered by a key borrowed from each element, which spares
+/// cloning keys such as `String` fields.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct SortKeyComparator<F>(pub F);
+
+impl<K: Ord + ?Sized, T, F> Compare<T> for SortKeyComparator<F>
+where
+    F: Fn(&T) -> &K,
+{
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        self.0(a).cmp(self.0(b))
+    }
+}
+
+/// A max-heap, the default [`BinaryHeap`].
+///
+/// [`BinaryHeap`]: struct.BinaryHeap.html
+pub type MaxHeap<T> = BinaryHeap<T, MaxComparator>;
+
+/// A min-heap, created by [`BinaryHeap::new_min`] and
+/// [`BinaryHeap::with_capacity_min`].
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::MinHeap;
+///
+/// struct Timers {
+///     deadlines: MinHeap<u64>,
+/// }
+///
+/// let mut timers = Timers { deadlines: MinHeap::new_min() };
+/// timers.deadlines.extend(vec![30, 10, 20]);
+/// assert_eq!(timers.deadlines.pop(), Some(10));
+/// ```
+///
+/// [`BinaryHeap::new_min`]: struct.BinaryHeap.html#method.new_min
+/// [`BinaryHeap::with_capacity_min`]: struct.BinaryHeap.html#method.with_capacity_min
+pub type MinHeap<T> = BinaryHeap<T, MinComparator>;
+
+/// A heap ordered by a comparison function, created by
+/// [`BinaryHeap::new_by`] and [`BinaryHeap::with_capacity_by`].
+///
+/// Closures cannot be named, so `F` defaults to a function pointer, which
+/// any closure that captures nothing coerces to.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::HeapBy;
+///
+/// let mut heap: HeapBy<(u32, &str)> = HeapBy::new_by(|a, b| b.0.cmp(&a.0));
+/// heap.push((2, "b"));
+/// heap.push((1, "a"));
+/// assert_eq!(heap.pop(), Some((1, "a")));
+/// ```
+///
+/// [`BinaryHeap::new_by`]: struct.BinaryHeap.html#method.new_by
+/// [`BinaryHeap::with_capacity_by`]: struct.BinaryHeap.html#method.with_capacity_by
+pub type HeapBy<T, F = fn(&T, &T) -> Ordering> = BinaryHeap<T, FnComparator<F>>;
+
+/// A heap ordered by a key function, created by [`BinaryHeap::new_by_key`]
+/// and [`BinaryHeap::with_capacity_by_key`].
+///
+/// To name the type, use a function pointer such as `fn(&T) -> u64` for
+/// `F`.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::HeapByKey;
+///
+/// struct Task {
+///     priority: u8,
+/// }
+///
+/// let mut heap: HeapByKey<Task, fn(&Task) -> u8> = HeapByKey::new_by_key(|t| t.priority);
+/// heap.push(Task { priority: 1 });
+/// heap.push(Task { priority: 7 });
+/// assert_eq!(heap.pop().map(|t| t.priority), Some(7));
+/// ```
+///
+/// [`BinaryHeap::new_by_key`]: struct.BinaryHeap.html#method.new_by_key
+/// [`BinaryHeap::with_capacity_by_key`]: struct.BinaryHeap.html#method.with_capacity_by_key
+pub type HeapByKey<T, F> = BinaryHeap<T, KeyComparator<F>>;
+
+/// A heap popping the least element according to a comparison function,
+/// created by [`BinaryHeap::new_min_by`] and
+/// [`BinaryHeap::with_capacity_min_by`].
+///
+/// Like for [`HeapBy`], `F` defaults to a function pointer.
+///
+/// [`BinaryHeap::new_min_by`]: struct.BinaryHeap.html#method.new_min_by
+/// [`BinaryHeap::with_capacity_min_by`]: struct.BinaryHeap.html#method.with_capacity_min_by
+/// [`HeapBy`]: type.HeapBy.html
+pub type MinHeapBy<T, F = fn(&T, &T) -> Ordering> = BinaryHeap<T, MinFnComparator<F>>;
+
+/// A heap popping the element with the least key first, created by
+/// [`BinaryHeap::new_min_by_key`] and
+/// [`BinaryHeap::with_capacity_min_by_key`].
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::MinHeapByKey;
+///
+/// struct Timer {
+///     deadline: u64,
+/// }
+///
+/// let mut timers: MinHeapByKey<Timer, fn(&Timer) -> u64> =
+///     MinHeapByKey::new_min_by_key(|t| t.deadline);
+/// timers.push(Timer { deadline: 30 });
+/// timers.push(Timer { deadline: 10 });
+/// assert_eq!(timers.pop().map(|t| t.deadline), Some(10));
+/// ```
+///
+/// [`BinaryHeap::new_min_by_key`]: struct.BinaryHeap.html#method.new_min_by_key
+/// [`BinaryHeap::with_capacity_min_by_key`]: struct.BinaryHeap.html#method.with_capacity_min_by_key
+pub type MinHeapByKey<T, F> = BinaryHeap<T, MinKeyComparator<F>>;
+
+/// A heap ordered by a borrowed key, created by
+/// [`BinaryHeap::new_by_sort_key`] and
+/// [`BinaryHeap::with_capacity_by_sort_key`].
+///
+/// To name the type, use a function pointer such as `fn(&T) -> &str` for
+/// `F`.
+///
+/// [`BinaryHeap::new_by_sort_key`]: struct.BinaryHeap.html#method.new_by_sort_key
+/// [`BinaryHeap::with_capacity_by_sort_key`]: struct.BinaryHeap.html#method.with_capacity_by_sort_key
+pub type HeapBySortKey<T, F> = BinaryHeap<T, SortKeyComparator<F>>;
+
 /// Structure wrapping a mutable reference to the greatest item on a
 /// `BinaryHeap`.
 ///
@@ -356,8 +909,8 @@ impl<T: fmt::Debug, C: Compare<T>> fmt::Debug for PeekMut<'_, T, C> {
 impl<T, C: Compare<T>> Drop for PeekMut<'_, T, C> {
     fn drop(&mut self) {
         if self.sift {
-            // SAFETY: PeekMut is only instantiated for non-empty heaps.
-            unsafe { self.heap.sift_down(0) };
+            self.heap.sift_down(0);
+            self.heap.strict_check("PeekMut");
         }
     }
 }
@@ -366,19 +919,17 @@ impl<T, C: Compare<T>> Drop for PeekMut<'_, T, C> {
 impl<T, C: Compare<T>> Deref for PeekMut<'_, T, C> {
     type Target = T;
     fn deref(&self) -> &T {
-        debug_assert!(!self.heap.is_empty());
-        // SAFE: PeekMut is only instantiated for non-empty heaps
-        unsafe { self.heap.data.get_unchecked(0) }
+        // PeekMut is only instantiated for non-empty heaps.
+        &self.heap.data[0]
     }
 }
 
 // #[stable(feature = "binary_heap_peek_mut", since = "1.12.0")]
 impl<T, C: Compare<T>> DerefMut for PeekMut<'_, T, C> {
     fn deref_mut(&mut self) -> &mut T {
-        debug_assert!(!self.heap.is_empty());
         self.sift = true;
-        // SAFE: PeekMut is only instantiated for non-empty heaps
-        unsafe { self.heap.data.get_unchecked_mut(0) }
+        // PeekMut is only instantiated for non-empty heaps.
+        &mut self.heap.data[0]
     }
 }
 
@@ -390,42 +941,244 @@ impl<'a, T, C: Compare<T>> PeekMut<'a, T, C> {
         this.sift = false;
         value
     }
-}
 
-// #[stable(feature = "rust1", since = "1.0.0")]
-impl<T: Clone, C: Clone> Clone for BinaryHeap<T, C> {
-    fn clone(&self) -> Self {
-        BinaryHeap {
-            data: self.data.clone(),
-            cmp: self.cmp.clone(),
+    /// Moves the peeked value to its place in the heap if it has been
+    /// changed, and makes the guard refer to the new greatest item.
+    ///
+    /// Returns `true` if the greatest item is now a different one. This
+    /// lets a loop change the top item and move it into place repeatedly
+    /// without getting a new guard each time.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    ///
+    /// // Give out work to the least loaded worker; entries are (load, id).
+    /// let mut workers = BinaryHeap::new_min();
+    /// workers.extend([(0, 'a'), (0, 'b')]);
+    ///
+    /// let mut least = workers.peek_mut().unwrap();
+    /// let mut order = Vec::new();
+    /// for cost in [3, 1, 1, 2] {
+    ///     order.push(least.1);
+    ///     least.0 += cost;
+    ///     least.refresh();
+    /// }
+    /// drop(least);
+    /// assert_eq!(order, ['a', 'b', 'b', 'b']);
+    /// assert_eq!(workers.into_sorted_vec(), [(4, 'b'), (3, 'a')]);
+    /// ```
+    pub fn refresh(&mut self) -> bool {
+        if !self.sift {
+            return false;
         }
+        self.sift = false;
+        let moved = self.heap.sift_down(0) != 0;
+        self.heap.strict_check("PeekMut::refresh");
+        moved
     }
+}
 
-    fn clone_from(&mut self, source: &Self) {
-        self.data.clone_from(&source.data);
+/// Structure giving mutable access to all items of a `BinaryHeap`, which
+/// restores the heap property when dropped.
+///
+/// This `struct` is created by the [`iter_mut`] and [`as_mut_slice`] methods
+/// on [`BinaryHeap`]. Iterate over `&mut` the guard, or use it as a mutable
+/// slice; the references it hands out borrow the guard, so they cannot
+/// outlive the rebuild. See [`iter_mut`] for more.
+///
+/// [`iter_mut`]: BinaryHeap::iter_mut
+/// [`as_mut_slice`]: BinaryHeap::as_mut_slice
+pub struct SliceMut<'a, T: 'a, C: 'a + Compare<T>> {
+    heap: &'a mut BinaryHeap<T, C>,
+    rebuild: bool,
+}
+
+impl<T: fmt::Debug, C: Compare<T>> fmt::Debug for SliceMut<'_, T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SliceMut").field(&self.heap.data).finish()
     }
 }
 
-// #[stable(feature = "rust1", since = "1.0.0")]
-impl<T: Ord> Default for BinaryHeap<T> {
-    /// Creates an empty `BinaryHeap<T>`.
-    #[inline]
-    fn default() -> BinaryHeap<T> {
-        BinaryHeap::new()
+impl<T, C: Compare<T>> Drop for SliceMut<'_, T, C> {
+    fn drop(&mut self) {
+        if self.rebuild {
+            self.heap.rebuild();
+            self.heap.strict_check("iter_mut");
+        }
     }
 }
 
-// #[stable(feature = "binaryheap_debug", since = "1.4.0")]
-impl<T: fmt::Debug, C> fmt::Debug for BinaryHeap<T, C> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_list().entries(self.iter()).finish()
+impl<T, C: Compare<T>> Deref for SliceMut<'_, T, C> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        &self.heap.data
     }
 }
 
-impl<T, C: Compare<T> + Default> BinaryHeap<T, C> {
-    /// Generic constructor for `BinaryHeap` from [`Vec`].
-    ///
-    /// Because `BinaryHeap` stores the elements in its internal `Vec`,
+impl<T, C: Compare<T>> DerefMut for SliceMut<'_, T, C> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.rebuild = true;
+        &mut self.heap.data
+    }
+}
+
+impl<'g, T, C: Compare<T>> IntoIterator for &'g mut SliceMut<'_, T, C> {
+    type Item = &'g mut T;
+    type IntoIter = slice::IterMut<'g, T>;
+
+    fn into_iter(self) -> slice::IterMut<'g, T> {
+        self.deref_mut().iter_mut()
+    }
+}
+
+/// Structure wrapping a mutable reference to the comparator of a
+/// `BinaryHeap`, which rebuilds the heap when dropped.
+///
+/// This `struct` is created by the [`cmp_mut`] method on [`BinaryHeap`]. See
+/// its documentation for more.
+///
+/// [`cmp_mut`]: BinaryHeap::cmp_mut
+pub struct CmpMut<'a, T: 'a, C: 'a + Compare<T>> {
+    heap: &'a mut BinaryHeap<T, C>,
+    rebuild: bool,
+}
+
+impl<T, C: Compare<T> + fmt::Debug> fmt::Debug for CmpMut<'_, T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CmpMut").field(&self.heap.cmp).finish()
+    }
+}
+
+impl<T, C: Compare<T>> Drop for CmpMut<'_, T, C> {
+    fn drop(&mut self) {
+        if self.rebuild {
+            self.heap.rebuild();
+            self.heap.strict_check("cmp_mut");
+        }
+    }
+}
+
+impl<T, C: Compare<T>> Deref for CmpMut<'_, T, C> {
+    type Target = C;
+    fn deref(&self) -> &C {
+        &self.heap.cmp
+    }
+}
+
+impl<T, C: Compare<T>> DerefMut for CmpMut<'_, T, C> {
+    fn deref_mut(&mut self) -> &mut C {
+        self.rebuild = true;
+        &mut self.heap.cmp
+    }
+}
+
+// #[stable(feature = "rust1", since = "1.0.0")]
+impl<T: Clone, C: Clone> Clone for BinaryHeap<T, C> {
+    fn clone(&self) -> Self {
+        BinaryHeap {
+            data: self.data.clone(),
+            cmp: self.cmp.clone(),
+            growth: self.growth,
+            // The clone has a buffer of its own, which is not frozen.
+            #[cfg(feature = "alloc_guard")]
+            on_realloc: None,
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.alloc_check(source.len());
+        self.data.clone_from(&source.data);
+        self.growth = source.growth;
+    }
+}
+
+/// Two heaps are equal if they hold the same elements, each as many times,
+/// whatever their layout and however they were built.
+///
+/// The elements are compared by `Ord`, not by the heaps' comparators.
+/// Comparing two heaps of the same length sorts references to their
+/// elements, which takes *O*(*n* log(*n*)) time and *O*(*n*) memory.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::BinaryHeap;
+///
+/// let a = BinaryHeap::from(vec![1, 2, 2, 3]);
+/// let mut b = BinaryHeap::new();
+/// b.extend(vec![2, 3, 1, 2]);
+/// assert_eq!(a, b);
+/// b.pop();
+/// assert_ne!(a, b);
+/// ```
+impl<T: Ord, C> PartialEq for BinaryHeap<T, C> {
+    fn eq(&self, other: &Self) -> bool {
+        if self.len() != other.len() {
+            return false;
+        }
+        let mut a: Vec<&T> = self.data.iter().collect();
+        let mut b: Vec<&T> = other.data.iter().collect();
+        a.sort_unstable();
+        b.sort_unstable();
+        a == b
+    }
+}
+
+impl<T: Ord, C> Eq for BinaryHeap<T, C> {}
+
+/// Hashes the elements regardless of their order, consistently with
+/// `PartialEq`, so that heaps can be keys of a `HashMap`.
+///
+/// Each element is hashed on its own with a fixed-key hasher and the results
+/// are summed, so hashing takes *O*(*n*) time and no memory. The sum and the
+/// length are then fed to the given hasher.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::BinaryHeap;
+/// use std::collections::HashSet;
+///
+/// let mut seen = HashSet::new();
+/// seen.insert(BinaryHeap::from(vec![1, 2, 3]));
+/// assert!(seen.contains(&BinaryHeap::from(vec![3, 1, 2])));
+/// ```
+impl<T: Hash, C> Hash for BinaryHeap<T, C> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let sum = self.data.iter().fold(0u64, |sum, item| {
+            let mut hasher = DefaultHasher::new();
+            item.hash(&mut hasher);
+            sum.wrapping_add(hasher.finish())
+        });
+        state.write_usize(self.len());
+        state.write_u64(sum);
+    }
+}
+
+// #[stable(feature = "rust1", since = "1.0.0")]
+impl<T: Ord> Default for BinaryHeap<T> {
+    /// Creates an empty `BinaryHeap<T>`.
+    #[inline]
+    fn default() -> BinaryHeap<T> {
+        BinaryHeap::new()
+    }
+}
+
+// #[stable(feature = "binaryheap_debug", since = "1.4.0")]
+impl<T: fmt::Debug, C> fmt::Debug for BinaryHeap<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T, C: Compare<T> + Default> BinaryHeap<T, C> {
+    /// Generic constructor for `BinaryHeap` from [`Vec`].
+    ///
+    /// Because `BinaryHeap` stores the elements in its internal `Vec`,
     /// it's natural to construct it from `Vec`.
     ///
     /// [`Vec`]: https://doc.rust-lang.org/stable/std/vec/struct.Vec.html
@@ -434,6 +1187,130 @@ impl<T, C: Compare<T> + Default> BinaryHeap<T, C> {
     }
 }
 
+#[cfg(feature = "rayon")]
+impl<T: Send, C: Compare<T> + Default + Sync> BinaryHeap<T, C> {
+    /// Like [`from_vec`], but heapifies large vectors on the rayon thread
+    /// pool.
+    ///
+    /// `from_vec` itself stays sequential because it does not require the
+    /// elements and the comparator to be shareable between threads.
+    ///
+    /// Requires the `rayon` feature.
+    ///
+    /// [`from_vec`]: struct.BinaryHeap.html#method.from_vec
+    pub fn from_vec_par(vec: Vec<T>) -> Self {
+        BinaryHeap::from_vec_cmp_par(vec, C::default())
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T: Send, C: Compare<T> + Sync> BinaryHeap<T, C> {
+    /// Like [`from_vec_cmp`], but heapifies large vectors on the rayon thread
+    /// pool.
+    ///
+    /// Vectors shorter than an internal threshold are heapified sequentially,
+    /// since splitting the work would cost more than it saves. With the
+    /// `safe_only` feature, all vectors are, as sifting down from several
+    /// threads in one vector needs unsafe code.
+    ///
+    /// Requires the `rayon` feature.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::{BinaryHeap, MinComparator};
+    ///
+    /// let heap = BinaryHeap::from_vec_cmp_par((0..1_000_000).rev().collect(), MinComparator);
+    /// assert_eq!(heap.peek(), Some(&0));
+    /// ```
+    ///
+    /// [`from_vec_cmp`]: struct.BinaryHeap.html#method.from_vec_cmp
+    pub fn from_vec_cmp_par(vec: Vec<T>, cmp: C) -> Self {
+        let mut heap = BinaryHeap {
+            data: vec,
+            cmp,
+            growth: GrowthPolicy::Amortized,
+            #[cfg(feature = "alloc_guard")]
+            on_realloc: None,
+        };
+        if heap.len() < PARALLEL_REBUILD_THRESHOLD {
+            heap.rebuild();
+        } else {
+            heap.rebuild_parallel();
+        }
+        heap
+    }
+
+    /// Consumes the `BinaryHeap` and returns a vector in sorted
+    /// (ascending) order, sorting on the rayon thread pool.
+    ///
+    /// This ignores the heap structure and sorts the backing vector with a
+    /// parallel unstable sort, which is much faster than [`into_sorted_vec`]
+    /// for large heaps.
+    ///
+    /// Requires the `rayon` feature.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    ///
+    /// let heap = BinaryHeap::from([1, 5, 2, 4, 3]);
+    /// assert_eq!(heap.par_into_sorted_vec(), [1, 2, 3, 4, 5]);
+    /// ```
+    ///
+    /// [`into_sorted_vec`]: struct.BinaryHeap.html#method.into_sorted_vec
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn par_into_sorted_vec(self) -> Vec<T> {
+        let BinaryHeap { mut data, cmp, .. } = self;
+        data.par_sort_unstable_by(|a, b| cmp.compare(a, b));
+        data
+    }
+
+    /// Heapifies the data level by level, deepest level first, sifting down
+    /// the nodes of each level in parallel.
+    #[cfg(not(feature = "safe_only"))]
+    fn rebuild_parallel(&mut self) {
+        let len = self.data.len();
+        let internal = len / 2;
+        if internal == 0 {
+            return;
+        }
+        let data = SharedSlice(self.data.as_mut_ptr());
+        let cmp = &self.cmp;
+        // Node `i` is on level log2(i + 1), so the last internal node,
+        // `internal - 1`, is on level log2(internal).
+        let mut level = (usize::BITS - internal.leading_zeros() - 1) as usize;
+        loop {
+            let start = (1 << level) - 1;
+            let end = ((1 << (level + 1)) - 1).min(internal);
+            (start..end)
+                .into_par_iter()
+                .with_min_len(PARALLEL_REBUILD_MIN_NODES)
+                .for_each(|pos| {
+                    // SAFETY: the nodes of a level root disjoint subtrees and
+                    //  `sift_down_shared` only touches the subtree of `pos`,
+                    //  so no two threads access the same element. Every
+                    //  index involved is below `len`.
+                    unsafe { sift_down_shared(data.ptr(), len, pos, cmp) };
+                });
+            if level == 0 {
+                break;
+            }
+            level -= 1;
+        }
+    }
+
+    #[cfg(feature = "safe_only")]
+    fn rebuild_parallel(&mut self) {
+        self.rebuild();
+    }
+}
+
 impl<T, C: Compare<T>> BinaryHeap<T, C> {
     /// Generic constructor for `BinaryHeap` from [`Vec`] and comparator.
     ///
@@ -442,7 +1319,53 @@ impl<T, C: Compare<T>> BinaryHeap<T, C> {
     ///
     /// [`Vec`]: https://doc.rust-lang.org/stable/std/vec/struct.Vec.html
     pub fn from_vec_cmp(vec: Vec<T>, cmp: C) -> Self {
-        unsafe { BinaryHeap::from_vec_cmp_raw(vec, cmp, true) }
+        BinaryHeap::from_parts(vec, cmp, true)
+    }
+
+    /// Creates an empty `BinaryHeap` ordered by `cmp`.
+    ///
+    /// This generic version takes any comparator instance, such as a
+    /// comparator struct with state of its own.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::{BinaryHeap, KeyComparator};
+    /// let mut heap = BinaryHeap::new_with_cmp(KeyComparator(|k: &i32| k % 4));
+    /// heap.push(6);
+    /// heap.push(3);
+    /// heap.push(1);
+    /// assert_eq!(heap.pop(), Some(3));
+    /// ```
+    #[must_use]
+    pub fn new_with_cmp(cmp: C) -> Self {
+        BinaryHeap::from_parts(Vec::new(), cmp, false)
+    }
+
+    /// Creates an empty `BinaryHeap` ordered by `cmp` with a specific
+    /// capacity.
+    /// This preallocates enough memory for `capacity` elements,
+    /// so that the `BinaryHeap` does not have to be reallocated
+    /// until it contains at least that many values.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::{BinaryHeap, MinComparator};
+    /// let mut heap = BinaryHeap::with_capacity_and_cmp(10, MinComparator);
+    /// assert_eq!(heap.capacity(), 10);
+    /// heap.push(3);
+    /// heap.push(1);
+    /// heap.push(5);
+    /// assert_eq!(heap.pop(), Some(1));
+    /// ```
+    #[must_use]
+    pub fn with_capacity_and_cmp(capacity: usize, cmp: C) -> Self {
+        BinaryHeap::from_parts(Vec::with_capacity(capacity), cmp, false)
     }
 
     /// Generic constructor for `BinaryHeap` from [`Vec`] and comparator.
@@ -454,8 +1377,34 @@ impl<T, C: Compare<T>> BinaryHeap<T, C> {
     /// User is responsible for providing valid `rebuild` value.
     ///
     /// [`Vec`]: https://doc.rust-lang.org/stable/std/vec/struct.Vec.html
+    #[cfg(not(feature = "safe_only"))]
     pub unsafe fn from_vec_cmp_raw(vec: Vec<T>, cmp: C, rebuild: bool) -> Self {
-        let mut heap = BinaryHeap { data: vec, cmp };
+        BinaryHeap::from_parts(vec, cmp, rebuild)
+    }
+
+    /// Generic constructor for `BinaryHeap` from [`Vec`] and comparator.
+    ///
+    /// Because `BinaryHeap` stores the elements in its internal `Vec`,
+    /// it's natural to construct it from `Vec`.
+    ///
+    /// The `safe_only` feature, which forbids unsafe code, makes this
+    /// function safe; calls in `unsafe` blocks still compile. A wrong
+    /// `rebuild` value only breaks the order of the heap.
+    ///
+    /// [`Vec`]: https://doc.rust-lang.org/stable/std/vec/struct.Vec.html
+    #[cfg(feature = "safe_only")]
+    pub fn from_vec_cmp_raw(vec: Vec<T>, cmp: C, rebuild: bool) -> Self {
+        BinaryHeap::from_parts(vec, cmp, rebuild)
+    }
+
+    fn from_parts(vec: Vec<T>, cmp: C, rebuild: bool) -> Self {
+        let mut heap = BinaryHeap {
+            data: vec,
+            cmp,
+            growth: GrowthPolicy::Amortized,
+            #[cfg(feature = "alloc_guard")]
+            on_realloc: None,
+        };
         if rebuild && !heap.data.is_empty() {
             heap.rebuild();
         }
@@ -663,8 +1612,14 @@ where
     }
 }
 
-impl<T, C: Compare<T>> BinaryHeap<T, C> {
-    /// Replaces the comparator of binary heap.
+impl<T, F> BinaryHeap<T, MinFnComparator<F>>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    /// Creates an empty `BinaryHeap`.
+    ///
+    /// The `_min_by()` version will create a heap that pops the least element
+    /// according to given closure first.
     ///
     /// # Examples
     ///
@@ -672,53 +1627,24 @@ impl<T, C: Compare<T>> BinaryHeap<T, C> {
     ///
     /// ```
     /// use binary_heap_plus::BinaryHeap;
-    /// use compare::Compare;
-    /// use std::cmp::Ordering;
-    ///
-    /// struct Comparator {
-    ///     ascending: bool
-    /// }
-    ///
-    /// impl Compare<i32> for Comparator {
-    ///     fn compare(&self,l: &i32,r: &i32) -> Ordering {
-    ///         if self.ascending {
-    ///             r.cmp(l)
-    ///         } else {
-    ///             l.cmp(r)
-    ///         }
-    ///     }
-    /// }
-    ///
-    /// // construct a heap in ascending order.
-    /// let mut heap = BinaryHeap::from_vec_cmp(vec![3, 1, 5], Comparator { ascending: true });
-    ///
-    /// // replace the comparor
-    /// heap.replace_cmp(Comparator { ascending: false });
-    /// assert_eq!(heap.into_iter_sorted().collect::<Vec<_>>(), [5, 3, 1]);
+    /// let mut heap = BinaryHeap::new_min_by(|a: &(i32, char), b: &(i32, char)| a.0.cmp(&b.0));
+    /// heap.push((3, 'c'));
+    /// heap.push((1, 'a'));
+    /// heap.push((5, 'e'));
+    /// assert_eq!(heap.pop(), Some((1, 'a')));
     /// ```
-    #[inline]
-    pub fn replace_cmp(&mut self, cmp: C) {
-        unsafe {
-            self.replace_cmp_raw(cmp, true);
-        }
-    }
-
-    /// Replaces the comparator of binary heap.
-    ///
-    /// # Safety
-    /// User is responsible for providing valid `rebuild` value.
-    pub unsafe fn replace_cmp_raw(&mut self, cmp: C, rebuild: bool) {
-        self.cmp = cmp;
-        if rebuild && !self.data.is_empty() {
-            self.rebuild();
-        }
+    #[must_use]
+    pub fn new_min_by(f: F) -> Self {
+        BinaryHeap::from_vec_cmp(vec![], MinFnComparator(f))
     }
 
-    /// Returns a mutable reference to the greatest item in the binary heap, or
-    /// `None` if it is empty.
+    /// Creates an empty `BinaryHeap` with a specific capacity.
+    /// This preallocates enough memory for `capacity` elements,
+    /// so that the `BinaryHeap` does not have to be reallocated
+    /// until it contains at least that many values.
     ///
-    /// Note: If the `PeekMut` value is leaked, the heap may be in an
-    /// inconsistent state.
+    /// The `_min_by()` version will create a heap that pops the least element
+    /// according to given closure first.
     ///
     /// # Examples
     ///
@@ -726,20 +1652,264 @@ impl<T, C: Compare<T>> BinaryHeap<T, C> {
     ///
     /// ```
     /// use binary_heap_plus::BinaryHeap;
-    /// let mut heap = BinaryHeap::new();
-    /// assert!(heap.peek_mut().is_none());
-    ///
+    /// let mut heap = BinaryHeap::with_capacity_min_by(10, |a: &i32, b: &i32| a.cmp(b));
+    /// assert_eq!(heap.capacity(), 10);
+    /// heap.push(3);
     /// heap.push(1);
     /// heap.push(5);
-    /// heap.push(2);
-    /// {
-    ///     let mut val = heap.peek_mut().unwrap();
-    ///     *val = 0;
-    /// }
-    /// assert_eq!(heap.peek(), Some(&2));
+    /// assert_eq!(heap.pop(), Some(1));
     /// ```
+    #[must_use]
+    pub fn with_capacity_min_by(capacity: usize, f: F) -> Self {
+        BinaryHeap::from_vec_cmp(Vec::with_capacity(capacity), MinFnComparator(f))
+    }
+}
+
+impl<T, F, K: Ord> BinaryHeap<T, MinKeyComparator<F>>
+where
+    F: Fn(&T) -> K,
+{
+    /// Creates an empty `BinaryHeap`.
     ///
-    /// # Time complexity
+    /// The `_min_by_key()` version will create a heap that pops the element
+    /// with the least key converted by given closure first.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    /// let mut heap = BinaryHeap::new_min_by_key(|a: &i32| a % 4);
+    /// heap.push(3);
+    /// heap.push(1);
+    /// heap.push(6);
+    /// assert_eq!(heap.pop(), Some(1));
+    /// ```
+    #[must_use]
+    pub fn new_min_by_key(f: F) -> Self {
+        BinaryHeap::from_vec_cmp(vec![], MinKeyComparator(f))
+    }
+
+    /// Creates an empty `BinaryHeap` with a specific capacity.
+    /// This preallocates enough memory for `capacity` elements,
+    /// so that the `BinaryHeap` does not have to be reallocated
+    /// until it contains at least that many values.
+    ///
+    /// The `_min_by_key()` version will create a heap that pops the element
+    /// with the least key converted by given closure first.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    /// let mut heap = BinaryHeap::with_capacity_min_by_key(10, |a: &i32| a % 4);
+    /// assert_eq!(heap.capacity(), 10);
+    /// heap.push(3);
+    /// heap.push(1);
+    /// heap.push(6);
+    /// assert_eq!(heap.pop(), Some(1));
+    /// ```
+    #[must_use]
+    pub fn with_capacity_min_by_key(capacity: usize, f: F) -> Self {
+        BinaryHeap::from_vec_cmp(Vec::with_capacity(capacity), MinKeyComparator(f))
+    }
+}
+
+impl<T, F, K: Ord + ?Sized> BinaryHeap<T, SortKeyComparator<F>>
+where
+    F: Fn(&T) -> &K,
+{
+    /// Creates an empty `BinaryHeap`.
+    ///
+    /// The `_by_sort_key()` version will create a heap ordered by a key
+    /// borrowed from each element by given closure, so comparisons never
+    /// allocate.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    /// struct Job {
+    ///     name: String,
+    /// }
+    /// let mut heap = BinaryHeap::new_by_sort_key(|j: &Job| j.name.as_str());
+    /// heap.push(Job { name: "build".to_string() });
+    /// heap.push(Job { name: "test".to_string() });
+    /// assert_eq!(heap.pop().unwrap().name, "test");
+    /// ```
+    #[must_use]
+    pub fn new_by_sort_key(f: F) -> Self {
+        BinaryHeap::from_vec_cmp(vec![], SortKeyComparator(f))
+    }
+
+    /// Creates an empty `BinaryHeap` with a specific capacity.
+    /// This preallocates enough memory for `capacity` elements,
+    /// so that the `BinaryHeap` does not have to be reallocated
+    /// until it contains at least that many values.
+    ///
+    /// The `_by_sort_key()` version will create a heap ordered by a key
+    /// borrowed from each element by given closure.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    /// let mut heap = BinaryHeap::with_capacity_by_sort_key(10, |v: &Vec<u8>| &v[..]);
+    /// assert_eq!(heap.capacity(), 10);
+    /// heap.push(vec![1, 2]);
+    /// heap.push(vec![3]);
+    /// assert_eq!(heap.pop(), Some(vec![3]));
+    /// ```
+    #[must_use]
+    pub fn with_capacity_by_sort_key(capacity: usize, f: F) -> Self {
+        BinaryHeap::from_vec_cmp(Vec::with_capacity(capacity), SortKeyComparator(f))
+    }
+}
+
+impl<T, C: Compare<T>> BinaryHeap<T, C> {
+    /// Replaces the comparator of binary heap.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    /// use compare::Compare;
+    /// use std::cmp::Ordering;
+    ///
+    /// struct Comparator {
+    ///     ascending: bool
+    /// }
+    ///
+    /// impl Compare<i32> for Comparator {
+    ///     fn compare(&self,l: &i32,r: &i32) -> Ordering {
+    ///         if self.ascending {
+    ///             r.cmp(l)
+    ///         } else {
+    ///             l.cmp(r)
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// // construct a heap in ascending order.
+    /// let mut heap = BinaryHeap::from_vec_cmp(vec![3, 1, 5], Comparator { ascending: true });
+    ///
+    /// // replace the comparor
+    /// heap.replace_cmp(Comparator { ascending: false });
+    /// assert_eq!(heap.into_iter_sorted().collect::<Vec<_>>(), [5, 3, 1]);
+    /// ```
+    #[inline]
+    pub fn replace_cmp(&mut self, cmp: C) {
+        self.set_cmp(cmp, true);
+    }
+
+    /// Replaces the comparator of binary heap.
+    ///
+    /// # Safety
+    /// User is responsible for providing valid `rebuild` value.
+    #[cfg(not(feature = "safe_only"))]
+    pub unsafe fn replace_cmp_raw(&mut self, cmp: C, rebuild: bool) {
+        self.set_cmp(cmp, rebuild);
+    }
+
+    /// Replaces the comparator of binary heap.
+    ///
+    /// The `safe_only` feature, which forbids unsafe code, makes this
+    /// function safe; calls in `unsafe` blocks still compile. A wrong
+    /// `rebuild` value only breaks the order of the heap.
+    #[cfg(feature = "safe_only")]
+    pub fn replace_cmp_raw(&mut self, cmp: C, rebuild: bool) {
+        self.set_cmp(cmp, rebuild);
+    }
+
+    fn set_cmp(&mut self, cmp: C, rebuild: bool) {
+        self.cmp = cmp;
+        if rebuild && !self.data.is_empty() {
+            self.rebuild();
+        }
+    }
+
+    /// Returns a guard giving mutable access to the comparator. When the
+    /// guard is dropped, the heap is rebuilt if the comparator may have been
+    /// changed.
+    ///
+    /// This re-tunes a comparator that holds state, such as weights, in
+    /// place, where [`replace_cmp`] would need a whole new comparator.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    /// use compare::Compare;
+    /// use std::cmp::Ordering;
+    ///
+    /// struct Weighted {
+    ///     weights: [i32; 2],
+    /// }
+    ///
+    /// impl Compare<[i32; 2]> for Weighted {
+    ///     fn compare(&self, a: &[i32; 2], b: &[i32; 2]) -> Ordering {
+    ///         let score = |x: &[i32; 2]| x[0] * self.weights[0] + x[1] * self.weights[1];
+    ///         score(a).cmp(&score(b))
+    ///     }
+    /// }
+    ///
+    /// let cmp = Weighted { weights: [1, 0] };
+    /// let mut heap = BinaryHeap::from_vec_cmp(vec![[3, 1], [1, 5]], cmp);
+    /// assert_eq!(heap.peek(), Some(&[3, 1]));
+    ///
+    /// heap.cmp_mut().weights = [0, 1];
+    /// assert_eq!(heap.peek(), Some(&[1, 5]));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*n*) for the rebuild, unless the guard was only read.
+    ///
+    /// [`replace_cmp`]: BinaryHeap::replace_cmp
+    pub fn cmp_mut(&mut self) -> CmpMut<'_, T, C> {
+        CmpMut {
+            heap: self,
+            rebuild: false,
+        }
+    }
+
+    /// Returns a mutable reference to the greatest item in the binary heap, or
+    /// `None` if it is empty.
+    ///
+    /// Note: If the `PeekMut` value is leaked, the heap may be in an
+    /// inconsistent state.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    /// let mut heap = BinaryHeap::new();
+    /// assert!(heap.peek_mut().is_none());
+    ///
+    /// heap.push(1);
+    /// heap.push(5);
+    /// heap.push(2);
+    /// {
+    ///     let mut val = heap.peek_mut().unwrap();
+    ///     *val = 0;
+    /// }
+    /// assert_eq!(heap.peek(), Some(&2));
+    /// ```
+    ///
+    /// # Time complexity
     ///
     /// If the item is modified then the worst case time complexity is *O*(log(*n*)),
     /// otherwise it's *O*(1).
@@ -755,6 +1925,128 @@ impl<T, C: Compare<T>> BinaryHeap<T, C> {
         }
     }
 
+    /// Returns a guard giving mutable access to every item, in arbitrary
+    /// order. When the guard is dropped, the heap is rebuilt if any item may
+    /// have been changed.
+    ///
+    /// Iterate over `&mut` the guard to visit the items, for instance to
+    /// re-score every queued entry at once, which costs a single *O*(*n*)
+    /// rebuild instead of taking the heap apart with [`into_vec`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    ///
+    /// let mut heap = BinaryHeap::from(vec![(3, 'a'), (1, 'b'), (2, 'c')]);
+    /// for (score, name) in &mut heap.iter_mut() {
+    ///     if *name != 'a' {
+    ///         *score *= 10;
+    ///     }
+    /// }
+    /// assert_eq!(heap.pop(), Some((20, 'c')));
+    ///
+    /// // The guard also works as a slice.
+    /// let mut items = heap.iter_mut();
+    /// items.sort_unstable();
+    /// items[0].0 = 30;
+    /// drop(items);
+    /// assert_eq!(heap.peek(), Some(&(30, 'a')));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*n*) for the rebuild, unless the guard was only read.
+    ///
+    /// [`into_vec`]: BinaryHeap::into_vec
+    pub fn iter_mut(&mut self) -> SliceMut<'_, T, C> {
+        SliceMut {
+            heap: self,
+            rebuild: false,
+        }
+    }
+
+    /// Returns a guard that derefs to the underlying vector as a mutable
+    /// slice, in arbitrary order. When the guard is dropped, the heap is
+    /// rebuilt if the slice may have been changed.
+    ///
+    /// This is the same guard as [`iter_mut`] returns, for passing the items
+    /// to code that takes a `&mut [T]`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    ///
+    /// fn halve(scores: &mut [u32]) {
+    ///     for score in scores {
+    ///         *score /= 2;
+    ///     }
+    /// }
+    ///
+    /// let mut heap = BinaryHeap::from(vec![4, 9, 6]);
+    /// halve(&mut heap.as_mut_slice());
+    /// assert_eq!(heap.into_sorted_vec(), [2, 3, 4]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*n*) for the rebuild, unless the guard was only read.
+    ///
+    /// [`iter_mut`]: BinaryHeap::iter_mut
+    pub fn as_mut_slice(&mut self) -> SliceMut<'_, T, C> {
+        self.iter_mut()
+    }
+
+    /// Calls `f` with the underlying vector, then rebuilds the heap once,
+    /// unless `f` returns `false`.
+    ///
+    /// Within `f` the vector can be changed freely: items can be edited,
+    /// added, removed or reordered. Return `false` only if the vector is
+    /// still a valid heap, for instance because `f` merely inspected it or
+    /// truncated it, to skip the *O*(*n*) rebuild. With the `strict_asserts`
+    /// feature, a wrong `false` panics.
+    ///
+    /// If `f` panics, the heap keeps whatever the vector holds then, in no
+    /// particular order. If the capacity is frozen (see `freeze_capacity`)
+    /// and `f` grew the vector's buffer, this panics once `f` returns.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    ///
+    /// let mut heap = BinaryHeap::from(vec![3, 8, 1, 6]);
+    /// heap.batch_mut(|items| {
+    ///     items.retain(|&x| x != 8);
+    ///     items.extend(vec![7, 2]);
+    ///     for x in items.iter_mut() {
+    ///         *x *= 10;
+    ///     }
+    ///     true
+    /// });
+    /// assert_eq!(heap.into_sorted_vec(), [10, 20, 30, 60, 70]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*n*) for the rebuild, plus the cost of `f`.
+    pub fn batch_mut<F: FnOnce(&mut Vec<T>) -> bool>(&mut self, f: F) {
+        let capacity = self.data.capacity();
+        let rebuild = f(&mut self.data);
+        self.alloc_check_from(capacity, self.data.capacity());
+        if rebuild {
+            self.rebuild();
+        }
+        self.strict_check("batch_mut");
+    }
+
     /// Removes the greatest item from the binary heap and returns it, or `None` if it
     /// is empty.
     ///
@@ -776,14 +2068,22 @@ impl<T, C: Compare<T>> BinaryHeap<T, C> {
     /// The worst case cost of `pop` on a heap containing *n* elements is *O*(log(*n*)).
     // #[stable(feature = "rust1", since = "1.0.0")]
     pub fn pop(&mut self) -> Option<T> {
-        self.data.pop().map(|mut item| {
+        let item = self.data.pop().map(|mut item| {
             if !self.is_empty() {
                 swap(&mut item, &mut self.data[0]);
-                // SAFETY: !self.is_empty() means that self.len() > 0
-                unsafe { self.sift_down_to_bottom(0) };
+                if mem::size_of::<T>() > POP_SIFT_TO_BOTTOM_MAX_SIZE {
+                    self.sift_down(0);
+                } else {
+                    self.sift_down_to_bottom(0);
+                }
             }
             item
-        })
+        });
+        if item.is_some() {
+            self.wipe_vacated(1);
+        }
+        self.strict_check("pop");
+        item
     }
 
     /// Pushes an item onto the binary heap.
@@ -821,14 +2121,25 @@ impl<T, C: Compare<T>> BinaryHeap<T, C> {
     // #[stable(feature = "rust1", since = "1.0.0")]
     pub fn push(&mut self, item: T) {
         let old_len = self.len();
+        if old_len == self.data.capacity() {
+            self.alloc_check(old_len + 1);
+            self.grow();
+        }
         self.data.push(item);
-        // SAFETY: Since we pushed a new item it means that
-        //  old_len = self.len() - 1 < self.len()
-        unsafe { self.sift_up(0, old_len) };
+        self.sift_up(0, old_len);
+        self.strict_check("push");
     }
 
-    /// Consumes the `BinaryHeap` and returns a vector in sorted
-    /// (ascending) order.
+    /// Pushes an item while keeping at most `k` elements, for top-k
+    /// selection on a plain heap.
+    ///
+    /// If the heap has fewer than `k` elements, the item is pushed and `None`
+    /// is returned. Otherwise the item only goes in if it is less than the
+    /// greatest element, which it replaces; the element that does not make
+    /// the cut, either that greatest element or the item itself, is returned.
+    /// The heap thus keeps the `k` least elements pushed, so use a min-heap to
+    /// keep the `k` greatest. A heap already holding more than `k` elements is
+    /// not shrunk.
     ///
     /// # Examples
     ///
@@ -837,33 +2148,333 @@ impl<T, C: Compare<T>> BinaryHeap<T, C> {
     /// ```
     /// use binary_heap_plus::BinaryHeap;
     ///
-    /// let mut heap = BinaryHeap::from([1, 2, 4, 5, 7]);
-    /// heap.push(6);
-    /// heap.push(3);
-    ///
-    /// let vec = heap.into_sorted_vec();
-    /// assert_eq!(vec, [1, 2, 3, 4, 5, 6, 7]);
+    /// let mut heap = BinaryHeap::new_min();
+    /// for x in [5, 1, 8, 3, 9] {
+    ///     heap.push_with_limit(x, 3);
+    /// }
+    /// assert_eq!(heap.push_with_limit(2, 3), Some(2));
+    /// assert_eq!(heap.push_with_limit(7, 3), Some(5));
+    /// assert_eq!(heap.into_sorted_vec(), [9, 8, 7]);
     /// ```
-    #[must_use = "`self` will be dropped if the result is not used"]
-    // #[stable(feature = "binary_heap_extras_15", since = "1.5.0")]
-    pub fn into_sorted_vec(mut self) -> Vec<T> {
-        let mut end = self.len();
-        while end > 1 {
-            end -= 1;
-            // SAFETY: `end` goes from `self.len() - 1` to 1 (both included),
-            //  so it's always a valid index to access.
-            //  It is safe to access index 0 (i.e. `ptr`), because
-            //  1 <= end < self.len(), which means self.len() >= 2.
-            unsafe {
-                let ptr = self.data.as_mut_ptr();
-                ptr::swap(ptr, ptr.add(end));
-            }
-            // SAFETY: `end` goes from `self.len() - 1` to 1 (both included) so:
-            //  0 < 1 <= end <= self.len() - 1 < self.len()
-            //  Which means 0 < end and end < self.len().
-            unsafe { self.sift_down_range(0, end) };
-        }
-        self.into_vec()
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*k*)).
+    pub fn push_with_limit(&mut self, item: T, k: usize) -> Option<T> {
+        if self.len() < k {
+            self.push(item);
+            return None;
+        }
+        match self.data.first() {
+            Some(top) if self.cmp.compares_lt(&item, top) => {}
+            _ => return Some(item),
+        }
+        let old = mem::replace(&mut self.data[0], item);
+        self.sift_down(0);
+        self.strict_check("push_with_limit");
+        Some(old)
+    }
+
+    /// Returns the least element, the one that would be popped last, or
+    /// `None` if the heap is empty.
+    ///
+    /// Only the leaves can hold the least element, so only the second half
+    /// of the backing vector is scanned.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    /// let heap = BinaryHeap::from(vec![4, 9, 1, 7]);
+    /// assert_eq!(heap.worst(), Some(&1));
+    /// assert_eq!(heap.peek(), Some(&9));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*n*/2).
+    #[must_use]
+    pub fn worst(&self) -> Option<&T> {
+        self.data[self.len() / 2..]
+            .iter()
+            .min_by(|a, b| self.cmp.compare(a, b))
+    }
+
+    /// Returns the number of elements equal to `item`.
+    ///
+    /// Equality is decided by `PartialEq`, and the comparator is used to skip
+    /// the subtrees whose root is less than `item`, since their elements can
+    /// only be less too. The comparator must therefore rank equal elements as
+    /// equal, as all the comparators of this crate do for consistent `Ord`
+    /// and `PartialEq` implementations.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    ///
+    /// let heap = BinaryHeap::from(vec!["retry", "send", "retry", "ack"]);
+    /// assert_eq!(heap.count_of(&"retry"), 2);
+    /// assert_eq!(heap.count_of(&"nack"), 0);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*n*) in the worst case, but only the elements not less than `item`
+    /// and their children are visited.
+    #[must_use]
+    pub fn count_of(&self, item: &T) -> usize
+    where
+        T: PartialEq,
+    {
+        let mut count = 0;
+        let mut stack = Vec::new();
+        if !self.data.is_empty() {
+            stack.push(0);
+        }
+        while let Some(i) = stack.pop() {
+            let x = &self.data[i];
+            if self.cmp.compares_lt(x, item) {
+                continue;
+            }
+            if x == item {
+                count += 1;
+            }
+            stack.extend((2 * i + 1..2 * i + 3).filter(|&c| c < self.data.len()));
+        }
+        count
+    }
+
+    /// Replaces the element at `index` with `item`, moves `item` to its
+    /// place in the heap, and returns the old element.
+    ///
+    /// Together with [`position_of`] this changes the priority of any
+    /// element, for instance to implement decrease-key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    ///
+    /// let mut heap = BinaryHeap::from(vec![1, 5, 3]);
+    /// let i = heap.position_of(&1).unwrap();
+    /// assert_eq!(heap.replace_at(i, 9), 1);
+    /// assert_eq!(heap.into_sorted_vec(), [3, 5, 9]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)).
+    ///
+    /// [`position_of`]: BinaryHeap::position_of
+    pub fn replace_at(&mut self, index: usize, item: T) -> T {
+        let old = mem::replace(&mut self.data[index], item);
+        if self.sift_up(0, index) == index {
+            self.sift_down(index);
+        }
+        self.strict_check("replace_at");
+        old
+    }
+
+    /// Removes the element at `index` and returns it.
+    ///
+    /// The last element takes its place and is moved up or down as needed.
+    /// Together with [`position_of`] this removes a given element.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    ///
+    /// let mut heap = BinaryHeap::from(vec![1, 5, 3, 4]);
+    /// let i = heap.position_of(&3).unwrap();
+    /// assert_eq!(heap.swap_remove_index(i), 3);
+    /// assert_eq!(heap.into_sorted_vec(), [1, 4, 5]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)).
+    ///
+    /// [`position_of`]: BinaryHeap::position_of
+    pub fn swap_remove_index(&mut self, index: usize) -> T {
+        let item = self.data.swap_remove(index);
+        self.wipe_vacated(1);
+        // The removed element may have been the last one.
+        if index < self.len() && self.sift_up(0, index) == index {
+            self.sift_down(index);
+        }
+        self.strict_check("swap_remove_index");
+        item
+    }
+
+    /// Moves the element at `index` up the heap while it is greater than its
+    /// parent, and returns its new index.
+    ///
+    /// Call this after increasing an element in place. Everything but that
+    /// element must satisfy the heap property.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)).
+    pub fn sift_up_at(&mut self, index: usize) -> usize {
+        assert!(
+            index < self.len(),
+            "index {} out of bounds for a heap of length {}",
+            index,
+            self.len()
+        );
+        self.sift_up(0, index)
+    }
+
+    /// Moves the element at `index` down the heap while one of its children
+    /// is greater, and returns its new index.
+    ///
+    /// Call this after decreasing an element in place. Everything but that
+    /// element must satisfy the heap property.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    /// use std::cell::Cell;
+    ///
+    /// // Task ids ordered by priorities kept outside the heap.
+    /// let priorities = [Cell::new(3), Cell::new(7), Cell::new(5)];
+    /// let mut heap = BinaryHeap::new_by_key(|&id: &usize| priorities[id].get());
+    /// heap.extend(0..3);
+    /// assert_eq!(heap.peek(), Some(&1));
+    ///
+    /// priorities[1].set(0);
+    /// let i = heap.position_of(&1).unwrap();
+    /// heap.sift_down_at(i);
+    /// assert_eq!(heap.peek(), Some(&2));
+    ///
+    /// priorities[0].set(9);
+    /// let i = heap.position_of(&0).unwrap();
+    /// assert_eq!(heap.sift_up_at(i), 0);
+    /// assert_eq!(heap.into_sorted_vec(), [1, 2, 0]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)).
+    pub fn sift_down_at(&mut self, index: usize) -> usize {
+        assert!(
+            index < self.len(),
+            "index {} out of bounds for a heap of length {}",
+            index,
+            self.len()
+        );
+        self.sift_down(index)
+    }
+
+    /// Panics if the heap property does not hold, naming the operation that
+    /// just ran. Does nothing unless the `strict_asserts` feature is enabled.
+    #[inline(always)]
+    fn strict_check(&self, op: &str) {
+        #[cfg(feature = "strict_asserts")]
+        for i in 1..self.data.len() {
+            let parent = (i - 1) / 2;
+            if self.cmp.compares_lt(&self.data[parent], &self.data[i]) {
+                panic!(
+                    "heap property violated after `{}`: the element at {} is greater than its parent at {}",
+                    op, i, parent
+                );
+            }
+        }
+        #[cfg(not(feature = "strict_asserts"))]
+        let _ = op;
+    }
+
+    /// Makes room for at least one more element according to the growth
+    /// policy.
+    fn grow(&mut self) {
+        if let Some(additional) = self.growth_step() {
+            self.data.reserve_exact(additional);
+        }
+    }
+
+    /// Returns how many elements the growth policy reserves room for when
+    /// the buffer is full, or `None` to let `Vec` decide.
+    fn growth_step(&self) -> Option<usize> {
+        let cap = self.data.capacity();
+        match self.growth {
+            GrowthPolicy::Amortized => None,
+            GrowthPolicy::Exact => Some(1),
+            GrowthPolicy::Fixed(n) => Some(n.max(1)),
+            GrowthPolicy::Custom(f) => Some(f(cap).saturating_sub(cap).max(1)),
+        }
+    }
+
+    /// Consumes the `BinaryHeap` and returns a vector in sorted
+    /// (ascending) order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    ///
+    /// let mut heap = BinaryHeap::from([1, 2, 4, 5, 7]);
+    /// heap.push(6);
+    /// heap.push(3);
+    ///
+    /// let vec = heap.into_sorted_vec();
+    /// assert_eq!(vec, [1, 2, 3, 4, 5, 6, 7]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*n* log(*n*)). All but tiny heaps are sorted with
+    /// [`slice::sort_unstable_by`], which is considerably faster than
+    /// popping the elements in heap order.
+    ///
+    /// [`slice::sort_unstable_by`]: https://doc.rust-lang.org/stable/std/primitive.slice.html#method.sort_unstable_by
+    #[must_use = "`self` will be dropped if the result is not used"]
+    // #[stable(feature = "binary_heap_extras_15", since = "1.5.0")]
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        if self.len() >= SORT_INTO_VEC_THRESHOLD {
+            // Pattern-defeating quicksort beats heapsort by far on larger
+            // inputs, despite ignoring the existing heap order.
+            let BinaryHeap { mut data, cmp, .. } = self;
+            data.sort_unstable_by(|a, b| cmp.compare(a, b));
+            return data;
+        }
+        let mut end = self.len();
+        while end > 1 {
+            end -= 1;
+            self.data.swap(0, end);
+            self.sift_down_range(0, end);
+        }
+        self.into_vec()
     }
 
     // The implementations of sift_up and sift_down use unsafe blocks in
@@ -874,13 +2485,23 @@ impl<T, C: Compare<T>> BinaryHeap<T, C> {
     // the hole is filled back at the end of its scope, even on panic.
     // Using a hole reduces the constant factor compared to using swaps,
     // which involves twice as many moves.
+    //
+    // With the `safe_only` feature, they swap elements instead, like
+    // `sift_up_slice` and `sift_down_slice`, and do not prefetch.
 
-    /// # Safety
+    /// Take an element at `pos` and move it up the heap, but not above
+    /// `start`, while it is greater than its parent.
+    ///
+    /// Returns the new position of the element.
+    ///
+    /// # Panics
     ///
-    /// The caller must guarantee that `pos < self.len()`.
-    unsafe fn sift_up(&mut self, start: usize, pos: usize) -> usize {
+    /// Panics if `pos` is out of bounds.
+    #[cfg(not(feature = "safe_only"))]
+    fn sift_up(&mut self, start: usize, pos: usize) -> usize {
+        assert!(pos < self.len());
         // Take out the value at `pos` and create a hole.
-        // SAFETY: The caller guarantees that pos < self.len()
+        // SAFETY: pos < self.len() was checked above.
         let mut hole = unsafe { Hole::new(&mut self.data, pos) };
 
         while hole.pos() > start {
@@ -904,19 +2525,40 @@ impl<T, C: Compare<T>> BinaryHeap<T, C> {
         hole.pos()
     }
 
+    #[cfg(feature = "safe_only")]
+    fn sift_up(&mut self, start: usize, mut pos: usize) -> usize {
+        while pos > start {
+            let parent = (pos - 1) / 2;
+            if self.cmp.compares_le(&self.data[pos], &self.data[parent]) {
+                break;
+            }
+            self.data.swap(pos, parent);
+            pos = parent;
+        }
+        pos
+    }
+
     /// Take an element at `pos` and move it down the heap,
     /// while its children are larger.
     ///
-    /// # Safety
+    /// Returns the new position of the element.
+    ///
+    /// # Panics
     ///
-    /// The caller must guarantee that `pos < end <= self.len()`.
-    unsafe fn sift_down_range(&mut self, pos: usize, end: usize) {
-        // SAFETY: The caller guarantees that pos < end <= self.len().
+    /// Panics unless `pos < end <= self.len()`.
+    #[cfg(not(feature = "safe_only"))]
+    fn sift_down_range(&mut self, pos: usize, end: usize) -> usize {
+        assert!(pos < end && end <= self.len());
+        // SAFETY: pos < end <= self.len() was checked above.
         let mut hole = unsafe { Hole::new(&mut self.data, pos) };
         let mut child = 2 * hole.pos() + 1;
+        let prefetch = end.saturating_mul(mem::size_of::<T>()) >= PREFETCH_MIN_BYTES;
 
         // Loop invariant: child == 2 * hole.pos() + 1.
         while child <= end.saturating_sub(2) {
+            if prefetch {
+                hole.prefetch_grandchildren(child);
+            }
             // compare with the greater of the two children
             // SAFETY: child < end - 1 < self.len() and
             //  child + 1 < end <= self.len(), so they're valid indexes.
@@ -933,7 +2575,7 @@ impl<T, C: Compare<T>> BinaryHeap<T, C> {
                 .cmp
                 .compares_ge(hole.element(), unsafe { hole.get(child) })
             {
-                return;
+                return hole.pos();
             }
 
             // SAFETY: same as above.
@@ -952,16 +2594,38 @@ impl<T, C: Compare<T>> BinaryHeap<T, C> {
             //  child == 2 * hole.pos() + 1 != hole.pos().
             unsafe { hole.move_to(child) };
         }
+
+        hole.pos()
     }
 
-    /// # Safety
+    #[cfg(feature = "safe_only")]
+    fn sift_down_range(&mut self, mut pos: usize, end: usize) -> usize {
+        loop {
+            let mut child = 2 * pos + 1;
+            if child >= end {
+                return pos;
+            }
+            if child + 1 < end
+                && self
+                    .cmp
+                    .compares_le(&self.data[child], &self.data[child + 1])
+            {
+                child += 1;
+            }
+            if self.cmp.compares_ge(&self.data[pos], &self.data[child]) {
+                return pos;
+            }
+            self.data.swap(pos, child);
+            pos = child;
+        }
+    }
+
+    /// # Panics
     ///
-    /// The caller must guarantee that `pos < self.len()`.
-    unsafe fn sift_down(&mut self, pos: usize) {
+    /// Panics if `pos` is out of bounds.
+    fn sift_down(&mut self, pos: usize) -> usize {
         let len = self.len();
-        // SAFETY: pos < len is guaranteed by the caller and
-        //  obviously len = self.len() <= self.len().
-        unsafe { self.sift_down_range(pos, len) };
+        self.sift_down_range(pos, len)
     }
 
     /// Take an element at `pos` and move it all the way down the heap,
@@ -970,19 +2634,25 @@ impl<T, C: Compare<T>> BinaryHeap<T, C> {
     /// Note: This is faster when the element is known to be large / should
     /// be closer to the bottom.
     ///
-    /// # Safety
+    /// # Panics
     ///
-    /// The caller must guarantee that `pos < self.len()`.
-    unsafe fn sift_down_to_bottom(&mut self, mut pos: usize) {
+    /// Panics if `pos` is out of bounds.
+    #[cfg(not(feature = "safe_only"))]
+    fn sift_down_to_bottom(&mut self, mut pos: usize) {
         let end = self.len();
         let start = pos;
 
-        // SAFETY: The caller guarantees that pos < self.len().
+        assert!(pos < end);
+        // SAFETY: pos < self.len() was checked above.
         let mut hole = unsafe { Hole::new(&mut self.data, pos) };
         let mut child = 2 * hole.pos() + 1;
+        let prefetch = end.saturating_mul(mem::size_of::<T>()) >= PREFETCH_MIN_BYTES;
 
         // Loop invariant: child == 2 * hole.pos() + 1.
         while child <= end.saturating_sub(2) {
+            if prefetch {
+                hole.prefetch_grandchildren(child);
+            }
             // SAFETY: child < end - 1 < self.len() and
             //  child + 1 < end <= self.len(), so they're valid indexes.
             //  child == 2 * hole.pos() + 1 != hole.pos() and
@@ -1004,9 +2674,30 @@ impl<T, C: Compare<T>> BinaryHeap<T, C> {
         pos = hole.pos();
         drop(hole);
 
-        // SAFETY: pos is the position in the hole and was already proven
-        //  to be a valid index.
-        unsafe { self.sift_up(start, pos) };
+        self.sift_up(start, pos);
+    }
+
+    #[cfg(feature = "safe_only")]
+    fn sift_down_to_bottom(&mut self, mut pos: usize) {
+        let end = self.len();
+        let start = pos;
+
+        assert!(pos < end);
+        let mut child = 2 * pos + 1;
+        while child + 1 < end {
+            child +=
+                self.cmp
+                    .compares_le(&self.data[child], &self.data[child + 1]) as usize;
+            self.data.swap(pos, child);
+            pos = child;
+            child = 2 * pos + 1;
+        }
+        if child == end - 1 {
+            self.data.swap(pos, child);
+            pos = child;
+        }
+
+        self.sift_up(start, pos);
     }
 
     /// Rebuild assuming data[0..start] is still a proper heap.
@@ -1017,11 +2708,6 @@ impl<T, C: Compare<T>> BinaryHeap<T, C> {
 
         let tail_len = self.len() - start;
 
-        #[inline(always)]
-        fn log2_fast(x: usize) -> usize {
-            (usize::BITS - x.leading_zeros() - 1) as usize
-        }
-
         // `rebuild` takes O(self.len()) operations
         // and about 2 * self.len() comparisons in the worst case
         // while repeating `sift_up` takes O(tail_len * log(start)) operations
@@ -1040,20 +2726,80 @@ impl<T, C: Compare<T>> BinaryHeap<T, C> {
             self.rebuild();
         } else {
             for i in start..self.len() {
-                // SAFETY: The index `i` is always less than self.len().
-                unsafe { self.sift_up(0, i) };
+                self.sift_up(0, i);
+            }
+        }
+    }
+
+    /// Rebuild assuming data[0..start] is still a proper heap and
+    /// data[start..] is sorted greatest first.
+    fn rebuild_sorted_tail(&mut self, start: usize) {
+        if start == self.len() {
+            return;
+        }
+
+        let tail_len = self.len() - start;
+
+        // A run sorted greatest first is a heap by itself: an appended
+        // element whose parent is also new stays put after one comparison.
+        // Only the elements whose parent is an old one, at most `start + 1`
+        // of them, may move up by about log_2(start) levels, so repeating
+        // `sift_up` wins unless both parts are of similar size.
+        let better_to_rebuild =
+            start > 0 && 2 * self.len() < tail_len + tail_len.min(start + 1) * log2_fast(start);
+
+        if better_to_rebuild {
+            self.rebuild();
+        } else {
+            for i in start..self.len() {
+                self.sift_up(0, i);
             }
         }
     }
 
-    fn rebuild(&mut self) {
+    /// Restores the heap property over the whole heap.
+    ///
+    /// Call this after the order of the elements changed behind the heap's
+    /// back, for instance through interior mutability or because the
+    /// comparator's own state changed. The elements are ordered by the
+    /// comparator as it is now, which must be a total order again; while it
+    /// is not, the heap is in no particular order, though still memory-safe.
+    ///
+    /// If only one element changed and its index is known, [`sift_up_at`] or
+    /// [`sift_down_at`] is cheaper. [`replace_cmp`] rebuilds by itself.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    /// use std::cell::Cell;
+    ///
+    /// let priorities = [Cell::new(1), Cell::new(2), Cell::new(3)];
+    /// let mut heap = BinaryHeap::new_by_key(|&id: &usize| priorities[id].get());
+    /// heap.extend(0..3);
+    ///
+    /// // Reverse every priority, then fix the heap once.
+    /// for p in &priorities {
+    ///     p.set(10 - p.get());
+    /// }
+    /// heap.rebuild();
+    /// assert_eq!(heap.pop(), Some(0));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*n*), with at most 2*n* comparisons.
+    ///
+    /// [`sift_up_at`]: BinaryHeap::sift_up_at
+    /// [`sift_down_at`]: BinaryHeap::sift_down_at
+    /// [`replace_cmp`]: BinaryHeap::replace_cmp
+    pub fn rebuild(&mut self) {
         let mut n = self.len() / 2;
         while n > 0 {
             n -= 1;
-            // SAFETY: n starts from self.len() / 2 and goes down to 0.
-            //  The only case when !(n < self.len()) is if
-            //  self.len() == 0, but it's ruled out by the loop condition.
-            unsafe { self.sift_down(n) };
+            self.sift_down(n);
         }
     }
 
@@ -1076,21 +2822,38 @@ impl<T, C: Compare<T>> BinaryHeap<T, C> {
     /// ```
     // #[stable(feature = "binary_heap_append", since = "1.11.0")]
     pub fn append(&mut self, other: &mut Self) {
-        if self.len() < other.len() {
-            swap(self, other);
+        self.alloc_check(self.len() + other.len());
+        // Only the buffers change hands: the growth policy and the capacity
+        // guard stay with their heap, and a frozen buffer stays put.
+        if self.len() < other.len() && !self.capacity_frozen() {
+            swap(&mut self.data, &mut other.data);
         }
 
         let start = self.data.len();
+        let moved = other.data.len();
 
         self.data.append(&mut other.data);
+        other.wipe_vacated(moved);
 
         self.rebuild_tail(start);
+        self.strict_check("append");
     }
-}
 
-impl<T, C> BinaryHeap<T, C> {
-    /// Returns an iterator visiting all values in the underlying vector, in
-    /// arbitrary order.
+    /// Moves all the items of `items` into `self` in any order, leaving
+    /// `items` empty but keeping its capacity.
+    pub(crate) fn append_vec(&mut self, items: &mut Vec<T>) {
+        self.alloc_check(self.len() + items.len());
+        let start = self.data.len();
+        self.data.append(items);
+        self.rebuild_tail(start);
+        self.strict_check("append");
+    }
+
+    /// Pushes clones of the borrowed items onto the heap.
+    ///
+    /// `Extend<&T>` is only implemented for `Copy` elements, like for the
+    /// standard collections; this method takes any `Clone` elements, such as
+    /// `String` or `Arc<T>`.
     ///
     /// # Examples
     ///
@@ -1098,39 +2861,888 @@ impl<T, C> BinaryHeap<T, C> {
     ///
     /// ```
     /// use binary_heap_plus::BinaryHeap;
-    /// let heap = BinaryHeap::from([1, 2, 3, 4]);
     ///
-    /// // Print 1, 2, 3, 4 in arbitrary order
-    /// for x in heap.iter() {
-    ///     println!("{}", x);
-    /// }
+    /// let names = vec!["b".to_string(), "c".to_string(), "a".to_string()];
+    /// let mut heap = BinaryHeap::new();
+    /// heap.extend_cloned(&names);
+    /// assert_eq!(heap.pop().as_deref(), Some("c"));
     /// ```
-    // #[stable(feature = "rust1", since = "1.0.0")]
-    pub fn iter(&self) -> Iter<'_, T> {
-        Iter {
-            iter: self.data.iter(),
-        }
+    pub fn extend_cloned<'a, I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = &'a T>,
+        T: 'a + Clone,
+    {
+        self.extend(iter.into_iter().cloned());
     }
 
-    /// Returns an iterator which retrieves elements in heap order.
-    /// This method consumes the original heap.
+    /// Pushes the items of a run sorted in ascending order, the order of
+    /// [`into_sorted_vec`], onto the heap.
     ///
-    /// # Examples
+    /// Appending a sorted run is cheaper than pushing its items one by one:
+    /// most of them are already in place once the run is reversed. Large
+    /// runs appended to a heap of similar size are heapified together with
+    /// it instead, like in [`append`].
     ///
-    /// Basic usage:
+    /// The heap stays valid even if the items are not sorted; they are then
+    /// only pushed more slowly.
+    ///
+    /// # Examples
     ///
     /// ```
     /// use binary_heap_plus::BinaryHeap;
-    /// let heap = BinaryHeap::from([1, 2, 3, 4, 5]);
     ///
-    /// assert_eq!(heap.into_iter_sorted().take(2).collect::<Vec<_>>(), [5, 4]);
+    /// let mut heap = BinaryHeap::from([4, 9]);
+    /// heap.append_sorted_ascending(vec![1, 3, 5, 7]);
+    ///
+    /// assert_eq!(heap.into_sorted_vec(), [1, 3, 4, 5, 7, 9]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*k* + min(*k*, *n*) \* log(*n*)) for a run of *k* items, and never
+    /// worse than *O*(*n* + *k*).
+    ///
+    /// [`into_sorted_vec`]: BinaryHeap::into_sorted_vec
+    /// [`append`]: BinaryHeap::append
+    pub fn append_sorted_ascending<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let start = self.data.len();
+        self.extend_data(iter);
+        self.data[start..].reverse();
+        self.rebuild_sorted_tail(start);
+        self.strict_check("append_sorted_ascending");
+    }
+
+    /// Pushes the items of a run sorted in descending order, the order in
+    /// which [`pop`] returns them, onto the heap.
+    ///
+    /// See [`append_sorted_ascending`] for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    ///
+    /// // A min-heap pops the smallest items first.
+    /// let mut heap = BinaryHeap::new_min();
+    /// heap.push(4);
+    /// heap.append_sorted_descending(vec![1, 3, 5, 7]);
+    ///
+    /// assert_eq!(heap.pop(), Some(1));
+    /// assert_eq!(heap.into_sorted_vec(), [7, 5, 4, 3]);
+    /// ```
+    ///
+    /// [`pop`]: BinaryHeap::pop
+    /// [`append_sorted_ascending`]: BinaryHeap::append_sorted_ascending
+    pub fn append_sorted_descending<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let start = self.data.len();
+        self.extend_data(iter);
+        self.rebuild_sorted_tail(start);
+        self.strict_check("append_sorted_descending");
+    }
+
+    /// Returns an iterator which pops elements in heap order until `budget`
+    /// has elapsed.
+    ///
+    /// The clock is read before the first pop and then once every
+    /// [`check_every`] pops (every pop by default), so the iterator may run
+    /// over budget by up to that many pops. Elements that are not popped stay
+    /// in the heap, which remains valid whether or not the iterator is
+    /// exhausted.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    /// use std::time::Duration;
+    ///
+    /// let mut heap = BinaryHeap::from([1, 2, 3, 4, 5]);
+    ///
+    /// let popped: Vec<_> = heap.drain_for(Duration::from_secs(60)).take(2).collect();
+    /// assert_eq!(popped, [5, 4]);
+    ///
+    /// // A zero budget pops nothing.
+    /// assert_eq!(heap.drain_for(Duration::from_secs(0)).count(), 0);
+    /// assert_eq!(heap.len(), 3);
+    /// ```
+    ///
+    /// [`check_every`]: DrainFor::check_every
+    pub fn drain_for(&mut self, budget: Duration) -> DrainFor<'_, T, C> {
+        DrainFor {
+            heap: self,
+            deadline: Instant::now().checked_add(budget),
+            check_every: 1,
+            until_check: 0,
+            expired: false,
+        }
+    }
+}
+
+/// Fallible counterparts of the methods that allocate.
+///
+/// These report allocation failure instead of aborting or panicking. They
+/// still call the comparator, so they only avoid panics if it does.
+///
+/// Requires the `fallible` feature, which needs Rust 1.57.
+#[cfg(feature = "fallible")]
+#[clippy::msrv = "1.57"]
+impl<T, C: Compare<T>> BinaryHeap<T, C> {
+    /// Pushes an item onto the heap, or returns it as `Err` if the buffer
+    /// is full and cannot grow.
+    ///
+    /// The buffer grows according to the [growth policy]. While the capacity
+    /// is [frozen], a full buffer cannot grow: the item is returned without
+    /// calling the guard.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    ///
+    /// let mut heap = BinaryHeap::new();
+    /// assert_eq!(heap.try_push(3), Ok(()));
+    /// assert_eq!(heap.try_push(5), Ok(()));
+    /// assert_eq!(heap.peek(), Some(&5));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Same as [`push`].
+    ///
+    /// [growth policy]: BinaryHeap::set_growth_policy
+    /// [frozen]: BinaryHeap::freeze_capacity
+    /// [`push`]: BinaryHeap::push
+    pub fn try_push(&mut self, item: T) -> Result<(), T> {
+        if self.data.len() == self.data.capacity() {
+            if self.capacity_frozen() {
+                return Err(item);
+            }
+            let reserved = match self.growth_step() {
+                Some(additional) => self.data.try_reserve_exact(additional),
+                None => self.data.try_reserve(1),
+            };
+            if reserved.is_err() {
+                return Err(item);
+            }
+        }
+        self.push(item);
+        Ok(())
+    }
+
+    /// Moves all the elements of `other` into `self`, leaving `other` empty,
+    /// or leaves both heaps unchanged if the combined buffer cannot be
+    /// allocated.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the capacity overflows or the allocator reports
+    /// a failure, or, without calling the guard, if the capacity of `self`
+    /// is [frozen] and too small.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    ///
+    /// let mut a = BinaryHeap::from([1, 3]);
+    /// let mut b = BinaryHeap::from([2, 4]);
+    /// a.try_append(&mut b).unwrap();
+    /// assert_eq!(a.into_sorted_vec(), [1, 2, 3, 4]);
+    /// assert!(b.is_empty());
+    /// ```
+    ///
+    /// [frozen]: BinaryHeap::freeze_capacity
+    pub fn try_append(&mut self, other: &mut Self) -> Result<(), TryReserveError> {
+        self.frozen_check(other.len())?;
+        // `append` moves the shorter heap into the longer one.
+        if self.len() < other.len() && !self.capacity_frozen() {
+            other.data.try_reserve(self.len())?;
+        } else {
+            self.data.try_reserve(other.len())?;
+        }
+        self.append(other);
+        Ok(())
+    }
+}
+
+/// The error the `try_` methods return when the capacity is frozen: as after
+/// an overflow, the buffer cannot grow. `TryReserveError` has no constructor.
+#[cfg(feature = "fallible")]
+#[clippy::msrv = "1.57"]
+fn frozen_error() -> TryReserveError {
+    Vec::<u8>::new().try_reserve(usize::MAX).unwrap_err()
+}
+
+#[cfg(feature = "fallible")]
+#[clippy::msrv = "1.57"]
+impl<T, C> BinaryHeap<T, C> {
+    /// Tries to reserve capacity for at least `additional` more elements.
+    /// See [`reserve`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the capacity overflows or the allocator reports
+    /// a failure, or, without calling the guard, if the capacity is [frozen]
+    /// and too small.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    ///
+    /// let mut heap: BinaryHeap<u8> = BinaryHeap::new();
+    /// heap.try_reserve(100).unwrap();
+    /// assert!(heap.capacity() >= 100);
+    /// assert!(heap.try_reserve(usize::MAX).is_err());
+    /// ```
+    ///
+    /// [`reserve`]: BinaryHeap::reserve
+    /// [frozen]: BinaryHeap::freeze_capacity
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.frozen_check(additional)?;
+        self.data.try_reserve(additional)
+    }
+
+    /// Tries to reserve the minimum capacity for exactly `additional` more
+    /// elements. See [`reserve_exact`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the capacity overflows or the allocator reports
+    /// a failure, or, without calling the guard, if the capacity is [frozen]
+    /// and too small.
+    ///
+    /// [`reserve_exact`]: BinaryHeap::reserve_exact
+    /// [frozen]: BinaryHeap::freeze_capacity
+    pub fn try_reserve_exact(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.frozen_check(additional)?;
+        self.data.try_reserve_exact(additional)
+    }
+
+    /// Fails if the capacity is frozen and the buffer has no room for
+    /// `additional` more elements.
+    fn frozen_check(&self, additional: usize) -> Result<(), TryReserveError> {
+        if self.capacity_frozen() && self.data.capacity() - self.len() < additional {
+            return Err(frozen_error());
+        }
+        Ok(())
+    }
+}
+
+/// Graphviz export, for visualizing the tree.
+///
+/// Requires the `dot` feature.
+#[cfg(feature = "dot")]
+impl<T, C> BinaryHeap<T, C> {
+    /// Renders the heap as a Graphviz DOT digraph, labelling each node with
+    /// the `Debug` representation of its element.
+    ///
+    /// Nodes are named `n0`, `n1`, ... after their index in the heap, so
+    /// `n0` is the root. See [`to_dot_with`] to choose the labels.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    ///
+    /// let heap = BinaryHeap::from(vec![1, 3, 2]);
+    /// assert_eq!(
+    ///     heap.to_dot(),
+    ///     "digraph heap {\n    \
+    ///          n0 [label=\"3\"];\n    \
+    ///          n1 [label=\"1\"];\n    \
+    ///          n0 -> n1;\n    \
+    ///          n2 [label=\"2\"];\n    \
+    ///          n0 -> n2;\n\
+    ///      }\n"
+    /// );
+    /// ```
+    ///
+    /// [`to_dot_with`]: BinaryHeap::to_dot_with
+    #[must_use]
+    pub fn to_dot(&self) -> String
+    where
+        T: fmt::Debug,
+    {
+        self.to_dot_with(|item| format!("{:?}", item))
+    }
+
+    /// Renders the heap as a Graphviz DOT digraph, labelling each node with
+    /// the string returned by `label` for its element.
+    ///
+    /// Quotes, backslashes and newlines in the labels are escaped.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    ///
+    /// let heap = BinaryHeap::from(vec![("b", 2), ("a", 1)]);
+    /// let dot = heap.to_dot_with(|&(name, _)| name.to_string());
+    /// assert!(dot.contains("n1 [label=\"a\"];"));
+    /// ```
+    #[must_use]
+    pub fn to_dot_with<F>(&self, mut label: F) -> String
+    where
+        F: FnMut(&T) -> String,
+    {
+        use std::fmt::Write;
+
+        let mut dot = String::from("digraph heap {\n");
+        for (i, item) in self.data.iter().enumerate() {
+            let mut escaped = String::new();
+            for c in label(item).chars() {
+                match c {
+                    '"' | '\\' => {
+                        escaped.push('\\');
+                        escaped.push(c);
+                    }
+                    '\n' => escaped.push_str("\\n"),
+                    c => escaped.push(c),
+                }
+            }
+            // Writing to a `String` cannot fail.
+            let _ = writeln!(dot, "    n{} [label=\"{}\"];", i, escaped);
+            if i > 0 {
+                let _ = writeln!(dot, "    n{} -> n{};", (i - 1) / 2, i);
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Randomized extraction.
+///
+/// Requires the `rand` feature.
+#[cfg(feature = "rand")]
+impl<T, C: Compare<T>> BinaryHeap<T, C> {
+    /// Removes an element chosen uniformly at random and returns it, or
+    /// returns `None` if the heap is empty.
+    ///
+    /// Like [`swap_remove_index`], it moves the last element into the gap
+    /// and repairs the heap around it, so the greatest element is no more
+    /// likely to go than any other.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    ///
+    /// let mut heap = BinaryHeap::from(vec![1, 5, 3]);
+    /// let evicted = heap.pop_random(&mut rand::thread_rng()).unwrap();
+    /// assert!([1, 3, 5].contains(&evicted));
+    /// assert_eq!(heap.len(), 2);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)), plus one call to the random number generator.
+    ///
+    /// [`swap_remove_index`]: BinaryHeap::swap_remove_index
+    pub fn pop_random<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        let index = rng.gen_range(0..self.len());
+        Some(self.swap_remove_index(index))
+    }
+}
+
+/// Capacity freezing, for code that must not allocate.
+///
+/// Requires the `alloc_guard` feature.
+#[cfg(feature = "alloc_guard")]
+impl<T, C> BinaryHeap<T, C> {
+    /// Freezes the capacity: from now on, any operation that would
+    /// reallocate the heap's buffer panics instead, before reallocating.
+    ///
+    /// Reserve the capacity you need, then freeze it, to make sure that code
+    /// with real-time constraints never allocates through the heap. Growing
+    /// ([`push`], [`reserve`], [`append`], `extend` and the like) and
+    /// shrinking ([`shrink_to_fit`], [`shrink_to`]) are both caught, and
+    /// [`append`] no longer adopts the other heap's buffer. A clone of a
+    /// frozen heap is not frozen. The `try_push`, `try_append` and
+    /// `try_reserve*` methods of the `fallible` feature return an error
+    /// instead of growing the buffer.
+    ///
+    /// When items come from an iterator, they are checked one by one, except
+    /// with rayon's `par_extend`, which is only reported after it
+    /// reallocated. Growing the vector within [`batch_mut`] is likewise
+    /// reported once the closure returns.
+    ///
+    /// # Examples
+    ///
+    /// ```should_panic
+    /// use binary_heap_plus::BinaryHeap;
+    ///
+    /// let mut heap = BinaryHeap::with_capacity(2);
+    /// heap.freeze_capacity();
+    /// heap.extend(vec![1, 2]);
+    /// while heap.pop().is_some() {}
+    /// heap.extend(vec![3, 4]);
+    /// // The third element does not fit.
+    /// heap.push(5);
+    /// ```
+    ///
+    /// [`push`]: BinaryHeap::push
+    /// [`reserve`]: BinaryHeap::reserve
+    /// [`append`]: BinaryHeap::append
+    /// [`batch_mut`]: BinaryHeap::batch_mut
+    /// [`shrink_to_fit`]: BinaryHeap::shrink_to_fit
+    /// [`shrink_to`]: BinaryHeap::shrink_to
+    pub fn freeze_capacity(&mut self) {
+        self.on_realloc = Some(realloc_panic);
+    }
+
+    /// Freezes the capacity like [`freeze_capacity`], but calls `on_realloc`
+    /// instead of panicking, and then lets the operation reallocate. The
+    /// `try_push`, `try_append` and `try_reserve*` methods still fail
+    /// without calling it.
+    ///
+    /// `on_realloc` receives the current capacity and the capacity the
+    /// operation asked for. It runs before the reallocation, on the thread
+    /// that caused it, so it can capture a backtrace.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// static REALLOCS: AtomicUsize = AtomicUsize::new(0);
+    ///
+    /// let mut heap = BinaryHeap::with_capacity(2);
+    /// heap.freeze_capacity_with(|_, _| {
+    ///     REALLOCS.fetch_add(1, Ordering::Relaxed);
+    /// });
+    /// heap.extend(vec![1, 2, 3]);
+    /// assert_eq!(REALLOCS.load(Ordering::Relaxed), 1);
+    /// assert_eq!(heap.len(), 3);
+    /// ```
+    ///
+    /// [`freeze_capacity`]: BinaryHeap::freeze_capacity
+    pub fn freeze_capacity_with(&mut self, on_realloc: fn(usize, usize)) {
+        self.on_realloc = Some(on_realloc);
+    }
+
+    /// Lifts a freeze set by [`freeze_capacity`] or
+    /// [`freeze_capacity_with`].
+    ///
+    /// [`freeze_capacity`]: BinaryHeap::freeze_capacity
+    /// [`freeze_capacity_with`]: BinaryHeap::freeze_capacity_with
+    pub fn unfreeze_capacity(&mut self) {
+        self.on_realloc = None;
+    }
+
+    /// Returns `true` if the capacity is frozen.
+    #[must_use]
+    pub fn is_capacity_frozen(&self) -> bool {
+        self.capacity_frozen()
+    }
+}
+
+#[cfg(feature = "alloc_guard")]
+fn realloc_panic(capacity: usize, needed: usize) {
+    panic!(
+        "heap would reallocate while its capacity is frozen: capacity {}, requested {}",
+        capacity, needed
+    );
+}
+
+impl<T, C> BinaryHeap<T, C> {
+    /// Returns an iterator visiting all values in the underlying vector, in
+    /// arbitrary order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    /// let heap = BinaryHeap::from([1, 2, 3, 4]);
+    ///
+    /// // Print 1, 2, 3, 4 in arbitrary order
+    /// for x in heap.iter() {
+    ///     println!("{}", x);
+    /// }
+    /// ```
+    // #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            iter: self.data.iter(),
+        }
+    }
+
+    /// Returns all values in the underlying vector, in arbitrary order.
+    ///
+    /// The greatest element comes first; the layout is otherwise that of the
+    /// implicit tree, as described for [`layers`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    /// let heap = BinaryHeap::from([1, 2, 3, 4]);
+    ///
+    /// assert_eq!(heap.as_slice().len(), 4);
+    /// assert_eq!(heap.as_slice()[0], 4);
+    /// ```
+    ///
+    /// [`layers`]: BinaryHeap::layers
+    #[must_use]
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    /// Returns the comparator.
+    pub(crate) fn cmp(&self) -> &C {
+        &self.cmp
+    }
+
+    /// Returns an iterator which retrieves elements in heap order.
+    /// This method consumes the original heap.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    /// let heap = BinaryHeap::from([1, 2, 3, 4, 5]);
+    ///
+    /// assert_eq!(heap.into_iter_sorted().take(2).collect::<Vec<_>>(), [5, 4]);
+    /// ```
+    // #[unstable(feature = "binary_heap_into_iter_sorted", issue = "59278")]
+    pub fn into_iter_sorted(self) -> IntoIterSorted<T, C> {
+        IntoIterSorted { inner: self }
+    }
+
+    /// Returns a [`Stream`] which yields elements in heap order.
+    /// This method consumes the original heap.
+    ///
+    /// After yielding [`DEFAULT_STREAM_BUDGET`] elements in a row, the stream
+    /// returns `Pending` once (after waking its task) so that draining a
+    /// large heap does not starve other tasks on the executor. Change the
+    /// budget with [`IntoStreamSorted::budget`].
+    ///
+    /// Requires the `futures` feature.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    /// use futures::executor::block_on;
+    /// use futures::stream::StreamExt;
+    ///
+    /// let heap = BinaryHeap::from([1, 2, 3, 4, 5]);
+    /// let top: Vec<_> = block_on(heap.into_stream_sorted().take(2).collect());
+    /// assert_eq!(top, [5, 4]);
+    /// ```
+    ///
+    /// [`Stream`]: https://docs.rs/futures-core/0.3/futures_core/stream/trait.Stream.html
+    /// [`DEFAULT_STREAM_BUDGET`]: constant.DEFAULT_STREAM_BUDGET.html
+    /// [`IntoStreamSorted::budget`]: struct.IntoStreamSorted.html#method.budget
+    #[cfg(feature = "futures")]
+    pub fn into_stream_sorted(self) -> IntoStreamSorted<T, C> {
+        IntoStreamSorted {
+            inner: self,
+            budget: DEFAULT_STREAM_BUDGET,
+            remaining: DEFAULT_STREAM_BUDGET,
+        }
+    }
+
+    /// Returns the greatest item in the binary heap, or `None` if it is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    /// let mut heap = BinaryHeap::new();
+    /// assert_eq!(heap.peek(), None);
+    ///
+    /// heap.push(1);
+    /// heap.push(5);
+    /// heap.push(2);
+    /// assert_eq!(heap.peek(), Some(&5));
+    ///
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(1) in the worst case.
+    #[must_use]
+    // #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn peek(&self) -> Option<&T> {
+        self.data.first()
+    }
+
+    /// Returns the number of bytes the heap has allocated for its buffer,
+    /// that is its capacity times the size of `T`.
+    ///
+    /// Memory owned by the elements themselves, such as the buffers of
+    /// `String` elements, is not included, nor is the bookkeeping overhead of
+    /// the allocator, which depends on the allocator in use.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    /// let mut heap: BinaryHeap<u64> = BinaryHeap::with_capacity(100);
+    /// assert_eq!(heap.memory_usage(), heap.capacity() * 8);
+    /// heap.clear();
+    /// heap.shrink_to_fit();
+    /// assert_eq!(heap.memory_usage(), 0);
+    /// ```
+    #[must_use]
+    pub fn memory_usage(&self) -> usize {
+        self.data.capacity() * mem::size_of::<T>()
+    }
+
+    /// Returns the number of levels of the tree, or 0 if the heap is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    ///
+    /// let heap = BinaryHeap::from(vec![1, 2, 3, 4]);
+    /// assert_eq!(heap.depth(), 3);
+    /// ```
+    #[must_use]
+    pub fn depth(&self) -> usize {
+        (usize::BITS - self.data.len().leading_zeros()) as usize
+    }
+
+    /// Returns an iterator over the levels of the tree, from the root down.
+    ///
+    /// Each level is a slice of the underlying vector: level `d` holds the
+    /// elements at indices `2^d - 1` up to `2^(d + 1) - 1`, and only the last
+    /// level may be incomplete.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    ///
+    /// let heap = BinaryHeap::from(vec![1, 2, 3, 4, 5, 6]);
+    /// let widths: Vec<usize> = heap.layers().map(|level| level.len()).collect();
+    /// assert_eq!(widths, [1, 2, 3]);
+    /// assert_eq!(heap.layers().next(), Some(&[6][..]));
+    /// ```
+    pub fn layers(&self) -> Layers<'_, T> {
+        Layers {
+            rest: &self.data,
+            width: 1,
+        }
+    }
+
+    /// Returns the number of elements in the subtree rooted at `index`,
+    /// counting the element itself, or 0 if `index` is out of bounds.
+    ///
+    /// Indices refer to the order of [`iter`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    ///
+    /// let heap = BinaryHeap::from(vec![1, 2, 3, 4, 5, 6]);
+    /// assert_eq!(heap.subtree_len(0), 6);
+    /// assert_eq!(heap.subtree_len(1), 3);
+    /// assert_eq!(heap.subtree_len(2), 2);
+    /// assert_eq!(heap.subtree_len(6), 0);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)).
+    ///
+    /// [`iter`]: BinaryHeap::iter
+    #[must_use]
+    pub fn subtree_len(&self, index: usize) -> usize {
+        let len = self.data.len();
+        let (mut first, mut last) = (index, index);
+        let mut count = 0;
+        // The subtree's nodes on each level form the range first..=last.
+        while first < len {
+            count += last.min(len - 1) - first + 1;
+            first = match first.checked_mul(2).and_then(|i| i.checked_add(1)) {
+                Some(i) => i,
+                None => break,
+            };
+            last = last.saturating_mul(2).saturating_add(2);
+        }
+        count
+    }
+
+    /// Returns the index of the first element equal to `item`, or `None` if
+    /// there is none.
+    ///
+    /// Indices refer to the order of [`iter`], and stay valid until the heap
+    /// is next modified. Equality is decided by `PartialEq`, not by the
+    /// comparator.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    ///
+    /// let heap = BinaryHeap::from(vec![1, 5, 2]);
+    /// assert_eq!(heap.position_of(&5), Some(0));
+    /// let i = heap.position_of(&2).unwrap();
+    /// assert_eq!(heap.iter().nth(i), Some(&2));
+    /// assert_eq!(heap.position_of(&7), None);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*n*).
+    ///
+    /// [`iter`]: BinaryHeap::iter
+    #[must_use]
+    pub fn position_of(&self, item: &T) -> Option<usize>
+    where
+        T: PartialEq,
+    {
+        self.data.iter().position(|x| x == item)
+    }
+
+    /// Returns the element at `index`, or `None` if `index` is out of
+    /// bounds.
+    ///
+    /// Indices refer to the order of [`iter`]; index 0 is the greatest
+    /// element.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    ///
+    /// let heap = BinaryHeap::from(vec![1, 5, 2]);
+    /// assert_eq!(heap.get(0), Some(&5));
+    /// assert_eq!(heap.get(3), None);
+    /// ```
+    ///
+    /// [`iter`]: BinaryHeap::iter
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.data.get(index)
+    }
+
+    /// Returns the index of the parent of the element at `index`, or `None`
+    /// if it is the root or `index` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    ///
+    /// let heap = BinaryHeap::from(vec![1, 2, 3, 4, 5]);
+    /// assert_eq!(heap.parent_of(4), Some(1));
+    /// assert_eq!(heap.parent_of(0), None);
+    /// assert_eq!(heap.parent_of(5), None);
+    /// ```
+    #[must_use]
+    pub fn parent_of(&self, index: usize) -> Option<usize> {
+        if index == 0 || index >= self.data.len() {
+            None
+        } else {
+            Some((index - 1) / 2)
+        }
+    }
+
+    /// Returns the indices of the children of the element at `index`.
+    ///
+    /// The range holds two indices for inner nodes, one for the last inner
+    /// node when the length is even, and none for leaves or if `index` is out
+    /// of bounds.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    ///
+    /// let heap = BinaryHeap::from(vec![1, 2, 3, 4, 5, 6]);
+    /// assert_eq!(heap.children_of(0), 1..3);
+    /// assert_eq!(heap.children_of(2), 5..6);
+    /// assert!(heap.children_of(3).is_empty());
+    ///
+    /// for child in heap.children_of(1) {
+    ///     assert!(heap.get(child) <= heap.get(1));
+    /// }
+    /// ```
+    #[must_use]
+    pub fn children_of(&self, index: usize) -> Range<usize> {
+        let len = self.data.len();
+        // Out of bounds indices have no children either, so clamp first to
+        // keep the arithmetic from overflowing.
+        let first = index.min(len).saturating_mul(2).saturating_add(1).min(len);
+        let end = first.saturating_add(2).min(len);
+        first..end
+    }
+
+    /// Returns the number of elements the binary heap can hold without reallocating.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
     /// ```
-    // #[unstable(feature = "binary_heap_into_iter_sorted", issue = "59278")]
-    pub fn into_iter_sorted(self) -> IntoIterSorted<T, C> {
-        IntoIterSorted { inner: self }
+    /// use binary_heap_plus::BinaryHeap;
+    /// let mut heap = BinaryHeap::with_capacity(100);
+    /// assert!(heap.capacity() >= 100);
+    /// heap.push(4);
+    /// ```
+    #[must_use]
+    // #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn capacity(&self) -> usize {
+        self.data.capacity()
     }
 
-    /// Returns the greatest item in the binary heap, or `None` if it is empty.
+    /// Overwrites the unused part of the buffer with zeroes.
+    ///
+    /// [`pop`], [`drain`] and `into_iter` zero the slots they move items
+    /// out of, but items removed in [`batch_mut`] leave their bytes behind
+    /// in the spare capacity. Call this after such removals to wipe those
+    /// copies without touching the remaining items.
+    ///
+    /// Requires the `zeroize` feature.
     ///
     /// # Examples
     ///
@@ -1138,41 +3750,66 @@ impl<T, C> BinaryHeap<T, C> {
     ///
     /// ```
     /// use binary_heap_plus::BinaryHeap;
-    /// let mut heap = BinaryHeap::new();
-    /// assert_eq!(heap.peek(), None);
-    ///
-    /// heap.push(1);
-    /// heap.push(5);
-    /// heap.push(2);
-    /// assert_eq!(heap.peek(), Some(&5));
     ///
+    /// let mut heap = BinaryHeap::from(vec![[7u8; 32], [9u8; 32]]);
+    /// heap.batch_mut(|items| {
+    ///     items.retain(|x| x[0] != 9);
+    ///     false
+    /// });
+    /// heap.zeroize_spare_capacity();
+    /// assert_eq!(heap.len(), 1);
     /// ```
     ///
-    /// # Time complexity
+    /// [`pop`]: BinaryHeap::pop
+    /// [`drain`]: BinaryHeap::drain
+    /// [`batch_mut`]: BinaryHeap::batch_mut
+    #[cfg(feature = "zeroize")]
+    #[clippy::msrv = "1.60"] // zeroize itself requires Rust 1.60.
+    pub fn zeroize_spare_capacity(&mut self) {
+        self.data.spare_capacity_mut().zeroize();
+    }
+
+    /// Zeroes the `n` slots past the elements, which a removal just moved
+    /// elements out of.
+    #[cfg(feature = "zeroize")]
+    #[clippy::msrv = "1.60"]
+    fn wipe_vacated(&mut self, n: usize) {
+        self.data.spare_capacity_mut()[..n].zeroize();
+    }
+
+    #[cfg(not(feature = "zeroize"))]
+    #[inline(always)]
+    fn wipe_vacated(&mut self, _n: usize) {}
+
+    /// Returns the policy [`push`] uses to grow the buffer when it is full.
     ///
-    /// Cost is *O*(1) in the worst case.
+    /// [`push`]: BinaryHeap::push
     #[must_use]
-    // #[stable(feature = "rust1", since = "1.0.0")]
-    pub fn peek(&self) -> Option<&T> {
-        self.data.get(0)
+    pub fn growth_policy(&self) -> GrowthPolicy {
+        self.growth
     }
 
-    /// Returns the number of elements the binary heap can hold without reallocating.
+    /// Sets the policy [`push`] uses to grow the buffer when it is full.
     ///
     /// # Examples
     ///
     /// Basic usage:
     ///
     /// ```
-    /// use binary_heap_plus::BinaryHeap;
-    /// let mut heap = BinaryHeap::with_capacity(100);
-    /// assert!(heap.capacity() >= 100);
-    /// heap.push(4);
+    /// use binary_heap_plus::{BinaryHeap, GrowthPolicy};
+    ///
+    /// let mut heap = BinaryHeap::with_capacity(4);
+    /// // Grow by half of the current capacity instead of doubling it.
+    /// heap.set_growth_policy(GrowthPolicy::Custom(|cap| cap + cap / 2));
+    /// heap.extend([1, 2, 3, 4]);
+    /// let cap = heap.capacity();
+    /// heap.push(5);
+    /// assert_eq!(heap.capacity(), cap + cap / 2);
     /// ```
-    #[must_use]
-    // #[stable(feature = "rust1", since = "1.0.0")]
-    pub fn capacity(&self) -> usize {
-        self.data.capacity()
+    ///
+    /// [`push`]: BinaryHeap::push
+    pub fn set_growth_policy(&mut self, policy: GrowthPolicy) {
+        self.growth = policy;
     }
 
     /// Reserves the minimum capacity for exactly `additional` more elements to be inserted in the
@@ -1201,6 +3838,7 @@ impl<T, C> BinaryHeap<T, C> {
     /// [`reserve`]: BinaryHeap::reserve
     // #[stable(feature = "rust1", since = "1.0.0")]
     pub fn reserve_exact(&mut self, additional: usize) {
+        self.alloc_check(self.len().saturating_add(additional));
         self.data.reserve_exact(additional);
     }
 
@@ -1224,6 +3862,7 @@ impl<T, C> BinaryHeap<T, C> {
     /// ```
     // #[stable(feature = "rust1", since = "1.0.0")]
     pub fn reserve(&mut self, additional: usize) {
+        self.alloc_check(self.len().saturating_add(additional));
         self.data.reserve(additional);
     }
 
@@ -1243,6 +3882,7 @@ impl<T, C> BinaryHeap<T, C> {
     /// ```
     // #[stable(feature = "rust1", since = "1.0.0")]
     pub fn shrink_to_fit(&mut self) {
+        self.shrink_check(0);
         self.data.shrink_to_fit();
     }
 
@@ -1265,9 +3905,72 @@ impl<T, C> BinaryHeap<T, C> {
     /// ```
     #[inline]
     pub fn shrink_to(&mut self, min_capacity: usize) {
+        self.shrink_check(min_capacity);
         self.data.shrink_to(min_capacity)
     }
 
+    /// Reports a reallocation to the capacity guard if the capacity is
+    /// frozen and the buffer cannot hold `needed` elements. Does nothing
+    /// unless the `alloc_guard` feature is enabled.
+    #[inline(always)]
+    fn alloc_check(&self, needed: usize) {
+        self.alloc_check_from(self.data.capacity(), needed);
+    }
+
+    /// Like [`alloc_check`](BinaryHeap::alloc_check), for a buffer of the
+    /// given capacity, to check after the fact when the number of new
+    /// elements was not known.
+    #[inline(always)]
+    fn alloc_check_from(&self, capacity: usize, needed: usize) {
+        #[cfg(feature = "alloc_guard")]
+        if let Some(on_realloc) = self.on_realloc {
+            if needed > capacity {
+                on_realloc(capacity, needed);
+            }
+        }
+        #[cfg(not(feature = "alloc_guard"))]
+        let _ = (capacity, needed);
+    }
+
+    /// Reports a reallocation to the capacity guard if the capacity is
+    /// frozen and shrinking to `min_capacity` would release memory.
+    #[inline(always)]
+    fn shrink_check(&self, min_capacity: usize) {
+        let target = self.len().max(min_capacity);
+        #[cfg(feature = "alloc_guard")]
+        if let Some(on_realloc) = self.on_realloc {
+            if target < self.data.capacity() {
+                on_realloc(self.data.capacity(), target);
+            }
+        }
+        #[cfg(not(feature = "alloc_guard"))]
+        let _ = target;
+    }
+
+    /// Returns `true` if the `alloc_guard` feature is enabled and the
+    /// capacity is frozen.
+    #[inline(always)]
+    fn capacity_frozen(&self) -> bool {
+        #[cfg(feature = "alloc_guard")]
+        let frozen = self.on_realloc.is_some();
+        #[cfg(not(feature = "alloc_guard"))]
+        let frozen = false;
+        frozen
+    }
+
+    /// Extends the buffer with `iter`, checking each item against the
+    /// capacity guard while it is frozen.
+    fn extend_data<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        if !self.capacity_frozen() {
+            self.data.extend(iter);
+            return;
+        }
+        for item in iter {
+            self.alloc_check(self.data.len() + 1);
+            self.data.push(item);
+        }
+    }
+
     /// Consumes the `BinaryHeap` and returns the underlying vector
     /// in arbitrary order.
     ///
@@ -1360,7 +4063,10 @@ impl<T, C> BinaryHeap<T, C> {
     // #[stable(feature = "drain", since = "1.6.0")]
     pub fn drain(&mut self) -> Drain<'_, T> {
         Drain {
+            #[cfg(not(feature = "zeroize"))]
             iter: self.data.drain(..),
+            #[cfg(feature = "zeroize")]
+            iter: wipe::Drain::borrow(&mut self.data),
         }
     }
 
@@ -1390,18 +4096,20 @@ impl<T, C> BinaryHeap<T, C> {
 /// (because it was moved from or duplicated).
 /// In drop, `Hole` will restore the slice by filling the hole
 /// position with the value that was originally removed.
-struct Hole<'a, T: 'a> {
+#[cfg(not(feature = "safe_only"))]
+pub(crate) struct Hole<'a, T: 'a> {
     data: &'a mut [T],
     elt: ManuallyDrop<T>,
     pos: usize,
 }
 
+#[cfg(not(feature = "safe_only"))]
 impl<'a, T> Hole<'a, T> {
     /// Create a new `Hole` at index `pos`.
     ///
     /// Unsafe because pos must be within the data slice.
     #[inline]
-    unsafe fn new(data: &'a mut [T], pos: usize) -> Self {
+    pub(crate) unsafe fn new(data: &'a mut [T], pos: usize) -> Self {
         debug_assert!(pos < data.len());
         // SAFE: pos should be inside the slice
         let elt = unsafe { ptr::read(data.get_unchecked(pos)) };
@@ -1413,13 +4121,13 @@ impl<'a, T> Hole<'a, T> {
     }
 
     #[inline]
-    fn pos(&self) -> usize {
+    pub(crate) fn pos(&self) -> usize {
         self.pos
     }
 
     /// Returns a reference to the element removed.
     #[inline]
-    fn element(&self) -> &T {
+    pub(crate) fn element(&self) -> &T {
         &self.elt
     }
 
@@ -1427,7 +4135,7 @@ impl<'a, T> Hole<'a, T> {
     ///
     /// Unsafe because index must be within the data slice and not equal to pos.
     #[inline]
-    unsafe fn get(&self, index: usize) -> &T {
+    pub(crate) unsafe fn get(&self, index: usize) -> &T {
         debug_assert!(index != self.pos);
         debug_assert!(index < self.data.len());
         unsafe { self.data.get_unchecked(index) }
@@ -1437,7 +4145,7 @@ impl<'a, T> Hole<'a, T> {
     ///
     /// Unsafe because index must be within the data slice and not equal to pos.
     #[inline]
-    unsafe fn move_to(&mut self, index: usize) {
+    pub(crate) unsafe fn move_to(&mut self, index: usize) {
         debug_assert!(index != self.pos);
         debug_assert!(index < self.data.len());
         unsafe {
@@ -1448,8 +4156,68 @@ impl<'a, T> Hole<'a, T> {
         }
         self.pos = index;
     }
+
+    /// Hints the CPU to start loading the grandchildren of the node at
+    /// `child`'s parent, that is the children of `child` and `child + 1`,
+    /// which are contiguous.
+    #[inline(always)]
+    fn prefetch_grandchildren(&self, child: usize) {
+        let first = 2 * child + 1;
+        if first < self.data.len() {
+            let last = (first + 3).min(self.data.len() - 1);
+            prefetch_read(self.data.as_ptr().wrapping_add(first));
+            prefetch_read(self.data.as_ptr().wrapping_add(last));
+        }
+    }
 }
 
+/// Hints the CPU to start loading the cache line at `ptr`. Does nothing on
+/// targets without a stable prefetch instruction.
+#[cfg(not(feature = "safe_only"))]
+#[inline(always)]
+fn prefetch_read<T>(ptr: *const T) {
+    #[cfg(all(target_arch = "x86_64", target_feature = "sse"))]
+    // SAFETY: prefetching is only a hint and never faults, even for an
+    //  invalid address.
+    unsafe {
+        use core::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+        _mm_prefetch(ptr as *const i8, _MM_HINT_T0);
+    }
+    #[cfg(all(target_arch = "x86", target_feature = "sse"))]
+    // SAFETY: as above.
+    unsafe {
+        use core::arch::x86::{_mm_prefetch, _MM_HINT_T0};
+        _mm_prefetch(ptr as *const i8, _MM_HINT_T0);
+    }
+    #[cfg(not(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        target_feature = "sse"
+    )))]
+    let _ = ptr;
+}
+
+/// Returns the base-2 logarithm of `x`, rounded down. `x` must not be zero.
+#[inline(always)]
+fn log2_fast(x: usize) -> usize {
+    (usize::BITS - x.leading_zeros() - 1) as usize
+}
+
+/// Elements up to this size in bytes are popped with `sift_down_to_bottom`,
+/// which saves comparisons at the cost of extra moves. Larger ones use the
+/// classic sift-down: at 256 bytes it is about a third faster.
+const POP_SIFT_TO_BOTTOM_MAX_SIZE: usize = 64;
+
+/// From this length on, `into_sorted_vec` sorts the data instead of running
+/// heapsort on it. Sorting `u32`s is already faster at 16 elements and about
+/// three times faster at 10 000; heapsort is kept for tiny heaps only.
+const SORT_INTO_VEC_THRESHOLD: usize = 16;
+
+/// Sift-downs prefetch grandchildren once the heap data takes up at least
+/// this many bytes: smaller heaps mostly stay in the caches anyway.
+#[cfg(not(feature = "safe_only"))]
+const PREFETCH_MIN_BYTES: usize = 1 << 22;
+
+#[cfg(not(feature = "safe_only"))]
 impl<T> Drop for Hole<'_, T> {
     #[inline]
     fn drop(&mut self) {
@@ -1461,6 +4229,95 @@ impl<T> Drop for Hole<'_, T> {
     }
 }
 
+/// Below this length, `from_vec_cmp_par` heapifies sequentially.
+#[cfg(feature = "rayon")]
+const PARALLEL_REBUILD_THRESHOLD: usize = 1 << 16;
+
+/// Minimum number of sift-downs handed to a rayon job.
+#[cfg(all(feature = "rayon", not(feature = "safe_only")))]
+const PARALLEL_REBUILD_MIN_NODES: usize = 1 << 10;
+
+/// The heap data, shared between the threads of `rebuild_parallel`.
+#[cfg(all(feature = "rayon", not(feature = "safe_only")))]
+struct SharedSlice<T>(*mut T);
+
+// SAFETY: `rebuild_parallel` only lets threads access disjoint elements.
+#[cfg(all(feature = "rayon", not(feature = "safe_only")))]
+unsafe impl<T: Send> Send for SharedSlice<T> {}
+#[cfg(all(feature = "rayon", not(feature = "safe_only")))]
+unsafe impl<T: Send> Sync for SharedSlice<T> {}
+
+#[cfg(all(feature = "rayon", not(feature = "safe_only")))]
+impl<T> SharedSlice<T> {
+    // A method rather than field access, so closures capture the whole
+    // (`Sync`) struct instead of the raw pointer.
+    fn ptr(&self) -> *mut T {
+        self.0
+    }
+}
+
+/// Like `Hole`, but over a raw pointer so that several threads can have
+/// holes in the same slice.
+#[cfg(all(feature = "rayon", not(feature = "safe_only")))]
+struct RawHole<T> {
+    data: *mut T,
+    elt: ManuallyDrop<T>,
+    pos: usize,
+}
+
+#[cfg(all(feature = "rayon", not(feature = "safe_only")))]
+impl<T> RawHole<T> {
+    /// Unsafe because moves must stay within the caller's part of the slice.
+    #[inline]
+    unsafe fn move_to(&mut self, index: usize) {
+        unsafe { ptr::copy_nonoverlapping(self.data.add(index), self.data.add(self.pos), 1) };
+        self.pos = index;
+    }
+}
+
+#[cfg(all(feature = "rayon", not(feature = "safe_only")))]
+impl<T> Drop for RawHole<T> {
+    #[inline]
+    fn drop(&mut self) {
+        // fill the hole again
+        unsafe { ptr::copy_nonoverlapping(&*self.elt, self.data.add(self.pos), 1) };
+    }
+}
+
+/// `sift_down_range(pos, end)` over a raw pointer.
+///
+/// # Safety
+///
+/// `pos < end`, `data` must be valid for `end` elements and no other thread
+/// may access the subtree rooted at `pos` during the call.
+#[cfg(all(feature = "rayon", not(feature = "safe_only")))]
+unsafe fn sift_down_shared<T, C: Compare<T>>(data: *mut T, end: usize, pos: usize, cmp: &C) {
+    let mut hole = RawHole {
+        data,
+        elt: ManuallyDrop::new(unsafe { ptr::read(data.add(pos)) }),
+        pos,
+    };
+    let mut child = 2 * pos + 1;
+
+    // Loop invariant: child == 2 * hole.pos + 1.
+    while child <= end.saturating_sub(2) {
+        // compare with the greater of the two children
+        // SAFETY: child + 1 < end, and both children are in the subtree.
+        child += unsafe { cmp.compares_le(&*data.add(child), &*data.add(child + 1)) } as usize;
+
+        // if we are already in order, stop.
+        if cmp.compares_ge(&hole.elt, unsafe { &*data.add(child) }) {
+            return;
+        }
+        unsafe { hole.move_to(child) };
+        child = 2 * hole.pos + 1;
+    }
+
+    if child == end - 1 && cmp.compares_lt(&hole.elt, unsafe { &*data.add(child) }) {
+        unsafe { hole.move_to(child) };
+    }
+}
+
 /// An iterator over the elements of a `BinaryHeap`.
 ///
 /// This `struct` is created by [`BinaryHeap::iter()`]. See its
@@ -1478,6 +4335,26 @@ impl<T: fmt::Debug> fmt::Debug for Iter<'_, T> {
     }
 }
 
+impl<'a, T> Iter<'a, T> {
+    /// Returns the elements not yet yielded, in the order the iterator
+    /// would yield them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    ///
+    /// let heap = BinaryHeap::from(vec![1, 2, 3]);
+    /// let mut iter = heap.iter();
+    /// iter.next();
+    /// assert_eq!(iter.as_slice(), &heap.as_slice()[1..]);
+    /// ```
+    #[must_use]
+    pub fn as_slice(&self) -> &'a [T] {
+        self.iter.as_slice()
+    }
+}
+
 // FIXME(#26925) Remove in favor of `#[derive(Clone)]`
 // #[stable(feature = "rust1", since = "1.0.0")]
 impl<T> Clone for Iter<'_, T> {
@@ -1526,6 +4403,49 @@ impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
 // #[stable(feature = "fused", since = "1.26.0")]
 // impl<'a, T> FusedIterator for Iter<'a, T> {}
 
+/// An iterator over the levels of a `BinaryHeap`, as slices.
+///
+/// This `struct` is created by [`BinaryHeap::layers()`]. See its
+/// documentation for more.
+///
+/// [`BinaryHeap::layers()`]: struct.BinaryHeap.html#method.layers
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+#[derive(Clone, Debug)]
+pub struct Layers<'a, T> {
+    rest: &'a [T],
+    width: usize,
+}
+
+impl<'a, T> Iterator for Layers<'a, T> {
+    type Item = &'a [T];
+
+    fn next(&mut self) -> Option<&'a [T]> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        let (level, rest) = self.rest.split_at(self.width.min(self.rest.len()));
+        self.rest = rest;
+        self.width = self.width.saturating_mul(2);
+        Some(level)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.rest.is_empty() {
+            return (0, Some(0));
+        }
+        // The remaining elements start at index `width - 1`, so the levels
+        // left are those of a heap of `width - 1 + rest.len()` elements,
+        // minus the `width.trailing_zeros()` levels already yielded.
+        let total = (self.width - 1).saturating_add(self.rest.len());
+        let levels = (usize::BITS - total.leading_zeros() - self.width.trailing_zeros()) as usize;
+        (levels, Some(levels))
+    }
+}
+
+impl<T> ExactSizeIterator for Layers<'_, T> {}
+
+impl<T> FusedIterator for Layers<'_, T> {}
+
 /// An owning iterator over the elements of a `BinaryHeap`.
 ///
 /// This `struct` is created by [`BinaryHeap::into_iter()`]
@@ -1535,7 +4455,10 @@ impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
 // #[stable(feature = "rust1", since = "1.0.0")]
 #[derive(Clone)]
 pub struct IntoIter<T> {
+    #[cfg(not(feature = "zeroize"))]
     iter: vec::IntoIter<T>,
+    #[cfg(feature = "zeroize")]
+    iter: wipe::IntoIter<T>,
 }
 
 // #[stable(feature = "collection_debug", since = "1.17.0")]
@@ -1547,6 +4470,27 @@ impl<T: fmt::Debug> fmt::Debug for IntoIter<T> {
     }
 }
 
+impl<T> IntoIter<T> {
+    /// Returns the elements not yet yielded, in the order the iterator
+    /// would yield them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use binary_heap_plus::BinaryHeap;
+    ///
+    /// let heap = BinaryHeap::from(vec![1, 2, 3]);
+    /// let layout = heap.as_slice().to_vec();
+    /// let mut iter = heap.into_iter();
+    /// iter.next_back();
+    /// assert_eq!(iter.as_slice(), &layout[..2]);
+    /// ```
+    #[must_use]
+    pub fn as_slice(&self) -> &[T] {
+        self.iter.as_slice()
+    }
+}
+
 // #[stable(feature = "rust1", since = "1.0.0")]
 impl<T> Iterator for IntoIter<T> {
     type Item = T;
@@ -1600,6 +4544,195 @@ impl<T, C: Compare<T>> Iterator for IntoIterSorted<T, C> {
         let exact = self.inner.len();
         (exact, Some(exact))
     }
+
+    #[inline]
+    fn count(self) -> usize {
+        self.inner.len()
+    }
+
+    fn last(self) -> Option<T> {
+        // The least element is one of the leaves, which make up the second
+        // half of the data.
+        let BinaryHeap { mut data, cmp, .. } = self.inner;
+        let least = (data.len() / 2..data.len()).reduce(|a, b| {
+            if cmp.compares_lt(&data[b], &data[a]) {
+                b
+            } else {
+                a
+            }
+        })?;
+        Some(data.swap_remove(least))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<T> {
+        let len = self.inner.len();
+        if n >= len {
+            self.inner.clear();
+            return None;
+        }
+        // Popping `n` elements takes about 2 * n * log_2(len) comparisons,
+        // while selecting the nth one and heapifying the rest take about
+        // 4 * len.
+        if n * log2_fast(len) <= 2 * len {
+            for _ in 0..n {
+                self.inner.pop();
+            }
+            return self.inner.pop();
+        }
+        let BinaryHeap { data, cmp, .. } = &mut self.inner;
+        data.select_nth_unstable_by(n, |a, b| cmp.compare(b, a));
+        data.drain(..n);
+        let item = data.swap_remove(0);
+        self.inner.rebuild();
+        Some(item)
+    }
+}
+
+/// Number of elements an [`IntoStreamSorted`] yields before it lets other
+/// tasks run.
+///
+/// [`IntoStreamSorted`]: struct.IntoStreamSorted.html
+#[cfg(feature = "futures")]
+pub const DEFAULT_STREAM_BUDGET: usize = 128;
+
+/// A stream which pops elements from a `BinaryHeap` in heap order.
+///
+/// This `struct` is created by [`BinaryHeap::into_stream_sorted()`]. See its
+/// documentation for more.
+///
+/// [`BinaryHeap::into_stream_sorted()`]: struct.BinaryHeap.html#method.into_stream_sorted
+#[cfg(feature = "futures")]
+#[must_use = "streams do nothing unless polled"]
+#[derive(Clone, Debug)]
+pub struct IntoStreamSorted<T, C> {
+    inner: BinaryHeap<T, C>,
+    budget: usize,
+    remaining: usize,
+}
+
+#[cfg(feature = "futures")]
+impl<T, C> IntoStreamSorted<T, C> {
+    /// Sets how many elements are yielded in a row before the stream yields
+    /// to the executor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `budget` is zero.
+    pub fn budget(mut self, budget: usize) -> Self {
+        assert!(budget > 0, "stream budget must be positive");
+        self.budget = budget;
+        self.remaining = budget;
+        self
+    }
+
+    /// Consumes the stream and returns the remaining elements as a heap.
+    pub fn into_heap(self) -> BinaryHeap<T, C> {
+        self.inner
+    }
+}
+
+// The heap is never pinned structurally.
+#[cfg(feature = "futures")]
+impl<T, C> Unpin for IntoStreamSorted<T, C> {}
+
+#[cfg(feature = "futures")]
+impl<T, C: Compare<T>> Stream for IntoStreamSorted<T, C> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let this = self.get_mut();
+        if this.remaining == 0 && !this.inner.is_empty() {
+            this.remaining = this.budget;
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+        let item = this.inner.pop();
+        if item.is_some() {
+            this.remaining -= 1;
+        }
+        Poll::Ready(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let exact = self.inner.len();
+        (exact, Some(exact))
+    }
+}
+
+#[cfg(feature = "futures")]
+impl<T, C: Compare<T>> FusedStream for IntoStreamSorted<T, C> {
+    fn is_terminated(&self) -> bool {
+        self.inner.is_empty()
+    }
+}
+
+/// An iterator which pops elements from a `BinaryHeap` in heap order until a
+/// time budget is exhausted.
+///
+/// This `struct` is created by [`BinaryHeap::drain_for()`]. See its
+/// documentation for more.
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct DrainFor<'a, T: 'a, C: 'a + Compare<T>> {
+    heap: &'a mut BinaryHeap<T, C>,
+    // `None` if the budget overflows `Instant`, i.e. it never runs out.
+    deadline: Option<Instant>,
+    check_every: usize,
+    until_check: usize,
+    expired: bool,
+}
+
+impl<'a, T, C: Compare<T>> DrainFor<'a, T, C> {
+    /// Reads the clock only once every `n` pops instead of before every pop.
+    ///
+    /// Use this when popping is much cheaper than reading the clock.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is zero.
+    pub fn check_every(mut self, n: usize) -> Self {
+        assert!(n > 0, "check interval must be positive");
+        self.check_every = n;
+        self
+    }
+}
+
+impl<T: fmt::Debug, C: Compare<T>> fmt::Debug for DrainFor<'_, T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DrainFor")
+            .field("heap", &self.heap)
+            .field("deadline", &self.deadline)
+            .field("check_every", &self.check_every)
+            .finish()
+    }
+}
+
+impl<T, C: Compare<T>> Iterator for DrainFor<'_, T, C> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        if self.expired {
+            return None;
+        }
+        if self.until_check == 0 {
+            if matches!(self.deadline, Some(deadline) if Instant::now() >= deadline) {
+                self.expired = true;
+                return None;
+            }
+            self.until_check = self.check_every;
+        }
+        self.until_check -= 1;
+        self.heap.pop()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.expired {
+            (0, Some(0))
+        } else {
+            (0, Some(self.heap.len()))
+        }
+    }
 }
 
 /// A draining iterator over the elements of a `BinaryHeap`.
@@ -1609,7 +4742,10 @@ impl<T, C: Compare<T>> Iterator for IntoIterSorted<T, C> {
 // #[stable(feature = "drain", since = "1.6.0")]
 #[derive(Debug)]
 pub struct Drain<'a, T: 'a> {
+    #[cfg(not(feature = "zeroize"))]
     iter: vec::Drain<'a, T>,
+    #[cfg(feature = "zeroize")]
+    iter: wipe::Drain<'a, T>,
 }
 
 // #[stable(feature = "drain", since = "1.6.0")]
@@ -1712,7 +4848,10 @@ impl<T, C> IntoIterator for BinaryHeap<T, C> {
     /// ```
     fn into_iter(self) -> IntoIter<T> {
         IntoIter {
+            #[cfg(not(feature = "zeroize"))]
             iter: self.data.into_iter(),
+            #[cfg(feature = "zeroize")]
+            iter: wipe::IntoIter::new(self.data),
         }
     }
 }
@@ -1766,6 +4905,118 @@ impl<'a, T: 'a + Copy, C: Compare<T>> Extend<&'a T> for BinaryHeap<T, C> {
     }
 }
 
+/// Collects the items into per-thread vectors, concatenates them and
+/// heapifies the result once.
+#[cfg(feature = "rayon")]
+impl<T: Send, C: Compare<T> + Default + Sync> FromParallelIterator<T> for BinaryHeap<T, C> {
+    fn from_par_iter<I>(par_iter: I) -> Self
+    where
+        I: IntoParallelIterator<Item = T>,
+    {
+        BinaryHeap::from_vec_par(Vec::from_par_iter(par_iter))
+    }
+}
+
+/// Appends the items in parallel, then restores the heap property once.
+#[cfg(feature = "rayon")]
+impl<T: Send, C: Compare<T>> ParallelExtend<T> for BinaryHeap<T, C> {
+    fn par_extend<I>(&mut self, par_iter: I)
+    where
+        I: IntoParallelIterator<Item = T>,
+    {
+        let (start, capacity) = (self.data.len(), self.data.capacity());
+        self.data.par_extend(par_iter);
+        self.alloc_check_from(capacity, self.data.len());
+        self.rebuild_tail(start);
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, T: 'a + Copy + Send + Sync, C: Compare<T>> ParallelExtend<&'a T> for BinaryHeap<T, C> {
+    fn par_extend<I>(&mut self, par_iter: I)
+    where
+        I: IntoParallelIterator<Item = &'a T>,
+    {
+        let (start, capacity) = (self.data.len(), self.data.capacity());
+        self.data.par_extend(par_iter);
+        self.alloc_check_from(capacity, self.data.len());
+        self.rebuild_tail(start);
+    }
+}
+
+/// Iterates over the items in parallel, in arbitrary order.
+#[cfg(feature = "rayon")]
+impl<T: Send, C> IntoParallelIterator for BinaryHeap<T, C> {
+    type Item = T;
+    type Iter = rayon::vec::IntoIter<T>;
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.data.into_par_iter()
+    }
+}
+
+/// Iterates over references to the items in parallel, in arbitrary order.
+#[cfg(feature = "rayon")]
+impl<'a, T: Sync, C> IntoParallelIterator for &'a BinaryHeap<T, C> {
+    type Item = &'a T;
+    type Iter = rayon::slice::Iter<'a, T>;
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.data[..].into_par_iter()
+    }
+}
+
+/// Wipes every item, empties the heap and wipes the whole buffer, including
+/// spare capacity left behind by earlier pops.
+///
+/// Wrap the heap in [`Zeroizing`] to wipe it when it is dropped.
+///
+/// Requires the `zeroize` feature.
+///
+/// [`Zeroizing`]: https://docs.rs/zeroize/1/zeroize/struct.Zeroizing.html
+#[cfg(feature = "zeroize")]
+impl<T: Zeroize, C> Zeroize for BinaryHeap<T, C> {
+    fn zeroize(&mut self) {
+        self.data.zeroize();
+    }
+}
+
+/// Items that wipe themselves on drop are wiped when the heap is dropped.
+/// The spare capacity holds no copies of items removed by `pop`, `drain` or
+/// `into_iter`, which zero the slots they vacate; after other removals, see
+/// [`BinaryHeap::zeroize_spare_capacity()`]. Copies left in a buffer that
+/// was given up when the heap grew are out of reach, so reserve the
+/// capacity up front.
+///
+/// [`BinaryHeap::zeroize_spare_capacity()`]: struct.BinaryHeap.html#method.zeroize_spare_capacity
+#[cfg(feature = "zeroize")]
+impl<T: ZeroizeOnDrop, C> ZeroizeOnDrop for BinaryHeap<T, C> {}
+
+/// Wipes the items not yet yielded, leaving them in the iterator.
+#[cfg(feature = "zeroize")]
+impl<T: Zeroize> Zeroize for IntoIter<T> {
+    fn zeroize(&mut self) {
+        self.iter.as_mut_slice().iter_mut().zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<T: ZeroizeOnDrop> ZeroizeOnDrop for IntoIter<T> {}
+
+/// Wipes the items not yet yielded and empties the iterator.
+#[cfg(feature = "zeroize")]
+impl<T: Zeroize, C> Zeroize for IntoIterSorted<T, C> {
+    fn zeroize(&mut self) {
+        self.inner.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<T: ZeroizeOnDrop, C> ZeroizeOnDrop for IntoIterSorted<T, C> {}
+
+#[cfg(feature = "zeroize")]
+impl<T: ZeroizeOnDrop> ZeroizeOnDrop for Drain<'_, T> {}
+
 // #[unstable(feature = "collection_placement",
 //            reason = "placement protocol is subject to change",
 //            issue = "30172")]
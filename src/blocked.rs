@@ -0,0 +1,331 @@
+//! A heap laid out in page-sized blocks of subtrees.
+
+use crate::binary_heap::MaxComparator;
+use crate::queue::PriorityQueue;
+use compare::Compare;
+use std::fmt;
+use std::mem;
+use std::slice;
+
+/// Block size in bytes that [`BlockedBinaryHeap::new`] fits its blocks into.
+///
+/// [`BlockedBinaryHeap::new`]: struct.BlockedBinaryHeap.html#method.new
+pub const DEFAULT_BLOCK_BYTES: usize = 4096;
+
+/// Largest block height accepted by [`BlockedBinaryHeap::with_block_height`].
+const MAX_BLOCK_HEIGHT: u32 = 24;
+
+/// A priority queue whose tree is stored in blocks of complete subtrees.
+///
+/// A [`BinaryHeap`] stores its tree level by level, so the elements visited
+/// by one sift lie further and further apart as it moves down: on a heap
+/// much larger than the CPU caches, nearly every level costs a cache miss,
+/// and a TLB miss too once the levels are more than a page apart.
+///
+/// `BlockedBinaryHeap` stores complete subtrees of `block_height` levels in
+/// contiguous blocks, like the B-heap described by Poul-Henning Kamp, so a
+/// sift touches one block per `block_height` levels. With the default
+/// height, a block fills one 4 KiB page. Blocks are filled one at a time, so
+/// the storage stays dense, and the tree is only about `block_height` levels
+/// deeper than a complete binary tree.
+///
+/// The API mirrors the core of [`BinaryHeap`]; the greatest element
+/// according to the comparator `C` is popped first. With a block height of
+/// 1 the layout is exactly that of `BinaryHeap`.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::{BlockedBinaryHeap, MinComparator};
+///
+/// let mut heap = BlockedBinaryHeap::with_block_height_cmp(2, MinComparator);
+/// heap.extend(vec![7, 3, 9, 1, 8, 2]);
+///
+/// assert_eq!(heap.pop(), Some(1));
+/// assert_eq!(heap.pop(), Some(2));
+/// assert_eq!(heap.peek(), Some(&3));
+/// assert_eq!(heap.into_sorted_vec(), [9, 8, 7, 3]);
+/// ```
+///
+/// [`BinaryHeap`]: struct.BinaryHeap.html
+pub struct BlockedBinaryHeap<T, C = MaxComparator> {
+    data: Vec<T>,
+    height: u32,
+    cmp: C,
+}
+
+impl<T: fmt::Debug, C> fmt::Debug for BlockedBinaryHeap<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.data.iter()).finish()
+    }
+}
+
+impl<T: Clone, C: Clone> Clone for BlockedBinaryHeap<T, C> {
+    fn clone(&self) -> Self {
+        BlockedBinaryHeap {
+            data: self.data.clone(),
+            height: self.height,
+            cmp: self.cmp.clone(),
+        }
+    }
+}
+
+impl<T: Ord> Default for BlockedBinaryHeap<T> {
+    fn default() -> Self {
+        BlockedBinaryHeap::new()
+    }
+}
+
+impl<T: Ord> BlockedBinaryHeap<T> {
+    /// Creates an empty max-heap whose blocks fit in
+    /// [`DEFAULT_BLOCK_BYTES`].
+    ///
+    /// [`DEFAULT_BLOCK_BYTES`]: constant.DEFAULT_BLOCK_BYTES.html
+    #[must_use]
+    pub fn new() -> Self {
+        BlockedBinaryHeap::with_cmp(MaxComparator)
+    }
+
+    /// Creates an empty max-heap with blocks of `height` levels.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `height` is zero or greater than 24.
+    #[must_use]
+    pub fn with_block_height(height: u32) -> Self {
+        BlockedBinaryHeap::with_block_height_cmp(height, MaxComparator)
+    }
+}
+
+impl<T, C> BlockedBinaryHeap<T, C> {
+    /// Creates an empty heap ordered by `cmp`, whose blocks fit in
+    /// [`DEFAULT_BLOCK_BYTES`].
+    ///
+    /// [`DEFAULT_BLOCK_BYTES`]: constant.DEFAULT_BLOCK_BYTES.html
+    pub fn with_cmp(cmp: C) -> Self {
+        let per_block = DEFAULT_BLOCK_BYTES / mem::size_of::<T>().max(1);
+        // The largest height whose 2^height - 1 nodes fit in a block.
+        let height = (usize::BITS - (per_block + 1).leading_zeros() - 1).clamp(1, MAX_BLOCK_HEIGHT);
+        BlockedBinaryHeap::with_block_height_cmp(height, cmp)
+    }
+
+    /// Creates an empty heap ordered by `cmp`, with blocks of `height`
+    /// levels.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `height` is zero or greater than 24.
+    pub fn with_block_height_cmp(height: u32, cmp: C) -> Self {
+        assert!(
+            (1..=MAX_BLOCK_HEIGHT).contains(&height),
+            "block height must be between 1 and 24"
+        );
+        BlockedBinaryHeap {
+            data: Vec::new(),
+            height,
+            cmp,
+        }
+    }
+
+    /// Returns the number of tree levels stored in each block.
+    #[must_use]
+    pub fn block_height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the number of elements in the heap.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns the number of elements the heap can hold without
+    /// reallocating.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.data.capacity()
+    }
+
+    /// Returns an iterator visiting all elements in arbitrary order.
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.data.iter()
+    }
+
+    /// Drops all elements, keeping the allocated capacity.
+    pub fn clear(&mut self) {
+        self.data.clear();
+    }
+
+    /// Consumes the heap and returns the elements in arbitrary order.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_vec(self) -> Vec<T> {
+        self.data
+    }
+
+    /// Returns the greatest element, or `None` if the heap is empty.
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.data.first()
+    }
+
+    /// Returns the number of nodes per block.
+    fn block_len(&self) -> usize {
+        (1 << self.height) - 1
+    }
+
+    /// Returns the number of nodes per block that have both children in the
+    /// same block.
+    fn inner_len(&self) -> usize {
+        (1 << (self.height - 1)) - 1
+    }
+
+    /// Returns the position of the parent of the node at `pos`, which must
+    /// not be the root.
+    ///
+    /// Blocks are numbered in breadth-first order of the tree of blocks, in
+    /// which every block has `2^height` children: two below each of its
+    /// leaves.
+    fn parent(&self, pos: usize) -> usize {
+        let block_len = self.block_len();
+        let (block, local) = (pos / block_len, pos % block_len);
+        if local > 0 {
+            return block * block_len + (local - 1) / 2;
+        }
+        let slot = (block - 1) % (1 << self.height);
+        let parent_block = (block - 1) >> self.height;
+        parent_block * block_len + self.inner_len() + slot / 2
+    }
+
+    /// Returns the positions of the children of the node at `pos`.
+    /// Positions past the end do not exist yet.
+    fn children(&self, pos: usize) -> (usize, usize) {
+        let block_len = self.block_len();
+        let (block, local) = (pos / block_len, pos % block_len);
+        let inner_len = self.inner_len();
+        if local < inner_len {
+            let left = block * block_len + 2 * local + 1;
+            return (left, left + 1);
+        }
+        // The children of a leaf are the roots of two consecutive blocks.
+        // Those of far-out leaves may lie beyond `usize::MAX`; they cannot
+        // exist anyway.
+        let leaf = local - inner_len;
+        let left = block
+            .checked_mul(1 << self.height)
+            .and_then(|b| b.checked_add(1 + 2 * leaf))
+            .and_then(|b| b.checked_mul(block_len))
+            .unwrap_or(usize::MAX);
+        (left, left.saturating_add(block_len))
+    }
+}
+
+impl<T, C: Compare<T>> BlockedBinaryHeap<T, C> {
+    /// Pushes an item onto the heap.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)), touching one block every `block_height` levels.
+    pub fn push(&mut self, item: T) {
+        self.data.push(item);
+        self.sift_up(self.data.len() - 1);
+    }
+
+    /// Removes the greatest element and returns it, or `None` if the heap is
+    /// empty.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)), touching one block every `block_height` levels.
+    pub fn pop(&mut self) -> Option<T> {
+        let item = self.data.pop().map(|mut item| {
+            if !self.data.is_empty() {
+                mem::swap(&mut item, &mut self.data[0]);
+            }
+            item
+        });
+        self.sift_down(0);
+        item
+    }
+
+    /// Consumes the heap and returns its elements in ascending order.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        let mut sorted = Vec::with_capacity(self.len());
+        while let Some(item) = self.pop() {
+            sorted.push(item);
+        }
+        sorted.reverse();
+        sorted
+    }
+
+    // Sifting swaps elements instead of moving a hole around, so the heap
+    // stays a valid permutation even if the comparator panics.
+
+    fn sift_up(&mut self, mut pos: usize) {
+        while pos > 0 {
+            let parent = self.parent(pos);
+            if self.cmp.compares_le(&self.data[pos], &self.data[parent]) {
+                break;
+            }
+            self.data.swap(pos, parent);
+            pos = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut pos: usize) {
+        let end = self.data.len();
+        loop {
+            let (left, right) = self.children(pos);
+            if left >= end {
+                break;
+            }
+            let child = if right < end && self.cmp.compares_le(&self.data[left], &self.data[right])
+            {
+                right
+            } else {
+                left
+            };
+            if self.cmp.compares_ge(&self.data[pos], &self.data[child]) {
+                break;
+            }
+            self.data.swap(pos, child);
+            pos = child;
+        }
+    }
+}
+
+impl<T, C: Compare<T>> Extend<T> for BlockedBinaryHeap<T, C> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+impl<T, C: Compare<T>> PriorityQueue<T> for BlockedBinaryHeap<T, C> {
+    fn push(&mut self, item: T) {
+        BlockedBinaryHeap::push(self, item);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        BlockedBinaryHeap::pop(self)
+    }
+
+    fn peek(&self) -> Option<&T> {
+        BlockedBinaryHeap::peek(self)
+    }
+
+    fn len(&self) -> usize {
+        BlockedBinaryHeap::len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        BlockedBinaryHeap::is_empty(self)
+    }
+}
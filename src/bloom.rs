@@ -0,0 +1,291 @@
+//! A heap with a counting Bloom filter for fast membership checks.
+
+use crate::binary_heap::{BinaryHeap, MaxComparator};
+use crate::queue::PriorityQueue;
+use compare::Compare;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::slice;
+
+/// Number of counters probed per element.
+const PROBES: u64 = 4;
+/// Counters per element, which keeps false positives near 1%.
+const COUNTERS_PER_ELEMENT: usize = 10;
+const MIN_COUNTERS: usize = 64;
+
+/// A counting Bloom filter: counters that saturate at `u8::MAX` stay there,
+/// so that removals never produce false negatives.
+struct Filter<S> {
+    counters: Vec<u8>,
+    hasher: S,
+}
+
+impl<S: BuildHasher> Filter<S> {
+    fn hash<T: Hash + ?Sized>(&self, item: &T) -> u64 {
+        let mut hasher = self.hasher.build_hasher();
+        item.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Returns the counters of the item with hash `hash`, by double hashing.
+    fn slots(&self, hash: u64) -> impl Iterator<Item = usize> {
+        let mask = self.counters.len() as u64 - 1;
+        let (h1, h2) = (hash, (hash >> 32) | 1);
+        (0..PROBES).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) & mask) as usize)
+    }
+
+    fn insert<T: Hash + ?Sized>(&mut self, item: &T) {
+        for slot in self.slots(self.hash(item)) {
+            let counter = &mut self.counters[slot];
+            *counter = counter.saturating_add(1);
+        }
+    }
+
+    fn remove<T: Hash + ?Sized>(&mut self, item: &T) {
+        for slot in self.slots(self.hash(item)) {
+            let counter = &mut self.counters[slot];
+            if *counter != u8::MAX {
+                *counter -= 1;
+            }
+        }
+    }
+
+    fn might_contain<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        self.slots(self.hash(item))
+            .all(|slot| self.counters[slot] != 0)
+    }
+
+    /// Resets the filter with room for `len` elements and inserts `items`.
+    fn rebuild<'a, T: Hash + 'a>(&mut self, len: usize, items: impl Iterator<Item = &'a T>) {
+        let counters = (len * COUNTERS_PER_ELEMENT)
+            .next_power_of_two()
+            .max(MIN_COUNTERS);
+        self.counters.clear();
+        self.counters.resize(counters, 0);
+        for item in items {
+            self.insert(item);
+        }
+    }
+}
+
+/// A [`BinaryHeap`] with a counting Bloom filter of its elements, so that
+/// [`contains`] answers most misses in *O*(1) instead of scanning the heap.
+///
+/// Every push adds the element to the filter and every removal, including
+/// [`pop`] and [`retain`], takes it out again, so the filter never reports
+/// an element in the heap as absent. When it reports a possible hit,
+/// `contains` confirms it with a scan, which makes it exact. With about ten
+/// one-byte counters per element, which the filter keeps by doubling and
+/// refilling itself as the heap grows, around 1% of misses need the scan.
+///
+/// This suits workloads that check for an element before pushing it, to
+/// avoid duplicates, on large heaps.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::BloomBinaryHeap;
+///
+/// let mut heap = BloomBinaryHeap::new();
+/// for url in ["/a", "/b", "/a", "/c"] {
+///     if !heap.contains(&url) {
+///         heap.push(url);
+///     }
+/// }
+/// assert_eq!(heap.len(), 3);
+///
+/// assert_eq!(heap.pop(), Some("/c"));
+/// assert!(!heap.contains(&"/c"));
+/// ```
+///
+/// [`BinaryHeap`]: struct.BinaryHeap.html
+/// [`contains`]: BloomBinaryHeap::contains
+/// [`pop`]: BloomBinaryHeap::pop
+/// [`retain`]: BloomBinaryHeap::retain
+pub struct BloomBinaryHeap<T, C = MaxComparator, S = RandomState> {
+    heap: BinaryHeap<T, C>,
+    filter: Filter<S>,
+}
+
+impl<T: fmt::Debug, C, S> fmt::Debug for BloomBinaryHeap<T, C, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.heap.iter()).finish()
+    }
+}
+
+impl<T: Ord + Hash> Default for BloomBinaryHeap<T> {
+    fn default() -> Self {
+        BloomBinaryHeap::new()
+    }
+}
+
+impl<T: Ord + Hash> BloomBinaryHeap<T> {
+    /// Creates an empty max-heap.
+    #[must_use]
+    pub fn new() -> Self {
+        BloomBinaryHeap::with_cmp(MaxComparator)
+    }
+}
+
+impl<T: Hash, C: Compare<T>> BloomBinaryHeap<T, C> {
+    /// Creates an empty heap ordered by `cmp`.
+    pub fn with_cmp(cmp: C) -> Self {
+        BloomBinaryHeap::with_cmp_and_hasher(cmp, RandomState::new())
+    }
+}
+
+impl<T, C, S> BloomBinaryHeap<T, C, S> {
+    /// Returns the number of elements in the heap.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Returns the greatest element, or `None` if the heap is empty.
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.heap.peek()
+    }
+
+    /// Returns an iterator visiting all elements in arbitrary order.
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.heap.as_slice().iter()
+    }
+
+    /// Returns the underlying heap, dropping the filter.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_heap(self) -> BinaryHeap<T, C> {
+        self.heap
+    }
+}
+
+impl<T: Hash, C: Compare<T>, S: BuildHasher> BloomBinaryHeap<T, C, S> {
+    /// Creates an empty heap ordered by `cmp` whose filter hashes with
+    /// `hasher`.
+    pub fn with_cmp_and_hasher(cmp: C, hasher: S) -> Self {
+        BloomBinaryHeap::from_heap_and_hasher(BinaryHeap::new_with_cmp(cmp), hasher)
+    }
+
+    /// Adds a filter to `heap`, hashing with `hasher`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*n*).
+    pub fn from_heap_and_hasher(heap: BinaryHeap<T, C>, hasher: S) -> Self {
+        let mut filter = Filter {
+            counters: Vec::new(),
+            hasher,
+        };
+        filter.rebuild(heap.len(), heap.iter());
+        BloomBinaryHeap { heap, filter }
+    }
+
+    /// Pushes an item onto the heap and the filter.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)), plus hashing `item`, amortized over the occasional
+    /// refill of a filter that grew full.
+    pub fn push(&mut self, item: T) {
+        self.filter.insert(&item);
+        self.heap.push(item);
+        if self.heap.len() * COUNTERS_PER_ELEMENT > self.filter.counters.len() {
+            self.filter.rebuild(self.heap.len() * 2, self.heap.iter());
+        }
+    }
+
+    /// Removes the greatest element and returns it, or `None` if the heap is
+    /// empty.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)), plus hashing the element.
+    pub fn pop(&mut self) -> Option<T> {
+        let item = self.heap.pop()?;
+        self.filter.remove(&item);
+        Some(item)
+    }
+
+    /// Returns `false` if `item` is certainly not in the heap, and `true` if
+    /// it may be, in *O*(1).
+    #[must_use]
+    pub fn might_contain(&self, item: &T) -> bool {
+        self.filter.might_contain(item)
+    }
+
+    /// Checks if the heap contains an element equal to `item`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(1) when the filter rules `item` out, *O*(*n*) otherwise.
+    #[must_use]
+    pub fn contains(&self, item: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.might_contain(item) && self.heap.iter().any(|x| x == item)
+    }
+
+    /// Keeps only the elements for which `f` returns `true`, and takes the
+    /// others out of the filter.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*n*).
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        let filter = &mut self.filter;
+        self.heap.batch_mut(|items| {
+            items.retain(|item| {
+                let keep = f(item);
+                if !keep {
+                    filter.remove(item);
+                }
+                keep
+            });
+            true
+        });
+    }
+
+    /// Drops all elements and empties the filter.
+    pub fn clear(&mut self) {
+        self.heap.clear();
+        self.filter.rebuild(0, self.heap.iter());
+    }
+}
+
+impl<T: Hash, C: Compare<T>, S: BuildHasher> Extend<T> for BloomBinaryHeap<T, C, S> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+impl<T: Hash, C: Compare<T>, S: BuildHasher> PriorityQueue<T> for BloomBinaryHeap<T, C, S> {
+    fn push(&mut self, item: T) {
+        BloomBinaryHeap::push(self, item);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        BloomBinaryHeap::pop(self)
+    }
+
+    fn peek(&self) -> Option<&T> {
+        BloomBinaryHeap::peek(self)
+    }
+
+    fn len(&self) -> usize {
+        BloomBinaryHeap::len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        BloomBinaryHeap::is_empty(self)
+    }
+}
@@ -0,0 +1,322 @@
+//! A heap bounded by the memory its elements use.
+
+use crate::binary_heap::MaxComparator;
+use compare::Compare;
+use std::fmt;
+use std::mem;
+
+/// An element and the cost it was charged when pushed.
+struct Entry<T> {
+    item: T,
+    cost: usize,
+}
+
+/// A priority queue that keeps the approximate byte cost of its elements
+/// within a budget, evicting the least elements when a push exceeds it.
+///
+/// Each element is charged a cost when pushed: `size_of::<T>()` by default,
+/// or whatever the closure given to [`with_cost`] returns, for instance to
+/// add the heap memory a `String` owns. The cost is remembered, so the
+/// closure is called once per element.
+///
+/// The elements are kept in a min-max heap, so both the greatest element,
+/// which [`pop`] removes, and the least, which is evicted first, are found in
+/// *O*(1) and removed in *O*(log(*n*)).
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::BudgetedHeap;
+///
+/// let cost = |s: &String| std::mem::size_of::<String>() + s.capacity();
+/// let mut heap = BudgetedHeap::with_cost(100, cost);
+/// assert!(heap.push("b".repeat(40)).is_empty());
+/// assert!(heap.push("c".repeat(10)).is_empty());
+///
+/// // Pushing past the budget evicts the least element.
+/// let evicted = heap.push("a".repeat(20));
+/// assert_eq!(evicted, ["a".repeat(20)]);
+/// assert!(heap.used() <= heap.budget());
+/// assert_eq!(heap.pop(), Some("c".repeat(10)));
+/// ```
+///
+/// [`with_cost`]: BudgetedHeap::with_cost
+/// [`pop`]: BudgetedHeap::pop
+pub struct BudgetedHeap<T, C = MaxComparator, F = fn(&T) -> usize> {
+    data: Vec<Entry<T>>,
+    cmp: C,
+    cost: F,
+    budget: usize,
+    used: usize,
+}
+
+impl<T: fmt::Debug, C, F> fmt::Debug for BudgetedHeap<T, C, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BudgetedHeap")
+            .field(
+                "items",
+                &self.data.iter().map(|e| &e.item).collect::<Vec<_>>(),
+            )
+            .field("used", &self.used)
+            .field("budget", &self.budget)
+            .finish()
+    }
+}
+
+fn size_of_item<T>(_: &T) -> usize {
+    mem::size_of::<T>()
+}
+
+impl<T: Ord> BudgetedHeap<T> {
+    /// Creates an empty max-heap that charges `size_of::<T>()` bytes per
+    /// element against `budget`.
+    #[must_use]
+    pub fn new(budget: usize) -> Self {
+        BudgetedHeap::with_cost_cmp(budget, size_of_item, MaxComparator)
+    }
+}
+
+impl<T: Ord, F: Fn(&T) -> usize> BudgetedHeap<T, MaxComparator, F> {
+    /// Creates an empty max-heap that charges each element what `cost`
+    /// returns for it against `budget`.
+    #[must_use]
+    pub fn with_cost(budget: usize, cost: F) -> Self {
+        BudgetedHeap::with_cost_cmp(budget, cost, MaxComparator)
+    }
+}
+
+impl<T, C, F> BudgetedHeap<T, C, F> {
+    /// Returns the number of elements in the heap.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns the total cost of the elements in the heap.
+    #[must_use]
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    /// Returns the budget.
+    #[must_use]
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    /// Returns an iterator visiting all elements in arbitrary order.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &T> {
+        self.data.iter().map(|e| &e.item)
+    }
+
+    /// Drops all elements.
+    pub fn clear(&mut self) {
+        self.data.clear();
+        self.used = 0;
+    }
+
+    /// Consumes the heap and returns its elements in arbitrary order.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_vec(self) -> Vec<T> {
+        self.data.into_iter().map(|e| e.item).collect()
+    }
+}
+
+impl<T, C: Compare<T>, F: Fn(&T) -> usize> BudgetedHeap<T, C, F> {
+    /// Creates an empty heap ordered by `cmp` that charges each element what
+    /// `cost` returns for it against `budget`.
+    #[must_use]
+    pub fn with_cost_cmp(budget: usize, cost: F, cmp: C) -> Self {
+        BudgetedHeap {
+            data: Vec::new(),
+            cmp,
+            cost,
+            budget,
+            used: 0,
+        }
+    }
+
+    /// Pushes an item, then evicts the least elements until the elements fit
+    /// in the budget again, and returns them, least first.
+    ///
+    /// The item itself is evicted if it is the least element, or if its cost
+    /// alone exceeds the budget.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)) per element pushed or evicted.
+    pub fn push(&mut self, item: T) -> Vec<T> {
+        let cost = (self.cost)(&item);
+        self.used += cost;
+        self.data.push(Entry { item, cost });
+        self.bubble_up(self.data.len() - 1);
+        self.evict()
+    }
+
+    /// Returns the greatest element, or `None` if the heap is empty.
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.max_index().map(|i| &self.data[i].item)
+    }
+
+    /// Returns the least element, the next to be evicted, or `None` if the
+    /// heap is empty.
+    #[must_use]
+    pub fn peek_worst(&self) -> Option<&T> {
+        self.data.first().map(|e| &e.item)
+    }
+
+    /// Removes the greatest element and returns it, or `None` if the heap is
+    /// empty.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)).
+    pub fn pop(&mut self) -> Option<T> {
+        let i = self.max_index()?;
+        Some(self.remove(i))
+    }
+
+    /// Removes the least element and returns it, or `None` if the heap is
+    /// empty.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)).
+    pub fn pop_worst(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        Some(self.remove(0))
+    }
+
+    /// Changes the budget, and evicts the least elements until the elements
+    /// fit in it, returning them, least first.
+    pub fn set_budget(&mut self, budget: usize) -> Vec<T> {
+        self.budget = budget;
+        self.evict()
+    }
+
+    fn evict(&mut self) -> Vec<T> {
+        let mut evicted = Vec::new();
+        while self.used > self.budget {
+            match self.pop_worst() {
+                Some(item) => evicted.push(item),
+                None => break,
+            }
+        }
+        evicted
+    }
+
+    // The rest is a min-max heap: the elements on even levels, starting with
+    // the root, are less than their descendants, and those on odd levels are
+    // greater. See Atkinson et al., "Min-Max Heaps and Generalized Priority
+    // Queues", 1986.
+
+    fn less(&self, a: usize, b: usize) -> bool {
+        self.cmp.compares_lt(&self.data[a].item, &self.data[b].item)
+    }
+
+    /// Compares `a` and `b` as the level of `at` orders them: `a < b` on a
+    /// min level, `a > b` on a max level.
+    fn before(&self, at: usize, a: usize, b: usize) -> bool {
+        if is_min_level(at) {
+            self.less(a, b)
+        } else {
+            self.less(b, a)
+        }
+    }
+
+    fn max_index(&self) -> Option<usize> {
+        match self.data.len() {
+            0 => None,
+            1 => Some(0),
+            2 => Some(1),
+            _ => Some(if self.less(1, 2) { 2 } else { 1 }),
+        }
+    }
+
+    fn remove(&mut self, i: usize) -> T {
+        let entry = self.data.swap_remove(i);
+        self.used -= entry.cost;
+        if i < self.data.len() {
+            self.trickle_down(i);
+        }
+        entry.item
+    }
+
+    fn bubble_up(&mut self, i: usize) {
+        if i == 0 {
+            return;
+        }
+        let parent = (i - 1) / 2;
+        if self.before(parent, i, parent) {
+            // The element belongs on the levels of its parent.
+            self.data.swap(i, parent);
+            self.bubble_up_levels(parent);
+        } else {
+            self.bubble_up_levels(i);
+        }
+    }
+
+    fn bubble_up_levels(&mut self, mut i: usize) {
+        while i >= 3 {
+            let grandparent = ((i - 1) / 2 - 1) / 2;
+            if !self.before(i, i, grandparent) {
+                break;
+            }
+            self.data.swap(i, grandparent);
+            i = grandparent;
+        }
+    }
+
+    fn trickle_down(&mut self, mut i: usize) {
+        let len = self.data.len();
+        loop {
+            let first_child = 2 * i + 1;
+            if first_child >= len {
+                return;
+            }
+            // The extreme of the children and grandchildren, which are
+            // contiguous in two runs.
+            let candidates =
+                (first_child..(first_child + 2).min(len)).chain(4 * i + 3..(4 * i + 7).min(len));
+            let m = candidates
+                .reduce(|m, c| if self.before(i, c, m) { c } else { m })
+                .unwrap();
+            if !self.before(i, m, i) {
+                return;
+            }
+            self.data.swap(i, m);
+            if m <= first_child + 1 {
+                // A child, on the other kind of level, which has no
+                // descendants left to check.
+                return;
+            }
+            let parent = (m - 1) / 2;
+            if self.before(parent, m, parent) {
+                self.data.swap(m, parent);
+            }
+            i = m;
+        }
+    }
+}
+
+fn is_min_level(i: usize) -> bool {
+    (usize::BITS - (i + 1).leading_zeros()) % 2 == 1
+}
+
+/// Evicts the least elements as needed after each push.
+impl<T, C: Compare<T>, F: Fn(&T) -> usize> Extend<T> for BudgetedHeap<T, C, F> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
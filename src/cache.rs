@@ -0,0 +1,327 @@
+//! A bounded cache that evicts its lowest-priority entry, built on
+//! [`BinaryHeap`].
+//!
+//! [`BinaryHeap`]: struct.BinaryHeap.html
+
+use crate::binary_heap::BinaryHeap;
+use compare::Compare;
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::mem;
+
+struct Slot<V, P> {
+    value: V,
+    priority: P,
+    generation: u64,
+}
+
+struct HeapEntry<K, P> {
+    priority: P,
+    generation: u64,
+    key: K,
+}
+
+/// Orders entries by lowest priority, then by least recent update.
+#[derive(Clone, Copy, Default, Debug)]
+struct EvictionOrder;
+
+impl<K, P: Ord> Compare<HeapEntry<K, P>> for EvictionOrder {
+    fn compare(&self, a: &HeapEntry<K, P>, b: &HeapEntry<K, P>) -> Ordering {
+        b.priority
+            .cmp(&a.priority)
+            .then_with(|| b.generation.cmp(&a.generation))
+    }
+}
+
+/// A bounded key-value cache that evicts the entry with the lowest priority.
+///
+/// Inserting into a full cache evicts the entry with the lowest priority;
+/// among equal priorities, the entry that was inserted or reprioritized
+/// least recently goes first. Priorities can be changed with
+/// [`reprioritize`] and [`touch`], which makes the cache a building block for
+/// cost-aware policies such as GreedyDual-Size: use
+/// [`last_evicted_priority`] as the inflation value *L* when computing new
+/// priorities.
+///
+/// Priority updates are lazy: the superseded heap entry stays behind and is
+/// skipped when it reaches the top. The heap is compacted once stale entries
+/// outnumber live ones.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::PriorityCache;
+///
+/// let mut cache = PriorityCache::new(2);
+/// cache.insert("a", 1, 10);
+/// cache.insert("b", 2, 20);
+///
+/// // "a" is hot: raise its priority above "b".
+/// cache.touch(&"a", |_value, priority| priority + 15);
+///
+/// assert_eq!(cache.insert("c", 3, 5), Some(("b", 2, 20)));
+/// assert!(cache.contains_key(&"a"));
+/// assert_eq!(cache.last_evicted_priority(), Some(&20));
+/// ```
+///
+/// [`reprioritize`]: PriorityCache::reprioritize
+/// [`touch`]: PriorityCache::touch
+/// [`last_evicted_priority`]: PriorityCache::last_evicted_priority
+pub struct PriorityCache<K, V, P> {
+    map: HashMap<K, Slot<V, P>>,
+    heap: BinaryHeap<HeapEntry<K, P>, EvictionOrder>,
+    capacity: usize,
+    next_generation: u64,
+    last_evicted: Option<P>,
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, P: fmt::Debug> fmt::Debug for PriorityCache<K, V, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.map
+                    .iter()
+                    .map(|(k, slot)| (k, (&slot.value, &slot.priority))),
+            )
+            .finish()
+    }
+}
+
+impl<K, V, P> PriorityCache<K, V, P>
+where
+    K: Hash + Eq + Clone,
+    P: Ord + Clone,
+{
+    /// Creates an empty cache holding at most `capacity` entries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "cache capacity must be positive");
+        PriorityCache {
+            map: HashMap::with_capacity(capacity),
+            heap: BinaryHeap::from_vec(Vec::with_capacity(capacity)),
+            capacity,
+            next_generation: 0,
+            last_evicted: None,
+        }
+    }
+
+    /// Inserts `value` under `key` with the given priority.
+    ///
+    /// If `key` is already present, its value and priority are replaced and
+    /// nothing is evicted. Otherwise, if the cache is full, the entry with the
+    /// lowest priority is evicted first and returned.
+    pub fn insert(&mut self, key: K, value: V, priority: P) -> Option<(K, V, P)> {
+        let generation = self.bump_generation();
+        if let Some(slot) = self.map.get_mut(&key) {
+            slot.value = value;
+            slot.priority = priority.clone();
+            slot.generation = generation;
+            self.push_entry(key, priority, generation);
+            self.purge_stale();
+            return None;
+        }
+
+        let evicted = if self.map.len() >= self.capacity {
+            self.pop_lowest()
+        } else {
+            None
+        };
+        self.map.insert(
+            key.clone(),
+            Slot {
+                value,
+                priority: priority.clone(),
+                generation,
+            },
+        );
+        self.push_entry(key, priority, generation);
+        evicted
+    }
+
+    /// Returns a reference to the value under `key` without changing its
+    /// priority.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.get(key).map(|slot| &slot.value)
+    }
+
+    /// Returns a mutable reference to the value under `key` without changing
+    /// its priority.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.get_mut(key).map(|slot| &mut slot.value)
+    }
+
+    /// Returns the priority of the entry under `key`.
+    pub fn priority<Q>(&self, key: &Q) -> Option<&P>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.get(key).map(|slot| &slot.priority)
+    }
+
+    /// Returns `true` if the cache holds an entry under `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.contains_key(key)
+    }
+
+    /// Sets the priority of the entry under `key`, returning the old one, or
+    /// `None` if the key is absent.
+    pub fn reprioritize<Q>(&mut self, key: &Q, priority: P) -> Option<P>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let key = self.map.get_key_value(key)?.0.clone();
+        let generation = self.bump_generation();
+        let slot = self.map.get_mut::<K>(&key)?;
+        let old = mem::replace(&mut slot.priority, priority.clone());
+        slot.generation = generation;
+        self.push_entry(key, priority, generation);
+        self.purge_stale();
+        Some(old)
+    }
+
+    /// Records a hit on `key`, recomputing its priority from its value and
+    /// current priority with `f`. Returns the value, or `None` if the key is
+    /// absent.
+    pub fn touch<Q, F>(&mut self, key: &Q, f: F) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        F: FnOnce(&V, &P) -> P,
+    {
+        let slot = self.map.get(key)?;
+        let priority = f(&slot.value, &slot.priority);
+        self.reprioritize(key, priority);
+        self.get(key)
+    }
+
+    /// Removes the entry under `key` and returns its value and priority.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<(V, P)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let slot = self.map.remove(key)?;
+        self.purge_stale();
+        Some((slot.value, slot.priority))
+    }
+
+    /// Returns the entry that would be evicted next.
+    #[must_use]
+    pub fn peek_lowest(&self) -> Option<(&K, &V, &P)> {
+        // `purge_stale` keeps the top of the heap live.
+        let entry = self.heap.peek()?;
+        let slot = &self.map[&entry.key];
+        Some((&entry.key, &slot.value, &slot.priority))
+    }
+
+    /// Evicts the entry with the lowest priority and returns it.
+    pub fn pop_lowest(&mut self) -> Option<(K, V, P)> {
+        let entry = self.heap.pop()?;
+        let slot = self
+            .map
+            .remove(&entry.key)
+            .expect("top of the heap is always live");
+        self.last_evicted = Some(slot.priority.clone());
+        self.purge_stale();
+        Some((entry.key, slot.value, slot.priority))
+    }
+
+    /// Returns the priority of the most recently evicted entry.
+    ///
+    /// This is the inflation value *L* of GreedyDual-style policies.
+    #[must_use]
+    pub fn last_evicted_priority(&self) -> Option<&P> {
+        self.last_evicted.as_ref()
+    }
+
+    fn bump_generation(&mut self) -> u64 {
+        let generation = self.next_generation;
+        self.next_generation += 1;
+        generation
+    }
+
+    fn push_entry(&mut self, key: K, priority: P, generation: u64) {
+        self.heap.push(HeapEntry {
+            priority,
+            generation,
+            key,
+        });
+        if self.heap.len() > 2 * self.map.len() + 16 {
+            self.compact();
+        }
+    }
+
+    fn is_live(&self, entry: &HeapEntry<K, P>) -> bool {
+        self.map
+            .get(&entry.key)
+            .map_or(false, |slot| slot.generation == entry.generation)
+    }
+
+    /// Pops stale entries until the top of the heap is live.
+    fn purge_stale(&mut self) {
+        while let Some(top) = self.heap.peek() {
+            if self.is_live(top) {
+                break;
+            }
+            self.heap.pop();
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn heap_len(&self) -> usize {
+        self.heap.len()
+    }
+
+    fn compact(&mut self) {
+        let entries = self
+            .map
+            .iter()
+            .map(|(key, slot)| HeapEntry {
+                priority: slot.priority.clone(),
+                generation: slot.generation,
+                key: key.clone(),
+            })
+            .collect();
+        self.heap = BinaryHeap::from_vec(entries);
+    }
+}
+
+impl<K, V, P> PriorityCache<K, V, P> {
+    /// Returns the maximum number of entries.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of entries.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Checks if the cache is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
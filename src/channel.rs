@@ -0,0 +1,260 @@
+//! A multi-producer, single-consumer channel that delivers messages in
+//! priority order.
+
+use crate::binary_heap::{BinaryHeap, MaxComparator};
+use crate::util;
+use compare::Compare;
+use std::fmt;
+use std::sync::mpsc::{RecvError, RecvTimeoutError, SendError, TryRecvError};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+struct State<T, C> {
+    heap: BinaryHeap<T, C>,
+    senders: usize,
+    receiver_alive: bool,
+}
+
+struct Shared<T, C> {
+    state: Mutex<State<T, C>>,
+    available: Condvar,
+}
+
+impl<T, C> Shared<T, C> {
+    fn lock(&self) -> MutexGuard<'_, State<T, C>> {
+        util::lock(&self.state)
+    }
+}
+
+/// Creates a priority channel whose receiver delivers the greatest pending
+/// message according to `C`.
+///
+/// The channel is unbounded and behaves like [`std::sync::mpsc::channel`],
+/// including its error types, except that messages are received in priority
+/// order rather than in the order they were sent.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::{priority_channel, MinComparator};
+/// use std::thread;
+///
+/// let (tx, rx) = priority_channel::<u32, MinComparator>();
+/// let producers: Vec<_> = (0..2)
+///     .map(|t| {
+///         let tx = tx.clone();
+///         thread::spawn(move || {
+///             for i in 0..3 {
+///                 tx.send(t * 10 + i).unwrap();
+///             }
+///         })
+///     })
+///     .collect();
+/// for p in producers {
+///     p.join().unwrap();
+/// }
+/// drop(tx);
+///
+/// let received: Vec<_> = rx.iter().collect();
+/// assert_eq!(received, [0, 1, 2, 10, 11, 12]);
+/// ```
+///
+/// [`std::sync::mpsc::channel`]: https://doc.rust-lang.org/std/sync/mpsc/fn.channel.html
+pub fn priority_channel<T, C: Compare<T> + Default>(
+) -> (PrioritySender<T, C>, PriorityReceiver<T, C>) {
+    priority_channel_cmp(C::default())
+}
+
+/// Creates a priority channel ordered by the given comparator.
+///
+/// See [`priority_channel`] for details.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::{priority_channel_cmp, KeyComparator};
+///
+/// let (tx, rx) = priority_channel_cmp(KeyComparator(|job: &(&str, u8)| job.1));
+/// tx.send(("backup", 1)).unwrap();
+/// tx.send(("page oncall", 9)).unwrap();
+/// assert_eq!(rx.recv(), Ok(("page oncall", 9)));
+/// ```
+///
+/// [`priority_channel`]: fn.priority_channel.html
+pub fn priority_channel_cmp<T, C: Compare<T>>(
+    cmp: C,
+) -> (PrioritySender<T, C>, PriorityReceiver<T, C>) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            heap: BinaryHeap::from_vec_cmp(vec![], cmp),
+            senders: 1,
+            receiver_alive: true,
+        }),
+        available: Condvar::new(),
+    });
+    (
+        PrioritySender {
+            shared: Arc::clone(&shared),
+        },
+        PriorityReceiver { shared },
+    )
+}
+
+/// The sending half of a [`priority_channel`].
+///
+/// Senders can be cloned to send from several threads.
+///
+/// [`priority_channel`]: fn.priority_channel.html
+pub struct PrioritySender<T, C = MaxComparator> {
+    shared: Arc<Shared<T, C>>,
+}
+
+impl<T, C> fmt::Debug for PrioritySender<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrioritySender").finish()
+    }
+}
+
+impl<T, C> Clone for PrioritySender<T, C> {
+    fn clone(&self) -> Self {
+        self.shared.lock().senders += 1;
+        PrioritySender {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T, C> Drop for PrioritySender<T, C> {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.senders -= 1;
+        if state.senders == 0 {
+            drop(state);
+            self.shared.available.notify_all();
+        }
+    }
+}
+
+impl<T, C: Compare<T>> PrioritySender<T, C> {
+    /// Sends a message, waking the receiver if it is waiting.
+    ///
+    /// Returns the message back if the receiver has been dropped.
+    pub fn send(&self, message: T) -> Result<(), SendError<T>> {
+        let mut state = self.shared.lock();
+        if !state.receiver_alive {
+            return Err(SendError(message));
+        }
+        state.heap.push(message);
+        drop(state);
+        self.shared.available.notify_one();
+        Ok(())
+    }
+}
+
+/// The receiving half of a [`priority_channel`].
+///
+/// [`priority_channel`]: fn.priority_channel.html
+pub struct PriorityReceiver<T, C = MaxComparator> {
+    shared: Arc<Shared<T, C>>,
+}
+
+impl<T, C> fmt::Debug for PriorityReceiver<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PriorityReceiver").finish()
+    }
+}
+
+impl<T, C> Drop for PriorityReceiver<T, C> {
+    fn drop(&mut self) {
+        self.shared.lock().receiver_alive = false;
+    }
+}
+
+impl<T, C: Compare<T>> PriorityReceiver<T, C> {
+    /// Returns the greatest pending message without waiting.
+    ///
+    /// Fails with [`TryRecvError::Empty`] if no message is pending, or with
+    /// [`TryRecvError::Disconnected`] if additionally every sender has been
+    /// dropped.
+    ///
+    /// [`TryRecvError::Empty`]: https://doc.rust-lang.org/std/sync/mpsc/enum.TryRecvError.html
+    /// [`TryRecvError::Disconnected`]: https://doc.rust-lang.org/std/sync/mpsc/enum.TryRecvError.html
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        let mut state = self.shared.lock();
+        match state.heap.pop() {
+            Some(message) => Ok(message),
+            None if state.senders == 0 => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    /// Waits for a message and returns the greatest pending one.
+    ///
+    /// Fails once no message is pending and every sender has been dropped.
+    pub fn recv(&self) -> Result<T, RecvError> {
+        let mut state = self.shared.lock();
+        loop {
+            if let Some(message) = state.heap.pop() {
+                return Ok(message);
+            }
+            if state.senders == 0 {
+                return Err(RecvError);
+            }
+            state = self
+                .shared
+                .available
+                .wait(state)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    /// Like [`recv`], but waits for at most `timeout`.
+    ///
+    /// [`recv`]: PriorityReceiver::recv
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.shared.lock();
+        loop {
+            if let Some(message) = state.heap.pop() {
+                return Ok(message);
+            }
+            if state.senders == 0 {
+                return Err(RecvTimeoutError::Disconnected);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(RecvTimeoutError::Timeout);
+            }
+            state = self
+                .shared
+                .available
+                .wait_timeout(state, deadline - now)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+    }
+
+    /// Returns an iterator that waits for messages and ends once every
+    /// sender has been dropped and no message is pending.
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        std::iter::from_fn(move || self.recv().ok())
+    }
+
+    /// Returns an iterator over the messages that are pending right now,
+    /// in priority order, without waiting.
+    pub fn try_iter(&self) -> impl Iterator<Item = T> + '_ {
+        std::iter::from_fn(move || self.try_recv().ok())
+    }
+
+    /// Returns the number of pending messages.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.shared.lock().heap.len()
+    }
+
+    /// Checks if no message is pending.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
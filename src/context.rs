@@ -0,0 +1,253 @@
+//! A heap whose comparisons read an external context.
+
+use crate::array::{sift_down_slice, sift_up_slice};
+use compare::Compare;
+use std::cmp::Ordering;
+use std::fmt;
+use std::mem;
+use std::slice;
+
+/// A comparator that is handed a context, such as a lookup table, with each
+/// comparison.
+///
+/// This is implemented for closures taking the two elements and the
+/// context, and by [`ContextKeyComparator`].
+///
+/// [`ContextKeyComparator`]: struct.ContextKeyComparator.html
+pub trait ContextCompare<T, Ctx: ?Sized> {
+    /// Compares `a` and `b` in the context `ctx`.
+    fn compare(&self, a: &T, b: &T, ctx: &Ctx) -> Ordering;
+}
+
+impl<T, Ctx: ?Sized, F> ContextCompare<T, Ctx> for F
+where
+    F: Fn(&T, &T, &Ctx) -> Ordering,
+{
+    fn compare(&self, a: &T, b: &T, ctx: &Ctx) -> Ordering {
+        self(a, b, ctx)
+    }
+}
+
+/// The context comparator ordered by a key computed from each element and
+/// the context.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct ContextKeyComparator<F>(pub F);
+
+impl<K: Ord, T, Ctx: ?Sized, F> ContextCompare<T, Ctx> for ContextKeyComparator<F>
+where
+    F: Fn(&T, &Ctx) -> K,
+{
+    fn compare(&self, a: &T, b: &T, ctx: &Ctx) -> Ordering {
+        self.0(a, ctx).cmp(&self.0(b, ctx))
+    }
+}
+
+/// Binds a context to a context comparator for the duration of one
+/// operation.
+struct InContext<'a, C, Ctx: ?Sized> {
+    cmp: &'a C,
+    ctx: &'a Ctx,
+}
+
+impl<T, C: ContextCompare<T, Ctx>, Ctx: ?Sized> Compare<T> for InContext<'_, C, Ctx> {
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        self.cmp.compare(a, b, self.ctx)
+    }
+}
+
+/// A binary heap whose comparator reads a context that is passed to each
+/// operation instead of being stored in the heap or in the elements.
+///
+/// The priorities can then be derived from state that lives elsewhere and
+/// changes between operations, such as the world state scored by a game AI.
+/// Every operation that compares elements takes the context. As long as the
+/// order of the elements is the same in each context passed, the heap works
+/// like a [`BinaryHeap`]; after a change that reorders elements already in
+/// the heap, call [`rebuild`] with the new context.
+///
+/// The greatest element according to the comparator is popped first.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::{ContextBinaryHeap, ContextKeyComparator};
+/// use std::collections::HashMap;
+///
+/// // Actions scored by the current world state.
+/// let mut utility = HashMap::new();
+/// utility.insert("eat", 3);
+/// utility.insert("flee", 1);
+/// utility.insert("explore", 2);
+///
+/// let mut actions = ContextBinaryHeap::new(ContextKeyComparator(
+///     |action: &&str, utility: &HashMap<&str, i32>| utility[action],
+/// ));
+/// for action in ["eat", "flee", "explore"] {
+///     actions.push(action, &utility);
+/// }
+/// assert_eq!(actions.peek(), Some(&"eat"));
+///
+/// // A predator shows up.
+/// utility.insert("flee", 10);
+/// actions.rebuild(&utility);
+/// assert_eq!(actions.pop(&utility), Some("flee"));
+/// assert_eq!(actions.pop(&utility), Some("eat"));
+/// ```
+///
+/// [`BinaryHeap`]: struct.BinaryHeap.html
+/// [`rebuild`]: ContextBinaryHeap::rebuild
+pub struct ContextBinaryHeap<T, C> {
+    data: Vec<T>,
+    cmp: C,
+}
+
+impl<T: fmt::Debug, C> fmt::Debug for ContextBinaryHeap<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.data.iter()).finish()
+    }
+}
+
+impl<T: Clone, C: Clone> Clone for ContextBinaryHeap<T, C> {
+    fn clone(&self) -> Self {
+        ContextBinaryHeap {
+            data: self.data.clone(),
+            cmp: self.cmp.clone(),
+        }
+    }
+}
+
+impl<T, C> ContextBinaryHeap<T, C> {
+    /// Creates an empty heap ordered by `cmp`.
+    pub fn new(cmp: C) -> Self {
+        ContextBinaryHeap {
+            data: Vec::new(),
+            cmp,
+        }
+    }
+
+    /// Creates an empty heap ordered by `cmp` with space for at least
+    /// `capacity` elements.
+    pub fn with_capacity(capacity: usize, cmp: C) -> Self {
+        ContextBinaryHeap {
+            data: Vec::with_capacity(capacity),
+            cmp,
+        }
+    }
+
+    /// Returns the number of elements in the heap.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns the element that was greatest in the context of the last
+    /// operation, or `None` if the heap is empty.
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.data.first()
+    }
+
+    /// Returns an iterator visiting all elements in arbitrary order.
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.data.iter()
+    }
+
+    /// Returns the comparator.
+    #[must_use]
+    pub fn comparator(&self) -> &C {
+        &self.cmp
+    }
+
+    /// Removes all elements.
+    pub fn clear(&mut self) {
+        self.data.clear();
+    }
+
+    /// Consumes the heap and returns its elements in arbitrary order.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_vec(self) -> Vec<T> {
+        self.data
+    }
+
+    /// Pushes an item onto the heap, comparing in the context `ctx`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)).
+    pub fn push<Ctx: ?Sized>(&mut self, item: T, ctx: &Ctx)
+    where
+        C: ContextCompare<T, Ctx>,
+    {
+        let cmp = InContext {
+            cmp: &self.cmp,
+            ctx,
+        };
+        self.data.push(item);
+        let pos = self.data.len() - 1;
+        sift_up_slice(&mut self.data, pos, &cmp);
+    }
+
+    /// Removes the greatest element in the context `ctx` and returns it, or
+    /// `None` if the heap is empty.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)).
+    pub fn pop<Ctx: ?Sized>(&mut self, ctx: &Ctx) -> Option<T>
+    where
+        C: ContextCompare<T, Ctx>,
+    {
+        let cmp = InContext {
+            cmp: &self.cmp,
+            ctx,
+        };
+        self.data.pop().map(|mut item| {
+            if !self.data.is_empty() {
+                mem::swap(&mut item, &mut self.data[0]);
+                sift_down_slice(&mut self.data, 0, &cmp);
+            }
+            item
+        })
+    }
+
+    /// Restores the heap order after the context changed, comparing in the
+    /// new context `ctx`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*n*).
+    pub fn rebuild<Ctx: ?Sized>(&mut self, ctx: &Ctx)
+    where
+        C: ContextCompare<T, Ctx>,
+    {
+        let cmp = InContext {
+            cmp: &self.cmp,
+            ctx,
+        };
+        for pos in (0..self.data.len() / 2).rev() {
+            sift_down_slice(&mut self.data, pos, &cmp);
+        }
+    }
+
+    /// Consumes the heap and returns its elements sorted in ascending order
+    /// in the context `ctx`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*n* log(*n*)).
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_sorted_vec<Ctx: ?Sized>(self, ctx: &Ctx) -> Vec<T>
+    where
+        C: ContextCompare<T, Ctx>,
+    {
+        let ContextBinaryHeap { mut data, cmp } = self;
+        data.sort_by(|a, b| cmp.compare(a, b, ctx));
+        data
+    }
+}
@@ -0,0 +1,202 @@
+//! A heap with cheap copy-on-write snapshots.
+
+use crate::binary_heap::{BinaryHeap, Iter, MaxComparator};
+use crate::queue::PriorityQueue;
+use compare::Compare;
+use std::fmt;
+use std::iter::FromIterator;
+use std::sync::Arc;
+
+/// A [`BinaryHeap`] behind an [`Arc`], whose snapshots share its storage
+/// until one of them is modified.
+///
+/// [`snapshot`] only bumps the reference count, in *O*(1). The first
+/// mutation of a heap whose storage is shared clones it, in *O*(*n*), and
+/// later mutations are as cheap as on a plain `BinaryHeap`. A writer can
+/// thus hand snapshots to readers on other threads, which pop from their
+/// copy while the writer carries on, each seeing a consistent heap.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::CowHeap;
+/// use std::thread;
+///
+/// let mut heap: CowHeap<u32> = vec![3, 1, 4].into_iter().collect();
+/// let mut snapshot = heap.snapshot();
+///
+/// let reader = thread::spawn(move || {
+///     let mut popped = Vec::new();
+///     while let Some(x) = snapshot.pop() {
+///         popped.push(x);
+///     }
+///     popped
+/// });
+/// heap.push(9);
+///
+/// assert_eq!(reader.join().unwrap(), [4, 3, 1]);
+/// assert_eq!(heap.into_sorted_vec(), [1, 3, 4, 9]);
+/// ```
+///
+/// [`BinaryHeap`]: struct.BinaryHeap.html
+/// [`Arc`]: https://doc.rust-lang.org/std/sync/struct.Arc.html
+/// [`snapshot`]: CowHeap::snapshot
+pub struct CowHeap<T, C = MaxComparator> {
+    heap: Arc<BinaryHeap<T, C>>,
+}
+
+impl<T: fmt::Debug, C> fmt::Debug for CowHeap<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// Shares the storage, like [`snapshot`](CowHeap::snapshot).
+impl<T, C> Clone for CowHeap<T, C> {
+    fn clone(&self) -> Self {
+        CowHeap {
+            heap: Arc::clone(&self.heap),
+        }
+    }
+}
+
+impl<T: Ord> Default for CowHeap<T> {
+    fn default() -> Self {
+        CowHeap::new()
+    }
+}
+
+impl<T: Ord> CowHeap<T> {
+    /// Creates an empty max-heap.
+    #[must_use]
+    pub fn new() -> Self {
+        CowHeap::from(BinaryHeap::new())
+    }
+}
+
+impl<T, C> From<BinaryHeap<T, C>> for CowHeap<T, C> {
+    fn from(heap: BinaryHeap<T, C>) -> Self {
+        CowHeap {
+            heap: Arc::new(heap),
+        }
+    }
+}
+
+impl<T: Ord> FromIterator<T> for CowHeap<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        CowHeap::from(iter.into_iter().collect::<BinaryHeap<T>>())
+    }
+}
+
+impl<T, C> CowHeap<T, C> {
+    /// Returns a heap sharing the storage of this one, in *O*(1).
+    ///
+    /// Modifying either heap afterwards leaves the other as it was.
+    #[must_use]
+    pub fn snapshot(&self) -> Self {
+        self.clone()
+    }
+
+    /// Checks if the storage is shared with a snapshot, so that the next
+    /// mutation will clone it.
+    #[must_use]
+    pub fn is_shared(&self) -> bool {
+        Arc::strong_count(&self.heap) > 1
+    }
+
+    /// Returns the underlying heap.
+    #[must_use]
+    pub fn heap(&self) -> &BinaryHeap<T, C> {
+        &self.heap
+    }
+
+    /// Returns the number of elements in the heap.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Returns an iterator visiting all elements in arbitrary order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.heap.iter()
+    }
+}
+
+impl<T: Clone, C: Clone> CowHeap<T, C> {
+    /// Returns the underlying heap mutably, cloning it first if its storage
+    /// is shared.
+    pub fn heap_mut(&mut self) -> &mut BinaryHeap<T, C> {
+        Arc::make_mut(&mut self.heap)
+    }
+
+    /// Returns the underlying heap, cloning it if its storage is shared.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_heap(self) -> BinaryHeap<T, C> {
+        Arc::try_unwrap(self.heap).unwrap_or_else(|heap| (*heap).clone())
+    }
+}
+
+impl<T: Clone, C: Compare<T> + Clone> CowHeap<T, C> {
+    /// Returns the greatest element, or `None` if the heap is empty.
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.heap.peek()
+    }
+
+    /// Pushes an item onto the heap, cloning the storage first if it is
+    /// shared.
+    pub fn push(&mut self, item: T) {
+        self.heap_mut().push(item);
+    }
+
+    /// Removes the greatest element and returns it, or `None` if the heap is
+    /// empty. The storage is cloned first if it is shared and not empty.
+    pub fn pop(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        self.heap_mut().pop()
+    }
+
+    /// Consumes the heap and returns its elements in ascending order,
+    /// cloning them if the storage is shared.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        self.into_heap().into_sorted_vec()
+    }
+}
+
+/// Clones the storage first if it is shared.
+impl<T: Clone, C: Compare<T> + Clone> Extend<T> for CowHeap<T, C> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.heap_mut().extend(iter);
+    }
+}
+
+impl<T: Clone, C: Compare<T> + Clone> PriorityQueue<T> for CowHeap<T, C> {
+    fn push(&mut self, item: T) {
+        CowHeap::push(self, item);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        CowHeap::pop(self)
+    }
+
+    fn peek(&self) -> Option<&T> {
+        CowHeap::peek(self)
+    }
+
+    fn len(&self) -> usize {
+        CowHeap::len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        CowHeap::is_empty(self)
+    }
+}
@@ -0,0 +1,445 @@
+//! A queue of values that expire after a delay, for async code.
+
+use crate::tracked::{PositionMap, TrackedBinaryHeap};
+use crate::util::lock;
+use compare::Compare;
+#[cfg(feature = "futures")]
+use futures_core::stream::Stream;
+use std::cmp::Ordering;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A source of time for a [`DelayQueue`], which lets it run on any async
+/// runtime.
+///
+/// With tokio, for instance, `sleep_until` can return
+/// `tokio::time::sleep_until(deadline.into())`. [`ThreadTimer`] needs no
+/// runtime at all.
+///
+/// [`DelayQueue`]: struct.DelayQueue.html
+/// [`ThreadTimer`]: struct.ThreadTimer.html
+pub trait Timer {
+    /// The future returned by [`sleep_until`].
+    ///
+    /// [`sleep_until`]: Timer::sleep_until
+    type Sleep: Future<Output = ()>;
+
+    /// Returns a future that completes once `deadline` has passed.
+    fn sleep_until(&mut self, deadline: Instant) -> Self::Sleep;
+
+    /// Returns the current time, which decides whether a value has expired.
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A [`Timer`] that waits for each deadline on a thread of its own, so it
+/// works with any executor, or none.
+///
+/// A thread is spawned each time the [`DelayQueue`] starts waiting for a new
+/// earliest deadline, which suits tests and light use. Busy services should
+/// implement [`Timer`] with the timer of their runtime.
+///
+/// [`Timer`]: trait.Timer.html
+/// [`DelayQueue`]: struct.DelayQueue.html
+#[derive(Clone, Copy, Default, Debug)]
+pub struct ThreadTimer;
+
+impl Timer for ThreadTimer {
+    type Sleep = ThreadSleep;
+
+    fn sleep_until(&mut self, deadline: Instant) -> ThreadSleep {
+        ThreadSleep {
+            deadline,
+            state: None,
+        }
+    }
+}
+
+/// The future returned by [`ThreadTimer`].
+///
+/// [`ThreadTimer`]: struct.ThreadTimer.html
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[derive(Debug)]
+pub struct ThreadSleep {
+    deadline: Instant,
+    /// Whether the deadline passed, and the waker to call when it does,
+    /// shared with the sleeping thread once it is spawned.
+    state: Option<Arc<Mutex<(bool, Waker)>>>,
+}
+
+impl Future for ThreadSleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if Instant::now() >= self.deadline {
+            return Poll::Ready(());
+        }
+        match &self.state {
+            Some(state) => {
+                let mut state = lock(state);
+                if state.0 {
+                    return Poll::Ready(());
+                }
+                if !state.1.will_wake(cx.waker()) {
+                    state.1 = cx.waker().clone();
+                }
+            }
+            None => {
+                let state = Arc::new(Mutex::new((false, cx.waker().clone())));
+                let shared = Arc::clone(&state);
+                let deadline = self.deadline;
+                thread::spawn(move || {
+                    let now = Instant::now();
+                    if deadline > now {
+                        thread::sleep(deadline - now);
+                    }
+                    let mut state = lock(&shared);
+                    state.0 = true;
+                    state.1.wake_by_ref();
+                });
+                self.state = Some(state);
+            }
+        }
+        Poll::Pending
+    }
+}
+
+/// Identifies a value in a [`DelayQueue`], to remove it or change its
+/// deadline.
+///
+/// [`DelayQueue`]: struct.DelayQueue.html
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct DelayKey(u64);
+
+/// A value taken from a [`DelayQueue`] once its deadline passed.
+///
+/// [`DelayQueue`]: struct.DelayQueue.html
+#[derive(Debug)]
+pub struct Expired<T> {
+    value: T,
+    deadline: Instant,
+    key: DelayKey,
+}
+
+impl<T> Expired<T> {
+    /// Returns the value.
+    pub fn get_ref(&self) -> &T {
+        &self.value
+    }
+
+    /// Returns the deadline the value was scheduled for.
+    #[must_use]
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    /// Returns the key the value had in the queue.
+    #[must_use]
+    pub fn key(&self) -> DelayKey {
+        self.key
+    }
+
+    /// Consumes the entry and returns the value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+struct Entry<T> {
+    deadline: Instant,
+    key: u64,
+    value: T,
+}
+
+fn key_of<T>(entry: &Entry<T>) -> u64 {
+    entry.key
+}
+
+/// Orders entries by earliest deadline, then by insertion order.
+#[derive(Clone, Copy, Default, Debug)]
+struct DeadlineOrder;
+
+impl<T> Compare<Entry<T>> for DeadlineOrder {
+    fn compare(&self, a: &Entry<T>, b: &Entry<T>) -> Ordering {
+        b.deadline.cmp(&a.deadline).then_with(|| b.key.cmp(&a.key))
+    }
+}
+
+type EntryHeap<T> =
+    TrackedBinaryHeap<Entry<T>, PositionMap<u64, fn(&Entry<T>) -> u64>, DeadlineOrder>;
+
+/// A queue of values that become available once their deadline passes,
+/// like tokio-util's `DelayQueue` but independent of the async runtime.
+///
+/// The values are kept in a [`TrackedBinaryHeap`] ordered by deadline, so
+/// that [`remove`] and [`reset_at`] find a value by its [`DelayKey`] in
+/// *O*(1) and update the heap in *O*(log(*n*)). Values with equal deadlines
+/// expire in insertion order.
+///
+/// [`poll_expired`] returns the values whose deadline passed, and otherwise
+/// waits on a single sleep future from the [`Timer`] for the earliest
+/// deadline. With the `futures` feature, the queue is also a `Stream` of the
+/// expired values, which ends whenever the queue is empty.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::{DelayQueue, ThreadTimer};
+/// use futures::future::poll_fn;
+/// use std::time::Duration;
+///
+/// let mut queue = DelayQueue::new(ThreadTimer);
+/// queue.insert("slow", Duration::from_millis(20));
+/// let key = queue.insert("cancelled", Duration::from_millis(5));
+/// queue.insert("fast", Duration::from_millis(10));
+/// assert_eq!(queue.remove(key), Some("cancelled"));
+///
+/// futures::executor::block_on(async {
+///     let first = poll_fn(|cx| queue.poll_expired(cx)).await.unwrap();
+///     assert_eq!(first.into_inner(), "fast");
+///     let second = poll_fn(|cx| queue.poll_expired(cx)).await.unwrap();
+///     assert_eq!(second.into_inner(), "slow");
+/// });
+/// assert!(queue.is_empty());
+/// ```
+///
+/// [`TrackedBinaryHeap`]: struct.TrackedBinaryHeap.html
+/// [`DelayKey`]: struct.DelayKey.html
+/// [`Timer`]: trait.Timer.html
+/// [`remove`]: DelayQueue::remove
+/// [`reset_at`]: DelayQueue::reset_at
+/// [`poll_expired`]: DelayQueue::poll_expired
+pub struct DelayQueue<T, R: Timer = ThreadTimer> {
+    heap: EntryHeap<T>,
+    timer: R,
+    /// The sleep for the earliest deadline when it was last polled.
+    sleep: Option<(Instant, Pin<Box<R::Sleep>>)>,
+    /// The task that last waited, woken when an earlier deadline is inserted.
+    waker: Option<Waker>,
+    next_key: u64,
+}
+
+impl<T: fmt::Debug, R: Timer + fmt::Debug> fmt::Debug for DelayQueue<T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DelayQueue")
+            .field(
+                "entries",
+                &self
+                    .heap
+                    .iter()
+                    .map(|e| (e.deadline, &e.value))
+                    .collect::<Vec<_>>(),
+            )
+            .field("timer", &self.timer)
+            .finish()
+    }
+}
+
+impl<T, R: Timer + Default> Default for DelayQueue<T, R> {
+    fn default() -> Self {
+        DelayQueue::new(R::default())
+    }
+}
+
+impl<T, R: Timer> DelayQueue<T, R> {
+    /// Creates an empty queue that waits with `timer`.
+    pub fn new(timer: R) -> Self {
+        DelayQueue {
+            heap: TrackedBinaryHeap::with_cmp(
+                DeadlineOrder,
+                PositionMap::new(key_of::<T> as fn(&Entry<T>) -> u64),
+            ),
+            timer,
+            sleep: None,
+            waker: None,
+            next_key: 0,
+        }
+    }
+
+    /// Returns the timer.
+    pub fn timer(&self) -> &R {
+        &self.timer
+    }
+
+    /// Returns the number of values in the queue, expired or not.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Checks if the queue is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Returns the earliest deadline, or `None` if the queue is empty.
+    #[must_use]
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.heap.peek().map(|e| e.deadline)
+    }
+
+    /// Returns the deadline of the value with key `key`, or `None` if it is
+    /// no longer in the queue.
+    #[must_use]
+    pub fn deadline(&self, key: DelayKey) -> Option<Instant> {
+        self.index_of(key).map(|i| self.heap.as_slice()[i].deadline)
+    }
+
+    /// Inserts a value that expires at `deadline` and returns its key.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)).
+    pub fn insert_at(&mut self, value: T, deadline: Instant) -> DelayKey {
+        let key = self.next_key;
+        self.next_key += 1;
+        self.push(Entry {
+            deadline,
+            key,
+            value,
+        });
+        DelayKey(key)
+    }
+
+    /// Inserts a value that expires `timeout` from now, by the clock of the
+    /// timer, and returns its key.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)).
+    pub fn insert(&mut self, value: T, timeout: Duration) -> DelayKey {
+        let deadline = self.timer.now() + timeout;
+        self.insert_at(value, deadline)
+    }
+
+    /// Removes the value with key `key` and returns it, or returns `None` if
+    /// it is no longer in the queue.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)).
+    pub fn remove(&mut self, key: DelayKey) -> Option<T> {
+        let i = self.index_of(key)?;
+        Some(self.heap.remove_at(i).value)
+    }
+
+    /// Changes the deadline of the value with key `key`, which keeps its
+    /// key. Returns `false` if the value is no longer in the queue.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)).
+    pub fn reset_at(&mut self, key: DelayKey, deadline: Instant) -> bool {
+        let i = match self.index_of(key) {
+            Some(i) => i,
+            None => return false,
+        };
+        let mut entry = self.heap.remove_at(i);
+        entry.deadline = deadline;
+        self.push(entry);
+        true
+    }
+
+    /// Removes all values.
+    pub fn clear(&mut self) {
+        self.heap.clear();
+        self.sleep = None;
+    }
+
+    /// Removes the value with the earliest deadline if that deadline has
+    /// passed, by the clock of the timer, and returns it. Never waits.
+    pub fn pop_expired(&mut self) -> Option<Expired<T>> {
+        if self.next_deadline()? > self.timer.now() {
+            return None;
+        }
+        let Entry {
+            deadline,
+            key,
+            value,
+        } = self.heap.pop()?;
+        Some(Expired {
+            value,
+            deadline,
+            key: DelayKey(key),
+        })
+    }
+
+    /// Removes and returns the value with the earliest deadline once that
+    /// deadline has passed, or returns `Ready(None)` if the queue is empty.
+    ///
+    /// While the earliest value has not expired, this returns `Pending` and
+    /// wakes the task when it does, or when an earlier deadline is inserted.
+    pub fn poll_expired(&mut self, cx: &mut Context<'_>) -> Poll<Option<Expired<T>>> {
+        let deadline = match self.next_deadline() {
+            Some(deadline) => deadline,
+            None => {
+                self.sleep = None;
+                return Poll::Ready(None);
+            }
+        };
+        if let Some(expired) = self.pop_expired() {
+            return Poll::Ready(Some(expired));
+        }
+        if self.sleep.as_ref().map_or(true, |(d, _)| *d != deadline) {
+            let sleep = Box::pin(self.timer.sleep_until(deadline));
+            self.sleep = Some((deadline, sleep));
+        }
+        let (_, sleep) = self.sleep.as_mut().unwrap();
+        if sleep.as_mut().poll(cx).is_ready() {
+            self.sleep = None;
+            if let Some(expired) = self.pop_expired() {
+                return Poll::Ready(Some(expired));
+            }
+            // The timer fired before its clock reached the deadline; poll
+            // again with a fresh sleep.
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+        match &self.waker {
+            Some(waker) if waker.will_wake(cx.waker()) => {}
+            _ => self.waker = Some(cx.waker().clone()),
+        }
+        Poll::Pending
+    }
+
+    fn index_of(&self, key: DelayKey) -> Option<usize> {
+        self.heap.tracker().index_of(&key.0)
+    }
+
+    fn push(&mut self, entry: Entry<T>) {
+        let key = entry.key;
+        self.heap.push(entry);
+        // The sleep is for a later deadline, or there is none.
+        if self.index_of(DelayKey(key)) == Some(0) {
+            if let Some(waker) = self.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// Yields the expired values, and ends whenever the queue is empty.
+///
+/// Requires the `futures` feature.
+#[cfg(feature = "futures")]
+impl<T, R: Timer> Stream for DelayQueue<T, R> {
+    type Item = Expired<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Expired<T>>> {
+        self.get_mut().poll_expired(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.len()))
+    }
+}
+
+// Nothing is pinned structurally: the sleep future is boxed.
+impl<T, R: Timer> Unpin for DelayQueue<T, R> {}
@@ -0,0 +1,271 @@
+//! An earliest-deadline-first (EDF) scheduling queue built on [`BinaryHeap`].
+//!
+//! [`BinaryHeap`]: struct.BinaryHeap.html
+
+use crate::binary_heap::BinaryHeap;
+use compare::Compare;
+use std::cmp::Ordering;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// A task queued in an [`EdfQueue`].
+///
+/// [`EdfQueue`]: struct.EdfQueue.html
+#[derive(Clone, Debug)]
+pub struct EdfTask<T> {
+    /// The payload supplied when the task was pushed.
+    pub value: T,
+    /// The instant by which the task must have completed.
+    pub deadline: Instant,
+    /// The expected execution time of the task, if known.
+    pub budget: Option<Duration>,
+    seq: u64,
+}
+
+impl<T> EdfTask<T> {
+    /// Returns `true` if the deadline has already passed at `now`.
+    pub fn is_missed(&self, now: Instant) -> bool {
+        self.deadline < now
+    }
+
+    /// Returns the time left until the deadline, or `None` if it was missed.
+    pub fn slack(&self, now: Instant) -> Option<Duration> {
+        self.deadline.checked_duration_since(now)
+    }
+
+    /// Consumes the task and returns its payload.
+    pub fn into_value(self) -> T {
+        self.value
+    }
+}
+
+/// Orders tasks by earliest deadline, then by insertion order.
+#[derive(Clone, Copy, Default, Debug)]
+struct DeadlineOrder;
+
+impl<T> Compare<EdfTask<T>> for DeadlineOrder {
+    fn compare(&self, a: &EdfTask<T>, b: &EdfTask<T>) -> Ordering {
+        b.deadline
+            .cmp(&a.deadline)
+            .then_with(|| b.seq.cmp(&a.seq))
+    }
+}
+
+type MissHook<T> = Box<dyn FnMut(&EdfTask<T>, Instant) + Send>;
+
+/// An earliest-deadline-first queue.
+///
+/// Tasks are popped in order of their deadline; tasks sharing a deadline are
+/// popped in the order they were pushed. A hook registered with
+/// [`on_deadline_miss`] is invoked for every task that is popped after its
+/// deadline has passed.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::EdfQueue;
+/// use std::time::{Duration, Instant};
+///
+/// let now = Instant::now();
+/// let mut queue = EdfQueue::new();
+/// queue.push("render", now + Duration::from_millis(16));
+/// queue.push("input", now + Duration::from_millis(4));
+///
+/// assert_eq!(queue.pop_next(now).unwrap().value, "input");
+/// assert_eq!(queue.pop_next(now).unwrap().value, "render");
+/// assert!(queue.pop_next(now).is_none());
+/// ```
+///
+/// [`on_deadline_miss`]: EdfQueue::on_deadline_miss
+pub struct EdfQueue<T> {
+    heap: BinaryHeap<EdfTask<T>, DeadlineOrder>,
+    next_seq: u64,
+    missed: u64,
+    on_miss: Option<MissHook<T>>,
+}
+
+impl<T> Default for EdfQueue<T> {
+    fn default() -> Self {
+        EdfQueue::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for EdfQueue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EdfQueue")
+            .field("tasks", &self.heap)
+            .field("missed", &self.missed)
+            .finish()
+    }
+}
+
+impl<T> EdfQueue<T> {
+    /// Creates an empty `EdfQueue`.
+    #[must_use]
+    pub fn new() -> Self {
+        EdfQueue {
+            heap: BinaryHeap::from_vec(vec![]),
+            next_seq: 0,
+            missed: 0,
+            on_miss: None,
+        }
+    }
+
+    /// Registers a hook called with every task popped after its deadline.
+    ///
+    /// The hook receives the task and the `now` passed to [`pop_next`].
+    /// Registering a hook replaces any previous one.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::EdfQueue;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    /// use std::time::{Duration, Instant};
+    ///
+    /// let misses = Arc::new(AtomicUsize::new(0));
+    /// let counter = Arc::clone(&misses);
+    ///
+    /// let start = Instant::now();
+    /// let mut queue = EdfQueue::new();
+    /// queue.on_deadline_miss(move |_task, _now| {
+    ///     counter.fetch_add(1, Ordering::SeqCst);
+    /// });
+    /// queue.push(1, start + Duration::from_millis(5));
+    ///
+    /// let late = start + Duration::from_millis(10);
+    /// assert!(queue.pop_next(late).unwrap().is_missed(late));
+    /// assert_eq!(misses.load(Ordering::SeqCst), 1);
+    /// assert_eq!(queue.missed_count(), 1);
+    /// ```
+    ///
+    /// [`pop_next`]: EdfQueue::pop_next
+    pub fn on_deadline_miss<F>(&mut self, hook: F)
+    where
+        F: FnMut(&EdfTask<T>, Instant) + Send + 'static,
+    {
+        self.on_miss = Some(Box::new(hook));
+    }
+
+    /// Pushes a task with the given deadline and no execution budget.
+    pub fn push(&mut self, value: T, deadline: Instant) {
+        self.push_task(value, deadline, None);
+    }
+
+    /// Pushes a task with the given deadline and expected execution time.
+    ///
+    /// Budgets are used by [`first_predicted_miss`] to detect deadlines that
+    /// cannot be met even if every task runs back to back.
+    ///
+    /// [`first_predicted_miss`]: EdfQueue::first_predicted_miss
+    pub fn push_with_budget(&mut self, value: T, deadline: Instant, budget: Duration) {
+        self.push_task(value, deadline, Some(budget));
+    }
+
+    fn push_task(&mut self, value: T, deadline: Instant, budget: Option<Duration>) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.heap.push(EdfTask {
+            value,
+            deadline,
+            budget,
+            seq,
+        });
+    }
+
+    /// Removes the task with the earliest deadline and returns it, or `None`
+    /// if the queue is empty.
+    ///
+    /// If the task's deadline is before `now`, the miss counter is incremented
+    /// and the deadline-miss hook, if any, is called before returning.
+    pub fn pop_next(&mut self, now: Instant) -> Option<EdfTask<T>> {
+        let task = self.heap.pop()?;
+        if task.is_missed(now) {
+            self.missed += 1;
+            if let Some(hook) = self.on_miss.as_mut() {
+                hook(&task, now);
+            }
+        }
+        Some(task)
+    }
+
+    /// Returns the task with the earliest deadline, or `None` if the queue is
+    /// empty.
+    #[must_use]
+    pub fn peek(&self) -> Option<&EdfTask<T>> {
+        self.heap.peek()
+    }
+
+    /// Returns the first task that would miss its deadline if all queued
+    /// tasks were run back to back from `now` in EDF order.
+    ///
+    /// Tasks without a budget are assumed to take no time. A task whose
+    /// finish time overflows `Instant` counts as a miss. Returns `None` if
+    /// every deadline can be met.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::EdfQueue;
+    /// use std::time::{Duration, Instant};
+    ///
+    /// let now = Instant::now();
+    /// let ms = Duration::from_millis;
+    /// let mut queue = EdfQueue::new();
+    /// queue.push_with_budget("a", now + ms(10), ms(6));
+    /// queue.push_with_budget("b", now + ms(12), ms(4));
+    /// assert!(queue.first_predicted_miss(now).is_none());
+    ///
+    /// queue.push_with_budget("c", now + ms(11), ms(3));
+    /// assert_eq!(queue.first_predicted_miss(now).unwrap().value, "b");
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*n* * log(*n*)) on a queue of *n* tasks.
+    #[must_use]
+    pub fn first_predicted_miss(&self, now: Instant) -> Option<&EdfTask<T>> {
+        let mut tasks: Vec<_> = self.heap.iter().collect();
+        tasks.sort_by(|a, b| DeadlineOrder.compare(b, a));
+
+        let mut finish = now;
+        tasks.into_iter().find(|task| {
+            let budget = task.budget.unwrap_or_default();
+            match finish.checked_add(budget) {
+                Some(next) => {
+                    finish = next;
+                    finish > task.deadline
+                }
+                None => true,
+            }
+        })
+    }
+
+    /// Returns the number of tasks popped after their deadline.
+    #[must_use]
+    pub fn missed_count(&self) -> u64 {
+        self.missed
+    }
+
+    /// Returns the number of queued tasks.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Checks if the queue is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Drops all queued tasks.
+    pub fn clear(&mut self) {
+        self.heap.clear();
+    }
+}
@@ -0,0 +1,210 @@
+//! A C interface to a heap of `u64` keys carrying opaque pointers.
+//!
+//! The functions are declared in `include/binary_heap_plus.h`, which
+//! cbindgen generates from this module with the `cbindgen.toml` at the root
+//! of the repository. To call them from C or C++, depend on this crate from
+//! a `staticlib` or `cdylib` crate and link its library.
+//!
+//! The heap only stores the pointers: it never dereferences nor frees them.
+//! Panics, for instance from the `strict_asserts` feature, abort the process
+//! instead of unwinding into the caller, which is undefined behavior.
+//!
+//! Requires the `ffi` feature.
+//!
+//! # Examples
+//!
+//! ```c
+//! BhpHeap *heap = bhp_heap_new_min();
+//! bhp_heap_push(heap, 30, &timer_a);
+//! bhp_heap_push(heap, 10, &timer_b);
+//!
+//! uint64_t deadline;
+//! void *timer;
+//! while (bhp_heap_pop(heap, &deadline, &timer)) {
+//!     fire(timer, deadline);
+//! }
+//! bhp_heap_free(heap);
+//! ```
+
+use crate::binary_heap::BinaryHeap;
+use compare::Compare;
+use std::cmp::Ordering;
+use std::os::raw::c_void;
+use std::panic::{self, AssertUnwindSafe};
+use std::process;
+use std::ptr;
+
+/// A heap entry: the key it is ordered by and the pointer it carries.
+struct Entry {
+    key: u64,
+    value: *mut c_void,
+}
+
+/// Orders entries by key only, greatest or least first.
+#[derive(Clone, Copy)]
+enum KeyOrder {
+    Max,
+    Min,
+}
+
+impl Compare<Entry> for KeyOrder {
+    fn compare(&self, a: &Entry, b: &Entry) -> Ordering {
+        match self {
+            KeyOrder::Max => a.key.cmp(&b.key),
+            KeyOrder::Min => b.key.cmp(&a.key),
+        }
+    }
+}
+
+/// A heap of `u64` keys, each carrying an opaque pointer.
+///
+/// C code only handles pointers to it, created by [`bhp_heap_new`] or
+/// [`bhp_heap_new_min`] and released with [`bhp_heap_free`].
+///
+/// [`bhp_heap_new`]: fn.bhp_heap_new.html
+/// [`bhp_heap_new_min`]: fn.bhp_heap_new_min.html
+/// [`bhp_heap_free`]: fn.bhp_heap_free.html
+pub struct BhpHeap {
+    heap: BinaryHeap<Entry, KeyOrder>,
+}
+
+impl BhpHeap {
+    fn new_boxed(order: KeyOrder) -> *mut BhpHeap {
+        Box::into_raw(Box::new(BhpHeap {
+            heap: BinaryHeap::from_vec_cmp(Vec::new(), order),
+        }))
+    }
+}
+
+/// Runs `f`, aborting the process if it panics, so that no panic unwinds out
+/// of an `extern "C"` function.
+pub(crate) fn abort_on_panic<R, F: FnOnce() -> R>(f: F) -> R {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => result,
+        Err(_) => process::abort(),
+    }
+}
+
+/// Writes the entry to the out-pointers that are not null.
+///
+/// # Safety
+///
+/// Each pointer must be null or valid for writes.
+unsafe fn write_entry(entry: &Entry, key_out: *mut u64, value_out: *mut *mut c_void) {
+    if !key_out.is_null() {
+        ptr::write(key_out, entry.key);
+    }
+    if !value_out.is_null() {
+        ptr::write(value_out, entry.value);
+    }
+}
+
+/// Creates an empty heap that pops the greatest key first.
+///
+/// Release it with [`bhp_heap_free`].
+///
+/// [`bhp_heap_free`]: fn.bhp_heap_free.html
+#[no_mangle]
+pub extern "C" fn bhp_heap_new() -> *mut BhpHeap {
+    abort_on_panic(|| BhpHeap::new_boxed(KeyOrder::Max))
+}
+
+/// Creates an empty heap that pops the least key first.
+///
+/// Release it with [`bhp_heap_free`].
+///
+/// [`bhp_heap_free`]: fn.bhp_heap_free.html
+#[no_mangle]
+pub extern "C" fn bhp_heap_new_min() -> *mut BhpHeap {
+    abort_on_panic(|| BhpHeap::new_boxed(KeyOrder::Min))
+}
+
+/// Destroys a heap. The pointers it still carries are not freed.
+///
+/// # Safety
+///
+/// `heap` must be null or a heap created by this module that was not freed
+/// yet.
+#[no_mangle]
+pub unsafe extern "C" fn bhp_heap_free(heap: *mut BhpHeap) {
+    abort_on_panic(|| {
+        if !heap.is_null() {
+            drop(Box::from_raw(heap));
+        }
+    })
+}
+
+/// Pushes `key` carrying `value`.
+///
+/// # Safety
+///
+/// `heap` must be a live heap created by this module, not used by another
+/// thread during the call.
+#[no_mangle]
+pub unsafe extern "C" fn bhp_heap_push(heap: *mut BhpHeap, key: u64, value: *mut c_void) {
+    abort_on_panic(|| (*heap).heap.push(Entry { key, value }))
+}
+
+/// Removes the entry with the greatest key, or the least for a heap created
+/// by [`bhp_heap_new_min`], writes its key and pointer to the out-pointers
+/// that are not null, and returns `true`. Returns `false` if the heap is
+/// empty.
+///
+/// # Safety
+///
+/// `heap` must be a live heap created by this module, not used by another
+/// thread during the call. `key_out` and `value_out` must be null or valid
+/// for writes.
+///
+/// [`bhp_heap_new_min`]: fn.bhp_heap_new_min.html
+#[no_mangle]
+pub unsafe extern "C" fn bhp_heap_pop(
+    heap: *mut BhpHeap,
+    key_out: *mut u64,
+    value_out: *mut *mut c_void,
+) -> bool {
+    abort_on_panic(|| match (*heap).heap.pop() {
+        Some(entry) => {
+            write_entry(&entry, key_out, value_out);
+            true
+        }
+        None => false,
+    })
+}
+
+/// Writes the key and pointer of the entry [`bhp_heap_pop`] would remove to
+/// the out-pointers that are not null, and returns `true`. Returns `false`
+/// if the heap is empty.
+///
+/// # Safety
+///
+/// `heap` must be a live heap created by this module, not modified by
+/// another thread during the call. `key_out` and `value_out` must be null or
+/// valid for writes.
+///
+/// [`bhp_heap_pop`]: fn.bhp_heap_pop.html
+#[no_mangle]
+pub unsafe extern "C" fn bhp_heap_peek(
+    heap: *const BhpHeap,
+    key_out: *mut u64,
+    value_out: *mut *mut c_void,
+) -> bool {
+    abort_on_panic(|| match (*heap).heap.peek() {
+        Some(entry) => {
+            write_entry(entry, key_out, value_out);
+            true
+        }
+        None => false,
+    })
+}
+
+/// Returns the number of entries in the heap.
+///
+/// # Safety
+///
+/// `heap` must be a live heap created by this module, not modified by
+/// another thread during the call.
+#[no_mangle]
+pub unsafe extern "C" fn bhp_heap_len(heap: *const BhpHeap) -> usize {
+    abort_on_panic(|| (*heap).heap.len())
+}
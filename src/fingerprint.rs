@@ -0,0 +1,239 @@
+//! A heap that detects elements mutated while in the heap.
+//!
+//! Requires the `fingerprint` feature.
+
+use crate::binary_heap::{BinaryHeap, MaxComparator};
+use crate::queue::PriorityQueue;
+use compare::Compare;
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+fn fingerprint<T: Hash>(item: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    item.hash(&mut hasher);
+    hasher.finish()
+}
+
+struct Stamped<T> {
+    item: T,
+    fingerprint: u64,
+}
+
+/// Orders stamped elements by their items.
+struct ByItem<C>(C);
+
+impl<T, C: Compare<T>> Compare<Stamped<T>> for ByItem<C> {
+    fn compare(&self, a: &Stamped<T>, b: &Stamped<T>) -> Ordering {
+        self.0.compare(&a.item, &b.item)
+    }
+}
+
+/// A [`BinaryHeap`] that hashes each element when it is pushed and checks
+/// the hash again when the element is peeked at or popped, to catch
+/// elements that were changed while in the heap.
+///
+/// Changing the order of an element through interior mutability while it is
+/// in the heap is a logic error that silently breaks the heap. With a `Hash`
+/// implementation that covers the fields the order depends on, this heap
+/// panics with the offending element instead, once that element reaches the
+/// top. [`verify`] checks every element at once.
+///
+/// Hashing adds *O*(1) work to `push`, `peek` and `pop`, and the heap
+/// stores a `u64` per element. It is available with the `fingerprint`
+/// feature; the `strict_asserts` feature checks the heap property itself.
+///
+/// # Examples
+///
+/// ```should_panic
+/// use binary_heap_plus::FingerprintedBinaryHeap;
+/// use std::cell::Cell;
+/// use std::cmp::Ordering;
+/// use std::hash::{Hash, Hasher};
+///
+/// #[derive(Debug, PartialEq, Eq)]
+/// struct Job(Cell<u32>);
+///
+/// impl Hash for Job {
+///     fn hash<H: Hasher>(&self, state: &mut H) {
+///         self.0.get().hash(state);
+///     }
+/// }
+/// # impl PartialOrd for Job {
+/// #     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+/// #         Some(self.cmp(other))
+/// #     }
+/// # }
+/// # impl Ord for Job {
+/// #     fn cmp(&self, other: &Self) -> Ordering {
+/// #         self.0.get().cmp(&other.0.get())
+/// #     }
+/// # }
+///
+/// let mut heap = FingerprintedBinaryHeap::new();
+/// heap.push(Job(Cell::new(1)));
+/// heap.push(Job(Cell::new(5)));
+/// heap.peek().unwrap().0.set(0);
+/// heap.pop(); // panics: the top changed while in the heap
+/// ```
+///
+/// [`BinaryHeap`]: struct.BinaryHeap.html
+/// [`verify`]: FingerprintedBinaryHeap::verify
+pub struct FingerprintedBinaryHeap<T, C = MaxComparator> {
+    heap: BinaryHeap<Stamped<T>, ByItem<C>>,
+}
+
+impl<T: fmt::Debug, C> fmt::Debug for FingerprintedBinaryHeap<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.heap.iter().map(|s| &s.item))
+            .finish()
+    }
+}
+
+impl<T: Ord + Hash + fmt::Debug> Default for FingerprintedBinaryHeap<T> {
+    fn default() -> Self {
+        FingerprintedBinaryHeap::new()
+    }
+}
+
+impl<T: Ord + Hash + fmt::Debug> FingerprintedBinaryHeap<T> {
+    /// Creates an empty max-heap.
+    #[must_use]
+    pub fn new() -> Self {
+        FingerprintedBinaryHeap::with_cmp(MaxComparator)
+    }
+}
+
+impl<T, C> FingerprintedBinaryHeap<T, C> {
+    /// Returns the number of elements in the heap.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Drops all elements.
+    pub fn clear(&mut self) {
+        self.heap.clear();
+    }
+
+    /// Consumes the heap and returns its elements in arbitrary order,
+    /// without checking them.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_vec(self) -> Vec<T> {
+        self.heap.into_vec().into_iter().map(|s| s.item).collect()
+    }
+}
+
+impl<T: Hash + fmt::Debug, C: Compare<T>> FingerprintedBinaryHeap<T, C> {
+    /// Creates an empty heap ordered by `cmp`.
+    pub fn with_cmp(cmp: C) -> Self {
+        FingerprintedBinaryHeap {
+            heap: BinaryHeap::new_with_cmp(ByItem(cmp)),
+        }
+    }
+
+    /// Returns the greatest element, or `None` if the heap is empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the greatest element changed since it was pushed.
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.heap.peek().map(|top| Self::check(top))
+    }
+
+    /// Hashes `item` and pushes it onto the heap.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)), plus hashing `item`.
+    pub fn push(&mut self, item: T) {
+        let fingerprint = fingerprint(&item);
+        self.heap.push(Stamped { item, fingerprint });
+    }
+
+    /// Removes the greatest element and returns it, or `None` if the heap is
+    /// empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the greatest element changed since it was pushed. The
+    /// element is then dropped.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)), plus hashing the element.
+    pub fn pop(&mut self) -> Option<T> {
+        let top = self.heap.pop()?;
+        Self::check(&top);
+        Some(top.item)
+    }
+
+    /// Checks every element against the hash taken when it was pushed.
+    ///
+    /// # Panics
+    ///
+    /// Panics with the first element found to have changed.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*n*), plus hashing every element.
+    pub fn verify(&self) {
+        for stamped in self.heap.iter() {
+            Self::check(stamped);
+        }
+    }
+
+    fn check(stamped: &Stamped<T>) -> &T {
+        if fingerprint(&stamped.item) != stamped.fingerprint {
+            changed(&stamped.item);
+        }
+        &stamped.item
+    }
+}
+
+#[cold]
+fn changed<T: fmt::Debug>(item: &T) -> ! {
+    panic!(
+        "element {:?} changed while in the heap; its position may be wrong",
+        item
+    );
+}
+
+impl<T: Hash + fmt::Debug, C: Compare<T>> Extend<T> for FingerprintedBinaryHeap<T, C> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+impl<T: Hash + fmt::Debug, C: Compare<T>> PriorityQueue<T> for FingerprintedBinaryHeap<T, C> {
+    fn push(&mut self, item: T) {
+        FingerprintedBinaryHeap::push(self, item);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        FingerprintedBinaryHeap::pop(self)
+    }
+
+    fn peek(&self) -> Option<&T> {
+        FingerprintedBinaryHeap::peek(self)
+    }
+
+    fn len(&self) -> usize {
+        FingerprintedBinaryHeap::len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        FingerprintedBinaryHeap::is_empty(self)
+    }
+}
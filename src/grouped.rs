@@ -0,0 +1,219 @@
+//! Top-k selection for each of many groups.
+
+use crate::binary_heap::{BinaryHeap, MaxComparator};
+use compare::{Compare, Rev};
+use std::collections::hash_map::{Entry, RandomState};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+
+/// Keeps the `k` greatest items pushed for each group key.
+///
+/// Each group is a [`BinaryHeap`] in the reverse order, holding at most `k`
+/// items, so that the least of the kept items is at hand to compare a new
+/// item with, as in [`BinaryHeap::push_with_limit`]. A push takes
+/// *O*(log(*k*)) time, and memory stays within `k` items per group however
+/// many are pushed.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::GroupedTopK;
+///
+/// // (region, sale)
+/// let sales = [("eu", 30), ("us", 50), ("eu", 10), ("eu", 70), ("us", 20), ("eu", 40)];
+/// let mut top = GroupedTopK::new(2);
+/// for (region, amount) in sales {
+///     top.push(region, amount);
+/// }
+///
+/// assert_eq!(top.tops(&"eu"), [&70, &40]);
+/// let map = top.into_sorted_map();
+/// assert_eq!(map[&"us"], [50, 20]);
+/// ```
+///
+/// [`BinaryHeap`]: struct.BinaryHeap.html
+/// [`BinaryHeap::push_with_limit`]: struct.BinaryHeap.html#method.push_with_limit
+pub struct GroupedTopK<K, T, C = MaxComparator, S = RandomState> {
+    groups: HashMap<K, BinaryHeap<T, Rev<C>>, S>,
+    k: usize,
+    cmp: C,
+}
+
+impl<K: fmt::Debug, T: fmt::Debug, C, S> fmt::Debug for GroupedTopK<K, T, C, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GroupedTopK")
+            .field("k", &self.k)
+            .field("groups", &self.groups)
+            .finish()
+    }
+}
+
+impl<K: Clone, T: Clone, C: Clone, S: Clone> Clone for GroupedTopK<K, T, C, S> {
+    fn clone(&self) -> Self {
+        GroupedTopK {
+            groups: self.groups.clone(),
+            k: self.k,
+            cmp: self.cmp.clone(),
+        }
+    }
+}
+
+impl<K: Hash + Eq, T: Ord> GroupedTopK<K, T> {
+    /// Creates an aggregator keeping the `k` greatest items of each group.
+    #[must_use]
+    pub fn new(k: usize) -> Self {
+        GroupedTopK::with_cmp(k, MaxComparator)
+    }
+}
+
+impl<K: Hash + Eq, T, C: Compare<T> + Clone> GroupedTopK<K, T, C> {
+    /// Creates an aggregator keeping the `k` greatest items of each group
+    /// according to `cmp`.
+    pub fn with_cmp(k: usize, cmp: C) -> Self {
+        GroupedTopK::with_cmp_and_hasher(k, cmp, RandomState::new())
+    }
+}
+
+impl<K, T, C, S> GroupedTopK<K, T, C, S> {
+    /// Creates an aggregator keeping the `k` greatest items of each group
+    /// according to `cmp`, which hashes the keys with `hasher`.
+    pub fn with_cmp_and_hasher(k: usize, cmp: C, hasher: S) -> Self {
+        GroupedTopK {
+            groups: HashMap::with_hasher(hasher),
+            k,
+            cmp,
+        }
+    }
+
+    /// Returns the number of items kept per group.
+    #[must_use]
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Returns the number of groups.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Checks if no item was kept in any group.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Returns an iterator over the group keys, in arbitrary order.
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.groups.keys()
+    }
+
+    /// Drops all groups.
+    pub fn clear(&mut self) {
+        self.groups.clear();
+    }
+}
+
+impl<K: Hash + Eq, T, C: Compare<T> + Clone, S: BuildHasher> GroupedTopK<K, T, C, S> {
+    /// Offers `item` to the group `key`, and returns the item that does not
+    /// make the group's top `k`: the least kept item it displaced, or `item`
+    /// itself, or `None` if the group had room.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*k*)), plus hashing the key.
+    pub fn push(&mut self, key: K, item: T) -> Option<T> {
+        if self.k == 0 {
+            return Some(item);
+        }
+        let k = self.k;
+        match self.groups.entry(key) {
+            Entry::Occupied(mut group) => group.get_mut().push_with_limit(item, k),
+            Entry::Vacant(group) => {
+                let mut heap = BinaryHeap::with_capacity_and_cmp(1, self.cmp.clone().rev());
+                heap.push(item);
+                group.insert(heap);
+                None
+            }
+        }
+    }
+
+    /// Returns the kept items of the group `key`, greatest first, or an empty
+    /// vector if there is no such group.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*k* log(*k*)).
+    #[must_use]
+    pub fn tops(&self, key: &K) -> Vec<&T> {
+        let mut items: Vec<&T> = match self.groups.get(key) {
+            Some(group) => group.iter().collect(),
+            None => return Vec::new(),
+        };
+        items.sort_by(|a, b| self.cmp.compare(b, a));
+        items
+    }
+
+    /// Returns the least of the kept items of the group `key`, which a new
+    /// item must exceed to enter a full group, or `None` if there is no such
+    /// group.
+    #[must_use]
+    pub fn threshold(&self, key: &K) -> Option<&T> {
+        self.groups.get(key)?.peek()
+    }
+
+    /// Removes the group `key` and returns its kept items, greatest first.
+    pub fn remove(&mut self, key: &K) -> Option<Vec<T>> {
+        self.groups.remove(key).map(BinaryHeap::into_sorted_vec)
+    }
+
+    /// Moves the items of every group of `other` into the matching group,
+    /// keeping the `k` greatest of both.
+    pub fn merge(&mut self, other: Self) {
+        let k = self.k;
+        for (key, group) in other.groups {
+            match self.groups.entry(key) {
+                Entry::Occupied(mut mine) => {
+                    let mine = mine.get_mut();
+                    for item in group.into_vec() {
+                        mine.push_with_limit(item, k);
+                    }
+                }
+                Entry::Vacant(mine) => {
+                    let mut group =
+                        BinaryHeap::from_vec_cmp(group.into_vec(), self.cmp.clone().rev());
+                    while group.len() > k {
+                        group.pop();
+                    }
+                    if !group.is_empty() {
+                        mine.insert(group);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Consumes the aggregator and returns the kept items of each group,
+    /// greatest first, ordered by key.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_sorted_map(self) -> BTreeMap<K, Vec<T>>
+    where
+        K: Ord,
+    {
+        self.groups
+            .into_iter()
+            .map(|(key, group)| (key, group.into_sorted_vec()))
+            .collect()
+    }
+}
+
+impl<K: Hash + Eq, T, C: Compare<T> + Clone, S: BuildHasher> Extend<(K, T)>
+    for GroupedTopK<K, T, C, S>
+{
+    fn extend<I: IntoIterator<Item = (K, T)>>(&mut self, iter: I) {
+        for (key, item) in iter {
+            self.push(key, item);
+        }
+    }
+}
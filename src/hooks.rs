@@ -0,0 +1,228 @@
+//! Callbacks observing the operations on a heap.
+
+use crate::binary_heap::{BinaryHeap, Iter, MaxComparator};
+use crate::queue::PriorityQueue;
+use compare::Compare;
+use std::fmt;
+
+/// Callbacks invoked by a [`HookedBinaryHeap`] as it is modified.
+///
+/// Every method has an empty default implementation, so implementors only
+/// override the events they care about. The `len` passed to each callback is
+/// the length of the heap after the operation, which is enough to track the
+/// queue depth.
+///
+/// `()` implements `HeapHooks` with no callbacks at all; the calls are then
+/// inlined away.
+///
+/// [`HookedBinaryHeap`]: struct.HookedBinaryHeap.html
+pub trait HeapHooks<T> {
+    /// Called with an item just before it is pushed.
+    #[inline]
+    fn on_push(&mut self, item: &T, len: usize) {
+        let _ = (item, len);
+    }
+
+    /// Called with an item just after it was popped.
+    #[inline]
+    fn on_pop(&mut self, item: &T, len: usize) {
+        let _ = (item, len);
+    }
+
+    /// Called after the heap was restructured as a whole, by
+    /// [`append`](struct.HookedBinaryHeap.html#method.append).
+    #[inline]
+    fn on_rebuild(&mut self, len: usize) {
+        let _ = len;
+    }
+}
+
+impl<T> HeapHooks<T> for () {}
+
+/// A [`BinaryHeap`] that reports pushes, pops and rebuilds to a
+/// [`HeapHooks`] implementation.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::{BinaryHeap, HeapHooks, HookedBinaryHeap};
+/// use std::time::Instant;
+///
+/// #[derive(Default)]
+/// struct Metrics {
+///     max_depth: usize,
+///     waits: Vec<u128>,
+/// }
+///
+/// impl HeapHooks<(u32, Instant)> for Metrics {
+///     fn on_push(&mut self, _item: &(u32, Instant), len: usize) {
+///         self.max_depth = self.max_depth.max(len);
+///     }
+///
+///     fn on_pop(&mut self, &(_, enqueued): &(u32, Instant), _len: usize) {
+///         self.waits.push(enqueued.elapsed().as_micros());
+///     }
+/// }
+///
+/// let mut heap = HookedBinaryHeap::new(BinaryHeap::new(), Metrics::default());
+/// heap.push((2, Instant::now()));
+/// heap.push((7, Instant::now()));
+/// assert_eq!(heap.pop().map(|(p, _)| p), Some(7));
+///
+/// assert_eq!(heap.hooks().max_depth, 2);
+/// assert_eq!(heap.hooks().waits.len(), 1);
+/// ```
+///
+/// [`BinaryHeap`]: struct.BinaryHeap.html
+/// [`HeapHooks`]: trait.HeapHooks.html
+pub struct HookedBinaryHeap<T, C = MaxComparator, H = ()> {
+    heap: BinaryHeap<T, C>,
+    hooks: H,
+}
+
+impl<T: fmt::Debug, C, H: fmt::Debug> fmt::Debug for HookedBinaryHeap<T, C, H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HookedBinaryHeap")
+            .field("heap", &self.heap)
+            .field("hooks", &self.hooks)
+            .finish()
+    }
+}
+
+impl<T: Clone, C: Clone, H: Clone> Clone for HookedBinaryHeap<T, C, H> {
+    fn clone(&self) -> Self {
+        HookedBinaryHeap {
+            heap: self.heap.clone(),
+            hooks: self.hooks.clone(),
+        }
+    }
+}
+
+impl<T, C, H> HookedBinaryHeap<T, C, H> {
+    /// Attaches `hooks` to `heap`. The elements already in the heap are not
+    /// reported.
+    pub fn new(heap: BinaryHeap<T, C>, hooks: H) -> Self {
+        HookedBinaryHeap { heap, hooks }
+    }
+
+    /// Returns the underlying heap.
+    #[must_use]
+    pub fn heap(&self) -> &BinaryHeap<T, C> {
+        &self.heap
+    }
+
+    /// Returns the hooks.
+    #[must_use]
+    pub fn hooks(&self) -> &H {
+        &self.hooks
+    }
+
+    /// Returns the hooks mutably, for instance to reset counters.
+    pub fn hooks_mut(&mut self) -> &mut H {
+        &mut self.hooks
+    }
+
+    /// Detaches the hooks and returns them with the heap.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_parts(self) -> (BinaryHeap<T, C>, H) {
+        (self.heap, self.hooks)
+    }
+
+    /// Returns the number of elements in the heap.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Returns an iterator visiting all elements in arbitrary order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.heap.iter()
+    }
+
+    /// Drops all elements without reporting them.
+    pub fn clear(&mut self) {
+        self.heap.clear();
+    }
+}
+
+impl<T, C: Compare<T>, H: HeapHooks<T>> HookedBinaryHeap<T, C, H> {
+    /// Returns the greatest element, or `None` if the heap is empty.
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.heap.peek()
+    }
+
+    /// Reports the item to [`HeapHooks::on_push`] and pushes it onto the
+    /// heap.
+    ///
+    /// [`HeapHooks::on_push`]: trait.HeapHooks.html#method.on_push
+    pub fn push(&mut self, item: T) {
+        self.hooks.on_push(&item, self.heap.len() + 1);
+        self.heap.push(item);
+    }
+
+    /// Removes the greatest element, reports it to [`HeapHooks::on_pop`] and
+    /// returns it, or returns `None` if the heap is empty.
+    ///
+    /// [`HeapHooks::on_pop`]: trait.HeapHooks.html#method.on_pop
+    pub fn pop(&mut self) -> Option<T> {
+        let item = self.heap.pop()?;
+        self.hooks.on_pop(&item, self.heap.len());
+        Some(item)
+    }
+
+    /// Moves all the elements of `other` into the heap, leaving `other`
+    /// empty, and reports [`HeapHooks::on_rebuild`].
+    ///
+    /// [`HeapHooks::on_rebuild`]: trait.HeapHooks.html#method.on_rebuild
+    pub fn append(&mut self, other: &mut BinaryHeap<T, C>) {
+        self.heap.append(other);
+        self.hooks.on_rebuild(self.heap.len());
+    }
+
+    /// Consumes the heap and returns its elements in ascending order,
+    /// without reporting them.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        self.heap.into_sorted_vec()
+    }
+}
+
+/// Reports every item to [`HeapHooks::on_push`].
+///
+/// [`HeapHooks::on_push`]: trait.HeapHooks.html#method.on_push
+impl<T, C: Compare<T>, H: HeapHooks<T>> Extend<T> for HookedBinaryHeap<T, C, H> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+impl<T, C: Compare<T>, H: HeapHooks<T>> PriorityQueue<T> for HookedBinaryHeap<T, C, H> {
+    fn push(&mut self, item: T) {
+        HookedBinaryHeap::push(self, item);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        HookedBinaryHeap::pop(self)
+    }
+
+    fn peek(&self) -> Option<&T> {
+        HookedBinaryHeap::peek(self)
+    }
+
+    fn len(&self) -> usize {
+        HookedBinaryHeap::len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        HookedBinaryHeap::is_empty(self)
+    }
+}
@@ -0,0 +1,277 @@
+//! A heap that keeps few elements as a sorted vector.
+
+use crate::array::{sift_down_slice, sift_up_slice};
+use crate::binary_heap::MaxComparator;
+use crate::queue::PriorityQueue;
+use compare::Compare;
+use std::fmt;
+use std::mem;
+use std::slice;
+
+/// Number of elements up to which [`HybridBinaryHeap::new`] keeps its
+/// elements sorted.
+///
+/// [`HybridBinaryHeap::new`]: struct.HybridBinaryHeap.html#method.new
+pub const DEFAULT_HYBRID_THRESHOLD: usize = 16;
+
+/// A priority queue that is a sorted vector while it is small and a binary
+/// heap beyond that.
+///
+/// For a handful of elements, keeping them sorted in ascending order beats
+/// a heap: [`push`] moves the new element down from the end, one swap per
+/// greater element, like a step of insertion sort, and [`pop`] simply takes
+/// the last one. Once a push takes the
+/// heap beyond `threshold` elements, the vector is reversed, which makes it
+/// a valid binary heap, and from then on the heap sifts like
+/// [`BinaryHeap`]. It goes back to a sorted vector when pops leave it at half
+/// the threshold, so a heap whose length hovers around the threshold does not
+/// switch on every operation.
+///
+/// Like `BinaryHeap`, the greatest element according to the comparator `C`
+/// is popped first.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::{HybridBinaryHeap, MinComparator};
+///
+/// let mut heap = HybridBinaryHeap::with_threshold_cmp(4, MinComparator);
+/// heap.extend(vec![7, 3, 9, 1]);
+/// assert!(heap.is_sorted());
+///
+/// heap.push(8);
+/// assert!(!heap.is_sorted());
+/// assert_eq!(heap.pop(), Some(1));
+/// assert_eq!(heap.pop(), Some(3));
+/// assert_eq!(heap.peek(), Some(&7));
+/// ```
+///
+/// [`push`]: HybridBinaryHeap::push
+/// [`pop`]: HybridBinaryHeap::pop
+/// [`BinaryHeap`]: struct.BinaryHeap.html
+pub struct HybridBinaryHeap<T, C = MaxComparator> {
+    /// Sorted in ascending order if `sorted`, a binary heap otherwise.
+    data: Vec<T>,
+    sorted: bool,
+    threshold: usize,
+    cmp: C,
+}
+
+impl<T: fmt::Debug, C> fmt::Debug for HybridBinaryHeap<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.data.iter()).finish()
+    }
+}
+
+impl<T: Clone, C: Clone> Clone for HybridBinaryHeap<T, C> {
+    fn clone(&self) -> Self {
+        HybridBinaryHeap {
+            data: self.data.clone(),
+            sorted: self.sorted,
+            threshold: self.threshold,
+            cmp: self.cmp.clone(),
+        }
+    }
+}
+
+impl<T: Ord> Default for HybridBinaryHeap<T> {
+    fn default() -> Self {
+        HybridBinaryHeap::new()
+    }
+}
+
+impl<T: Ord> HybridBinaryHeap<T> {
+    /// Creates an empty max-heap that stays sorted up to
+    /// [`DEFAULT_HYBRID_THRESHOLD`] elements.
+    ///
+    /// [`DEFAULT_HYBRID_THRESHOLD`]: constant.DEFAULT_HYBRID_THRESHOLD.html
+    #[must_use]
+    pub fn new() -> Self {
+        HybridBinaryHeap::with_threshold(DEFAULT_HYBRID_THRESHOLD)
+    }
+
+    /// Creates an empty max-heap that stays sorted up to `threshold`
+    /// elements.
+    #[must_use]
+    pub fn with_threshold(threshold: usize) -> Self {
+        HybridBinaryHeap::with_threshold_cmp(threshold, MaxComparator)
+    }
+}
+
+impl<T, C> HybridBinaryHeap<T, C> {
+    /// Creates an empty heap ordered by `cmp` that stays sorted up to
+    /// [`DEFAULT_HYBRID_THRESHOLD`] elements.
+    ///
+    /// [`DEFAULT_HYBRID_THRESHOLD`]: constant.DEFAULT_HYBRID_THRESHOLD.html
+    pub fn with_cmp(cmp: C) -> Self {
+        HybridBinaryHeap::with_threshold_cmp(DEFAULT_HYBRID_THRESHOLD, cmp)
+    }
+
+    /// Creates an empty heap ordered by `cmp` that stays sorted up to
+    /// `threshold` elements.
+    pub fn with_threshold_cmp(threshold: usize, cmp: C) -> Self {
+        HybridBinaryHeap {
+            data: Vec::new(),
+            sorted: true,
+            threshold,
+            cmp,
+        }
+    }
+
+    /// Returns the number of elements up to which the heap is kept sorted.
+    #[must_use]
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// Returns `true` if the elements are currently kept as a sorted vector
+    /// rather than a binary heap.
+    #[must_use]
+    pub fn is_sorted(&self) -> bool {
+        self.sorted
+    }
+
+    /// Returns the number of elements in the heap.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns the number of elements the heap can hold without
+    /// reallocating.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.data.capacity()
+    }
+
+    /// Returns an iterator visiting all elements in arbitrary order.
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.data.iter()
+    }
+
+    /// Drops all elements, keeping the allocated capacity.
+    pub fn clear(&mut self) {
+        self.data.clear();
+        self.sorted = true;
+    }
+
+    /// Consumes the heap and returns the elements in arbitrary order.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_vec(self) -> Vec<T> {
+        self.data
+    }
+
+    /// Returns the greatest element, or `None` if the heap is empty.
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        if self.sorted {
+            self.data.last()
+        } else {
+            self.data.first()
+        }
+    }
+}
+
+impl<T, C: Compare<T>> HybridBinaryHeap<T, C> {
+    /// Pushes an item onto the heap.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*t*) while the heap holds at most *t* = `threshold` elements,
+    /// with a small constant factor. *O*(log(*n*)) beyond that.
+    pub fn push(&mut self, item: T) {
+        if !self.sorted {
+            let pos = self.data.len();
+            self.data.push(item);
+            sift_up_slice(&mut self.data, pos, &self.cmp);
+            return;
+        }
+        if self.data.len() < self.threshold {
+            // Insertion sort step: scanning a few elements is faster than a
+            // binary search with its mispredicted branches.
+            let mut pos = self.data.len();
+            self.data.push(item);
+            while pos > 0 && self.cmp.compares_lt(&self.data[pos], &self.data[pos - 1]) {
+                self.data.swap(pos, pos - 1);
+                pos -= 1;
+            }
+            return;
+        }
+        // Sorted in descending order, the data is a valid heap.
+        self.data.reverse();
+        self.sorted = false;
+        self.push(item);
+    }
+
+    /// Removes the greatest element and returns it, or `None` if the heap is
+    /// empty.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(1) while the heap is sorted, *O*(log(*n*)) otherwise, plus an
+    /// *O*(*t* log(*t*)) sort when it shrinks to half the threshold *t*.
+    pub fn pop(&mut self) -> Option<T> {
+        if self.sorted {
+            return self.data.pop();
+        }
+        let item = self.data.pop().map(|mut item| {
+            if !self.data.is_empty() {
+                mem::swap(&mut item, &mut self.data[0]);
+                sift_down_slice(&mut self.data, 0, &self.cmp);
+            }
+            item
+        });
+        if self.data.len() <= self.threshold / 2 {
+            let cmp = &self.cmp;
+            self.data.sort_unstable_by(|a, b| cmp.compare(a, b));
+            self.sorted = true;
+        }
+        item
+    }
+
+    /// Consumes the heap and returns its elements in ascending order.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        if !self.sorted {
+            let cmp = &self.cmp;
+            self.data.sort_unstable_by(|a, b| cmp.compare(a, b));
+        }
+        self.data
+    }
+}
+
+impl<T, C: Compare<T>> Extend<T> for HybridBinaryHeap<T, C> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+impl<T, C: Compare<T>> PriorityQueue<T> for HybridBinaryHeap<T, C> {
+    fn push(&mut self, item: T) {
+        HybridBinaryHeap::push(self, item);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        HybridBinaryHeap::pop(self)
+    }
+
+    fn peek(&self) -> Option<&T> {
+        HybridBinaryHeap::peek(self)
+    }
+
+    fn len(&self) -> usize {
+        HybridBinaryHeap::len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        HybridBinaryHeap::is_empty(self)
+    }
+}
@@ -0,0 +1,314 @@
+//! Recording the operations on a heap for deterministic replay.
+//!
+//! Requires the `journal` feature.
+
+use crate::binary_heap::{BinaryHeap, Iter, MaxComparator};
+use crate::queue::PriorityQueue;
+use compare::Compare;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// An operation on a [`JournaledBinaryHeap`], as recorded in its journal.
+///
+/// With the `serde` feature, operations can be serialized, for instance to
+/// ship the journal of a lockstep simulation to the machine whose heap
+/// diverged.
+///
+/// [`JournaledBinaryHeap`]: struct.JournaledBinaryHeap.html
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HeapOp<T> {
+    /// [`BinaryHeap::push`](struct.BinaryHeap.html#method.push) of the item.
+    Push(T),
+    /// [`BinaryHeap::pop`](struct.BinaryHeap.html#method.pop).
+    Pop,
+    /// [`BinaryHeap::replace_at`](struct.BinaryHeap.html#method.replace_at)
+    /// of the index and item.
+    ReplaceAt(usize, T),
+    /// [`BinaryHeap::swap_remove_index`](struct.BinaryHeap.html#method.swap_remove_index)
+    /// of the index.
+    SwapRemoveIndex(usize),
+    /// [`BinaryHeap::clear`](struct.BinaryHeap.html#method.clear).
+    Clear,
+}
+
+impl<T> HeapOp<T> {
+    /// Performs the operation on `heap` and returns the element it removed,
+    /// if any.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the operation refers to an index that is out of bounds.
+    pub fn apply<C: Compare<T>>(self, heap: &mut BinaryHeap<T, C>) -> Option<T> {
+        match self {
+            HeapOp::Push(item) => {
+                heap.push(item);
+                None
+            }
+            HeapOp::Pop => heap.pop(),
+            HeapOp::ReplaceAt(index, item) => Some(heap.replace_at(index, item)),
+            HeapOp::SwapRemoveIndex(index) => Some(heap.swap_remove_index(index)),
+            HeapOp::Clear => {
+                heap.clear();
+                None
+            }
+        }
+    }
+}
+
+/// The destination of the operations recorded by a [`JournaledBinaryHeap`].
+///
+/// `Vec<HeapOp<T>>` is a sink that keeps the whole journal in memory.
+/// Implement this trait to write operations to a file or a socket instead,
+/// or to keep only the most recent ones.
+///
+/// [`JournaledBinaryHeap`]: struct.JournaledBinaryHeap.html
+pub trait JournalSink<T> {
+    /// Records an operation, just before it is performed.
+    fn record(&mut self, op: &HeapOp<T>);
+}
+
+impl<T: Clone> JournalSink<T> for Vec<HeapOp<T>> {
+    fn record(&mut self, op: &HeapOp<T>) {
+        self.push(op.clone());
+    }
+}
+
+impl<T, S: JournalSink<T> + ?Sized> JournalSink<T> for &mut S {
+    fn record(&mut self, op: &HeapOp<T>) {
+        (**self).record(op);
+    }
+}
+
+/// A [`BinaryHeap`] that records every operation to a [`JournalSink`].
+///
+/// The heap starts empty, so replaying its journal with
+/// [`BinaryHeap::replay`] rebuilds it exactly: not only the same elements,
+/// but the same layout, and the same results from every pop along the way.
+/// Comparing the replays of two journals step by step finds the first
+/// operation after which two heaps that should agree diverged.
+///
+/// This type is available with the `journal` feature.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::{BinaryHeap, HeapOp, JournaledBinaryHeap, MaxComparator};
+///
+/// let mut heap = JournaledBinaryHeap::new();
+/// heap.extend(vec![3, 1, 4]);
+/// assert_eq!(heap.pop(), Some(4));
+///
+/// let (heap, journal) = heap.into_parts();
+/// assert_eq!(journal[3], HeapOp::Pop);
+///
+/// let replayed = BinaryHeap::replay(journal, MaxComparator);
+/// assert_eq!(replayed.into_vec(), heap.into_vec());
+/// ```
+///
+/// [`BinaryHeap`]: struct.BinaryHeap.html
+/// [`BinaryHeap::replay`]: struct.BinaryHeap.html#method.replay
+/// [`JournalSink`]: trait.JournalSink.html
+pub struct JournaledBinaryHeap<T, C = MaxComparator, S = Vec<HeapOp<T>>> {
+    heap: BinaryHeap<T, C>,
+    sink: S,
+}
+
+impl<T: fmt::Debug, C, S> fmt::Debug for JournaledBinaryHeap<T, C, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JournaledBinaryHeap")
+            .field("heap", &self.heap)
+            .finish()
+    }
+}
+
+impl<T: Ord + Clone> Default for JournaledBinaryHeap<T> {
+    fn default() -> Self {
+        JournaledBinaryHeap::new()
+    }
+}
+
+impl<T: Ord + Clone> JournaledBinaryHeap<T> {
+    /// Creates an empty max-heap that keeps its journal in a `Vec`.
+    #[must_use]
+    pub fn new() -> Self {
+        JournaledBinaryHeap::with_cmp_sink(MaxComparator, Vec::new())
+    }
+}
+
+impl<T, C, S> JournaledBinaryHeap<T, C, S> {
+    /// Returns the underlying heap.
+    #[must_use]
+    pub fn heap(&self) -> &BinaryHeap<T, C> {
+        &self.heap
+    }
+
+    /// Returns the sink.
+    #[must_use]
+    pub fn sink(&self) -> &S {
+        &self.sink
+    }
+
+    /// Returns the sink mutably, for instance to flush it.
+    pub fn sink_mut(&mut self) -> &mut S {
+        &mut self.sink
+    }
+
+    /// Returns the heap and the sink.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_parts(self) -> (BinaryHeap<T, C>, S) {
+        (self.heap, self.sink)
+    }
+
+    /// Returns the number of elements in the heap.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Returns an iterator visiting all elements in arbitrary order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.heap.iter()
+    }
+}
+
+impl<T, C: Compare<T>, S: JournalSink<T>> JournaledBinaryHeap<T, C, S> {
+    /// Creates an empty heap ordered by `cmp` that records to `sink`.
+    pub fn with_cmp_sink(cmp: C, sink: S) -> Self {
+        JournaledBinaryHeap {
+            heap: BinaryHeap::from_vec_cmp(Vec::new(), cmp),
+            sink,
+        }
+    }
+
+    /// Returns the greatest element, or `None` if the heap is empty.
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.heap.peek()
+    }
+
+    /// Records and performs [`BinaryHeap::push`].
+    ///
+    /// [`BinaryHeap::push`]: struct.BinaryHeap.html#method.push
+    pub fn push(&mut self, item: T) {
+        self.perform(HeapOp::Push(item));
+    }
+
+    /// Records and performs [`BinaryHeap::pop`].
+    ///
+    /// [`BinaryHeap::pop`]: struct.BinaryHeap.html#method.pop
+    pub fn pop(&mut self) -> Option<T> {
+        self.perform(HeapOp::Pop)
+    }
+
+    /// Records and performs [`BinaryHeap::replace_at`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds. The operation is not recorded
+    /// then.
+    ///
+    /// [`BinaryHeap::replace_at`]: struct.BinaryHeap.html#method.replace_at
+    pub fn replace_at(&mut self, index: usize, item: T) -> T {
+        self.check_index(index);
+        self.perform(HeapOp::ReplaceAt(index, item)).unwrap()
+    }
+
+    /// Records and performs [`BinaryHeap::swap_remove_index`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds. The operation is not recorded
+    /// then.
+    ///
+    /// [`BinaryHeap::swap_remove_index`]: struct.BinaryHeap.html#method.swap_remove_index
+    pub fn swap_remove_index(&mut self, index: usize) -> T {
+        self.check_index(index);
+        self.perform(HeapOp::SwapRemoveIndex(index)).unwrap()
+    }
+
+    /// Records and performs [`BinaryHeap::clear`].
+    ///
+    /// [`BinaryHeap::clear`]: struct.BinaryHeap.html#method.clear
+    pub fn clear(&mut self) {
+        self.perform(HeapOp::Clear);
+    }
+
+    fn check_index(&self, index: usize) {
+        assert!(
+            index < self.len(),
+            "index {} out of bounds for a heap of length {}",
+            index,
+            self.len()
+        );
+    }
+
+    fn perform(&mut self, op: HeapOp<T>) -> Option<T> {
+        self.sink.record(&op);
+        op.apply(&mut self.heap)
+    }
+}
+
+impl<T, C: Compare<T>, S: JournalSink<T>> Extend<T> for JournaledBinaryHeap<T, C, S> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+impl<T, C: Compare<T>> BinaryHeap<T, C> {
+    /// Creates an empty heap ordered by `cmp` and performs the operations of
+    /// `journal` on it, in order.
+    ///
+    /// Replaying the journal of a [`JournaledBinaryHeap`] with an equivalent
+    /// comparator yields a heap identical to the journaled one, down to the
+    /// order of [`iter`]. To inspect the heap between operations, call
+    /// [`HeapOp::apply`] instead.
+    ///
+    /// This method is available with the `journal` feature.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an operation refers to an index that is out of bounds.
+    ///
+    /// [`JournaledBinaryHeap`]: struct.JournaledBinaryHeap.html
+    /// [`HeapOp::apply`]: enum.HeapOp.html#method.apply
+    /// [`iter`]: BinaryHeap::iter
+    pub fn replay<I: IntoIterator<Item = HeapOp<T>>>(journal: I, cmp: C) -> Self {
+        let mut heap = BinaryHeap::from_vec_cmp(Vec::new(), cmp);
+        for op in journal {
+            op.apply(&mut heap);
+        }
+        heap
+    }
+}
+
+impl<T, C: Compare<T>, S: JournalSink<T>> PriorityQueue<T> for JournaledBinaryHeap<T, C, S> {
+    fn push(&mut self, item: T) {
+        JournaledBinaryHeap::push(self, item);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        JournaledBinaryHeap::pop(self)
+    }
+
+    fn peek(&self) -> Option<&T> {
+        JournaledBinaryHeap::peek(self)
+    }
+
+    fn len(&self) -> usize {
+        JournaledBinaryHeap::len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        JournaledBinaryHeap::is_empty(self)
+    }
+}
@@ -0,0 +1,421 @@
+//! A priority queue of keys, whose priorities can be looked up and changed.
+
+use crate::binary_heap::MaxComparator;
+use crate::tracked::{PositionMap, TrackedBinaryHeap};
+use compare::Compare;
+use std::cmp::Ordering;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+
+fn key_of<K: Clone, P>(entry: &(K, P)) -> K {
+    entry.0.clone()
+}
+
+/// Orders `(key, priority)` pairs by priority only.
+#[derive(Clone, Copy, Default, Debug)]
+struct ByPriority<C>(C);
+
+impl<K, P, C: Compare<P>> Compare<(K, P)> for ByPriority<C> {
+    fn compare(&self, a: &(K, P), b: &(K, P)) -> Ordering {
+        self.0.compare(&a.1, &b.1)
+    }
+}
+
+type KeyedHeap<K, P, C, S> =
+    TrackedBinaryHeap<(K, P), PositionMap<K, fn(&(K, P)) -> K, S>, ByPriority<C>>;
+
+/// A priority queue of distinct keys, each with a priority that can be
+/// looked up, changed or removed by key.
+///
+/// The pairs are kept in a [`TrackedBinaryHeap`] whose [`PositionMap`]
+/// finds a key in *O*(1), so that changing or removing the priority of any
+/// key takes *O*(log(*n*)). [`entry`] looks a key up once and then updates
+/// or inserts it, for "raise this job's priority, or enqueue it" in a single
+/// step.
+///
+/// Each key is stored once in the heap and once in the map, hence
+/// `K: Clone`.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::KeyedPriorityQueue;
+///
+/// let mut jobs = KeyedPriorityQueue::new();
+/// jobs.push("build", 2);
+/// jobs.push("test", 1);
+///
+/// for (job, bump) in [("test", 5), ("deploy", 3)] {
+///     jobs.entry(job)
+///         .and_modify_priority(|p| *p += bump)
+///         .or_insert(bump);
+/// }
+///
+/// assert_eq!(jobs.pop(), Some(("test", 6)));
+/// assert_eq!(jobs.pop(), Some(("deploy", 3)));
+/// assert_eq!(jobs.pop(), Some(("build", 2)));
+/// ```
+///
+/// [`TrackedBinaryHeap`]: struct.TrackedBinaryHeap.html
+/// [`PositionMap`]: struct.PositionMap.html
+/// [`entry`]: KeyedPriorityQueue::entry
+pub struct KeyedPriorityQueue<K, P, C = MaxComparator, S = RandomState> {
+    heap: KeyedHeap<K, P, C, S>,
+}
+
+impl<K: fmt::Debug, P: fmt::Debug, C, S> fmt::Debug for KeyedPriorityQueue<K, P, C, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.heap.iter().map(|(k, p)| (k, p)))
+            .finish()
+    }
+}
+
+impl<K: Hash + Eq + Clone, P: Ord> Default for KeyedPriorityQueue<K, P> {
+    fn default() -> Self {
+        KeyedPriorityQueue::new()
+    }
+}
+
+impl<K: Hash + Eq + Clone, P: Ord> KeyedPriorityQueue<K, P> {
+    /// Creates an empty queue that pops the greatest priority first.
+    #[must_use]
+    pub fn new() -> Self {
+        KeyedPriorityQueue::with_cmp(MaxComparator)
+    }
+}
+
+impl<K: Hash + Eq + Clone, P, C: Compare<P>> KeyedPriorityQueue<K, P, C> {
+    /// Creates an empty queue that orders the priorities by `cmp`.
+    pub fn with_cmp(cmp: C) -> Self {
+        KeyedPriorityQueue::with_cmp_and_hasher(cmp, RandomState::new())
+    }
+}
+
+impl<K, P, C, S> KeyedPriorityQueue<K, P, C, S> {
+    /// Returns the number of keys in the queue.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Checks if the queue is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Returns an iterator visiting all keys and their priorities, in
+    /// arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &P)> {
+        self.heap.iter().map(|(k, p)| (k, p))
+    }
+}
+
+impl<K: Hash + Eq + Clone, P, C: Compare<P>, S: BuildHasher> KeyedPriorityQueue<K, P, C, S> {
+    /// Creates an empty queue that orders the priorities by `cmp` and hashes
+    /// the keys with `hasher`.
+    pub fn with_cmp_and_hasher(cmp: C, hasher: S) -> Self {
+        KeyedPriorityQueue {
+            heap: TrackedBinaryHeap::with_cmp(
+                ByPriority(cmp),
+                PositionMap::with_hasher(key_of::<K, P> as fn(&(K, P)) -> K, hasher),
+            ),
+        }
+    }
+
+    /// Checks if `key` is in the queue.
+    #[must_use]
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.index_of(key).is_some()
+    }
+
+    /// Returns the priority of `key`, or `None` if it is not in the queue.
+    #[must_use]
+    pub fn priority(&self, key: &K) -> Option<&P> {
+        self.index_of(key).map(|i| &self.heap.as_slice()[i].1)
+    }
+
+    /// Returns the key with the greatest priority and its priority, or
+    /// `None` if the queue is empty.
+    #[must_use]
+    pub fn peek(&self) -> Option<(&K, &P)> {
+        self.heap.peek().map(|(k, p)| (k, p))
+    }
+
+    /// Inserts `key` with `priority`, or changes its priority if it is
+    /// already in the queue, and returns the old priority.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)).
+    pub fn push(&mut self, key: K, priority: P) -> Option<P> {
+        match self.entry(key) {
+            Entry::Occupied(mut entry) => Some(entry.set_priority(priority)),
+            Entry::Vacant(entry) => {
+                entry.insert(priority);
+                None
+            }
+        }
+    }
+
+    /// Removes the key with the greatest priority and returns it with its
+    /// priority, or `None` if the queue is empty.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)).
+    pub fn pop(&mut self) -> Option<(K, P)> {
+        self.heap.pop()
+    }
+
+    /// Removes `key` and returns its priority, or `None` if it is not in
+    /// the queue.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)).
+    pub fn remove(&mut self, key: &K) -> Option<P> {
+        let i = self.index_of(key)?;
+        Some(self.heap.remove_at(i).1)
+    }
+
+    /// Returns the entry of `key`, to read, change or remove its priority,
+    /// or to insert it.
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, P, C, S> {
+        match self.index_of(&key) {
+            Some(index) => Entry::Occupied(OccupiedEntry {
+                heap: &mut self.heap,
+                index,
+                key,
+            }),
+            None => Entry::Vacant(VacantEntry {
+                heap: &mut self.heap,
+                key,
+            }),
+        }
+    }
+
+    /// Removes all keys.
+    pub fn clear(&mut self) {
+        self.heap.clear();
+    }
+
+    fn index_of(&self, key: &K) -> Option<usize> {
+        self.heap.tracker().index_of(key)
+    }
+}
+
+impl<K: Hash + Eq + Clone, P, C: Compare<P>, S: BuildHasher> Extend<(K, P)>
+    for KeyedPriorityQueue<K, P, C, S>
+{
+    fn extend<I: IntoIterator<Item = (K, P)>>(&mut self, iter: I) {
+        for (key, priority) in iter {
+            self.push(key, priority);
+        }
+    }
+}
+
+/// The entry of a key in a [`KeyedPriorityQueue`], which may be vacant.
+///
+/// This `enum` is created by [`KeyedPriorityQueue::entry`].
+///
+/// [`KeyedPriorityQueue`]: struct.KeyedPriorityQueue.html
+/// [`KeyedPriorityQueue::entry`]: struct.KeyedPriorityQueue.html#method.entry
+pub enum Entry<'a, K, P, C = MaxComparator, S = RandomState> {
+    /// The key is in the queue.
+    Occupied(OccupiedEntry<'a, K, P, C, S>),
+    /// The key is not in the queue.
+    Vacant(VacantEntry<'a, K, P, C, S>),
+}
+
+impl<K: fmt::Debug, P: fmt::Debug, C, S> fmt::Debug for Entry<'_, K, P, C, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Entry::Occupied(entry) => f.debug_tuple("Entry").field(entry).finish(),
+            Entry::Vacant(entry) => f.debug_tuple("Entry").field(entry).finish(),
+        }
+    }
+}
+
+impl<'a, K: Hash + Eq + Clone, P, C: Compare<P>, S: BuildHasher> Entry<'a, K, P, C, S> {
+    /// Returns the key of the entry.
+    #[must_use]
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    /// Inserts the key with `priority` if it is not in the queue, and
+    /// returns its priority.
+    pub fn or_insert(self, priority: P) -> &'a P {
+        self.or_insert_with(|| priority)
+    }
+
+    /// Inserts the key with the priority returned by `f` if it is not in the
+    /// queue, and returns its priority.
+    pub fn or_insert_with<F: FnOnce() -> P>(self, f: F) -> &'a P {
+        match self {
+            Entry::Occupied(entry) => entry.into_priority(),
+            Entry::Vacant(entry) => entry.insert(f()),
+        }
+    }
+
+    /// Changes the priority with `f` if the key is in the queue, and
+    /// returns the entry.
+    pub fn and_modify_priority<F: FnOnce(&mut P)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(mut entry) => {
+                entry.modify_priority(f);
+                Entry::Occupied(entry)
+            }
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+        }
+    }
+
+    /// Removes the key and returns its priority, or returns `None` if it is
+    /// not in the queue.
+    pub fn remove(self) -> Option<P> {
+        match self {
+            Entry::Occupied(entry) => Some(entry.remove()),
+            Entry::Vacant(_) => None,
+        }
+    }
+}
+
+/// The entry of a key that is in a [`KeyedPriorityQueue`].
+///
+/// [`KeyedPriorityQueue`]: struct.KeyedPriorityQueue.html
+pub struct OccupiedEntry<'a, K, P, C = MaxComparator, S = RandomState> {
+    heap: &'a mut KeyedHeap<K, P, C, S>,
+    index: usize,
+    key: K,
+}
+
+impl<K: fmt::Debug, P: fmt::Debug, C, S> fmt::Debug for OccupiedEntry<'_, K, P, C, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OccupiedEntry")
+            .field("key", &self.key)
+            .field("priority", &self.heap.as_slice()[self.index].1)
+            .finish()
+    }
+}
+
+impl<'a, K: Hash + Eq + Clone, P, C: Compare<P>, S: BuildHasher> OccupiedEntry<'a, K, P, C, S> {
+    /// Returns the key of the entry.
+    #[must_use]
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Returns the priority of the key.
+    #[must_use]
+    pub fn priority(&self) -> &P {
+        &self.heap.as_slice()[self.index].1
+    }
+
+    /// Returns the priority of the key, borrowed for as long as the queue.
+    #[must_use]
+    pub fn into_priority(self) -> &'a P {
+        &self.heap.as_slice()[self.index].1
+    }
+
+    /// Changes the priority of the key to `priority` and returns the old
+    /// one.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)).
+    pub fn set_priority(&mut self, priority: P) -> P {
+        let (key, old) = self.heap.remove_at(self.index);
+        self.reinsert(key, priority);
+        old
+    }
+
+    /// Changes the priority of the key with `f`.
+    ///
+    /// If `f` panics, the key is left out of the queue.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)).
+    pub fn modify_priority<F: FnOnce(&mut P)>(&mut self, f: F) {
+        let (key, mut priority) = self.heap.remove_at(self.index);
+        f(&mut priority);
+        self.reinsert(key, priority);
+    }
+
+    /// Removes the key and returns its priority.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)).
+    pub fn remove(self) -> P {
+        self.remove_entry().1
+    }
+
+    /// Removes the key and returns it with its priority.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)).
+    pub fn remove_entry(self) -> (K, P) {
+        self.heap.remove_at(self.index)
+    }
+
+    fn reinsert(&mut self, key: K, priority: P) {
+        self.heap.push((key, priority));
+        self.index = self
+            .heap
+            .tracker()
+            .index_of(&self.key)
+            .expect("the key was just pushed");
+    }
+}
+
+/// The entry of a key that is not in a [`KeyedPriorityQueue`].
+///
+/// [`KeyedPriorityQueue`]: struct.KeyedPriorityQueue.html
+pub struct VacantEntry<'a, K, P, C = MaxComparator, S = RandomState> {
+    heap: &'a mut KeyedHeap<K, P, C, S>,
+    key: K,
+}
+
+impl<K: fmt::Debug, P, C, S> fmt::Debug for VacantEntry<'_, K, P, C, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("VacantEntry").field(&self.key).finish()
+    }
+}
+
+impl<'a, K: Hash + Eq + Clone, P, C: Compare<P>, S: BuildHasher> VacantEntry<'a, K, P, C, S> {
+    /// Returns the key of the entry.
+    #[must_use]
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Returns the key, without inserting it.
+    #[must_use]
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    /// Inserts the key with `priority` and returns its priority.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)).
+    pub fn insert(self, priority: P) -> &'a P {
+        let index_key = self.key.clone();
+        self.heap.push((self.key, priority));
+        let i = self
+            .heap
+            .tracker()
+            .index_of(&index_key)
+            .expect("the key was just pushed");
+        &self.heap.as_slice()[i].1
+    }
+}
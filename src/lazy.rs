@@ -0,0 +1,189 @@
+//! A heap that defers restoring its order until it is read.
+
+use crate::binary_heap::{BinaryHeap, Iter, MaxComparator};
+use compare::Compare;
+use std::fmt;
+use std::iter::Chain;
+use std::slice;
+
+/// A priority queue whose pushes are buffered until the next read.
+///
+/// [`push`] appends the item to an unordered buffer in *O*(1). The next
+/// [`peek`] or [`pop`] moves the whole buffer into the underlying
+/// [`BinaryHeap`] at once, like [`BinaryHeap::append`]: it sifts each
+/// pending item up, or rebuilds the heap when that is cheaper. A long run of
+/// pushes between reads thus costs *O*(*n*) overall instead of
+/// *O*(*k* log(*n*)).
+///
+/// Since reading may reorder the heap, [`peek`] takes `&mut self`.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::LazyBinaryHeap;
+///
+/// let mut heap = LazyBinaryHeap::new();
+/// heap.extend(vec![3, 1, 4, 1, 5]);
+/// assert_eq!(heap.pending_len(), 5);
+///
+/// assert_eq!(heap.peek(), Some(&5));
+/// assert_eq!(heap.pending_len(), 0);
+/// heap.push(9);
+/// assert_eq!(heap.pop(), Some(9));
+/// assert_eq!(heap.into_sorted_vec(), [1, 1, 3, 4, 5]);
+/// ```
+///
+/// [`push`]: LazyBinaryHeap::push
+/// [`peek`]: LazyBinaryHeap::peek
+/// [`pop`]: LazyBinaryHeap::pop
+/// [`BinaryHeap`]: struct.BinaryHeap.html
+/// [`BinaryHeap::append`]: struct.BinaryHeap.html#method.append
+pub struct LazyBinaryHeap<T, C = MaxComparator> {
+    heap: BinaryHeap<T, C>,
+    pending: Vec<T>,
+}
+
+impl<T: fmt::Debug, C> fmt::Debug for LazyBinaryHeap<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Clone, C: Clone> Clone for LazyBinaryHeap<T, C> {
+    fn clone(&self) -> Self {
+        LazyBinaryHeap {
+            heap: self.heap.clone(),
+            pending: self.pending.clone(),
+        }
+    }
+}
+
+impl<T: Ord> Default for LazyBinaryHeap<T> {
+    fn default() -> Self {
+        LazyBinaryHeap::new()
+    }
+}
+
+impl<T: Ord> LazyBinaryHeap<T> {
+    /// Creates an empty max-heap.
+    #[must_use]
+    pub fn new() -> Self {
+        LazyBinaryHeap::from(BinaryHeap::new())
+    }
+}
+
+impl<T, C: Compare<T>> LazyBinaryHeap<T, C> {
+    /// Creates an empty heap ordered by `cmp`.
+    pub fn with_cmp(cmp: C) -> Self {
+        LazyBinaryHeap::from(BinaryHeap::from_vec_cmp(Vec::new(), cmp))
+    }
+}
+
+impl<T, C> From<BinaryHeap<T, C>> for LazyBinaryHeap<T, C> {
+    fn from(heap: BinaryHeap<T, C>) -> Self {
+        LazyBinaryHeap {
+            heap,
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl<T, C> LazyBinaryHeap<T, C> {
+    /// Returns the number of elements in the heap, including pending ones.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.heap.len() + self.pending.len()
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty() && self.pending.is_empty()
+    }
+
+    /// Returns the number of pushed elements not yet moved into the heap.
+    #[must_use]
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Returns an iterator visiting all elements in arbitrary order.
+    pub fn iter(&self) -> Chain<Iter<'_, T>, slice::Iter<'_, T>> {
+        self.heap.iter().chain(self.pending.iter())
+    }
+
+    /// Drops all elements, keeping the allocated capacity.
+    pub fn clear(&mut self) {
+        self.heap.clear();
+        self.pending.clear();
+    }
+
+    /// Consumes the heap and returns the elements in arbitrary order.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_vec(self) -> Vec<T> {
+        let mut vec = self.heap.into_vec();
+        vec.extend(self.pending);
+        vec
+    }
+}
+
+impl<T, C: Compare<T>> LazyBinaryHeap<T, C> {
+    /// Pushes an item into the pending buffer.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(1) amortized.
+    pub fn push(&mut self, item: T) {
+        self.pending.push(item);
+    }
+
+    /// Moves the pending elements into the heap.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(min(*k* log(*n*), *n*)) for *k* pending elements.
+    pub fn flush(&mut self) {
+        if !self.pending.is_empty() {
+            self.heap.append_vec(&mut self.pending);
+        }
+    }
+
+    /// Flushes the pending elements and returns the greatest element, or
+    /// `None` if the heap is empty.
+    pub fn peek(&mut self) -> Option<&T> {
+        self.flush();
+        self.heap.peek()
+    }
+
+    /// Flushes the pending elements, then removes the greatest element and
+    /// returns it, or `None` if the heap is empty.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)) plus the cost of [`flush`].
+    ///
+    /// [`flush`]: LazyBinaryHeap::flush
+    pub fn pop(&mut self) -> Option<T> {
+        self.flush();
+        self.heap.pop()
+    }
+
+    /// Flushes the pending elements and returns the underlying heap.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_heap(mut self) -> BinaryHeap<T, C> {
+        self.flush();
+        self.heap
+    }
+
+    /// Consumes the heap and returns its elements in ascending order.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        self.into_heap().into_sorted_vec()
+    }
+}
+
+impl<T, C: Compare<T>> Extend<T> for LazyBinaryHeap<T, C> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.pending.extend(iter);
+    }
+}
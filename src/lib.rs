@@ -3,12 +3,146 @@
 //!
 //! Added features include:
 //! * Heaps other than max heap.
-//! * Optional [`serde`] feature.
+//! * Optional [`serde`] feature. Deserialized heaps are rebuilt, or checked
+//!   with [`deserialize_checked()`], [`serde_sorted`] writes the elements
+//!   in sorted order, and [`HeapSeed`] supplies the comparator at
+//!   deserialization time.
+//! * Optional `borsh` feature implementing borsh serialization. Like with
+//!   serde, deserialized heaps are rebuilt, or checked with
+//!   [`borsh_deserialize_checked()`].
+//! * Optional `schemars` feature implementing `JsonSchema` for heaps, which
+//!   describes the form written by the `serde` implementation.
+//! * Optional `rkyv` feature archiving heaps as [`ArchivedBinaryHeap`],
+//!   which can be read in place without deserializing.
+//! * Optional `futures` feature providing `Stream` adaptors such as
+//!   [`BinaryHeap::into_stream_sorted()`].
+//! * Optional `rand` feature providing [`BinaryHeap::pop_random()`].
+//! * Optional `rayon` feature for collecting, extending and iterating over
+//!   heaps with parallel iterators.
+//! * Optional `smallvec` feature providing [`SmallBinaryHeap`], which stores
+//!   its first elements inline.
+//! * Optional `concurrent` feature providing the lock-free
+//!   [`ConcurrentPriorityQueue`].
+//! * Optional `dot` feature providing [`BinaryHeap::to_dot()`], which renders
+//!   the tree for Graphviz.
+//! * Optional `fallible` feature providing methods such as
+//!   [`BinaryHeap::try_push()`] that report allocation failure.
+//! * Optional `ffi` feature providing the C interface in [`ffi`].
+//! * Optional `alloc_guard` feature providing
+//!   [`BinaryHeap::freeze_capacity()`], which catches any later reallocation,
+//!   for real-time code.
+//! * Optional `zeroize` feature implementing [`Zeroize`] for heaps and their
+//!   iterators, to wipe sensitive items.
+//! * Optional `spill` feature providing [`SpillingHeap`], which spills to
+//!   temporary files when it grows beyond a memory threshold.
+//! * Optional `shadow` feature providing [`ShadowBinaryHeap`], which checks
+//!   every operation against a sorted `Vec` for debugging.
+//! * Optional `fingerprint` feature providing [`FingerprintedBinaryHeap`],
+//!   which hashes each element when pushed and panics if it changed by the
+//!   time it is peeked at or popped.
+//! * Optional `strict_asserts` feature, which checks the heap property after
+//!   every `push`, `pop` and `append` and panics at the first operation that
+//!   finds it broken, for instance by an element mutated through a `Cell`.
+//! * Optional `safe_only` feature, which replaces the pointer-based sifting
+//!   with swaps and forbids unsafe code in the whole crate, for audits that
+//!   require it. [`ArrayHeap`] then allocates its storage once, and the
+//!   `ffi`, `rkyv` and `zeroize` features, which cannot work without unsafe
+//!   code, are unavailable.
+//! * Optional `journal` feature providing [`JournaledBinaryHeap`], which
+//!   records every operation so that [`BinaryHeap::replay`] can rebuild the
+//!   heap exactly.
+//! * Optional `testing` feature implementing proptest's and quickcheck's
+//!   `Arbitrary` for [`BinaryHeap`], plus [`heap_strategy()`] and
+//!   [`PanickingComparator`] for testing panic safety.
+//! * [`BlockedBinaryHeap`], a cache-friendlier layout for very large heaps.
+//! * [`BloomBinaryHeap`], whose Bloom filter answers most failed `contains`
+//!   checks without scanning.
+//! * [`HybridBinaryHeap`], which keeps small heaps as a sorted vector.
+//! * [`HookedBinaryHeap`], which reports its operations to [`HeapHooks`]
+//!   callbacks, for instance to collect metrics.
+//! * [`LazyBinaryHeap`], which buffers pushes until the next read.
+//! * [`BudgetedHeap`], which evicts its least elements to stay within a
+//!   memory budget.
+//! * [`CowHeap`], whose snapshots share the storage until one is modified.
+//! * [`SoaBinaryHeap`], which stores priorities apart from the values so
+//!   that sifting only reads the priorities.
+//! * [`ContextBinaryHeap`], whose comparator reads a context passed to
+//!   each operation, such as a lookup table that changes between them.
+//! * [`DelayQueue`], whose values expire after a delay, with a `Stream`
+//!   of expired values that works on any async runtime.
+//! * [`GroupedTopK`], which keeps the top `k` items of each group key.
+//! * [`algorithms::replacement_selection`], which splits its input into
+//!   sorted runs for external sorting, and [`algorithms::partial_sort`],
+//!   which sorts the `k` least elements of a slice to its front.
+//! * [`KeyedPriorityQueue`], whose keys' priorities can be changed in place
+//!   through an entry API.
+//! * [`MultiQueue`], a thread-safe queue that trades exact order for
+//!   throughput under contention.
+//! * [`WeakHeap`], which holds `Weak` references and skips the dropped ones.
+//! * [`TrackedBinaryHeap`], which reports where its elements move to a
+//!   [`PositionTracker`], to maintain an index for targeted updates.
+//! * [`TupleCmp`], which orders tuples field by field, each ascending or
+//!   descending.
+//! * [`SortSpec`], a comparator built at runtime from sort keys, which
+//!   [`SortKeys`] can parse from strings such as `"-priority,+created_at"`.
+//! * The [`PriorityQueue`] trait, implemented by most of the heaps, for code
+//!   that is generic over the heap type.
+//! * A [`prelude`] to glob-import the heaps, comparators and traits,
+//!   including the `Compare` trait.
+//! * Scheduling queues built on the heap, such as [`EdfQueue`] and [`AgingHeap`].
 //!
 //! [`BinaryHeap`]: struct.BinaryHeap.html
+//! [`ArchivedBinaryHeap`]: struct.ArchivedBinaryHeap.html
+//! [`borsh_deserialize_checked()`]: fn.borsh_deserialize_checked.html
+//! [`EdfQueue`]: struct.EdfQueue.html
+//! [`ArrayHeap`]: struct.ArrayHeap.html
+//! [`BlockedBinaryHeap`]: struct.BlockedBinaryHeap.html
+//! [`BloomBinaryHeap`]: struct.BloomBinaryHeap.html
+//! [`HybridBinaryHeap`]: struct.HybridBinaryHeap.html
+//! [`HookedBinaryHeap`]: struct.HookedBinaryHeap.html
+//! [`HeapHooks`]: trait.HeapHooks.html
+//! [`LazyBinaryHeap`]: struct.LazyBinaryHeap.html
+//! [`CowHeap`]: struct.CowHeap.html
+//! [`BudgetedHeap`]: struct.BudgetedHeap.html
+//! [`WeakHeap`]: struct.WeakHeap.html
+//! [`SoaBinaryHeap`]: struct.SoaBinaryHeap.html
+//! [`ContextBinaryHeap`]: struct.ContextBinaryHeap.html
+//! [`DelayQueue`]: struct.DelayQueue.html
+//! [`GroupedTopK`]: struct.GroupedTopK.html
+//! [`algorithms::replacement_selection`]: algorithms/fn.replacement_selection.html
+//! [`algorithms::partial_sort`]: algorithms/fn.partial_sort.html
+//! [`KeyedPriorityQueue`]: struct.KeyedPriorityQueue.html
+//! [`MultiQueue`]: struct.MultiQueue.html
+//! [`TrackedBinaryHeap`]: struct.TrackedBinaryHeap.html
+//! [`PositionTracker`]: trait.PositionTracker.html
+//! [`TupleCmp`]: struct.TupleCmp.html
+//! [`SortSpec`]: struct.SortSpec.html
+//! [`SortKeys`]: struct.SortKeys.html
+//! [`PriorityQueue`]: trait.PriorityQueue.html
+//! [`ffi`]: ffi/index.html
+//! [`prelude`]: prelude/index.html
+//! [`AgingHeap`]: struct.AgingHeap.html
+//! [`ConcurrentPriorityQueue`]: struct.ConcurrentPriorityQueue.html
+//! [`SmallBinaryHeap`]: struct.SmallBinaryHeap.html
+//! [`SpillingHeap`]: struct.SpillingHeap.html
+//! [`ShadowBinaryHeap`]: struct.ShadowBinaryHeap.html
+//! [`FingerprintedBinaryHeap`]: struct.FingerprintedBinaryHeap.html
+//! [`JournaledBinaryHeap`]: struct.JournaledBinaryHeap.html
+//! [`BinaryHeap::replay`]: struct.BinaryHeap.html#method.replay
+//! [`BinaryHeap::pop_random()`]: struct.BinaryHeap.html#method.pop_random
+//! [`heap_strategy()`]: fn.heap_strategy.html
+//! [`PanickingComparator`]: struct.PanickingComparator.html
+//! [`BinaryHeap::into_stream_sorted()`]: struct.BinaryHeap.html#method.into_stream_sorted
+//! [`BinaryHeap::try_push()`]: struct.BinaryHeap.html#method.try_push
+//! [`BinaryHeap::to_dot()`]: struct.BinaryHeap.html#method.to_dot
+//! [`deserialize_checked()`]: fn.deserialize_checked.html
+//! [`HeapSeed`]: struct.HeapSeed.html
+//! [`serde_sorted`]: serde_sorted/index.html
+//! [`BinaryHeap::freeze_capacity()`]: struct.BinaryHeap.html#method.freeze_capacity
 //! [`std::collections::BinaryHeap`]:
 //! https://doc.rust-lang.org/stable/std/collections/struct.BinaryHeap.html
 //! [`serde`]: https://docs.serde.rs/serde/
+//! [`Zeroize`]: https://docs.rs/zeroize/1/zeroize/trait.Zeroize.html
 //!
 //! # Quick start
 //!
@@ -105,16 +239,187 @@
 //! * [`BinaryHeap::new_min()`] creates a min heap.
 //! * [`BinaryHeap::new_by()`] creates a heap sorted by the given closure.
 //! * [`BinaryHeap::new_by_key()`] creates a heap sorted by the key generated by the given closure.
+//! * [`BinaryHeap::new_min_by()`] and [`BinaryHeap::new_min_by_key()`] create heaps that pop the
+//!   least element first.
+//! * [`BinaryHeap::new_with_cmp()`] and [`BinaryHeap::with_capacity_and_cmp()`] create heaps
+//!   ordered by any comparator instance.
 //!
 //! [`BinaryHeap::new()`]: struct.BinaryHeap.html#method.new
 //! [`BinaryHeap::new_min()`]: struct.BinaryHeap.html#method.new_min
 //! [`BinaryHeap::new_by()`]: struct.BinaryHeap.html#method.new_by
 //! [`BinaryHeap::new_by_key()`]: struct.BinaryHeap.html#method.new_by_key
+//! [`BinaryHeap::new_min_by()`]: struct.BinaryHeap.html#method.new_min_by
+//! [`BinaryHeap::new_min_by_key()`]: struct.BinaryHeap.html#method.new_min_by_key
+//! [`BinaryHeap::new_with_cmp()`]: struct.BinaryHeap.html#method.new_with_cmp
+//! [`BinaryHeap::with_capacity_and_cmp()`]: struct.BinaryHeap.html#method.with_capacity_and_cmp
+
+#![cfg_attr(feature = "safe_only", forbid(unsafe_code))]
+
+#[cfg(all(feature = "safe_only", feature = "ffi"))]
+compile_error!("the `safe_only` feature cannot be combined with `ffi`, whose functions are unsafe");
+#[cfg(all(feature = "safe_only", feature = "rkyv"))]
+compile_error!("the `safe_only` feature cannot be combined with `rkyv`, which needs unsafe impls");
+#[cfg(all(feature = "safe_only", feature = "zeroize"))]
+compile_error!(
+    "the `safe_only` feature cannot be combined with `zeroize`, which wipes memory through pointers"
+);
 
 mod binary_heap;
 pub use crate::binary_heap::*;
 
-/// An intermediate trait for specialization of `Extend`.
+mod aging;
+pub use crate::aging::*;
+
+pub mod algorithms;
+
+mod array;
+pub use crate::array::*;
+
+mod async_heap;
+pub use crate::async_heap::*;
+
+mod blocked;
+pub use crate::blocked::*;
+
+mod bloom;
+pub use crate::bloom::*;
+
+mod budget;
+pub use crate::budget::*;
+
+mod cache;
+pub use crate::cache::*;
+
+mod channel;
+pub use crate::channel::*;
+
+#[cfg(feature = "rkyv")]
+mod archive;
+#[cfg(feature = "rkyv")]
+pub use crate::archive::*;
+
+mod context;
+pub use crate::context::*;
+
+mod cow;
+pub use crate::cow::*;
+
+mod delay;
+pub use crate::delay::*;
+
+mod edf;
+pub use crate::edf::*;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+#[cfg(feature = "fingerprint")]
+mod fingerprint;
+#[cfg(feature = "fingerprint")]
+pub use crate::fingerprint::*;
+
+mod grouped;
+pub use crate::grouped::*;
+
+mod hooks;
+pub use crate::hooks::*;
+
+mod hybrid;
+pub use crate::hybrid::*;
+
+#[cfg(feature = "journal")]
+mod journal;
+#[cfg(feature = "journal")]
+pub use crate::journal::*;
+
+mod keyed;
+pub use crate::keyed::*;
+
+mod lazy;
+pub use crate::lazy::*;
+
+#[cfg(feature = "concurrent")]
+mod lockfree;
+#[cfg(feature = "concurrent")]
+pub use crate::lockfree::*;
+
+mod mlfq;
+pub use crate::mlfq::*;
+
+mod multiqueue;
+pub use crate::multiqueue::*;
+
+pub mod prelude;
+
+mod queue;
+pub use crate::queue::*;
+
+mod retry;
+pub use crate::retry::*;
+
+mod segmented;
+pub use crate::segmented::*;
+
+#[cfg(feature = "serde")]
+pub mod serde_sorted;
+
+#[cfg(feature = "shadow")]
+mod shadow;
+#[cfg(feature = "shadow")]
+pub use crate::shadow::*;
+
+mod sharded;
+pub use crate::sharded::*;
+
+#[cfg(feature = "smallvec")]
+mod small;
+#[cfg(feature = "smallvec")]
+pub use crate::small::*;
+
+mod soa;
+pub use crate::soa::*;
+
+mod sort_spec;
+pub use crate::sort_spec::*;
+
+#[cfg(feature = "spill")]
+mod spill;
+#[cfg(feature = "spill")]
+pub use crate::spill::*;
+
+mod steal;
+pub use crate::steal::*;
+
+#[cfg(feature = "testing")]
+mod testing;
+#[cfg(feature = "testing")]
+pub use crate::testing::*;
+
+mod sync;
+pub use crate::sync::*;
+
+mod tracked;
+pub use crate::tracked::*;
+
+mod tuple_cmp;
+pub use crate::tuple_cmp::*;
+
+mod util;
+
+mod wfq;
+pub use crate::wfq::*;
+
+mod weak;
+pub use crate::weak::*;
+
+mod wheel;
+pub use crate::wheel::*;
+
+#[cfg(feature = "zeroize")]
+#[clippy::msrv = "1.60"]
+mod wipe;
+
+// An intermediate trait for specialization of `Extend`.
 // #[doc(hidden)]
 // trait SpecExtend<I: IntoIterator> {
 //     /// Extends `self` with the contents of the given iterator.
@@ -131,6 +436,7 @@ mod from_liballoc {
     // use std::collections::binary_heap::{Drain, PeekMut};
 
     #[test]
+    #[allow(clippy::explicit_counter_loop)]
     fn test_iterator() {
         let data = vec![5, 9, 3];
         let iterout = [9, 5, 3];
@@ -449,6 +755,7 @@ mod from_liballoc {
 
         static DROP_COUNTER: AtomicUsize = AtomicUsize::new(0);
 
+        #[allow(clippy::derive_ord_xor_partial_ord)]
         #[derive(Eq, PartialEq, PartialOrd, Clone, Debug)]
         struct PanicOrd<T>(T, bool);
 
@@ -519,31 +826,3551 @@ mod from_liballoc {
     }
 }
 
-#[cfg(feature = "serde")]
 #[cfg(test)]
-mod tests_serde {
+mod tests_binary_heap {
+    // Tests for methods that `std::collections::BinaryHeap` does not have.
+
     use super::binary_heap::*;
-    use serde_json;
+    use std::thread::sleep;
+    use std::time::Duration;
 
     #[test]
-    fn deserialized_same_small_vec() {
-        let heap = BinaryHeap::from(vec![1, 2, 3]);
-        let serialized = serde_json::to_string(&heap).unwrap();
-        let deserialized: BinaryHeap<i32> = serde_json::from_str(&serialized).unwrap();
+    fn drain_for_checks_clock_in_batches() {
+        let mut heap = BinaryHeap::from((0..10).collect::<Vec<_>>());
+        let mut drain = heap.drain_for(Duration::from_millis(5)).check_every(3);
+        assert_eq!(drain.next(), Some(9));
+        sleep(Duration::from_millis(10));
+        // The budget is exhausted, but the clock is not read again until
+        // the current batch of three pops is complete.
+        assert_eq!(drain.next(), Some(8));
+        assert_eq!(drain.next(), Some(7));
+        assert_eq!(drain.next(), None);
+        assert_eq!(drain.next(), None);
+        assert_eq!(heap.len(), 7);
+        assert_eq!(heap.peek(), Some(&6));
+    }
 
-        let v0: Vec<_> = heap.into_iter().collect();
-        let v1: Vec<_> = deserialized.into_iter().collect();
-        assert_eq!(v0, v1);
+    #[test]
+    fn drain_for_empties_heap_within_budget() {
+        let mut heap = BinaryHeap::from(vec![3, 1, 2]);
+        let popped: Vec<_> = heap.drain_for(Duration::from_secs(3600)).collect();
+        assert_eq!(popped, [3, 2, 1]);
+        assert!(heap.is_empty());
     }
+
     #[test]
-    fn deserialized_same() {
-        let vec: Vec<i32> = (0..1000).collect();
-        let heap = BinaryHeap::from(vec);
-        let serialized = serde_json::to_string(&heap).unwrap();
-        let deserialized: BinaryHeap<i32> = serde_json::from_str(&serialized).unwrap();
+    fn large_heap_pops_in_order() {
+        // Large enough for sift-downs to prefetch.
+        let mut heap: BinaryHeap<u64> =
+            BinaryHeap::from_vec((0..600_000).map(|i| (i * 7919) % 600_011).collect());
+        let mut last = u64::MAX;
+        for _ in 0..1000 {
+            let x = heap.pop().unwrap();
+            assert!(x <= last);
+            last = x;
+        }
+        let sorted = heap.into_sorted_vec();
+        assert!(sorted.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(sorted.len(), 599_000);
+    }
 
-        let v0: Vec<_> = heap.into_iter().collect();
-        let v1: Vec<_> = deserialized.into_iter().collect();
-        assert_eq!(v0, v1);
+    #[test]
+    fn large_elements_pop_in_order() {
+        // Large enough for `pop` to use the classic sift-down.
+        let mut heap = BinaryHeap::new_min();
+        for i in 0..200u64 {
+            heap.push([(i * 37) % 101; 16]);
+        }
+        let mut last = 0;
+        while let Some(x) = heap.pop() {
+            assert!(x[0] >= last);
+            last = x[0];
+        }
+    }
+
+    #[test]
+    fn peek_mut_refresh_retargets_guard() {
+        let mut heap = BinaryHeap::from(vec![5, 3, 4]);
+        let mut top = heap.peek_mut().unwrap();
+        assert!(!top.refresh());
+        *top = 1;
+        assert!(top.refresh());
+        assert_eq!(*top, 4);
+        *top = 4;
+        assert!(!top.refresh());
+        PeekMut::pop(top);
+        assert_eq!(heap.into_sorted_vec(), [1, 3]);
+    }
+
+    #[test]
+    fn growth_policy_bounds_push_reallocation() {
+        let mut heap = BinaryHeap::new_min();
+        heap.set_growth_policy(GrowthPolicy::Exact);
+        for x in (0..5).rev() {
+            heap.push(x);
+            assert_eq!(heap.capacity(), heap.len());
+        }
+        // A custom policy that does not grow still makes room for the item.
+        heap.set_growth_policy(GrowthPolicy::Custom(|cap| cap));
+        heap.push(5);
+        assert_eq!(heap.capacity(), 6);
+        heap.set_growth_policy(GrowthPolicy::Fixed(0));
+        heap.push(6);
+        assert_eq!(heap.capacity(), 7);
+        assert!(matches!(
+            heap.clone().growth_policy(),
+            GrowthPolicy::Fixed(0)
+        ));
+        assert_eq!(heap.into_sorted_vec(), [6, 5, 4, 3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn into_iter_sorted_skips_ahead() {
+        let heap = BinaryHeap::from((0..1000).map(|i| (i * 7) % 1000).collect::<Vec<_>>());
+        assert_eq!(heap.clone().into_iter_sorted().count(), 1000);
+        assert_eq!(heap.clone().into_iter_sorted().last(), Some(0));
+        assert_eq!(BinaryHeap::<i32>::new().into_iter_sorted().last(), None);
+
+        // Both by popping and by selection.
+        let mut iter = heap.into_iter_sorted();
+        assert_eq!(iter.nth(2), Some(997));
+        assert_eq!(iter.nth(900), Some(96));
+        assert_eq!(iter.size_hint(), (96, Some(96)));
+        assert_eq!(iter.next(), Some(95));
+        assert_eq!(iter.nth(95), None);
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn layers_and_subtrees_cover_the_tree() {
+        for len in 0..70 {
+            let heap = BinaryHeap::from((0..len).collect::<Vec<_>>());
+            let layers = heap.layers();
+            assert_eq!(layers.len(), heap.depth());
+            let mut seen = 0;
+            for (d, level) in layers.enumerate() {
+                assert!(level.len() == 1 << d || seen + level.len() == len);
+                seen += level.len();
+            }
+            assert_eq!(seen, len);
+            if len > 0 {
+                assert_eq!(heap.subtree_len(0), len);
+            }
+            for i in 0..len {
+                let children = heap.subtree_len(2 * i + 1) + heap.subtree_len(2 * i + 2);
+                assert_eq!(heap.subtree_len(i), 1 + children);
+            }
+        }
+        assert_eq!(BinaryHeap::from(vec![1]).subtree_len(usize::MAX), 0);
+    }
+
+    #[test]
+    fn parents_and_children_agree() {
+        for len in 0..20 {
+            let heap = BinaryHeap::from((0..len).collect::<Vec<_>>());
+            let mut children = 0;
+            for i in 0..len {
+                for child in heap.children_of(i) {
+                    assert_eq!(heap.parent_of(child), Some(i));
+                    assert!(heap.get(child) <= heap.get(i));
+                }
+                children += heap.children_of(i).len();
+            }
+            // Every element but the root is somebody's child.
+            assert_eq!(children, len.saturating_sub(1));
+            assert!(heap.children_of(len).is_empty());
+            assert_eq!(heap.parent_of(len), None);
+            assert_eq!(heap.get(len), None);
+        }
+        assert!(BinaryHeap::from(vec![1]).children_of(usize::MAX).is_empty());
+    }
+
+    #[test]
+    fn equal_heaps_hash_alike() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        fn hash_of(heap: &BinaryHeap<u32>) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            heap.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let items: Vec<u32> = (0..100).map(|i| (i * 37) % 23).collect();
+        let built = BinaryHeap::from(items.clone());
+        let mut pushed = BinaryHeap::new();
+        pushed.extend(items.iter().rev().copied());
+        assert_ne!(built.clone().into_vec(), pushed.clone().into_vec());
+        assert_eq!(built, pushed);
+        assert_eq!(hash_of(&built), hash_of(&pushed));
+
+        // Same length and sum, different multiset.
+        let mut other = pushed.clone();
+        other.pop();
+        other.push(0);
+        assert_ne!(built, other);
+        assert_ne!(hash_of(&built), hash_of(&other));
+        assert_ne!(
+            hash_of(&BinaryHeap::from(vec![1, 1])),
+            hash_of(&BinaryHeap::from(vec![0, 2]))
+        );
+    }
+
+    #[test]
+    fn iter_mut_rebuilds_after_rescoring() {
+        let mut heap = BinaryHeap::from((0..100).collect::<Vec<_>>());
+        for item in &mut heap.iter_mut() {
+            // Reverses the order of even items and moves odd ones last.
+            *item = if *item % 2 == 0 {
+                1000 - *item
+            } else {
+                *item - 1000
+            };
+        }
+        let mut expected: Vec<i32> = (0..100)
+            .map(|i| if i % 2 == 0 { 1000 - i } else { i - 1000 })
+            .collect();
+        expected.sort_unstable();
+        assert_eq!(heap.peek(), expected.last());
+        assert_eq!(heap.into_sorted_vec(), expected);
+    }
+
+    #[test]
+    fn as_mut_slice_rebuilds_after_writes() {
+        let mut heap = BinaryHeap::from(vec![5, 1, 8, 3]);
+        let before = heap.as_slice().to_vec();
+        assert_eq!(heap.as_mut_slice().len(), 4);
+        assert_eq!(heap.as_slice(), &before[..]);
+
+        heap.as_mut_slice().reverse();
+        assert_eq!(heap.peek(), Some(&8));
+        assert_eq!(heap.into_sorted_vec(), [1, 3, 5, 8]);
+    }
+
+    #[test]
+    fn cmp_mut_rebuilds_only_after_writes() {
+        use compare::Compare;
+        use std::cmp::Ordering;
+
+        #[derive(Debug)]
+        struct Flip(bool);
+        impl Compare<i32> for Flip {
+            fn compare(&self, a: &i32, b: &i32) -> Ordering {
+                if self.0 {
+                    b.cmp(a)
+                } else {
+                    a.cmp(b)
+                }
+            }
+        }
+
+        let mut heap = BinaryHeap::from_vec_cmp((0..20).collect(), Flip(false));
+        let layout = heap.as_slice().to_vec();
+        assert!(!heap.cmp_mut().0);
+        assert_eq!(format!("{:?}", heap.cmp_mut()), "CmpMut(Flip(false))");
+        assert_eq!(heap.as_slice(), &layout[..]);
+
+        heap.cmp_mut().0 = true;
+        assert_eq!(heap.peek(), Some(&0));
+        assert_eq!(heap.into_sorted_vec(), (0..20).rev().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn cmp_constructors_start_empty() {
+        let heap: BinaryHeap<i32, _> = BinaryHeap::new_with_cmp(MinComparator);
+        assert!(heap.is_empty());
+        assert_eq!(heap.capacity(), 0);
+
+        let mut heap = BinaryHeap::with_capacity_and_cmp(16, KeyComparator(|k: &i32| -k));
+        assert!(heap.is_empty());
+        assert!(heap.capacity() >= 16);
+        heap.extend(vec![4, 1, 3]);
+        assert_eq!(heap.into_sorted_vec(), [4, 3, 1]);
+    }
+
+    #[test]
+    fn min_by_constructors_reverse_the_closure() {
+        type Item = (u8, char);
+        type Key = fn(&Item) -> u8;
+        let by = |a: &Item, b: &Item| a.0.cmp(&b.0);
+        let by_key: Key = |a| a.0;
+        let items = vec![(2, 'b'), (0, 'z'), (1, 'a')];
+
+        let mut heaps: Vec<MinHeapBy<Item>> = vec![
+            BinaryHeap::new_min_by(by),
+            BinaryHeap::with_capacity_min_by(8, by),
+        ];
+        for heap in &mut heaps {
+            heap.extend(items.clone());
+            assert_eq!(heap.pop(), Some((0, 'z')));
+        }
+        let mut heaps: Vec<MinHeapByKey<Item, Key>> = vec![
+            BinaryHeap::new_min_by_key(by_key),
+            BinaryHeap::with_capacity_min_by_key(8, by_key),
+        ];
+        for heap in &mut heaps {
+            heap.extend(items.clone());
+            assert_eq!(
+                heap.clone().into_sorted_vec(),
+                [(2, 'b'), (1, 'a'), (0, 'z')]
+            );
+        }
+    }
+
+    #[test]
+    fn sort_key_is_borrowed() {
+        // `Name` is not `Clone`, so the key can only be borrowed.
+        #[derive(PartialEq, Eq, PartialOrd, Ord, Debug)]
+        struct Name(String);
+        struct Job {
+            name: Name,
+            id: u32,
+        }
+
+        let mut heap: HeapBySortKey<Job, fn(&Job) -> &Name> =
+            BinaryHeap::new_by_sort_key(|j| &j.name);
+        for (id, name) in ["b", "c", "a"].iter().enumerate() {
+            heap.push(Job {
+                name: Name(name.to_string()),
+                id: id as u32,
+            });
+        }
+        let ids: Vec<u32> = std::iter::from_fn(|| heap.pop()).map(|j| j.id).collect();
+        assert_eq!(ids, [1, 0, 2]);
+    }
+
+    #[test]
+    fn push_with_limit_keeps_the_k_least() {
+        let mut heap = BinaryHeap::new();
+        let mut displaced = Vec::new();
+        for x in [7, 2, 9, 4, 4, 1, 8, 3] {
+            displaced.extend(heap.push_with_limit(x, 4));
+        }
+        assert_eq!(heap.clone().into_sorted_vec(), [1, 2, 3, 4]);
+        displaced.sort_unstable();
+        assert_eq!(displaced, [4, 7, 8, 9]);
+        assert_eq!(heap.push_with_limit(5, 2), Some(5));
+        assert_eq!(heap.len(), 4);
+        assert_eq!(BinaryHeap::new().push_with_limit(0, 0), Some(0));
+    }
+
+    #[test]
+    fn worst_is_the_last_popped() {
+        assert_eq!(BinaryHeap::<u8>::new().worst(), None);
+        for len in 1..40u32 {
+            let heap = BinaryHeap::from((0..len).map(|x| x * 17 % 41).collect::<Vec<_>>());
+            let last = heap.clone().into_sorted_vec()[0];
+            assert_eq!(heap.worst(), Some(&last));
+            let heap = BinaryHeap::from_vec_cmp(heap.into_vec(), MinComparator);
+            assert_eq!(heap.worst(), heap.iter().max());
+        }
+    }
+
+    #[test]
+    fn count_of_matches_a_scan() {
+        let items: Vec<u32> = (0..200).map(|x| x * 7 % 13).collect();
+        let max = BinaryHeap::from(items.clone());
+        let min = BinaryHeap::from_vec_cmp(items.clone(), MinComparator);
+        for v in 0..15 {
+            let expected = items.iter().filter(|&&x| x == v).count();
+            assert_eq!(max.count_of(&v), expected);
+            assert_eq!(min.count_of(&v), expected);
+        }
+        assert_eq!(BinaryHeap::<u32>::new().count_of(&0), 0);
+    }
+
+    #[test]
+    fn vec_from_heap() {
+        fn into_vec<H>(heap: H) -> Vec<u8>
+        where
+            Vec<u8>: From<H>,
+        {
+            Vec::from(heap)
+        }
+        let heap = BinaryHeap::from_vec_cmp(vec![2, 7, 1], MinComparator);
+        let layout = heap.as_slice().to_vec();
+        assert_eq!(into_vec(heap.clone()), layout);
+        let vec: Vec<u8> = heap.into();
+        assert_eq!(vec, layout);
+    }
+
+    #[test]
+    fn extend_cloned_keeps_the_source() {
+        use std::rc::Rc;
+
+        fn fill<T: Ord + Clone>(heap: &mut BinaryHeap<T>, items: &[T]) {
+            heap.extend_cloned(items);
+        }
+
+        let items: Vec<Rc<str>> = vec!["x".into(), "z".into(), "y".into()];
+        let mut heap = BinaryHeap::new();
+        fill(&mut heap, &items);
+        assert_eq!(Rc::strong_count(&items[1]), 2);
+        assert_eq!(heap.pop().as_deref(), Some("z"));
+        assert_eq!(Rc::strong_count(&items[1]), 1);
+        assert_eq!(heap.len(), 2);
+    }
+
+    #[test]
+    fn iterators_expose_the_remainder() {
+        let heap = BinaryHeap::from(vec![5, 1, 8, 3]);
+        let layout = heap.as_slice().to_vec();
+        let mut iter = heap.iter();
+        iter.next();
+        iter.next_back();
+        assert_eq!(iter.as_slice(), &layout[1..3]);
+
+        let mut iter = heap.into_iter();
+        iter.next();
+        assert_eq!(iter.as_slice(), &layout[1..]);
+        iter.by_ref().for_each(drop);
+        assert!(iter.as_slice().is_empty());
+    }
+
+    #[test]
+    fn batch_mut_rebuilds_unless_told_not_to() {
+        let mut heap = BinaryHeap::from((0..50).collect::<Vec<_>>());
+        heap.batch_mut(|items| {
+            items.iter_mut().for_each(|x| *x = 100 - *x);
+            true
+        });
+        assert_eq!(heap.peek(), Some(&100));
+
+        // Truncating a heap leaves a heap.
+        heap.batch_mut(|items| {
+            items.truncate(10);
+            false
+        });
+        assert_eq!(heap.len(), 10);
+        assert_eq!(heap.pop(), Some(100));
+    }
+
+    #[test]
+    fn replace_at_moves_both_ways() {
+        let mut heap = BinaryHeap::from((0..200).collect::<Vec<_>>());
+        let mut expected: Vec<_> = (0..200).collect();
+        for step in 0..1000 {
+            let index = (step * 37) % heap.len();
+            let item = (step * 101) % 997;
+            let old = heap.replace_at(index, item);
+            let pos = expected.iter().position(|&x| x == old).unwrap();
+            expected[pos] = item;
+        }
+        expected.sort_unstable();
+        assert_eq!(heap.into_sorted_vec(), expected);
+    }
+
+    #[test]
+    fn swap_remove_index_removes_anywhere() {
+        for len in 1..40 {
+            for index in 0..len {
+                let mut heap = BinaryHeap::from((0..len).map(|i| (i * 7) % 41).collect::<Vec<_>>());
+                let removed = heap.swap_remove_index(index);
+                let mut expected: Vec<_> = (0..len).map(|i| (i * 7) % 41).collect();
+                let pos = expected.iter().position(|&x| x == removed).unwrap();
+                expected.remove(pos);
+                expected.sort_unstable();
+                assert_eq!(heap.into_sorted_vec(), expected);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "index 3 out of bounds for a heap of length 3")]
+    fn sift_at_checks_bounds() {
+        BinaryHeap::from(vec![1, 2, 3]).sift_down_at(3);
+    }
+
+    #[test]
+    fn append_sorted_keeps_heap_valid() {
+        // Both the sift-up and the rebuild path, and an unsorted run.
+        for &(old, new) in &[(1000, 10), (10, 1000), (500, 500), (0, 50)] {
+            let mut heap = BinaryHeap::from((0..old).map(|i| (i * 7) % 1009).collect::<Vec<_>>());
+            heap.append_sorted_ascending(0..new);
+            heap.append_sorted_descending((0..new).rev());
+            heap.append_sorted_descending(0..new);
+            let mut expected: Vec<_> = (0..old).map(|i| (i * 7) % 1009).collect();
+            for _ in 0..3 {
+                expected.extend(0..new);
+            }
+            expected.sort_unstable();
+            assert_eq!(heap.into_sorted_vec(), expected);
+        }
+    }
+}
+
+#[cfg(feature = "alloc_guard")]
+#[cfg(test)]
+mod tests_alloc_guard {
+    use super::binary_heap::BinaryHeap;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    type Op = (&'static str, fn(&mut BinaryHeap<i32>));
+
+    fn frozen_full() -> BinaryHeap<i32> {
+        let mut heap = BinaryHeap::with_capacity(4);
+        heap.extend(vec![4, 3, 2, 1]);
+        heap.shrink_to_fit();
+        heap.freeze_capacity();
+        heap
+    }
+
+    #[test]
+    fn every_reallocation_is_caught() {
+        let ops: [Op; 7] = [
+            ("push", |h| h.push(0)),
+            ("extend", |h| h.extend(vec![0])),
+            ("reserve", |h| h.reserve(1)),
+            ("reserve_exact", |h| h.reserve_exact(1)),
+            ("append", |h| h.append(&mut BinaryHeap::from(vec![0; 8]))),
+            ("append_sorted_ascending", |h| {
+                h.append_sorted_ascending(0..1)
+            }),
+            ("shrink_to_fit", |h| {
+                h.pop();
+                h.shrink_to_fit()
+            }),
+        ];
+        for &(name, op) in &ops {
+            let mut heap = frozen_full();
+            let result = catch_unwind(AssertUnwindSafe(|| op(&mut heap)));
+            assert!(result.is_err(), "{} reallocated", name);
+            assert_eq!(heap.capacity(), 4, "{}", name);
+        }
+    }
+
+    #[test]
+    fn operations_within_capacity_are_allowed() {
+        let mut heap = frozen_full();
+        heap.pop();
+        heap.push(5);
+        heap.reserve(0);
+        heap.shrink_to(4);
+        let mut other = BinaryHeap::with_capacity(8);
+        heap.clear();
+        other.extend(vec![1, 2, 3]);
+        // The longer heap would normally take over; the frozen one keeps its
+        // buffer.
+        heap.push(9);
+        heap.append(&mut other);
+        assert_eq!(heap.capacity(), 4);
+        assert_eq!(other.capacity(), 8);
+        assert_eq!(heap.into_sorted_vec(), [1, 2, 3, 9]);
+    }
+
+    #[test]
+    #[should_panic(expected = "heap would reallocate")]
+    fn batch_mut_growth_is_caught() {
+        let mut heap = frozen_full();
+        heap.batch_mut(|items| {
+            items.push(0);
+            true
+        });
+    }
+
+    #[test]
+    fn clones_and_unfrozen_heaps_grow() {
+        let mut heap = frozen_full();
+        let mut clone = heap.clone();
+        assert!(!clone.is_capacity_frozen());
+        clone.push(0);
+        heap.unfreeze_capacity();
+        heap.push(0);
+        assert_eq!(heap.len(), 5);
+    }
+
+    #[test]
+    fn callback_sees_the_requested_capacity() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static REQUESTED: AtomicUsize = AtomicUsize::new(0);
+
+        let mut heap = frozen_full();
+        heap.freeze_capacity_with(|capacity, requested| {
+            assert_eq!(capacity, 4);
+            REQUESTED.store(requested, Ordering::Relaxed);
+        });
+        heap.reserve(10);
+        assert_eq!(REQUESTED.load(Ordering::Relaxed), 14);
+        assert!(heap.capacity() >= 14);
+    }
+}
+
+#[cfg(test)]
+mod tests_async_heap {
+    use super::async_heap::*;
+    use futures::executor::block_on;
+    use futures::task::{noop_waker, Context, Poll};
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn dropped_waiter_hands_on_its_wakeup() {
+        let heap = AsyncHeap::<i32>::new();
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        let mut first = Box::pin(heap.pop());
+        let mut second = Box::pin(heap.pop());
+        assert!(first.as_mut().poll(&mut cx).is_pending());
+        assert!(second.as_mut().poll(&mut cx).is_pending());
+
+        // `first` is woken for this item but gives up before taking it.
+        heap.push(7);
+        drop(first);
+        assert_eq!(Pin::new(&mut second).poll(&mut cx), Poll::Ready(7));
+        assert!(heap.is_empty());
+    }
+
+    #[test]
+    fn consumers_on_other_threads_receive_every_item() {
+        let heap = Arc::new(AsyncHeap::new());
+        let consumers: Vec<_> = (0..4)
+            .map(|_| {
+                let heap = Arc::clone(&heap);
+                thread::spawn(move || {
+                    block_on(async {
+                        let mut items = Vec::new();
+                        for _ in 0..250 {
+                            items.push(heap.pop().await);
+                        }
+                        items
+                    })
+                })
+            })
+            .collect();
+        for i in 0..1000 {
+            heap.push(i);
+        }
+        let mut all: Vec<_> = consumers
+            .into_iter()
+            .flat_map(|c| c.join().unwrap())
+            .collect();
+        all.sort_unstable();
+        assert_eq!(all, (0..1000).collect::<Vec<_>>());
+    }
+}
+
+/// Differential tests of the heaps against `BinaryHeap`.
+#[cfg(test)]
+mod differential {
+    use super::binary_heap::BinaryHeap;
+    use compare::Compare;
+
+    pub(crate) enum Op {
+        Push(u32),
+        Pop,
+    }
+
+    /// `n` pushes of scrambled values below 1009, each `pop_every`-th one
+    /// followed by a pop.
+    pub(crate) fn interleaved(n: u32, pop_every: u32) -> Vec<Op> {
+        let mut ops = Vec::new();
+        for i in 0..n {
+            ops.push(Op::Push((i * 7919) % 1009));
+            if i % pop_every == 0 {
+                ops.push(Op::Pop);
+            }
+        }
+        ops
+    }
+
+    /// `rounds` rounds of fewer than `max_pushes` pushes followed by fewer
+    /// than `max_pops` pops, so the heap both grows and shrinks in batches.
+    pub(crate) fn batches(rounds: u32, max_pushes: u32, max_pops: u32) -> Vec<Op> {
+        let mut ops = Vec::new();
+        for round in 0..rounds {
+            for i in 0..(round * 37) % max_pushes {
+                ops.push(Op::Push((i * 7919 + round) % 1009));
+            }
+            for _ in 0..(round * 13) % max_pops {
+                ops.push(Op::Pop);
+            }
+        }
+        ops
+    }
+
+    /// Applies `ops` to `heap` and to a `BinaryHeap` ordered by `cmp`, then
+    /// pops both empty, and panics at the first pop or length that differs.
+    pub(crate) fn check_against<H, C: Compare<u32>>(
+        heap: &mut H,
+        cmp: C,
+        ops: Vec<Op>,
+        push: fn(&mut H, u32),
+        pop: fn(&mut H) -> Option<u32>,
+        len: fn(&H) -> usize,
+    ) {
+        let mut reference = BinaryHeap::from_vec_cmp(vec![], cmp);
+        for op in ops {
+            match op {
+                Op::Push(x) => {
+                    push(heap, x);
+                    reference.push(x);
+                }
+                Op::Pop => assert_eq!(pop(heap), reference.pop()),
+            }
+            assert_eq!(len(heap), reference.len());
+        }
+        while let Some(x) = pop(heap) {
+            assert_eq!(Some(x), reference.pop());
+        }
+        assert!(reference.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod tests_array {
+    use super::array::*;
+    use super::binary_heap::MaxComparator;
+    use super::differential::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn pops_match_binary_heap() {
+        let mut heap: ArrayHeap<u32, MaxComparator, 64> = ArrayHeap::new();
+        check_against(
+            &mut heap,
+            MaxComparator,
+            interleaved(90, 3),
+            |h, x| h.push(x).unwrap(),
+            |h| h.pop(),
+            |h| h.len(),
+        );
+    }
+
+    #[test]
+    fn rejects_pushes_when_full() {
+        let mut heap: ArrayHeap<u32, MaxComparator, 3> = ArrayHeap::new();
+        for x in [2, 7, 4] {
+            heap.push(x).unwrap();
+        }
+        assert!(heap.is_full());
+        assert_eq!(heap.push(9), Err(9));
+        assert_eq!(heap.as_slice().len(), 3);
+        assert_eq!(heap.pop(), Some(7));
+        assert_eq!(heap.push(9), Ok(()));
+        assert_eq!(heap.peek(), Some(&9));
+    }
+
+    #[test]
+    fn drops_remaining_elements() {
+        let marker = Rc::new(());
+        let mut heap: ArrayHeap<_, _, 4> =
+            ArrayHeap::with_cmp(|a: &Rc<()>, b: &Rc<()>| Rc::as_ptr(a).cmp(&Rc::as_ptr(b)));
+        for _ in 0..4 {
+            heap.push(Rc::clone(&marker)).unwrap();
+        }
+        let rejected = heap.push(Rc::clone(&marker)).unwrap_err();
+        drop(rejected);
+        let clone = heap.clone();
+        drop(heap.pop());
+        assert_eq!(Rc::strong_count(&marker), 8);
+        drop(heap);
+        drop(clone);
+        assert_eq!(Rc::strong_count(&marker), 1);
+    }
+}
+
+#[cfg(test)]
+mod tests_bloom {
+    use super::binary_heap::MinComparator;
+    use super::bloom::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn contains_matches_a_scan() {
+        let mut rng = StdRng::seed_from_u64(37);
+        let mut heap = BloomBinaryHeap::with_cmp(MinComparator);
+        for round in 0..8 {
+            for _ in 0..300 {
+                heap.push(rng.gen_range(0..20_000u32));
+            }
+            for _ in 0..100 {
+                heap.pop();
+            }
+            if round % 3 == 2 {
+                heap.retain(|x| x % 3 != 0);
+            }
+            let items: Vec<u32> = heap.iter().copied().collect();
+            let mut misses = 0;
+            let mut maybes = 0;
+            for x in 0..20_000 {
+                let present = items.contains(&x);
+                assert_eq!(heap.contains(&x), present, "{}", x);
+                if !present {
+                    misses += 1;
+                    if heap.might_contain(&x) {
+                        maybes += 1;
+                    }
+                }
+            }
+            // About 1% of misses get past the filter.
+            assert!(maybes * 20 < misses, "{} of {}", maybes, misses);
+        }
+    }
+
+    #[test]
+    fn pops_in_order() {
+        let mut heap = BloomBinaryHeap::new();
+        heap.extend(vec![3, 9, 1, 7]);
+        assert_eq!(heap.peek(), Some(&9));
+        assert_eq!(heap.pop(), Some(9));
+        assert!(!heap.contains(&9));
+        heap.clear();
+        assert!(heap.is_empty());
+        assert!(!heap.might_contain(&3));
+        assert_eq!(heap.into_heap().into_vec(), Vec::<i32>::new());
+    }
+}
+
+#[cfg(test)]
+mod tests_blocked {
+    use super::binary_heap::MaxComparator;
+    use super::blocked::*;
+    use super::differential::*;
+
+    #[test]
+    fn pops_match_binary_heap() {
+        for height in 1..=4 {
+            let mut heap = BlockedBinaryHeap::with_block_height(height);
+            check_against(
+                &mut heap,
+                MaxComparator,
+                interleaved(1000, 3),
+                |h, x| h.push(x),
+                |h| h.pop(),
+                |h| h.len(),
+            );
+        }
+    }
+
+    #[test]
+    fn sifts_across_block_boundaries() {
+        // Blocks of height 2 hold 3 elements: the root block, then one block
+        // under each of its 4 leaf children, and so on.
+        for len in [3, 4, 15, 16, 63, 64] {
+            let mut heap = BlockedBinaryHeap::with_block_height(2);
+            heap.extend(0..len);
+            assert_eq!(heap.peek(), Some(&(len - 1)));
+            // The new greatest element sifts up through every block on its
+            // path to the root, and popping it sifts the smallest one back
+            // down through them.
+            heap.push(-1);
+            heap.push(len);
+            assert_eq!(heap.pop(), Some(len));
+            let sorted: Vec<_> = (-1..len).collect();
+            assert_eq!(heap.into_sorted_vec(), sorted);
+        }
+    }
+
+    #[test]
+    fn default_blocks_fit_a_page() {
+        let heap: BlockedBinaryHeap<u64> = BlockedBinaryHeap::new();
+        assert_eq!(heap.block_height(), 9);
+        let heap: BlockedBinaryHeap<[u8; 8192]> = BlockedBinaryHeap::new();
+        assert_eq!(heap.block_height(), 1);
+    }
+}
+
+#[cfg(feature = "ffi")]
+#[cfg(test)]
+mod tests_ffi {
+    use super::binary_heap::*;
+    use super::ffi::*;
+    use std::os::raw::c_void;
+    use std::ptr;
+
+    #[test]
+    fn pops_keys_with_their_pointers() {
+        let (mut a, mut b) = (1u8, 2u8);
+        let (pa, pb) = (
+            &mut a as *mut u8 as *mut c_void,
+            &mut b as *mut u8 as *mut c_void,
+        );
+        unsafe {
+            let heap = bhp_heap_new_min();
+            bhp_heap_push(heap, 30, pa);
+            bhp_heap_push(heap, 10, pb);
+            assert_eq!(bhp_heap_len(heap), 2);
+
+            let (mut key, mut value) = (0, ptr::null_mut());
+            assert!(bhp_heap_peek(heap, &mut key, ptr::null_mut()));
+            assert_eq!(key, 10);
+            assert!(bhp_heap_pop(heap, &mut key, &mut value));
+            assert_eq!((key, value), (10, pb));
+            assert!(bhp_heap_pop(heap, ptr::null_mut(), &mut value));
+            assert_eq!(value, pa);
+            assert!(!bhp_heap_pop(heap, &mut key, &mut value));
+            bhp_heap_free(heap);
+
+            let heap = bhp_heap_new();
+            bhp_heap_push(heap, 10, pb);
+            bhp_heap_push(heap, 30, pa);
+            assert!(bhp_heap_peek(heap, &mut key, &mut value));
+            assert_eq!((key, value), (30, pa));
+            bhp_heap_free(heap);
+            bhp_heap_free(ptr::null_mut());
+        }
+    }
+
+    // Run in a child process by `panics_abort_instead_of_unwinding`.
+    #[test]
+    fn panicking_comparator_in_call() {
+        if std::env::var_os("BHP_FFI_PANIC_CHILD").is_none() {
+            return;
+        }
+        super::ffi::abort_on_panic(|| {
+            let mut heap = BinaryHeap::new_by(|_: &u8, _: &u8| panic!("comparator"));
+            heap.push(1);
+            heap.push(2);
+        });
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn panics_abort_instead_of_unwinding() {
+        use std::os::unix::process::ExitStatusExt;
+        use std::process::{Command, Stdio};
+
+        let status = Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", "tests_ffi::panicking_comparator_in_call"])
+            .env("BHP_FFI_PANIC_CHILD", "1")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .unwrap();
+        // An unwinding panic would fail the test with exit code 101 instead
+        // of SIGABRT.
+        assert_eq!(status.signal(), Some(6));
+    }
+
+    #[test]
+    fn header_declares_every_function() {
+        let header = include_str!("../include/binary_heap_plus.h");
+        let source = include_str!("ffi.rs");
+        let exported: Vec<&str> = source
+            .split("extern \"C\" fn ")
+            .skip(1)
+            .map(|rest| &rest[..rest.find('(').unwrap()])
+            .collect();
+        assert_eq!(exported.len(), 7);
+        for name in exported {
+            assert!(
+                header.contains(&format!(" *{}(", name)) || header.contains(&format!(" {}(", name)),
+                "{} is missing from the header",
+                name
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_algorithms {
+    use super::algorithms::*;
+    use super::binary_heap::MaxComparator;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn collect_runs<T>(pairs: impl Iterator<Item = (usize, T)>) -> Vec<Vec<T>> {
+        let mut runs: Vec<Vec<T>> = Vec::new();
+        for (run, item) in pairs {
+            if run == runs.len() {
+                runs.push(Vec::new());
+            }
+            assert_eq!(run, runs.len() - 1);
+            runs[run].push(item);
+        }
+        runs
+    }
+
+    #[test]
+    fn runs_are_sorted_and_hold_every_item() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let input: Vec<u32> = (0..10_000).map(|_| rng.gen()).collect();
+        let runs = collect_runs(replacement_selection(input.clone(), 100));
+
+        for run in &runs {
+            assert!(run.windows(2).all(|w| w[0] <= w[1]));
+        }
+        // Random input gives runs of about twice the memory.
+        assert!(runs.len() < 10_000 / 150, "{} runs", runs.len());
+        let mut all: Vec<u32> = runs.concat();
+        all.sort_unstable();
+        let mut expected = input;
+        expected.sort_unstable();
+        assert_eq!(all, expected);
+    }
+
+    #[test]
+    fn sorted_input_is_one_run() {
+        let runs = collect_runs(replacement_selection(0..1000, 10));
+        assert_eq!(runs, [(0..1000).collect::<Vec<_>>()]);
+
+        let runs = collect_runs(replacement_selection_cmp(0..1000, 10, MaxComparator));
+        assert_eq!(runs.len(), 100);
+        assert_eq!(runs[0], [9, 8, 7, 6, 5, 4, 3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn size_hint_counts_held_items() {
+        let mut runs = replacement_selection(vec![3, 1, 2], 2);
+        assert_eq!(runs.size_hint(), (3, Some(3)));
+        assert_eq!(runs.next(), Some((0, 1)));
+        assert_eq!(runs.size_hint(), (2, Some(2)));
+        assert_eq!(runs.by_ref().collect::<Vec<_>>(), [(0, 2), (0, 3)]);
+        assert_eq!(runs.next(), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_memory_panics() {
+        let _ = replacement_selection(vec![1], 0);
+    }
+
+    #[test]
+    fn partial_sort_matches_sorting() {
+        let mut rng = StdRng::seed_from_u64(12);
+        for len in [0, 1, 2, 7, 100, 1000] {
+            let input: Vec<u16> = (0..len).map(|_| rng.gen_range(0..300)).collect();
+            let mut sorted = input.clone();
+            sorted.sort_unstable();
+            for k in [0, 1, 5, len / 2, len, len + 3] {
+                let mut v = input.clone();
+                partial_sort(&mut v, k);
+                let k = k.min(len);
+                assert_eq!(v[..k], sorted[..k]);
+                v.sort_unstable();
+                assert_eq!(v, sorted);
+            }
+        }
+    }
+
+    #[test]
+    fn partial_sort_by_uses_the_order() {
+        let mut v = [3, 9, 4, 1, 8, 5];
+        partial_sort_by(&mut v, 2, |a, b| b.cmp(a));
+        assert_eq!(v[..2], [9, 8]);
+    }
+}
+
+#[cfg(test)]
+mod tests_grouped {
+    use super::binary_heap::MinComparator;
+    use super::grouped::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn matches_sorting_each_group() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let items: Vec<(u8, u32)> = (0..2000)
+            .map(|_| (rng.gen_range(0..10), rng.gen_range(0..500)))
+            .collect();
+
+        let mut expected: BTreeMap<u8, Vec<u32>> = BTreeMap::new();
+        for &(key, item) in &items {
+            expected.entry(key).or_default().push(item);
+        }
+        for group in expected.values_mut() {
+            group.sort_unstable_by(|a, b| b.cmp(a));
+            group.truncate(7);
+        }
+
+        let (left, right) = items.split_at(700);
+        let mut top = GroupedTopK::new(7);
+        top.extend(left.iter().copied());
+        let mut other = GroupedTopK::new(7);
+        other.extend(right.iter().copied());
+        top.merge(other);
+
+        assert_eq!(top.len(), expected.len());
+        assert_eq!(
+            top.tops(&3).into_iter().copied().collect::<Vec<_>>(),
+            expected[&3]
+        );
+        assert_eq!(top.threshold(&3), expected[&3].last());
+        assert_eq!(top.into_sorted_map(), expected);
+    }
+
+    #[test]
+    fn push_returns_what_does_not_make_the_cut() {
+        let mut top = GroupedTopK::with_cmp(2, MinComparator);
+        assert_eq!(top.push('a', 5), None);
+        assert_eq!(top.push('a', 3), None);
+        assert_eq!(top.push('a', 9), Some(9));
+        assert_eq!(top.push('a', 1), Some(5));
+        assert_eq!(top.tops(&'a'), [&1, &3]);
+        assert!(top.tops(&'b').is_empty());
+        assert_eq!(top.remove(&'a'), Some(vec![1, 3]));
+        assert!(top.is_empty());
+
+        let mut none = GroupedTopK::new(0);
+        assert_eq!(none.push('a', 1), Some(1));
+        assert!(none.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod tests_hooks {
+    use super::binary_heap::BinaryHeap;
+    use super::hooks::*;
+
+    #[derive(Default)]
+    struct Log(Vec<String>);
+
+    impl HeapHooks<i32> for Log {
+        fn on_push(&mut self, item: &i32, len: usize) {
+            self.0.push(format!("push {} {}", item, len));
+        }
+
+        fn on_pop(&mut self, item: &i32, len: usize) {
+            self.0.push(format!("pop {} {}", item, len));
+        }
+
+        fn on_rebuild(&mut self, len: usize) {
+            self.0.push(format!("rebuild {}", len));
+        }
+    }
+
+    #[test]
+    fn hooks_see_every_operation() {
+        let mut heap = HookedBinaryHeap::new(BinaryHeap::from(vec![1]), Log::default());
+        heap.extend(vec![5, 3]);
+        assert_eq!(heap.pop(), Some(5));
+        heap.append(&mut BinaryHeap::from(vec![4, 2]));
+        heap.clear();
+        assert_eq!(heap.pop(), None);
+        let (heap, log) = heap.into_parts();
+        assert!(heap.is_empty());
+        assert_eq!(log.0, ["push 5 2", "push 3 3", "pop 5 2", "rebuild 4"]);
+    }
+}
+
+#[cfg(feature = "journal")]
+#[cfg(test)]
+mod tests_journal {
+    use super::binary_heap::{BinaryHeap, MinComparator};
+    use super::journal::*;
+
+    #[test]
+    fn replay_rebuilds_the_same_heap() {
+        let mut heap = JournaledBinaryHeap::with_cmp_sink(MinComparator, Vec::new());
+        let mut popped = Vec::new();
+        for i in 0..300 {
+            heap.push((i * 37) % 101);
+            match i % 7 {
+                0 => popped.push(heap.pop()),
+                3 => popped.push(Some(heap.replace_at(i % heap.len(), i))),
+                5 => popped.push(Some(heap.swap_remove_index((i * 3) % heap.len()))),
+                _ => {}
+            }
+        }
+        let (heap, journal) = heap.into_parts();
+
+        let mut replayed = BinaryHeap::new_min();
+        let mut replayed_pops = Vec::new();
+        for op in journal.iter().cloned() {
+            let removes = !matches!(op, HeapOp::Push(_) | HeapOp::Clear);
+            let item = op.apply(&mut replayed);
+            if removes {
+                replayed_pops.push(item);
+            }
+        }
+        assert_eq!(replayed_pops, popped);
+        assert_eq!(replayed.into_vec(), heap.clone().into_vec());
+
+        let replayed = BinaryHeap::replay(journal, MinComparator);
+        assert_eq!(replayed.into_vec(), heap.into_vec());
+    }
+
+    #[test]
+    fn bad_index_is_not_recorded() {
+        let mut journal = Vec::new();
+        let mut heap = JournaledBinaryHeap::with_cmp_sink(MinComparator, &mut journal);
+        heap.push(1);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            heap.swap_remove_index(1);
+        }));
+        assert!(result.is_err());
+        heap.clear();
+        assert_eq!(journal, [HeapOp::Push(1), HeapOp::Clear]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn journal_round_trips_through_serde() {
+        use super::binary_heap::MaxComparator;
+
+        let mut heap = JournaledBinaryHeap::new();
+        heap.extend(vec![3, 1, 4]);
+        heap.replace_at(0, 0);
+        heap.pop();
+        let (heap, journal) = heap.into_parts();
+        let json = serde_json::to_string(&journal).unwrap();
+        let journal: Vec<HeapOp<i32>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            BinaryHeap::replay(journal, MaxComparator).into_vec(),
+            heap.into_vec()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests_hybrid {
+    use super::binary_heap::MaxComparator;
+    use super::differential::*;
+    use super::hybrid::*;
+
+    #[test]
+    fn pops_match_binary_heap_across_threshold() {
+        let mut heap = HybridBinaryHeap::with_threshold(16);
+        check_against(
+            &mut heap,
+            MaxComparator,
+            batches(20, 40, 30),
+            |h, x| h.push(x),
+            |h| h.pop(),
+            |h| h.len(),
+        );
+    }
+
+    #[test]
+    fn switches_to_a_heap_above_threshold() {
+        let mut heap = HybridBinaryHeap::with_threshold(8);
+        heap.extend([5, 1, 7, 3, 8, 2, 6, 4]);
+        assert!(heap.is_sorted());
+        assert_eq!(heap.peek(), Some(&8));
+        heap.push(0);
+        assert!(!heap.is_sorted());
+        assert_eq!(heap.peek(), Some(&8));
+    }
+
+    #[test]
+    fn switches_back_at_half_threshold() {
+        let mut heap = HybridBinaryHeap::with_threshold(8);
+        heap.extend(0..9);
+        assert!(!heap.is_sorted());
+        for _ in 0..4 {
+            heap.pop();
+            assert!(!heap.is_sorted());
+        }
+        assert_eq!(heap.pop(), Some(4));
+        assert!(heap.is_sorted());
+        assert_eq!(heap.into_vec(), [0, 1, 2, 3]);
+    }
+}
+
+#[cfg(test)]
+mod tests_lazy {
+    use super::binary_heap::MinComparator;
+    use super::differential::*;
+    use super::lazy::*;
+
+    #[test]
+    fn pops_match_binary_heap() {
+        // Small and large batches, flushed by sifting up and by rebuilding.
+        let mut heap = LazyBinaryHeap::with_cmp(MinComparator);
+        check_against(
+            &mut heap,
+            MinComparator,
+            batches(50, 200, 150),
+            |h, x| h.push(x),
+            |h| h.pop(),
+            |h| h.len(),
+        );
+    }
+
+    #[test]
+    fn reads_flush_pending_items() {
+        let mut heap = LazyBinaryHeap::new();
+        heap.extend([4, 9, 2]);
+        assert_eq!(heap.len(), 3);
+        assert_eq!(heap.pending_len(), 3);
+        assert_eq!(heap.pop(), Some(9));
+        assert_eq!(heap.pending_len(), 0);
+        heap.push(7);
+        assert_eq!(heap.pending_len(), 1);
+        assert_eq!(heap.peek(), Some(&7));
+        assert_eq!(heap.pending_len(), 0);
+    }
+}
+
+#[cfg(test)]
+mod tests_budget {
+    use super::budget::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn matches_a_sorted_vec() {
+        let mut rng = StdRng::seed_from_u64(1207);
+        let mut heap = BudgetedHeap::with_cost(1000, |x: &u32| *x as usize % 50);
+        let mut model: Vec<u32> = Vec::new();
+        for _ in 0..5000 {
+            match rng.gen_range(0..4) {
+                0 | 1 => {
+                    let x = rng.gen_range(0..200);
+                    model.push(x);
+                    model.sort_unstable();
+                    let mut evicted = Vec::new();
+                    while model.iter().map(|x| *x as usize % 50).sum::<usize>() > 1000 {
+                        evicted.push(model.remove(0));
+                    }
+                    assert_eq!(heap.push(x), evicted);
+                }
+                2 => assert_eq!(heap.pop(), model.pop()),
+                _ => {
+                    let least = if model.is_empty() {
+                        None
+                    } else {
+                        Some(model.remove(0))
+                    };
+                    assert_eq!(heap.pop_worst(), least);
+                }
+            }
+            assert_eq!(heap.len(), model.len());
+            assert_eq!(heap.peek(), model.last());
+            assert_eq!(heap.peek_worst(), model.first());
+        }
+    }
+
+    #[test]
+    fn shrinking_the_budget_evicts() {
+        let mut heap = BudgetedHeap::new(4 * 8);
+        heap.extend(1u64..=6);
+        assert_eq!(heap.len(), 4);
+        assert_eq!(heap.used(), 32);
+        assert_eq!(heap.set_budget(16), [3, 4]);
+        assert_eq!(heap.into_vec().len(), 2);
+    }
+}
+
+#[cfg(test)]
+mod tests_cache {
+    use super::cache::*;
+
+    #[test]
+    fn evicts_lowest_priority_then_least_recent() {
+        let mut cache = PriorityCache::new(3);
+        assert_eq!(cache.insert(1, 'a', 5), None);
+        assert_eq!(cache.insert(2, 'b', 5), None);
+        assert_eq!(cache.insert(3, 'c', 9), None);
+        assert_eq!(cache.insert(4, 'd', 7), Some((1, 'a', 5)));
+        assert_eq!(cache.insert(5, 'e', 7), Some((2, 'b', 5)));
+        assert_eq!(cache.peek_lowest(), Some((&4, &'d', &7)));
+        assert_eq!(cache.len(), 3);
+    }
+
+    #[test]
+    fn stale_entries_are_skipped_and_compacted() {
+        let mut cache = PriorityCache::new(4);
+        for k in 0..4 {
+            cache.insert(k, (), k);
+        }
+        for round in 0..100 {
+            for k in 0..4 {
+                cache.reprioritize(&k, 10 * round + (3 - k));
+            }
+        }
+        assert!(cache.heap_len() <= 2 * cache.len() + 16);
+        assert_eq!(cache.pop_lowest(), Some((3, (), 990)));
+        assert_eq!(cache.remove(&2), Some(((), 991)));
+        assert_eq!(cache.pop_lowest(), Some((1, (), 992)));
+        assert_eq!(cache.pop_lowest(), Some((0, (), 993)));
+        assert!(cache.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod tests_channel {
+    use super::binary_heap::MinComparator;
+    use super::channel::*;
+    use std::sync::mpsc::{RecvTimeoutError, TryRecvError};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn disconnect_is_reported_after_pending_messages() {
+        let (tx, rx) = priority_channel::<i32, MinComparator>();
+        let tx2 = tx.clone();
+        tx.send(5).unwrap();
+        tx2.send(2).unwrap();
+        drop(tx);
+        assert_eq!(rx.try_recv(), Ok(2));
+        assert_eq!(rx.try_recv(), Ok(5));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+        assert_eq!(
+            rx.recv_timeout(Duration::from_millis(10)),
+            Err(RecvTimeoutError::Timeout)
+        );
+        drop(tx2);
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+        assert!(rx.recv().is_err());
+    }
+
+    #[test]
+    fn send_fails_without_receiver() {
+        let (tx, rx) = priority_channel::<i32, MinComparator>();
+        drop(rx);
+        assert_eq!(tx.send(1).unwrap_err().0, 1);
+    }
+
+    #[test]
+    fn blocked_receiver_is_woken() {
+        let (tx, rx) = priority_channel::<i32, MinComparator>();
+        let consumer = thread::spawn(move || rx.iter().collect::<Vec<_>>());
+        for i in (0..100).rev() {
+            tx.send(i).unwrap();
+        }
+        drop(tx);
+        let mut received = consumer.join().unwrap();
+        received.sort_unstable();
+        assert_eq!(received, (0..100).collect::<Vec<_>>());
+    }
+}
+
+#[cfg(test)]
+mod tests_context {
+    use super::context::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn pops_in_the_order_of_the_context() {
+        let mut rng = StdRng::seed_from_u64(29);
+        let mut scores: Vec<u32> = (0..50).map(|_| rng.gen_range(0..1000)).collect();
+        let mut heap = ContextBinaryHeap::new(|a: &usize, b: &usize, scores: &[u32]| {
+            scores[*a].cmp(&scores[*b])
+        });
+        for i in 0..50 {
+            heap.push(i, &scores[..]);
+        }
+        for score in &mut scores {
+            *score = 1000 - *score;
+        }
+        heap.rebuild(&scores[..]);
+
+        let mut popped = Vec::new();
+        while let Some(i) = heap.pop(&scores[..]) {
+            popped.push(scores[i]);
+        }
+        let mut expected = scores.clone();
+        expected.sort_unstable_by(|a, b| b.cmp(a));
+        assert_eq!(popped, expected);
+    }
+
+    #[test]
+    fn into_sorted_vec_uses_the_key() {
+        let mut heap = ContextBinaryHeap::new(ContextKeyComparator(|x: &i32, offset: &i32| {
+            (x - offset).abs()
+        }));
+        for x in [1, 10, 4, 6] {
+            heap.push(x, &5);
+        }
+        assert_eq!(heap.peek(), Some(&10));
+        assert_eq!(heap.len(), 4);
+        assert_eq!(heap.into_sorted_vec(&0), [1, 4, 6, 10]);
+    }
+}
+
+#[cfg(test)]
+mod tests_cow {
+    use super::binary_heap::BinaryHeap;
+    use super::cow::*;
+
+    #[test]
+    fn clones_only_when_shared() {
+        let mut heap = CowHeap::from(BinaryHeap::from(vec![1, 5, 3]));
+        heap.push(2);
+        assert!(!heap.is_shared());
+
+        let snapshot = heap.snapshot();
+        assert!(heap.is_shared());
+        assert!(std::ptr::eq(snapshot.heap(), heap.heap()));
+
+        assert_eq!(heap.pop(), Some(5));
+        assert!(!heap.is_shared() && !snapshot.is_shared());
+        assert_eq!(snapshot.peek(), Some(&5));
+        assert_eq!(snapshot.into_sorted_vec(), [1, 2, 3, 5]);
+        assert_eq!(heap.into_sorted_vec(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn empty_pop_does_not_clone() {
+        let mut heap = CowHeap::<u8>::new();
+        let snapshot = heap.snapshot();
+        assert_eq!(heap.pop(), None);
+        assert!(std::ptr::eq(snapshot.heap(), heap.heap()));
+    }
+}
+
+#[cfg(test)]
+mod tests_delay {
+    use super::delay::*;
+    use futures::task::{waker, ArcWake, Context, Poll};
+    use std::cell::Cell;
+    use std::future::{pending, Pending};
+    use std::rc::Rc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    /// A clock that only moves when told to, whose sleeps never complete.
+    #[derive(Clone)]
+    struct ManualTimer(Rc<Cell<Instant>>);
+
+    impl Timer for ManualTimer {
+        type Sleep = Pending<()>;
+
+        fn sleep_until(&mut self, _deadline: Instant) -> Pending<()> {
+            pending()
+        }
+
+        fn now(&self) -> Instant {
+            self.0.get()
+        }
+    }
+
+    #[derive(Default)]
+    struct WakeCount(AtomicUsize);
+
+    impl ArcWake for WakeCount {
+        fn wake_by_ref(arc_self: &Arc<Self>) {
+            arc_self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn expires_in_deadline_order() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        let clock = ManualTimer(Rc::new(Cell::new(start)));
+        let mut queue = DelayQueue::new(clock.clone());
+        let wakes = Arc::new(WakeCount::default());
+        let waker = waker(Arc::clone(&wakes));
+        let mut cx = Context::from_waker(&waker);
+
+        let a = queue.insert("a", ms(30));
+        let b = queue.insert("b", ms(10));
+        let c = queue.insert("c", ms(10));
+        assert!(queue.poll_expired(&mut cx).is_pending());
+
+        // An earlier deadline wakes the waiting task; a later one does not.
+        queue.insert("d", ms(5));
+        assert_eq!(wakes.0.load(Ordering::SeqCst), 1);
+        assert!(queue.poll_expired(&mut cx).is_pending());
+        queue.insert("e", ms(50));
+        assert_eq!(wakes.0.load(Ordering::SeqCst), 1);
+
+        assert!(queue.reset_at(b, start + ms(40)));
+        assert_eq!(queue.deadline(b), Some(start + ms(40)));
+        assert_eq!(queue.remove(a), Some("a"));
+        assert_eq!(queue.remove(a), None);
+        assert!(!queue.reset_at(a, start));
+
+        clock.0.set(start + ms(40));
+        let mut expired = Vec::new();
+        while let Poll::Ready(Some(e)) = queue.poll_expired(&mut cx) {
+            expired.push((e.key(), e.into_inner()));
+        }
+        assert_eq!(expired[0].1, "d");
+        assert_eq!(expired[1..], [(c, "c"), (b, "b")]);
+        assert_eq!(queue.next_deadline(), Some(start + ms(50)));
+
+        queue.clear();
+        assert!(matches!(queue.poll_expired(&mut cx), Poll::Ready(None)));
+    }
+
+    #[test]
+    fn thread_timer_wakes_the_executor() {
+        let mut queue: DelayQueue<u64> = DelayQueue::default();
+        let ms = Duration::from_millis;
+        for i in (0..5).rev() {
+            queue.insert(i, ms(5 * i));
+        }
+        let started = Instant::now();
+        let expired: Vec<_> = futures::executor::block_on(async {
+            let mut expired = Vec::new();
+            while let Some(e) = futures::future::poll_fn(|cx| queue.poll_expired(cx)).await {
+                assert!(Instant::now() >= e.deadline());
+                expired.push(e.into_inner());
+            }
+            expired
+        });
+        assert_eq!(expired, [0, 1, 2, 3, 4]);
+        assert!(started.elapsed() >= ms(15));
+    }
+
+    #[cfg(feature = "futures")]
+    #[test]
+    fn stream_ends_when_empty() {
+        use futures::stream::StreamExt;
+
+        let mut queue = DelayQueue::new(ThreadTimer);
+        queue.insert('x', Duration::from_millis(2));
+        queue.insert('y', Duration::from_millis(1));
+        let items: Vec<_> = futures::executor::block_on(queue.map(Expired::into_inner).collect());
+        assert_eq!(items, ['y', 'x']);
+    }
+}
+
+#[cfg(test)]
+mod tests_edf {
+    use super::edf::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn same_deadline_pops_in_push_order() {
+        let now = Instant::now();
+        let deadline = now + Duration::from_millis(1);
+        let mut queue = EdfQueue::new();
+        for i in 0..10 {
+            queue.push(i, deadline);
+        }
+        let order: Vec<_> = std::iter::from_fn(|| queue.pop_next(now))
+            .map(EdfTask::into_value)
+            .collect();
+        assert_eq!(order, (0..10).collect::<Vec<_>>());
+        assert_eq!(queue.missed_count(), 0);
+    }
+
+    #[test]
+    fn missed_tasks_are_counted_without_hook() {
+        let start = Instant::now();
+        let mut queue = EdfQueue::new();
+        queue.push('a', start);
+        queue.push('b', start + Duration::from_secs(60));
+
+        let now = start + Duration::from_millis(1);
+        assert_eq!(queue.pop_next(now).unwrap().value, 'a');
+        assert_eq!(queue.pop_next(now).unwrap().value, 'b');
+        assert_eq!(queue.missed_count(), 1);
+    }
+
+    #[test]
+    fn overflowing_finish_time_is_a_predicted_miss() {
+        let now = Instant::now();
+        let mut queue = EdfQueue::new();
+        queue.push_with_budget('a', now + Duration::from_secs(1), Duration::from_millis(1));
+        queue.push_with_budget('b', now + Duration::from_secs(2), Duration::MAX);
+        assert_eq!(queue.first_predicted_miss(now).unwrap().value, 'b');
+    }
+}
+
+#[cfg(test)]
+mod tests_aging {
+    use super::aging::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn low_priority_is_not_starved() {
+        let mut heap = AgingHeap::new(|p: &u64, waited: Duration| p + waited.as_secs());
+        let start = Instant::now();
+        heap.push("low", 0, start);
+
+        // A steady stream of priority-10 work arrives every second.
+        let mut popped = Vec::new();
+        for s in 1..=20 {
+            let now = start + Duration::from_secs(s);
+            heap.push("high", 10, now);
+            popped.push(heap.pop(now).unwrap());
+        }
+        assert!(popped.contains(&"low"));
+    }
+
+    #[test]
+    fn refresh_granularity_delays_reordering() {
+        let mut heap = AgingHeap::new(|p: &u64, waited: Duration| p + waited.as_secs());
+        heap.set_refresh_granularity(Duration::from_secs(5));
+        let start = Instant::now();
+        heap.push('a', 0, start);
+        heap.push('b', 2, start);
+        assert_eq!(heap.peek(start), Some(&'b'));
+
+        // 'c' enters at the stale refresh time and is not yet outranked.
+        heap.push('c', 3, start + Duration::from_secs(1));
+        assert_eq!(heap.peek(start + Duration::from_secs(2)), Some(&'c'));
+        // Once the granularity elapses, 'b' has aged past 'c'.
+        assert_eq!(heap.peek(start + Duration::from_secs(5)), Some(&'b'));
+        assert_eq!(heap.len(), 3);
+    }
+}
+
+#[cfg(feature = "dot")]
+#[cfg(test)]
+mod tests_dot {
+    use super::binary_heap::*;
+
+    #[test]
+    fn to_dot_escapes_labels() {
+        let heap = BinaryHeap::from(vec!["say \"hi\"\n", "a\\b"]);
+        assert_eq!(
+            heap.to_dot_with(|s| s.to_string()),
+            "digraph heap {\n    n0 [label=\"say \\\"hi\\\"\\n\"];\n    \
+             n1 [label=\"a\\\\b\"];\n    n0 -> n1;\n}\n"
+        );
+        assert_eq!(BinaryHeap::<i32>::new().to_dot(), "digraph heap {\n}\n");
+    }
+}
+
+#[cfg(feature = "fallible")]
+#[cfg(test)]
+mod tests_fallible {
+    use super::binary_heap::*;
+
+    #[test]
+    fn try_push_hands_back_item_when_growth_fails() {
+        let mut heap = BinaryHeap::from(vec![1u64, 2]);
+        heap.set_growth_policy(GrowthPolicy::Custom(|_| usize::MAX));
+        assert_eq!(heap.try_push(3), Err(3));
+        assert_eq!(heap.len(), 2);
+
+        heap.set_growth_policy(GrowthPolicy::Exact);
+        assert_eq!(heap.try_push(3), Ok(()));
+        assert_eq!(heap.capacity(), 3);
+        assert_eq!(heap.peek(), Some(&3));
+    }
+
+    #[test]
+    fn try_append_moves_everything() {
+        let mut a = BinaryHeap::from(vec![1]);
+        let mut b = BinaryHeap::from(vec![4, 2, 3]);
+        assert!(a.try_append(&mut b).is_ok());
+        assert!(b.is_empty());
+        assert_eq!(a.into_sorted_vec(), [1, 2, 3, 4]);
+    }
+
+    #[cfg(feature = "alloc_guard")]
+    #[test]
+    fn try_push_fails_on_frozen_full_heap() {
+        let mut heap = BinaryHeap::with_capacity(2);
+        heap.freeze_capacity();
+        heap.extend(vec![1, 2]);
+        assert_eq!(heap.capacity(), 2);
+        assert_eq!(heap.try_push(3), Err(3));
+        assert_eq!(heap.len(), 2);
+        heap.pop();
+        assert_eq!(heap.try_push(3), Ok(()));
+    }
+
+    #[cfg(feature = "alloc_guard")]
+    #[test]
+    fn try_append_fails_on_frozen_full_heap() {
+        let mut a = BinaryHeap::with_capacity(2);
+        a.freeze_capacity();
+        a.extend(vec![1, 2]);
+        let mut b = BinaryHeap::from(vec![3]);
+        assert!(a.try_append(&mut b).is_err());
+        assert_eq!((a.len(), b.len()), (2, 1));
+
+        a.pop();
+        assert!(a.try_append(&mut b).is_ok());
+        assert_eq!(a.into_sorted_vec(), [1, 3]);
+    }
+
+    #[cfg(feature = "alloc_guard")]
+    #[test]
+    fn try_reserve_fails_on_frozen_heap() {
+        let mut heap = BinaryHeap::with_capacity(4);
+        heap.extend(vec![1, 2]);
+        heap.shrink_to(4);
+        heap.freeze_capacity();
+        assert!(heap.try_reserve(3).is_err());
+        assert!(heap.try_reserve_exact(3).is_err());
+        assert_eq!(heap.capacity(), 4);
+        assert!(heap.try_reserve(2).is_ok());
+        assert!(heap.try_reserve_exact(2).is_ok());
+    }
+}
+
+/// Scenarios shared by the tests of the concurrent queues.
+#[cfg(test)]
+mod concurrent {
+    use std::sync::Arc;
+    use std::thread;
+
+    /// Has 8 threads push 1000 distinct items each into `queue`, popping
+    /// after every other push, then drains `queue` and checks that every
+    /// item came out exactly once.
+    pub(crate) fn push_pop_loses_nothing<Q: Send + Sync + 'static>(
+        queue: Q,
+        push: fn(&Q, u32),
+        pop: fn(&Q) -> Option<u32>,
+        len: fn(&Q) -> usize,
+    ) {
+        let queue = Arc::new(queue);
+        let workers: Vec<_> = (0..8)
+            .map(|t| {
+                let queue = Arc::clone(&queue);
+                thread::spawn(move || {
+                    let mut popped = Vec::new();
+                    for i in 0..1000 {
+                        push(&queue, t * 1000 + i);
+                        if i % 2 == 0 {
+                            popped.extend(pop(&queue));
+                        }
+                    }
+                    popped
+                })
+            })
+            .collect();
+        let mut all: Vec<_> = workers
+            .into_iter()
+            .flat_map(|w| w.join().unwrap())
+            .collect();
+        assert_eq!(len(&queue), 8000 - all.len());
+
+        all.extend(std::iter::from_fn(|| pop(&queue)));
+        assert_eq!(len(&queue), 0);
+        all.sort_unstable();
+        assert_eq!(all, (0..8000).collect::<Vec<_>>());
+    }
+}
+
+#[cfg(feature = "concurrent")]
+#[cfg(test)]
+mod tests_lockfree {
+    use super::binary_heap::MinComparator;
+    use super::concurrent;
+    use super::lockfree::*;
+
+    #[test]
+    fn concurrent_push_pop_loses_nothing() {
+        let queue = ConcurrentPriorityQueue::<u32, MinComparator>::new();
+        concurrent::push_pop_loses_nothing(queue, |q, x| q.push(x), |q| q.pop(), |q| q.len());
+    }
+
+    #[test]
+    fn equal_items_pop_in_push_order() {
+        let queue = ConcurrentPriorityQueue::<Keyed, MinComparator>::new();
+        for label in ["x", "y", "z"] {
+            queue.push(Keyed(7, label));
+        }
+        queue.push(Keyed(3, "w"));
+        assert_eq!(queue.peek().map(|k| k.1), Some("w"));
+
+        let labels: Vec<_> = std::iter::from_fn(|| queue.pop()).map(|k| k.1).collect();
+        assert_eq!(labels, ["w", "x", "y", "z"]);
+    }
+
+    /// Compares by the first field only.
+    #[derive(Clone, Debug)]
+    struct Keyed(u32, &'static str);
+
+    impl PartialEq for Keyed {
+        fn eq(&self, other: &Self) -> bool {
+            self.0 == other.0
+        }
+    }
+
+    impl Eq for Keyed {}
+
+    impl PartialOrd for Keyed {
+        fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Keyed {
+        fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+            self.0.cmp(&other.0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_mlfq {
+    use super::mlfq::*;
+    use std::time::Duration;
+
+    #[test]
+    fn allotment_accumulates_across_runs() {
+        let mut sched = MlfqScheduler::new(vec![Duration::from_millis(10); 2]);
+        sched.push((), 0);
+        for _ in 0..3 {
+            let task = sched.pop_next().unwrap();
+            assert_eq!(task.level(), 0);
+            sched.requeue(task, Duration::from_millis(3));
+        }
+        let task = sched.pop_next().unwrap();
+        sched.requeue(task, Duration::from_millis(3));
+        assert_eq!(sched.level_len(0), 0);
+        assert_eq!(sched.level_len(1), 1);
+    }
+
+    #[test]
+    fn boost_restores_top_level_in_order() {
+        let q = Duration::from_millis(1);
+        let mut sched = MlfqScheduler::new(vec![q, q, q]);
+        sched.push('a', 2);
+        sched.push('b', 7);
+        sched.push('c', 1);
+        assert_eq!(sched.level_len(2), 2);
+
+        sched.boost();
+        assert_eq!(sched.level_len(0), 3);
+        let order: Vec<_> = std::iter::from_fn(|| sched.pop_next())
+            .map(|t| (t.value, t.level()))
+            .collect();
+        assert_eq!(order, [('c', 0), ('a', 0), ('b', 0)]);
+    }
+}
+
+#[cfg(feature = "rand")]
+#[cfg(test)]
+mod tests_rand {
+    use super::binary_heap::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn pop_random_is_uniform_and_keeps_the_heap() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut counts = [0; 4];
+        for _ in 0..4000 {
+            let mut heap = BinaryHeap::from(vec![0, 1, 2, 3]);
+            counts[heap.pop_random(&mut rng).unwrap()] += 1;
+        }
+        assert!(
+            counts.iter().all(|&n| (850..1150).contains(&n)),
+            "{:?}",
+            counts
+        );
+
+        let mut heap: BinaryHeap<u32> = (0..100).collect();
+        for _ in 0..50 {
+            heap.pop_random(&mut rng);
+        }
+        let rest = heap.clone().into_sorted_vec();
+        let popped: Vec<u32> = std::iter::from_fn(|| heap.pop()).collect();
+        assert_eq!(popped, rest.into_iter().rev().collect::<Vec<_>>());
+        assert_eq!(BinaryHeap::<u32>::new().pop_random(&mut rng), None);
+    }
+}
+
+#[cfg(feature = "rayon")]
+#[cfg(test)]
+mod tests_rayon {
+    use super::binary_heap::*;
+    use rayon::prelude::*;
+
+    #[test]
+    fn collect_from_parallel_iterator() {
+        let heap: BinaryHeap<u32, MinComparator> = (0..10_000u32).into_par_iter().rev().collect();
+        assert_eq!(heap.len(), 10_000);
+        assert_eq!(
+            heap.into_sorted_vec(),
+            (0..10_000).rev().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn par_extend_keeps_heap_valid() {
+        let mut heap: BinaryHeap<_> = BinaryHeap::from_vec(vec![500, 20_000]);
+        heap.par_extend((0..10_000).into_par_iter().map(|x| x * 3 % 10_007));
+        heap.par_extend(&[7, 8, 9][..]);
+        let sorted = heap.into_sorted_vec();
+        assert_eq!(sorted.len(), 10_005);
+        assert!(sorted.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(sorted.last(), Some(&20_000));
+    }
+
+    #[test]
+    fn parallel_rebuild_matches_sequential() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(7);
+        for &len in &[1 << 16, (1 << 16) + 1, 100_003] {
+            let data: Vec<u32> = (0..len).map(|_| rng.gen_range(0..1000)).collect();
+            let par: BinaryHeap<_> = BinaryHeap::from_vec_par(data.clone());
+            let seq: BinaryHeap<_> = BinaryHeap::from_vec(data);
+            assert_eq!(par.into_sorted_vec(), seq.into_sorted_vec());
+        }
+    }
+
+    #[test]
+    fn par_into_sorted_vec_respects_comparator() {
+        let data: Vec<i32> = (0..50_000).map(|x| x * 7919 % 50_021 - 25_000).collect();
+        let heap = BinaryHeap::from_vec_cmp(data, KeyComparator(|x: &i32| x.abs()));
+        let expected = heap.clone().into_sorted_vec();
+        let sorted = heap.par_into_sorted_vec();
+        let abs = |v: &[i32]| v.iter().map(|x| x.abs()).collect::<Vec<_>>();
+        assert_eq!(abs(&sorted), abs(&expected));
+    }
+
+    #[test]
+    fn parallel_iteration_visits_every_item() {
+        let heap: BinaryHeap<u64> = BinaryHeap::from_vec((1..=1000).collect());
+        assert_eq!(heap.par_iter().sum::<u64>(), 500_500);
+        assert_eq!(
+            (&heap).into_par_iter().filter(|&&x| x % 2 == 0).count(),
+            500
+        );
+        let mut doubled: Vec<_> = heap.into_par_iter().map(|x| x * 2).collect();
+        doubled.sort_unstable();
+        assert_eq!(doubled, (1..=1000).map(|x| x * 2).collect::<Vec<_>>());
+    }
+}
+
+#[cfg(test)]
+mod tests_queue {
+    use super::*;
+
+    fn drain_sorted<Q: PriorityQueue<i32>>(mut queue: Q) -> Vec<i32> {
+        let items = [5, 1, 8, 3, 9, 2, 7, 4, 6, 0];
+        for &x in &items {
+            queue.push(x);
+        }
+        assert_eq!(queue.len(), items.len());
+        assert_eq!(queue.peek(), Some(&9));
+        std::iter::from_fn(|| queue.pop()).collect()
+    }
+
+    #[test]
+    fn every_queue_pops_in_order() {
+        let sorted: Vec<i32> = (0..10).rev().collect();
+        assert_eq!(drain_sorted(BinaryHeap::new()), sorted);
+        assert_eq!(drain_sorted(BlockedBinaryHeap::new()), sorted);
+        assert_eq!(drain_sorted(HybridBinaryHeap::new()), sorted);
+        assert_eq!(drain_sorted(SegmentedBinaryHeap::new()), sorted);
+        assert_eq!(
+            drain_sorted(HookedBinaryHeap::new(BinaryHeap::new(), ())),
+            sorted
+        );
+    }
+
+    #[test]
+    fn meld_and_replace_at() {
+        let mut a = BinaryHeap::from(vec![1, 4]);
+        let mut b = BinaryHeap::from(vec![2, 3]);
+        a.meld(&mut b);
+        assert!(PriorityQueue::is_empty(&b));
+
+        let i = IndexedPriorityQueue::as_slice(&a)
+            .iter()
+            .position(|&x| x == 4)
+            .unwrap();
+        assert_eq!(IndexedPriorityQueue::replace_at(&mut a, i, 0), 4);
+        assert_eq!(a.into_sorted_vec(), [0, 1, 2, 3]);
+    }
+}
+
+#[cfg(test)]
+mod tests_retry {
+    use super::retry::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn jitter_stays_within_bounds_and_is_seeded() {
+        let policy = RetryPolicy {
+            initial: Duration::from_secs(10),
+            jitter: 0.25,
+            ..RetryPolicy::default()
+        };
+        let now = Instant::now();
+        let mut a = RetryQueue::with_seed(policy, 7);
+        let mut b = RetryQueue::with_seed(policy, 7);
+        for i in 0..100 {
+            let due = a.push(i, now).unwrap();
+            assert_eq!(b.push(i, now).unwrap(), due);
+            let delay = due - now;
+            assert!(delay > Duration::from_millis(7500) && delay <= Duration::from_secs(10));
+        }
+    }
+
+    #[test]
+    fn items_become_ready_in_due_order() {
+        let policy = RetryPolicy {
+            jitter: 0.0,
+            ..RetryPolicy::default()
+        };
+        let mut queue = RetryQueue::new(policy);
+        let now = Instant::now();
+        queue.push('a', now + Duration::from_secs(1)).unwrap();
+        queue.push('b', now).unwrap();
+        assert_eq!(queue.next_ready_at(), Some(now + policy.initial));
+
+        let later = now + Duration::from_secs(5);
+        assert_eq!(queue.pop_ready(later).unwrap().value, 'b');
+        assert_eq!(queue.pop_ready(later).unwrap().value, 'a');
+        assert!(queue.pop_ready(later).is_none());
+    }
+
+    #[test]
+    fn zero_retries_rejects_immediately() {
+        let policy = RetryPolicy {
+            max_retries: 0,
+            ..RetryPolicy::default()
+        };
+        let mut queue = RetryQueue::new(policy);
+        assert_eq!(queue.push(1, Instant::now()), Err(1));
+        assert!(queue.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod tests_segmented {
+    use super::binary_heap::MaxComparator;
+    use super::differential::*;
+    use super::segmented::*;
+
+    #[test]
+    fn pops_match_binary_heap() {
+        for &chunk_len in &[1, 2, 8, 64] {
+            let mut heap = SegmentedBinaryHeap::with_chunk_len(chunk_len);
+            check_against(
+                &mut heap,
+                MaxComparator,
+                interleaved(500, 3),
+                |h, x| h.push(x),
+                |h| h.pop(),
+                |h| h.len(),
+            );
+            assert_eq!(heap.chunks(), 0);
+        }
+    }
+
+    #[test]
+    fn releases_chunks_as_it_shrinks() {
+        let mut heap = SegmentedBinaryHeap::with_chunk_len(4);
+        heap.extend(0..17);
+        assert_eq!(heap.chunks(), 5);
+        while heap.pop().is_some() {
+            assert_eq!(heap.chunks(), (heap.len() + 3) / 4);
+            // Only the last released chunk is kept as a spare.
+            assert!(heap.capacity() <= (heap.chunks() + 1) * 4);
+        }
+    }
+
+    #[test]
+    fn keeps_one_spare_chunk() {
+        let mut heap = SegmentedBinaryHeap::with_chunk_len(2);
+        heap.extend(0..3);
+        assert_eq!(heap.chunks(), 2);
+        heap.pop();
+        assert_eq!(heap.chunks(), 1);
+        assert_eq!(heap.capacity(), 4);
+        assert!(heap.memory_usage() >= 4 * std::mem::size_of::<i32>());
+        heap.push(10);
+        assert_eq!(heap.chunks(), 2);
+        assert_eq!(heap.iter().count(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "power of two")]
+    fn rejects_odd_chunk_len() {
+        let _ = SegmentedBinaryHeap::<u8>::with_chunk_len(3);
+    }
+}
+
+#[cfg(test)]
+mod tests_sharded {
+    use super::binary_heap::MinComparator;
+    use super::concurrent;
+    use super::sharded::*;
+
+    #[test]
+    fn concurrent_push_pop_loses_nothing() {
+        let heap = ConcurrentBinaryHeap::with_shards_cmp(4, MinComparator);
+        concurrent::push_pop_loses_nothing(heap, |h, x| h.push(x), |h| h.pop(), |h| h.len());
+    }
+
+    #[test]
+    fn quiescent_pops_are_ordered() {
+        let heap = ConcurrentBinaryHeap::with_shards(3);
+        for x in [5, 1, 9, 3, 7, 2, 8] {
+            heap.push(x);
+        }
+        let popped: Vec<_> = std::iter::from_fn(|| heap.try_pop()).collect();
+        assert_eq!(popped, [9, 8, 7, 5, 3, 2, 1]);
+        assert!(heap.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod tests_multiqueue {
+    use super::binary_heap::MinComparator;
+    use super::concurrent;
+    use super::multiqueue::*;
+
+    #[test]
+    fn concurrent_push_pop_loses_nothing() {
+        let queue = MultiQueue::with_queues_cmp(8, MinComparator);
+        concurrent::push_pop_loses_nothing(queue, |q, x| q.push(x), |q| q.pop(), |q| q.len());
+    }
+
+    #[test]
+    fn pops_everything_with_high_rank() {
+        let queue = MultiQueue::with_queues(4);
+        for x in 0..1000 {
+            queue.push(x);
+        }
+        let popped: Vec<_> = std::iter::from_fn(|| queue.pop()).collect();
+        assert!(queue.is_empty());
+        assert_eq!(popped.len(), 1000);
+        // Each pop takes the top of some heap, and each heap holds about 50
+        // of the 200 greatest elements.
+        assert!(popped[..10].iter().all(|&x| x >= 800));
+
+        let exact = MultiQueue::with_queues(1);
+        for x in [3, 9, 1] {
+            exact.push(x);
+        }
+        let popped: Vec<_> = std::iter::from_fn(|| exact.pop()).collect();
+        assert_eq!(popped, [9, 3, 1]);
+    }
+
+    #[test]
+    fn pop_finds_a_lone_item_in_any_heap() {
+        // The two random heaps are usually empty, so only the final scan of
+        // every heap finds the item.
+        let queue = MultiQueue::with_queues(64);
+        for x in 0..100 {
+            queue.push(x);
+            assert_eq!(queue.pop(), Some(x));
+            assert_eq!(queue.pop(), None);
+        }
+    }
+}
+
+#[cfg(feature = "futures")]
+#[cfg(test)]
+mod tests_stream {
+    use super::binary_heap::*;
+    use futures::stream::{FusedStream, StreamExt};
+    use futures::task::{noop_waker, Context, Poll};
+
+    #[test]
+    fn stream_yields_after_budget() {
+        let heap: BinaryHeap<_, MinComparator> = BinaryHeap::from_vec((0..5).collect());
+        let mut stream = heap.into_stream_sorted().budget(2);
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        let mut polls = Vec::new();
+        loop {
+            match stream.poll_next_unpin(&mut cx) {
+                Poll::Ready(Some(x)) => polls.push(Some(x)),
+                Poll::Ready(None) => break,
+                Poll::Pending => polls.push(None),
+            }
+        }
+        assert_eq!(
+            polls,
+            [Some(0), Some(1), None, Some(2), Some(3), None, Some(4)]
+        );
+        assert!(stream.is_terminated());
+    }
+
+    #[test]
+    fn stream_matches_iter_sorted() {
+        let heap: BinaryHeap<_> = BinaryHeap::from_vec(vec![3, 1, 4, 1, 5, 9, 2, 6, 5, 3, 5]);
+        let streamed: Vec<_> =
+            futures::executor::block_on(heap.clone().into_stream_sorted().collect());
+        assert_eq!(streamed, heap.into_iter_sorted().collect::<Vec<_>>());
+    }
+}
+
+#[cfg(feature = "smallvec")]
+#[cfg(test)]
+mod tests_small {
+    use super::binary_heap::MinComparator;
+    use super::differential::*;
+    use super::small::*;
+
+    #[test]
+    fn pops_match_binary_heap_across_spill() {
+        let mut heap: SmallBinaryHeap<u32, MinComparator, 4> = SmallBinaryHeap::new();
+        check_against(
+            &mut heap,
+            MinComparator,
+            interleaved(100, 3),
+            |h, x| h.push(x),
+            |h| h.pop(),
+            |h| h.len(),
+        );
+    }
+
+    #[test]
+    fn spills_on_the_push_past_n() {
+        let mut heap: SmallBinaryHeap<u32, MinComparator, 4> = SmallBinaryHeap::new();
+        heap.extend([6, 2, 8, 4]);
+        assert!(!heap.spilled());
+        assert_eq!(heap.memory_usage(), 0);
+        heap.push(5);
+        assert!(heap.spilled());
+        assert_eq!(heap.as_slice().len(), 5);
+        let popped: Vec<_> = std::iter::from_fn(|| heap.pop()).collect();
+        assert_eq!(popped, [2, 4, 5, 6, 8]);
+    }
+
+    #[test]
+    fn memory_usage_counts_only_spilled_buffer() {
+        let mut heap: SmallBinaryHeap<u32, MinComparator, 2> = SmallBinaryHeap::new();
+        heap.extend([1, 2]);
+        assert_eq!(heap.memory_usage(), 0);
+        heap.push(3);
+        assert!(heap.memory_usage() >= 3 * 4);
+    }
+
+    #[test]
+    fn stays_inline_up_to_n() {
+        let mut heap: SmallBinaryHeap<_, _, 3> =
+            SmallBinaryHeap::with_cmp(|a: &u8, b: &u8| a.cmp(b));
+        heap.extend([1, 2, 3]);
+        assert!(!heap.spilled());
+        assert_eq!(heap.inline_size(), 3);
+        assert_eq!(heap.peek(), Some(&3));
+    }
+}
+
+#[cfg(feature = "strict_asserts")]
+#[cfg(test)]
+mod tests_strict_asserts {
+    use super::binary_heap::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn valid_heap_passes() {
+        let mut heap = BinaryHeap::from(vec![3, 1, 4, 1, 5]);
+        heap.append(&mut BinaryHeap::from(vec![9, 2, 6]));
+        *heap.peek_mut().unwrap() = 0;
+        assert_eq!(heap.pop(), Some(6));
+    }
+
+    #[test]
+    #[should_panic(expected = "heap property violated after `push`: \
+                               the element at 2 is greater than its parent at 0")]
+    fn mutation_through_cell_is_caught() {
+        let mut heap = BinaryHeap::new();
+        for x in [3, 2, 1] {
+            heap.push(Cell::new(x));
+        }
+        heap.iter().last().unwrap().set(10);
+        heap.push(Cell::new(0));
+    }
+}
+
+#[cfg(feature = "testing")]
+#[cfg(test)]
+mod tests_testing {
+    use super::binary_heap::*;
+    use super::testing::*;
+    use proptest::prelude::*;
+    use quickcheck::{Arbitrary, Gen};
+    use std::cell::Cell;
+    use std::cmp::Ordering;
+    use std::mem;
+    use std::ops::Range;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::rc::Rc;
+
+    fn is_max_heap(heap: &BinaryHeap<i32>) -> bool {
+        let data = heap.clone().into_vec();
+        (1..data.len()).all(|i| data[(i - 1) / 2] >= data[i])
+    }
+
+    proptest! {
+        #[test]
+        fn proptest_heaps_are_valid(heap in any::<BinaryHeap<i8, MinComparator>>()) {
+            let mut sorted = heap.clone().into_vec();
+            sorted.sort_unstable_by(|a, b| b.cmp(a));
+            prop_assert_eq!(heap.into_sorted_vec(), sorted);
+        }
+
+        #[test]
+        fn heap_strategy_respects_size(heap in heap_strategy::<_, MaxComparator>(any::<u8>(), 3..5)) {
+            prop_assert!((3..5).contains(&heap.len()));
+        }
+    }
+
+    #[test]
+    fn quickcheck_shrinks_to_valid_heaps() {
+        let mut g = Gen::new(50);
+        for _ in 0..20 {
+            let heap: BinaryHeap<i32> = Arbitrary::arbitrary(&mut g);
+            assert!(is_max_heap(&heap));
+            for smaller in heap.shrink().take(50) {
+                assert!(smaller.len() <= heap.len());
+                assert!(is_max_heap(&smaller));
+            }
+        }
+    }
+
+    /// Counts its drops in a shared counter.
+    struct Tracked(u32, Rc<Cell<usize>>);
+
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            self.1.set(self.1.get() + 1);
+        }
+    }
+
+    impl PartialEq for Tracked {
+        fn eq(&self, other: &Self) -> bool {
+            self.0 == other.0
+        }
+    }
+
+    impl Eq for Tracked {}
+
+    impl PartialOrd for Tracked {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Tracked {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.0.cmp(&other.0)
+        }
+    }
+
+    type PanickingHeap = BinaryHeap<Tracked, PanickingComparator>;
+
+    fn never_panicking() -> PanickingHeap {
+        BinaryHeap::from_vec_cmp(Vec::new(), PanickingComparator::new(0))
+    }
+
+    #[test]
+    fn comparator_panics_neither_leak_nor_double_drop() {
+        #[allow(clippy::type_complexity)]
+        let ops: [(&str, fn(&mut PanickingHeap, Vec<Tracked>, usize)); 8] = [
+            ("push", |heap, extra, _| heap.extend(extra)),
+            ("pop", |heap, _, _| while heap.pop().is_some() {}),
+            ("append", |heap, extra, _| {
+                heap.append(&mut BinaryHeap::from_vec_cmp(
+                    extra,
+                    PanickingComparator::new(0),
+                ))
+            }),
+            ("append_sorted_ascending", |heap, mut extra, _| {
+                extra.sort();
+                heap.append_sorted_ascending(extra);
+            }),
+            ("rebuild", |heap, extra, n| {
+                heap.append(&mut BinaryHeap::from_vec_cmp(
+                    extra,
+                    PanickingComparator::new(n),
+                ))
+            }),
+            ("peek_mut", |heap, _, _| {
+                if let Some(mut top) = heap.peek_mut() {
+                    top.0 = 0;
+                }
+            }),
+            ("into_sorted_vec", |heap, _, _| {
+                drop(mem::replace(heap, never_panicking()).into_sorted_vec())
+            }),
+            ("into_iter_sorted", |heap, _, _| {
+                drop(
+                    mem::replace(heap, never_panicking())
+                        .into_iter_sorted()
+                        .nth(20),
+                )
+            }),
+        ];
+        for &(name, op) in &ops {
+            for n in 1..300 {
+                let drops = Rc::new(Cell::new(0));
+                let items = |range: Range<u32>| -> Vec<_> {
+                    range
+                        .map(|i| Tracked((i * 7919) % 101, Rc::clone(&drops)))
+                        .collect()
+                };
+                let mut sorted = items(0..24);
+                sorted.sort_by(|a, b| b.cmp(a));
+                // SAFETY: a vector sorted in descending order is a max-heap.
+                #[cfg(not(feature = "safe_only"))]
+                let mut heap = unsafe {
+                    BinaryHeap::from_vec_cmp_raw(sorted, PanickingComparator::new(n), false)
+                };
+                #[cfg(feature = "safe_only")]
+                let mut heap =
+                    BinaryHeap::from_vec_cmp_raw(sorted, PanickingComparator::new(n), false);
+                let extra = items(24..40);
+
+                let _ = catch_unwind(AssertUnwindSafe(|| op(&mut heap, extra, n)));
+                assert_eq!(drops.get() + heap.len(), 40, "{} panicking at {}", name, n);
+                // The heap must still be usable, whether or not the
+                // comparator has panicked yet.
+                let _ = catch_unwind(AssertUnwindSafe(|| while heap.pop().is_some() {}));
+                drop(heap);
+                assert_eq!(drops.get(), 40, "{} panicking at {}", name, n);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "fingerprint")]
+#[cfg(test)]
+mod tests_fingerprint {
+    use super::binary_heap::MinComparator;
+    use super::fingerprint::*;
+    use std::cell::Cell;
+    use std::cmp::Ordering;
+    use std::hash::{Hash, Hasher};
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    #[derive(Debug)]
+    struct Job(Cell<u32>);
+
+    impl Hash for Job {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            self.0.get().hash(state);
+        }
+    }
+
+    impl PartialEq for Job {
+        fn eq(&self, other: &Self) -> bool {
+            self.0.get() == other.0.get()
+        }
+    }
+
+    impl Eq for Job {}
+
+    impl PartialOrd for Job {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Job {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.0.get().cmp(&other.0.get())
+        }
+    }
+
+    #[test]
+    fn unchanged_elements_pass() {
+        let mut heap = FingerprintedBinaryHeap::with_cmp(MinComparator);
+        heap.extend(vec![5, 1, 4, 2, 3]);
+        heap.verify();
+        assert_eq!(heap.peek(), Some(&1));
+        let popped: Vec<_> = std::iter::from_fn(|| heap.pop()).collect();
+        assert_eq!(popped, [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn catches_an_element_changed_in_place() {
+        let mut jobs = FingerprintedBinaryHeap::new();
+        jobs.extend((0..10).map(|i| Job(Cell::new(i))));
+        jobs.peek().unwrap().0.set(0);
+        let message = catch_unwind(AssertUnwindSafe(|| jobs.verify()))
+            .unwrap_err()
+            .downcast::<String>()
+            .unwrap();
+        assert!(message.contains("Job(Cell { value: 0 })"), "{}", message);
+        assert!(catch_unwind(AssertUnwindSafe(|| jobs.pop())).is_err());
+        assert_eq!(jobs.len(), 9);
+    }
+}
+
+#[cfg(feature = "shadow")]
+#[cfg(test)]
+mod tests_shadow {
+    use super::binary_heap::*;
+    use super::shadow::*;
+    use compare::Compare;
+    use std::cell::Cell;
+    use std::cmp::Ordering;
+    use std::rc::Rc;
+
+    #[test]
+    fn agrees_with_model() {
+        let mut heap = ShadowBinaryHeap::with_cmp(MinComparator);
+        heap.extend(vec![5, 1, 4, 1]);
+        heap.append(&mut BinaryHeap::from_vec_cmp(vec![3, 2], MinComparator));
+        assert_eq!(heap.pop(), Some(1));
+        assert_eq!(heap.peek(), Some(&1));
+        assert_eq!(heap.trace().len(), 6);
+        assert_eq!(heap.into_sorted_vec(), [5, 4, 3, 2, 1]);
+    }
+
+    /// Orders integers normally, or in reverse once `flipped` is set.
+    #[derive(Clone)]
+    struct Fickle(Rc<Cell<bool>>);
+
+    impl Compare<i32> for Fickle {
+        fn compare(&self, a: &i32, b: &i32) -> Ordering {
+            if self.0.get() {
+                b.cmp(a)
+            } else {
+                a.cmp(b)
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "heap diverged from its model: top is 0, expected 2\n\
+                               operations:\n  \
+                               push(0)\n  push(1)\n  push(2)\n  push(3)\n  \
+                               pop() -> Some(3)")]
+    fn inconsistent_comparator_is_caught() {
+        let flipped = Rc::new(Cell::new(false));
+        let mut heap = ShadowBinaryHeap::with_cmp(Fickle(Rc::clone(&flipped)));
+        heap.extend(0..4);
+        flipped.set(true);
+        while heap.pop().is_some() {}
+    }
+}
+
+#[cfg(test)]
+mod tests_soa {
+    use super::binary_heap::{BinaryHeap, MinComparator};
+    use super::soa::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn matches_binary_heap() {
+        let mut rng = StdRng::seed_from_u64(31);
+        let mut soa = SoaBinaryHeap::with_cmp(MinComparator);
+        let mut aos = BinaryHeap::new_min();
+        for i in 0..2000u32 {
+            if rng.gen_range(0..3) == 0 {
+                assert_eq!(soa.pop().map(|(p, _)| p), aos.pop().map(|(p, _)| p));
+            } else {
+                let priority = rng.gen_range(0..100u32);
+                soa.push(priority, i);
+                aos.push((priority, i));
+            }
+            assert_eq!(soa.peek_priority(), aos.peek().map(|(p, _)| p));
+        }
+        while let Some((priority, value)) = soa.pop() {
+            assert_eq!(aos.pop().map(|(p, _)| p), Some(priority));
+            assert!(value < 2000);
+        }
+        assert!(aos.is_empty());
+    }
+
+    #[test]
+    fn values_follow_their_priorities() {
+        let heap: SoaBinaryHeap<u8, String> = (0..50u8).map(|i| (i, i.to_string())).collect();
+        assert_eq!(heap.len(), 50);
+        for (priority, value) in heap.iter() {
+            assert_eq!(*value, priority.to_string());
+        }
+        let (priorities, values) = heap.into_vecs();
+        let heap = SoaBinaryHeap::from_vecs_cmp(priorities, values, MinComparator);
+        assert_eq!(heap.peek(), Some((&0, &"0".to_string())));
+    }
+
+    #[test]
+    fn panicking_comparator_keeps_pairs_together() {
+        use std::cell::Cell;
+        use std::panic::{self, AssertUnwindSafe};
+
+        let calls = Cell::new(0);
+        let cmp = |a: &u32, b: &u32| {
+            calls.set(calls.get() + 1);
+            assert!(calls.get() % 7 != 0, "comparator panicked");
+            a.cmp(b)
+        };
+        let mut heap = SoaBinaryHeap::with_cmp(cmp);
+        for i in (0..40u32).map(|i| i * 17 % 40) {
+            let _ = panic::catch_unwind(AssertUnwindSafe(|| heap.push(i, i.to_string())));
+        }
+        for _ in 0..10 {
+            let _ = panic::catch_unwind(AssertUnwindSafe(|| heap.pop()));
+        }
+        assert_eq!(heap.priorities().len(), heap.values().len());
+        for (priority, value) in heap.iter() {
+            assert_eq!(*value, priority.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_sort_spec {
+    use super::binary_heap::BinaryHeap;
+    use super::sort_spec::*;
+    use compare::Compare;
+    use std::cmp::Ordering;
+
+    type Row = (u8, i32, String);
+
+    fn keys() -> SortKeys<Row> {
+        let mut keys = SortKeys::new();
+        keys.register("a", |r: &Row| r.0)
+            .register("b", |r: &Row| r.1)
+            .register_by("c", |x: &Row, y: &Row| x.2.len().cmp(&y.2.len()));
+        keys
+    }
+
+    #[test]
+    fn parsed_spec_matches_the_builder() {
+        let rows: Vec<Row> = (0..60)
+            .map(|i| ((i % 3) as u8, i % 7 - 3, "x".repeat((i % 4) as usize)))
+            .collect();
+        let parsed = keys().parse(" -a ,b,+ c").unwrap();
+        let built = SortSpec::new()
+            .desc(|r: &Row| r.0)
+            .asc(|r: &Row| r.1)
+            .asc_by(|x: &Row, y: &Row| x.2.len().cmp(&y.2.len()));
+        for x in &rows {
+            for y in &rows {
+                assert_eq!(parsed.compare(x, y), built.compare(x, y));
+            }
+        }
+        assert_eq!(format!("{:?}", parsed), "[-a, +b, +c]");
+        assert_eq!(format!("{:?}", built), "[-<fn>, +<fn>, +<fn>]");
+
+        let mut heap = BinaryHeap::from_vec_cmp(rows, parsed);
+        let mut last = heap.pop().unwrap();
+        while let Some(row) = heap.pop() {
+            assert_ne!(heap.cmp().compare(&row, &last), Ordering::Greater);
+            last = row;
+        }
+    }
+
+    #[test]
+    fn parse_errors() {
+        let keys = keys();
+        assert_eq!(keys.parse("a,,b").unwrap_err(), SortSpecError::EmptyKey);
+        assert_eq!(keys.parse("-").unwrap_err(), SortSpecError::EmptyKey);
+        assert_eq!(
+            keys.parse("+a,-d").unwrap_err(),
+            SortSpecError::UnknownKey("d".to_string())
+        );
+        assert_eq!(
+            keys.parse("-d").unwrap_err().to_string(),
+            "unknown sort key `d`"
+        );
+        assert!(SortSpec::<Row>::new().is_empty());
+    }
+}
+
+#[cfg(feature = "spill")]
+#[cfg(test)]
+mod tests_spill {
+    use super::binary_heap::MinComparator;
+    use super::differential::*;
+    use super::spill::*;
+
+    #[test]
+    fn pops_match_binary_heap_across_runs() {
+        let mut heap = SpillingHeap::with_threshold_cmp(8, MinComparator);
+        check_against(
+            &mut heap,
+            MinComparator,
+            interleaved(500, 5),
+            |h, x| h.push(x).unwrap(),
+            |h| h.pop().unwrap(),
+            |h| h.len(),
+        );
+        assert_eq!(heap.runs(), 0);
+    }
+
+    #[test]
+    fn keeps_at_most_threshold_in_memory() {
+        let mut heap = SpillingHeap::with_threshold_cmp(4, MinComparator);
+        for x in (0..30u32).rev() {
+            heap.push(x).unwrap();
+            assert!(heap.in_memory_len() <= 4);
+        }
+        assert!(heap.runs() > 1);
+        for x in 0..30 {
+            assert_eq!(heap.peek(), Some(&x));
+            assert_eq!(heap.pop().unwrap(), Some(x));
+        }
+        assert_eq!(heap.runs(), 0);
+    }
+
+    #[test]
+    fn peek_sees_spilled_elements() {
+        let mut heap = SpillingHeap::with_threshold(2);
+        for s in ["b", "c", "a"] {
+            heap.push(s.to_string()).unwrap();
+        }
+        assert_eq!(heap.runs(), 1);
+        assert_eq!(heap.peek().map(String::as_str), Some("c"));
+        heap.clear();
+        assert!(heap.is_empty());
+        assert_eq!(heap.pop().unwrap(), None);
+    }
+}
+
+#[cfg(test)]
+mod tests_steal {
+    use super::binary_heap::MinComparator;
+    use super::steal::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn idle_worker_steals_best_top() {
+        let pool = WorkStealingPriorityPool::with_cmp(3, MinComparator);
+        pool.push(0, 5);
+        pool.push(0, 9);
+        pool.push(1, 2);
+        pool.push(1, 7);
+        assert_eq!(pool.local_len(2), 0);
+        // Worker 2 has nothing: it takes 2, the best of the tops 5 and 2.
+        assert_eq!(pool.pop(2), Some(2));
+        // Worker 0 prefers its own items.
+        assert_eq!(pool.pop(0), Some(5));
+        assert_eq!(pool.len(), 2);
+    }
+
+    #[test]
+    fn concurrent_workers_lose_nothing() {
+        let pool = Arc::new(WorkStealingPriorityPool::new(4));
+        let workers: Vec<_> = (0..4)
+            .map(|w| {
+                let pool = Arc::clone(&pool);
+                thread::spawn(move || {
+                    let mut popped = Vec::new();
+                    // Only even workers produce; odd workers live off stealing.
+                    for i in 0..1000 {
+                        if w % 2 == 0 {
+                            pool.push(w, w * 1000 + i);
+                        }
+                        popped.extend(pool.pop(w));
+                    }
+                    popped
+                })
+            })
+            .collect();
+        let mut all: Vec<_> = workers
+            .into_iter()
+            .flat_map(|w| w.join().unwrap())
+            .collect();
+        all.extend(std::iter::from_fn(|| pool.pop(0)));
+        all.sort_unstable();
+        let mut expected: Vec<_> = (0..1000).chain(2000..3000).collect();
+        expected.sort_unstable();
+        assert_eq!(all, expected);
+        assert!(pool.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod tests_sync {
+    use super::sync::*;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn pop_timeout_gives_up() {
+        let heap = SyncBinaryHeap::<i32>::new();
+        let start = Instant::now();
+        assert_eq!(heap.pop_timeout(Duration::from_millis(20)), None);
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        heap.push(4);
+        heap.push(9);
+        assert_eq!(heap.pop_timeout(Duration::from_secs(0)), Some(9));
+        assert_eq!(heap.try_pop(), Some(4));
+        assert_eq!(heap.try_pop(), None);
+    }
+
+    #[test]
+    fn waiting_consumers_receive_every_item() {
+        let heap = Arc::new(SyncBinaryHeap::new());
+        let consumers: Vec<_> = (0..4)
+            .map(|_| {
+                let heap = Arc::clone(&heap);
+                thread::spawn(move || (0..250).map(|_| heap.pop_wait()).collect::<Vec<_>>())
+            })
+            .collect();
+        for i in 0..1000 {
+            heap.push(i);
+        }
+        let mut all: Vec<_> = consumers
+            .into_iter()
+            .flat_map(|c| c.join().unwrap())
+            .collect();
+        all.sort_unstable();
+        assert_eq!(all, (0..1000).collect::<Vec<_>>());
+        assert!(heap.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod tests_keyed {
+    use super::binary_heap::MinComparator;
+    use super::keyed::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::collections::HashMap;
+
+    #[test]
+    fn matches_a_map() {
+        let mut queue = KeyedPriorityQueue::with_cmp(MinComparator);
+        let mut model: HashMap<u8, u32> = HashMap::new();
+        let mut rng = StdRng::seed_from_u64(23);
+        for _ in 0..3000 {
+            let key = rng.gen_range(0..40);
+            match rng.gen_range(0..5) {
+                0 => assert_eq!(queue.remove(&key), model.remove(&key)),
+                1 => {
+                    let priority = rng.gen_range(0..1000);
+                    assert_eq!(queue.push(key, priority), model.insert(key, priority));
+                }
+                2 => {
+                    let p = *queue
+                        .entry(key)
+                        .and_modify_priority(|p| *p /= 2)
+                        .or_insert(500);
+                    let expected = model.entry(key).and_modify(|p| *p /= 2).or_insert(500);
+                    assert_eq!(p, *expected);
+                }
+                3 => {
+                    if let Some((key, priority)) = queue.pop() {
+                        let least = model.values().min().copied();
+                        assert_eq!(Some(priority), least);
+                        assert_eq!(model.remove(&key), Some(priority));
+                    } else {
+                        assert!(model.is_empty());
+                    }
+                }
+                _ => assert_eq!(queue.priority(&key), model.get(&key)),
+            }
+            assert_eq!(queue.len(), model.len());
+        }
+    }
+
+    #[test]
+    fn entries() {
+        let mut queue = KeyedPriorityQueue::new();
+        queue.extend(vec![('a', 1), ('b', 2)]);
+
+        match queue.entry('a') {
+            Entry::Occupied(mut entry) => {
+                assert_eq!(*entry.priority(), 1);
+                assert_eq!(entry.set_priority(7), 1);
+                assert_eq!(*entry.priority(), 7);
+            }
+            Entry::Vacant(_) => panic!("'a' is in the queue"),
+        }
+        assert_eq!(queue.peek(), Some((&'a', &7)));
+
+        match queue.entry('c') {
+            Entry::Vacant(entry) => assert_eq!(entry.into_key(), 'c'),
+            Entry::Occupied(_) => panic!("'c' is not in the queue"),
+        }
+        assert!(!queue.contains_key(&'c'));
+
+        assert_eq!(queue.entry('b').remove(), Some(2));
+        assert_eq!(queue.entry('b').remove(), None);
+        assert_eq!(queue.entry('d').or_insert_with(|| 4), &4);
+        assert_eq!(queue.pop(), Some(('a', 7)));
+        assert_eq!(queue.pop(), Some(('d', 4)));
+        assert!(queue.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod tests_tracked {
+    use super::binary_heap::MinComparator;
+    use super::tracked::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn position_map_follows_every_operation() {
+        let mut heap = TrackedBinaryHeap::new(PositionMap::new(|&(_, id): &(u32, u32)| id));
+        let mut rng = StdRng::seed_from_u64(17);
+        let mut next_id = 0;
+        for _ in 0..2000 {
+            match rng.gen_range(0..4) {
+                0 | 1 => {
+                    heap.push((rng.gen_range(0..50), next_id));
+                    next_id += 1;
+                }
+                2 if !heap.is_empty() => {
+                    let i = rng.gen_range(0..heap.len());
+                    let id = heap.get(i).unwrap().1;
+                    heap.replace_at(i, (rng.gen_range(0..50), id));
+                }
+                3 if !heap.is_empty() => {
+                    let i = rng.gen_range(0..heap.len());
+                    heap.remove_at(i);
+                }
+                _ => {
+                    assert_eq!(heap.pop(), None);
+                }
+            }
+            let data = heap.as_slice();
+            assert!((1..data.len()).all(|i| data[i] <= data[(i - 1) / 2]));
+            assert_eq!(heap.tracker().map().len(), data.len());
+            for (i, &(_, id)) in data.iter().enumerate() {
+                assert_eq!(heap.tracker().index_of(&id), Some(i));
+            }
+        }
+        heap.clear();
+        assert!(heap.tracker().map().is_empty());
+    }
+
+    #[test]
+    fn on_move_reports_removals() {
+        let mut removed = Vec::new();
+        let mut heap = TrackedBinaryHeap::with_cmp(
+            MinComparator,
+            OnMove(|&x: &i32, i: Option<usize>| {
+                if i.is_none() {
+                    removed.push(x);
+                }
+            }),
+        );
+        heap.extend(vec![4, 2, 8, 6]);
+        assert_eq!(heap.pop(), Some(2));
+        assert_eq!(heap.replace_at(0, 7), 4);
+        heap.clear();
+        drop(heap);
+        removed[2..].sort_unstable();
+        assert_eq!(removed, [2, 4, 6, 7, 8]);
+    }
+}
+
+#[cfg(test)]
+mod tests_tuple_cmp {
+    use super::binary_heap::*;
+    use super::tuple_cmp::*;
+    use compare::Compare;
+    use std::cmp::Ordering;
+
+    #[test]
+    fn each_field_has_its_direction() {
+        let cmp = TupleCmp::<(Asc, Desc, Asc)>::new();
+        assert_eq!(cmp.compare(&(1, 0, 0), &(2, 0, 0)), Ordering::Greater);
+        assert_eq!(cmp.compare(&(1, 5, 0), &(1, 4, 9)), Ordering::Greater);
+        assert_eq!(cmp.compare(&(1, 5, 3), &(1, 5, 4)), Ordering::Greater);
+        assert_eq!(cmp.compare(&(1, 5, 3), &(1, 5, 3)), Ordering::Equal);
+
+        let items = vec![("b", 2u8), ("a", 2), ("b", 1), ("a", 1)];
+        let heap = BinaryHeap::from_vec_cmp(items, TupleCmp::<(Asc, Desc)>::new());
+        assert_eq!(
+            heap.into_iter_sorted().collect::<Vec<_>>(),
+            [("a", 2), ("a", 1), ("b", 2), ("b", 1)]
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests_wfq {
+    use super::wfq::*;
+
+    #[test]
+    fn cost_is_shared_by_weight() {
+        let mut wfq = WfqScheduler::new();
+        wfq.set_weight(1, 2);
+        for _ in 0..100 {
+            wfq.push(1, (), 10);
+            wfq.push(2, (), 5);
+        }
+        // Flow 1 sends items twice as expensive with twice the weight, so both
+        // flows are served at the same item rate.
+        let served: Vec<_> = (0..50).map(|_| wfq.pop().unwrap().0).collect();
+        let ones = served.iter().filter(|&&flow| flow == 1).count();
+        assert!((24..=26).contains(&ones), "flow 1 served {} times", ones);
+    }
+
+    #[test]
+    fn idle_flow_does_not_bank_credit() {
+        let mut wfq = WfqScheduler::new();
+        for i in 0..10 {
+            wfq.push('a', i, 1);
+        }
+        for _ in 0..5 {
+            wfq.pop();
+        }
+        // 'b' arrives late; it competes from the current virtual time instead
+        // of jumping ahead of all of 'a'.
+        wfq.push('b', 0, 1);
+        wfq.push('b', 1, 1);
+        let next: Vec<_> = (0..4).map(|_| wfq.pop().unwrap().0).collect();
+        assert_eq!(next, ['a', 'b', 'a', 'b']);
+        assert_eq!(wfq.active_flows(), 1);
+        assert_eq!(wfq.flow_len(&'a'), 3);
+    }
+}
+
+#[cfg(test)]
+mod tests_weak {
+    use super::binary_heap::MinComparator;
+    use super::weak::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn pop_skips_dropped_targets() {
+        let items: Vec<Arc<u32>> = (0..100).map(Arc::new).collect();
+        let mut heap = WeakHeap::with_cmp(MinComparator);
+        heap.extend(items.iter().rev());
+        let kept: Vec<Arc<u32>> = items.into_iter().filter(|x| **x % 3 == 0).collect();
+
+        assert_eq!(heap.peek().as_deref(), Some(&0));
+        assert_eq!(heap.live_len(), kept.len());
+        let popped: Vec<u32> = std::iter::from_fn(|| heap.pop()).map(|x| *x).collect();
+        let expected: Vec<u32> = kept.iter().map(|x| **x).collect();
+        assert_eq!(popped, expected);
+        assert!(heap.is_empty());
+    }
+
+    #[test]
+    fn push_compacts_dead_entries() {
+        let mut heap = WeakHeap::with_threshold(8);
+        let keep = Arc::new(0);
+        heap.push(&keep);
+        for i in 1..1000 {
+            heap.push(&Arc::new(i));
+            assert!(heap.len() <= 8);
+        }
+        assert_eq!(heap.live_len(), 1);
+        heap.purge();
+        assert_eq!(heap.len(), 1);
+        assert_eq!(heap.pop(), Some(keep));
+    }
+
+    #[test]
+    fn dropping_targets_mid_use_keeps_the_order() {
+        let mut items: Vec<Arc<u32>> = (0..64).map(|i| Arc::new(i * 37 % 64)).collect();
+        let mut heap = WeakHeap::new();
+        heap.extend(&items);
+        let mut last = u32::MAX;
+        while !items.is_empty() {
+            // Drop an arbitrary target between pops.
+            let i = items.len() * 5 / 7;
+            items.swap_remove(i);
+            if let Some(top) = heap.pop() {
+                assert!(*top <= last);
+                last = *top;
+                items.retain(|x| !Arc::ptr_eq(x, &top));
+            }
+        }
+        assert_eq!(heap.pop(), None);
+    }
+}
+
+#[cfg(test)]
+mod tests_wheel {
+    use super::wheel::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn horizon_saturates() {
+        let wheel: TimingWheel<()> = TimingWheel::new(Duration::from_millis(2), 500);
+        assert_eq!(wheel.horizon(), Duration::from_secs(1));
+        let wheel: TimingWheel<()> = TimingWheel::new(Duration::MAX / 2, 3);
+        assert_eq!(wheel.horizon(), Duration::MAX);
+    }
+
+    #[test]
+    fn expires_in_tick_order_across_wheel_and_overflow() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        let mut wheel = TimingWheel::with_origin(start, ms(1), 8);
+        for &t in &[30, 3, 12, 7, 100, 8, 9] {
+            wheel.schedule(start + ms(t), t);
+        }
+        assert_eq!(wheel.overflow_len(), 4);
+
+        // Advance in small steps so that overflow timers come due while the
+        // wheel is still turning.
+        let mut expired = Vec::new();
+        for now in (0..=40).step_by(5) {
+            expired.extend(wheel.advance(start + ms(now)));
+        }
+        assert_eq!(expired, [3, 7, 8, 9, 12, 30]);
+        expired.extend(wheel.advance(start + ms(10_000)));
+        assert_eq!(expired.last(), Some(&100));
+        assert!(wheel.is_empty());
+    }
+
+    #[test]
+    fn late_schedule_fires_on_next_advance() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        let mut wheel = TimingWheel::with_origin(start, ms(10), 4);
+        assert!(wheel.advance(start + ms(100)).is_empty());
+
+        wheel.schedule(start + ms(50), 'a');
+        wheel.schedule(start + ms(101), 'b');
+        assert_eq!(wheel.next_expiration(), Some(start + ms(100)));
+        assert_eq!(wheel.advance(start + ms(100)), ['a']);
+        assert_eq!(wheel.next_expiration(), Some(start + ms(110)));
+        assert!(wheel.advance(start + ms(109)).is_empty());
+        assert_eq!(wheel.advance(start + ms(110)), ['b']);
+    }
+
+    #[test]
+    fn cancel_and_reschedule_anywhere() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        let mut wheel = TimingWheel::with_origin(start, ms(1), 8);
+        wheel.advance(start + ms(10));
+        let overdue: Vec<_> = (0..3).map(|i| wheel.schedule(start, i)).collect();
+        let slot: Vec<_> = (10..13)
+            .map(|i| wheel.schedule(start + ms(15), i))
+            .collect();
+        let far: Vec<_> = (20..23)
+            .map(|i| wheel.schedule(start + ms(100), i))
+            .collect();
+        assert_eq!(wheel.len(), 9);
+
+        // Cancelling the first timer of a slot moves the last one in its
+        // place, whose handle must still work.
+        assert_eq!(wheel.cancel(overdue[0]), Some(0));
+        assert_eq!(wheel.cancel(slot[0]), Some(10));
+        assert_eq!(wheel.cancel(far[0]), Some(20));
+        assert_eq!(wheel.cancel(far[0]), None);
+        assert!(wheel.reschedule(overdue[2], start + ms(100)));
+        assert!(wheel.reschedule(slot[2], start + ms(12)));
+        assert!(wheel.reschedule(far[2], start));
+        assert_eq!(wheel.overflow_len(), 2);
+
+        assert_eq!(wheel.advance(start + ms(10)), [1, 22]);
+        assert!(!wheel.reschedule(far[2], start));
+        // Timers sharing a tick come out in no particular order.
+        let sorted = |mut v: Vec<i32>| {
+            v.sort_unstable();
+            v
+        };
+        assert_eq!(sorted(wheel.advance(start + ms(15))), [11, 12]);
+        assert_eq!(sorted(wheel.advance(start + ms(100))), [2, 21]);
+        assert!(wheel.is_empty());
+    }
+
+    #[test]
+    fn repeating_timers_follow_the_missed_ticks_policy() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        let run = |missed: MissedTicks, steps: &[u64]| {
+            let mut wheel = TimingWheel::with_origin(start, ms(1), 16);
+            wheel.set_missed_ticks(missed);
+            wheel.schedule_repeating(ms(10), ());
+            steps
+                .iter()
+                .map(|&t| {
+                    let fired = wheel.advance(start + ms(t)).len();
+                    (fired, wheel.next_expiration().unwrap() - start)
+                })
+                .collect::<Vec<_>>()
+        };
+        // The first deadline is at 10 ms; the advance at 35 ms is late.
+        let steps = [10, 35, 36, 37, 50];
+        assert_eq!(
+            run(MissedTicks::Burst, &steps),
+            [
+                (1, ms(20)),
+                (1, ms(35)),
+                (1, ms(40)),
+                (0, ms(40)),
+                (1, ms(50))
+            ]
+        );
+        assert_eq!(
+            run(MissedTicks::Delay, &steps),
+            [
+                (1, ms(20)),
+                (1, ms(45)),
+                (0, ms(45)),
+                (0, ms(45)),
+                (1, ms(60))
+            ]
+        );
+        assert_eq!(
+            run(MissedTicks::Skip, &steps),
+            [
+                (1, ms(20)),
+                (1, ms(40)),
+                (0, ms(40)),
+                (0, ms(40)),
+                (1, ms(60))
+            ]
+        );
+    }
+
+    #[test]
+    fn repeating_timer_can_be_moved_and_cancelled() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        let mut wheel = TimingWheel::with_origin(start, ms(1), 4);
+        let handle = wheel.schedule_repeating(ms(3), 'r');
+        assert_eq!(wheel.advance(start + ms(3)), ['r']);
+        // Moved into the overflow heap; the schedule continues from there.
+        assert!(wheel.reschedule(handle, start + ms(20)));
+        assert_eq!(wheel.overflow_len(), 1);
+        assert!(wheel.advance(start + ms(19)).is_empty());
+        assert_eq!(wheel.advance(start + ms(20)), ['r']);
+        assert_eq!(wheel.next_expiration(), Some(start + ms(23)));
+        assert_eq!(wheel.cancel(handle), Some('r'));
+        assert!(wheel.advance(start + ms(30)).is_empty());
+        assert!(!wheel.reschedule(handle, start));
+    }
+}
+
+#[cfg(feature = "zeroize")]
+#[cfg(test)]
+#[clippy::msrv = "1.60"]
+mod tests_zeroize {
+    use super::binary_heap::*;
+    use std::rc::Rc;
+    use zeroize::Zeroize;
+
+    /// Reads the spare capacity of `vec`, which must have been zeroed.
+    fn spare(vec: &mut Vec<u64>) -> Vec<u64> {
+        // SAFETY: zeroes are a valid `u64`.
+        vec.spare_capacity_mut()
+            .iter()
+            .map(|x| unsafe { x.assume_init() })
+            .collect()
+    }
+
+    #[test]
+    fn pop_wipes_vacated_slots() {
+        let mut heap = BinaryHeap::from(vec![0xaau64, 0xbb, 0xcc]);
+        assert_eq!(heap.pop(), Some(0xcc));
+        assert_eq!(heap.pop(), Some(0xbb));
+        let mut vec = heap.into_vec();
+        assert_eq!(vec, [0xaa]);
+        assert_eq!(spare(&mut vec), [0, 0]);
+    }
+
+    #[test]
+    fn drain_wipes_vacated_slots() {
+        let mut heap = BinaryHeap::from(vec![0xaau64, 0xbb, 0xcc, 0xdd]);
+        let mut drain = heap.drain();
+        assert!(drain.next().is_some());
+        assert!(drain.next_back().is_some());
+        drop(drain);
+        assert!(heap.is_empty());
+        assert_eq!(spare(&mut heap.into_vec()), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn zeroize_spare_capacity_wipes_batch_removals() {
+        let mut heap = BinaryHeap::from(vec![0xaau64, 0xbb, 0xcc]);
+        heap.batch_mut(|items| {
+            items.truncate(1);
+            true
+        });
+        heap.zeroize_spare_capacity();
+        let mut vec = heap.into_vec();
+        assert_eq!(vec.len(), 1);
+        assert_eq!(spare(&mut vec), [0, 0]);
+    }
+
+    #[test]
+    fn zeroize_empties_heap_and_iterators() {
+        let mut heap = BinaryHeap::from(vec![1u32, 2, 3]);
+        heap.zeroize();
+        assert!(heap.is_empty());
+        assert!(heap.capacity() >= 3);
+
+        let mut iter = BinaryHeap::from(vec![1u32, 2, 3]).into_iter();
+        iter.next();
+        iter.zeroize();
+        assert_eq!(iter.collect::<Vec<_>>(), [0, 0]);
+    }
+
+    #[test]
+    fn iterators_drop_the_items_not_yielded() {
+        let rc = Rc::new(());
+        let mut heap = BinaryHeap::new_by_key(|_: &Rc<()>| 0);
+        heap.extend(vec![Rc::clone(&rc); 4]);
+
+        let mut iter = heap.clone().into_iter();
+        drop(iter.next_back());
+        drop(iter);
+        assert_eq!(Rc::strong_count(&rc), 5);
+
+        heap.drain().next();
+        assert!(heap.is_empty());
+        assert_eq!(Rc::strong_count(&rc), 1);
+    }
+}
+
+#[cfg(feature = "serde")]
+#[cfg(test)]
+mod tests_serde {
+    use super::binary_heap::*;
+
+    #[test]
+    fn deserialized_same_small_vec() {
+        let heap = BinaryHeap::from(vec![1, 2, 3]);
+        let serialized = serde_json::to_string(&heap).unwrap();
+        let deserialized: BinaryHeap<i32> = serde_json::from_str(&serialized).unwrap();
+
+        let v0: Vec<_> = heap.into_iter().collect();
+        let v1: Vec<_> = deserialized.into_iter().collect();
+        assert_eq!(v0, v1);
+    }
+    #[test]
+    fn deserialized_same() {
+        let vec: Vec<i32> = (0..1000).collect();
+        let heap = BinaryHeap::from(vec);
+        let serialized = serde_json::to_string(&heap).unwrap();
+        let deserialized: BinaryHeap<i32> = serde_json::from_str(&serialized).unwrap();
+
+        let v0: Vec<_> = heap.into_iter().collect();
+        let v1: Vec<_> = deserialized.into_iter().collect();
+        assert_eq!(v0, v1);
+    }
+
+    #[test]
+    fn heap_seed_uses_the_given_comparator() {
+        use serde::de::DeserializeSeed;
+
+        let weights = [5, 1, 9, 3];
+        let by_weight = |a: &usize, b: &usize| weights[*a].cmp(&weights[*b]);
+        let heap = BinaryHeap::from_vec_cmp(vec![0, 1, 2, 3], by_weight);
+        let json = serde_json::to_string(heap.as_slice()).unwrap();
+
+        let mut de = serde_json::Deserializer::from_str(&json);
+        let heap = HeapSeed::new(by_weight).deserialize(&mut de).unwrap();
+        assert_eq!(heap.into_sorted_vec(), [1, 3, 0, 2]);
+
+        let mut de = serde_json::Deserializer::from_str(r#"{"data": []}"#);
+        assert!(HeapSeed::new(by_weight).deserialize(&mut de).is_err());
+    }
+
+    #[test]
+    fn deserialize_rebuilds_broken_order() {
+        let json = r#"{ "data": [1, 2, 3, 4, 5], "cmp": null }"#;
+        let heap: BinaryHeap<i32> = serde_json::from_str(json).unwrap();
+        assert_eq!(heap.peek(), Some(&5));
+        assert_eq!(heap.into_sorted_vec(), [1, 2, 3, 4, 5]);
+
+        let heap: BinaryHeap<i32, MinComparator> = serde_json::from_str(json).unwrap();
+        assert_eq!(heap.into_vec(), [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn serde_sorted_ignores_the_layout() {
+        #[derive(serde::Serialize, serde::Deserialize)]
+        struct Snapshot {
+            #[serde(with = "crate::serde_sorted")]
+            heap: BinaryHeap<i32, MinComparator>,
+        }
+
+        let items: Vec<i32> = (0..50).map(|i| (i * 7) % 50).collect();
+        let built = BinaryHeap::from_vec(items.clone());
+        let mut pushed = BinaryHeap::new_min();
+        pushed.extend(items.iter().rev());
+        assert_ne!(built.clone().into_vec(), pushed.clone().into_vec());
+
+        let a = serde_json::to_string(&Snapshot { heap: built }).unwrap();
+        let b = serde_json::to_string(&Snapshot { heap: pushed }).unwrap();
+        assert_eq!(a, b);
+        let sorted: Vec<String> = (0..50).rev().map(|i| i.to_string()).collect();
+        assert_eq!(
+            a,
+            format!(r#"{{"heap":{{"data":[{}],"cmp":null}}}}"#, sorted.join(","))
+        );
+
+        let snapshot: Snapshot = serde_json::from_str(&a).unwrap();
+        assert_eq!(snapshot.heap.peek(), Some(&0));
+    }
+
+    #[test]
+    fn deserialize_checked_rejects_broken_order() {
+        let valid = BinaryHeap::from((0..100).collect::<Vec<_>>());
+        let json = serde_json::to_string(&valid).unwrap();
+        let mut de = serde_json::Deserializer::from_str(&json);
+        let heap: BinaryHeap<i32> = deserialize_checked(&mut de).unwrap();
+        assert_eq!(heap.into_vec(), valid.into_vec());
+
+        let json = r#"{ "data": [5, 6, 1], "cmp": null }"#;
+        let mut de = serde_json::Deserializer::from_str(json);
+        let err = deserialize_checked::<_, i32, MaxComparator>(&mut de).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("the element at 1 is greater than its parent at 0"));
+    }
+}
+
+#[cfg(feature = "rkyv")]
+#[cfg(test)]
+mod tests_rkyv {
+    use super::*;
+    use rkyv::rancor::Error;
+    use rkyv::Archived;
+
+    type Archive = ArchivedBinaryHeap<Archived<i32>, MaxComparator>;
+
+    #[test]
+    fn archive_keeps_layout() {
+        let heap = BinaryHeap::from((0..100).collect::<Vec<_>>());
+        let bytes = rkyv::to_bytes::<Error>(&heap).unwrap();
+        let archived = rkyv::access::<Archive, Error>(&bytes).unwrap();
+        let layout: Vec<i32> = archived.iter().map(|x| x.to_native()).collect();
+        assert_eq!(layout, heap.as_slice());
+
+        let back: BinaryHeap<i32> = rkyv::deserialize::<_, Error>(archived).unwrap();
+        assert_eq!(back.into_vec(), heap.into_vec());
+    }
+
+    #[test]
+    fn access_rejects_broken_order() {
+        // SAFETY: the broken order is never read as a heap; it is only
+        //  archived.
+        let broken = unsafe { BinaryHeap::from_vec_cmp_raw(vec![5, 6, 1], MaxComparator, false) };
+        let bytes = rkyv::to_bytes::<Error>(&broken).unwrap();
+        let err = rkyv::access::<Archive, Error>(&bytes).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("the element at 1 is greater than its parent at 0"));
+
+        // SAFETY: the bytes were written by `to_bytes` for this type.
+        let archived = unsafe { rkyv::access_unchecked::<Archive>(&bytes) };
+        let heap: BinaryHeap<i32> = rkyv::deserialize::<_, Error>(archived).unwrap();
+        assert_eq!(heap.into_sorted_vec(), [1, 5, 6]);
+    }
+}
+
+#[cfg(all(feature = "schemars", feature = "serde"))]
+#[cfg(test)]
+mod tests_schemars {
+    use super::binary_heap::*;
+
+    #[test]
+    fn schema_matches_the_serialized_form() {
+        let schema = schemars::schema_for!(BinaryHeap<u32, MinComparator>);
+        let schema = schema.as_value();
+        assert_eq!(schema["type"], "object");
+        assert_eq!(schema["properties"]["data"]["type"], "array");
+        assert_eq!(schema["properties"]["data"]["items"]["type"], "integer");
+        assert_eq!(schema["properties"]["cmp"]["$ref"], "#/$defs/MinComparator");
+        assert_eq!(schema["$defs"]["MinComparator"]["type"], "null");
+
+        let heap = BinaryHeap::from_vec_cmp(vec![3u32, 1, 2], MinComparator);
+        let value = serde_json::to_value(&heap).unwrap();
+        let object = value.as_object().unwrap();
+        let properties = schema["properties"].as_object().unwrap();
+        assert!(object.keys().eq(properties.keys()));
+        assert!(value["cmp"].is_null());
+    }
+}
+
+#[cfg(feature = "borsh")]
+#[cfg(test)]
+mod tests_borsh {
+    use super::binary_heap::*;
+
+    #[test]
+    fn borsh_round_trip_keeps_layout() {
+        let heap = BinaryHeap::from_vec_cmp((0..100).collect::<Vec<_>>(), MinComparator);
+        let bytes = borsh::to_vec(&heap).unwrap();
+        assert_eq!(bytes, borsh::to_vec(heap.as_slice()).unwrap());
+
+        let back: BinaryHeap<i32, MinComparator> = borsh::from_slice(&bytes).unwrap();
+        assert_eq!(back.into_vec(), heap.into_vec());
+    }
+
+    #[test]
+    fn borsh_rebuilds_or_rejects_broken_order() {
+        let bytes = borsh::to_vec(&vec![5, 6, 1]).unwrap();
+        let heap: BinaryHeap<i32> = borsh::from_slice(&bytes).unwrap();
+        assert_eq!(heap.peek(), Some(&6));
+
+        let err = borsh_deserialize_checked::<_, i32, MaxComparator>(&mut &bytes[..]).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert!(err
+            .to_string()
+            .contains("the element at 1 is greater than its parent at 0"));
     }
 }
@@ -0,0 +1,186 @@
+//! A lock-free concurrent priority queue based on a skip list.
+//!
+//! Requires the `concurrent` feature.
+
+use crate::binary_heap::MaxComparator;
+use compare::Compare;
+use crossbeam_skiplist::SkipSet;
+use std::cmp::Ordering;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::atomic::{self, AtomicU64};
+
+/// An element of the skip list. The front of the list is the greatest
+/// element according to `C`; equal elements are ordered by insertion.
+struct Keyed<T, C> {
+    item: T,
+    seq: u64,
+    // `fn() -> C` keeps the key `Send` and `Sync` regardless of `C`.
+    cmp: PhantomData<fn() -> C>,
+}
+
+impl<T, C: Compare<T> + Default> Ord for Keyed<T, C> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        C::default()
+            .compare(&other.item, &self.item)
+            .then_with(|| self.seq.cmp(&other.seq))
+    }
+}
+
+impl<T, C: Compare<T> + Default> PartialOrd for Keyed<T, C> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T, C: Compare<T> + Default> PartialEq for Keyed<T, C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T, C: Compare<T> + Default> Eq for Keyed<T, C> {}
+
+/// A lock-free concurrent priority queue.
+///
+/// Elements are kept in a lock-free skip list ordered by the comparator, so
+/// that the greatest element is always at the front. [`push`] and [`pop`] are
+/// lock-free, and removed nodes are reclaimed with epoch-based garbage
+/// collection (`crossbeam-epoch`), so readers never observe freed memory.
+///
+/// Unlike [`ConcurrentBinaryHeap`], `pop` always removes the greatest element
+/// present in the queue. The comparator must be stateless, since it is
+/// instantiated with [`Default`] for every comparison, and popped elements
+/// are cloned out of the list because other threads may still be reading
+/// the removed node.
+///
+/// This type is available with the `concurrent` feature.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::{ConcurrentPriorityQueue, MinComparator};
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// let queue = Arc::new(ConcurrentPriorityQueue::<u32, MinComparator>::new());
+/// let handles: Vec<_> = (0..4)
+///     .map(|t| {
+///         let queue = Arc::clone(&queue);
+///         thread::spawn(move || {
+///             for i in 0..100 {
+///                 queue.push(t * 100 + i);
+///             }
+///         })
+///     })
+///     .collect();
+/// for h in handles {
+///     h.join().unwrap();
+/// }
+///
+/// assert_eq!(queue.pop(), Some(0));
+/// assert_eq!(queue.pop(), Some(1));
+/// assert_eq!(queue.len(), 398);
+/// ```
+///
+/// [`push`]: ConcurrentPriorityQueue::push
+/// [`pop`]: ConcurrentPriorityQueue::pop
+/// [`ConcurrentBinaryHeap`]: struct.ConcurrentBinaryHeap.html
+pub struct ConcurrentPriorityQueue<T, C = MaxComparator> {
+    list: SkipSet<Keyed<T, C>>,
+    next_seq: AtomicU64,
+}
+
+impl<T, C> fmt::Debug for ConcurrentPriorityQueue<T, C>
+where
+    T: fmt::Debug + Send + 'static,
+    C: Compare<T> + Default + 'static,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut list = f.debug_list();
+        for entry in self.list.iter() {
+            list.entry(&entry.value().item);
+        }
+        list.finish()
+    }
+}
+
+impl<T, C> Default for ConcurrentPriorityQueue<T, C>
+where
+    T: Send + 'static,
+    C: Compare<T> + Default + 'static,
+{
+    fn default() -> Self {
+        ConcurrentPriorityQueue::new()
+    }
+}
+
+impl<T, C> ConcurrentPriorityQueue<T, C>
+where
+    T: Send + 'static,
+    C: Compare<T> + Default + 'static,
+{
+    /// Creates an empty queue.
+    #[must_use]
+    pub fn new() -> Self {
+        ConcurrentPriorityQueue {
+            list: SkipSet::new(),
+            next_seq: AtomicU64::new(0),
+        }
+    }
+
+    /// Pushes an item onto the queue.
+    ///
+    /// # Time complexity
+    ///
+    /// Expected *O*(log(*n*)).
+    pub fn push(&self, item: T) {
+        let seq = self.next_seq.fetch_add(1, atomic::Ordering::Relaxed);
+        self.list.insert(Keyed {
+            item,
+            seq,
+            cmp: PhantomData,
+        });
+    }
+
+    /// Removes the greatest item from the queue and returns it, or `None` if
+    /// it is empty.
+    ///
+    /// Items that compare equal are popped in the order they were pushed.
+    ///
+    /// # Time complexity
+    ///
+    /// Expected *O*(1).
+    pub fn pop(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.list.pop_front().map(|e| e.value().item.clone())
+    }
+
+    /// Returns a clone of the greatest item, or `None` if the queue is empty.
+    #[must_use]
+    pub fn peek(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.list.front().map(|e| e.value().item.clone())
+    }
+
+    /// Returns the number of items in the queue.
+    ///
+    /// The value may be stale as soon as it is returned if other threads are
+    /// pushing or popping.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    /// Checks if the queue is empty. See [`len`] for caveats.
+    ///
+    /// [`len`]: ConcurrentPriorityQueue::len
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+}
@@ -0,0 +1,221 @@
+//! A multi-level feedback queue scheduler built on [`BinaryHeap`].
+//!
+//! [`BinaryHeap`]: struct.BinaryHeap.html
+
+use crate::binary_heap::BinaryHeap;
+use compare::Compare;
+use std::cmp::Ordering;
+use std::fmt;
+use std::mem;
+use std::time::Duration;
+
+/// A task handed out by an [`MlfqScheduler`].
+///
+/// [`MlfqScheduler`]: struct.MlfqScheduler.html
+#[derive(Clone, Debug)]
+pub struct MlfqTask<T> {
+    /// The payload supplied when the task was pushed.
+    pub value: T,
+    level: usize,
+    used: Duration,
+    seq: u64,
+}
+
+impl<T> MlfqTask<T> {
+    /// Returns the level the task was scheduled from; `0` is the highest.
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    /// Returns the run time the task has accumulated at its current level.
+    pub fn used(&self) -> Duration {
+        self.used
+    }
+
+    /// Consumes the task and returns its payload.
+    pub fn into_value(self) -> T {
+        self.value
+    }
+}
+
+/// Orders tasks by level (lowest first), then round robin within a level.
+#[derive(Clone, Copy, Default, Debug)]
+struct LevelOrder;
+
+impl<T> Compare<MlfqTask<T>> for LevelOrder {
+    fn compare(&self, a: &MlfqTask<T>, b: &MlfqTask<T>) -> Ordering {
+        b.level.cmp(&a.level).then_with(|| b.seq.cmp(&a.seq))
+    }
+}
+
+/// A multi-level feedback queue (MLFQ) scheduler.
+///
+/// Level `0` has the highest priority. Each level has a time allotment
+/// (quantum): a task that accumulates that much run time at a level, across
+/// any number of runs, is demoted to the next level when it is
+/// [requeued]. Tasks within a level are scheduled round robin. A periodic
+/// [`boost`] moves every task back to level `0` so that long-running tasks
+/// are not starved.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::MlfqScheduler;
+/// use std::time::Duration;
+///
+/// let ms = Duration::from_millis;
+/// let mut sched = MlfqScheduler::new(vec![ms(10), ms(20), ms(40)]);
+/// sched.push("batch", 0);
+/// sched.push("interactive", 0);
+///
+/// // "batch" uses its whole quantum and is demoted.
+/// let task = sched.pop_next().unwrap();
+/// assert_eq!(task.value, "batch");
+/// let quantum = sched.quantum(task.level());
+/// sched.requeue(task, quantum);
+///
+/// // "interactive" yields early and keeps its level.
+/// let task = sched.pop_next().unwrap();
+/// assert_eq!(task.value, "interactive");
+/// sched.requeue(task, ms(1));
+///
+/// assert_eq!(sched.pop_next().unwrap().value, "interactive");
+/// let task = sched.pop_next().unwrap();
+/// assert_eq!((task.value, task.level()), ("batch", 1));
+/// ```
+///
+/// [requeued]: MlfqScheduler::requeue
+/// [`boost`]: MlfqScheduler::boost
+pub struct MlfqScheduler<T> {
+    heap: BinaryHeap<MlfqTask<T>, LevelOrder>,
+    quanta: Vec<Duration>,
+    level_lens: Vec<usize>,
+    next_seq: u64,
+}
+
+impl<T: fmt::Debug> fmt::Debug for MlfqScheduler<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MlfqScheduler")
+            .field("quanta", &self.quanta)
+            .field("tasks", &self.heap)
+            .finish()
+    }
+}
+
+impl<T> MlfqScheduler<T> {
+    /// Creates a scheduler with one level per quantum, highest level first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `quanta` is empty.
+    pub fn new(quanta: Vec<Duration>) -> Self {
+        assert!(!quanta.is_empty(), "an MLFQ needs at least one level");
+        let levels = quanta.len();
+        MlfqScheduler {
+            heap: BinaryHeap::from_vec(vec![]),
+            quanta,
+            level_lens: vec![0; levels],
+            next_seq: 0,
+        }
+    }
+
+    /// Returns the number of levels.
+    #[must_use]
+    pub fn levels(&self) -> usize {
+        self.quanta.len()
+    }
+
+    /// Returns the time allotment of `level`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `level >= self.levels()`.
+    #[must_use]
+    pub fn quantum(&self, level: usize) -> Duration {
+        self.quanta[level]
+    }
+
+    /// Pushes a new task at `level`. Levels past the lowest are clamped to it.
+    pub fn push(&mut self, value: T, level: usize) {
+        let level = level.min(self.levels() - 1);
+        self.enqueue(MlfqTask {
+            value,
+            level,
+            used: Duration::from_secs(0),
+            seq: 0,
+        });
+    }
+
+    fn enqueue(&mut self, mut task: MlfqTask<T>) {
+        task.seq = self.next_seq;
+        self.next_seq += 1;
+        self.level_lens[task.level] += 1;
+        self.heap.push(task);
+    }
+
+    /// Removes the next task to run, or returns `None` if no task is queued.
+    ///
+    /// This is the oldest task of the highest non-empty level.
+    pub fn pop_next(&mut self) -> Option<MlfqTask<T>> {
+        let task = self.heap.pop()?;
+        self.level_lens[task.level] -= 1;
+        Some(task)
+    }
+
+    /// Puts a task that ran for `ran_for` back into the scheduler.
+    ///
+    /// The run time is added to the task's allotment usage at its level. Once
+    /// the usage reaches the level's quantum, the task is demoted one level
+    /// (unless it is already at the lowest) and its usage is reset. The task
+    /// joins the back of its level either way.
+    pub fn requeue(&mut self, mut task: MlfqTask<T>, ran_for: Duration) {
+        task.used += ran_for;
+        if task.used >= self.quanta[task.level] && task.level + 1 < self.levels() {
+            task.level += 1;
+            task.used = Duration::from_secs(0);
+        }
+        self.enqueue(task);
+    }
+
+    /// Moves every queued task to level `0` and resets its allotment usage.
+    ///
+    /// Tasks keep their relative order. Call this periodically to guarantee
+    /// that demoted tasks make progress.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*n* * log(*n*)) on a scheduler holding *n* tasks.
+    pub fn boost(&mut self) {
+        let heap = mem::replace(&mut self.heap, BinaryHeap::from_vec(vec![]));
+        for len in &mut self.level_lens {
+            *len = 0;
+        }
+        for mut task in heap.into_iter_sorted() {
+            task.level = 0;
+            task.used = Duration::from_secs(0);
+            self.enqueue(task);
+        }
+    }
+
+    /// Returns the number of tasks queued at `level`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `level >= self.levels()`.
+    #[must_use]
+    pub fn level_len(&self, level: usize) -> usize {
+        self.level_lens[level]
+    }
+
+    /// Returns the total number of queued tasks.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Checks if no task is queued.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+}
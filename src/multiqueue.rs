@@ -0,0 +1,240 @@
+//! A thread-safe heap with relaxed ordering that scales with the threads.
+
+use crate::binary_heap::{BinaryHeap, MaxComparator};
+use crate::util::{lock, splitmix64, try_lock};
+use compare::Compare;
+use std::cell::Cell;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+const DEFAULT_QUEUES: usize = 8;
+
+thread_local! {
+    static RNG: Cell<u64> = Cell::new(RandomState::new().build_hasher().finish());
+}
+
+/// Returns a random index below `n`, which must not be zero.
+fn random_below(n: usize) -> usize {
+    RNG.with(|rng| {
+        let mut state = rng.get();
+        let z = splitmix64(&mut state);
+        rng.set(state);
+        (z % n as u64) as usize
+    })
+}
+
+/// A thread-safe priority queue with relaxed ordering, made of several
+/// [`BinaryHeap`]s each guarded by its own lock.
+///
+/// `push` adds the item to a random heap, and `pop` removes the greater top
+/// of two random heaps. Neither waits for a lock held by another thread
+/// while another heap can be tried instead, so threads rarely contend.
+/// In exchange, `pop` returns *an* element of high priority rather than the
+/// greatest: with *k* heaps, the rank of the popped element is *O*(*k*) on
+/// average. This is the MultiQueue of Rihani, Sanders and Dementiev,
+/// "MultiQueues: Simple Relaxed Concurrent Priority Queues", 2015. About
+/// twice as many heaps as threads is a good start.
+///
+/// Use [`ConcurrentBinaryHeap`] where the greatest element must come first.
+///
+/// A comparator that panics leaves the affected heap valid; later
+/// operations ignore the resulting lock poisoning.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::MultiQueue;
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// let queue = Arc::new(MultiQueue::with_queues(4));
+/// let producers: Vec<_> = (0..4)
+///     .map(|t| {
+///         let queue = Arc::clone(&queue);
+///         thread::spawn(move || {
+///             for i in 0..100 {
+///                 queue.push(t * 100 + i);
+///             }
+///         })
+///     })
+///     .collect();
+/// for p in producers {
+///     p.join().unwrap();
+/// }
+///
+/// assert_eq!(queue.len(), 400);
+/// // One of the tops, each the greatest of its own heap.
+/// assert!(queue.pop().unwrap() >= 100);
+/// ```
+///
+/// [`BinaryHeap`]: struct.BinaryHeap.html
+/// [`ConcurrentBinaryHeap`]: struct.ConcurrentBinaryHeap.html
+pub struct MultiQueue<T, C = MaxComparator> {
+    queues: Vec<Mutex<BinaryHeap<T, C>>>,
+    cmp: C,
+    len: AtomicUsize,
+}
+
+impl<T: fmt::Debug, C> fmt::Debug for MultiQueue<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MultiQueue")
+            .field("queues", &self.queues)
+            .finish()
+    }
+}
+
+impl<T: Ord> Default for MultiQueue<T> {
+    fn default() -> Self {
+        MultiQueue::new()
+    }
+}
+
+impl<T: Ord> MultiQueue<T> {
+    /// Creates an empty max-queue with the default number of heaps.
+    #[must_use]
+    pub fn new() -> Self {
+        MultiQueue::with_queues(DEFAULT_QUEUES)
+    }
+
+    /// Creates an empty max-queue with `queues` heaps.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `queues` is zero.
+    #[must_use]
+    pub fn with_queues(queues: usize) -> Self {
+        MultiQueue::with_queues_cmp(queues, MaxComparator)
+    }
+}
+
+impl<T, C: Compare<T> + Clone> MultiQueue<T, C> {
+    /// Creates an empty queue with `queues` heaps ordered by `cmp`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `queues` is zero.
+    pub fn with_queues_cmp(queues: usize, cmp: C) -> Self {
+        assert!(queues > 0, "a multiqueue needs at least one heap");
+        MultiQueue {
+            queues: (0..queues)
+                .map(|_| Mutex::new(BinaryHeap::from_vec_cmp(vec![], cmp.clone())))
+                .collect(),
+            cmp,
+            len: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl<T, C: Compare<T>> MultiQueue<T, C> {
+    /// Pushes an item onto a random heap.
+    ///
+    /// A heap that is currently locked is passed over for another random
+    /// one; after as many tries as there are heaps, the last one is waited
+    /// for.
+    pub fn push(&self, item: T) {
+        let k = self.queues.len();
+        for _ in 1..k {
+            if let Some(mut queue) = try_lock(&self.queues[random_below(k)]) {
+                return self.push_locked(&mut queue, item);
+            }
+        }
+        self.push_locked(&mut lock(&self.queues[random_below(k)]), item);
+    }
+
+    fn push_locked(&self, queue: &mut BinaryHeap<T, C>, item: T) {
+        queue.push(item);
+        self.len.fetch_add(1, Ordering::Release);
+    }
+
+    /// Removes the greater top of two random heaps and returns it, or `None`
+    /// if the queue is empty.
+    ///
+    /// Heaps that are locked or empty are passed over for other random
+    /// ones. After as many tries as there are heaps, `pop` visits every heap
+    /// in turn, waiting for their locks, and removes the top of the first
+    /// that is not empty.
+    pub fn pop(&self) -> Option<T> {
+        let k = self.queues.len();
+        for _ in 0..k {
+            if self.is_empty() {
+                return None;
+            }
+            let a = random_below(k);
+            let mut best = try_lock(&self.queues[a]).filter(|q| !q.is_empty());
+            if k > 1 {
+                let mut b = random_below(k - 1);
+                if b >= a {
+                    b += 1;
+                }
+                // Only `try_lock` is used while holding a lock, so two pops
+                // locking the same heaps in opposite orders cannot deadlock.
+                if let Some(other) = try_lock(&self.queues[b]) {
+                    let better = match (best.as_ref().and_then(|q| q.peek()), other.peek()) {
+                        (_, None) => false,
+                        (None, Some(_)) => true,
+                        (Some(current), Some(top)) => self.cmp.compares_gt(top, current),
+                    };
+                    if better {
+                        best = Some(other);
+                    }
+                }
+            }
+            if let Some(mut queue) = best {
+                return self.pop_locked(&mut queue);
+            }
+        }
+        let start = random_below(k);
+        for i in 0..k {
+            let mut queue = lock(&self.queues[(start + i) % k]);
+            if !queue.is_empty() {
+                return self.pop_locked(&mut queue);
+            }
+        }
+        None
+    }
+
+    fn pop_locked(&self, queue: &mut BinaryHeap<T, C>) -> Option<T> {
+        let item = queue.pop();
+        if item.is_some() {
+            self.len.fetch_sub(1, Ordering::Release);
+        }
+        item
+    }
+
+    /// Returns the number of items in the queue.
+    ///
+    /// The value may be stale as soon as it is returned if other threads are
+    /// pushing or popping.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Acquire)
+    }
+
+    /// Checks if the queue is empty. See [`len`] for caveats.
+    ///
+    /// [`len`]: MultiQueue::len
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of heaps.
+    #[must_use]
+    pub fn queues(&self) -> usize {
+        self.queues.len()
+    }
+
+    /// Consumes the queue and merges the heaps into a single `BinaryHeap`,
+    /// which pops in exact order.
+    pub fn into_heap(self) -> BinaryHeap<T, C> {
+        let mut heap = BinaryHeap::from_vec_cmp(vec![], self.cmp);
+        for queue in self.queues {
+            let mut queue = queue.into_inner().unwrap_or_else(|e| e.into_inner());
+            heap.append(&mut queue);
+        }
+        heap
+    }
+}
@@ -0,0 +1,60 @@
+//! The heap types, comparators and traits, for glob import.
+//!
+//! Besides the items of this crate, the prelude re-exports the [`Compare`]
+//! trait of the `compare` crate, which bounds the comparator of every heap,
+//! so generic code needs no direct dependency on `compare`.
+//!
+//! # Examples
+//!
+//! ```
+//! use binary_heap_plus::prelude::*;
+//!
+//! fn top<T: Clone, C: Compare<T>>(heap: &BinaryHeap<T, C>) -> Option<T> {
+//!     heap.peek().cloned()
+//! }
+//!
+//! let heap: MinHeap<u32> = BinaryHeap::from_vec(vec![3, 1, 2]);
+//! assert_eq!(top(&heap), Some(1));
+//! ```
+//!
+//! [`Compare`]: https://docs.rs/compare/0.1/compare/trait.Compare.html
+
+pub use compare::Compare;
+
+pub use crate::array::ArrayHeap;
+pub use crate::binary_heap::{
+    BinaryHeap, FnComparator, HeapBy, HeapByKey, HeapBySortKey, KeyComparator, MaxComparator,
+    MaxHeap, MinComparator, MinFnComparator, MinHeap, MinHeapBy, MinHeapByKey, MinKeyComparator,
+    SortKeyComparator,
+};
+pub use crate::blocked::BlockedBinaryHeap;
+pub use crate::bloom::BloomBinaryHeap;
+pub use crate::budget::BudgetedHeap;
+pub use crate::context::{ContextBinaryHeap, ContextCompare};
+pub use crate::cow::CowHeap;
+pub use crate::delay::{DelayQueue, Timer};
+#[cfg(feature = "fingerprint")]
+pub use crate::fingerprint::FingerprintedBinaryHeap;
+pub use crate::grouped::GroupedTopK;
+pub use crate::hooks::{HeapHooks, HookedBinaryHeap};
+pub use crate::hybrid::HybridBinaryHeap;
+#[cfg(feature = "journal")]
+pub use crate::journal::JournaledBinaryHeap;
+pub use crate::keyed::KeyedPriorityQueue;
+pub use crate::lazy::LazyBinaryHeap;
+pub use crate::multiqueue::MultiQueue;
+pub use crate::queue::{IndexedPriorityQueue, MeldablePriorityQueue, PriorityQueue};
+pub use crate::segmented::SegmentedBinaryHeap;
+#[cfg(feature = "shadow")]
+pub use crate::shadow::ShadowBinaryHeap;
+pub use crate::sharded::ConcurrentBinaryHeap;
+#[cfg(feature = "smallvec")]
+pub use crate::small::SmallBinaryHeap;
+pub use crate::soa::SoaBinaryHeap;
+pub use crate::sort_spec::{SortKeys, SortSpec};
+#[cfg(feature = "spill")]
+pub use crate::spill::SpillingHeap;
+pub use crate::sync::SyncBinaryHeap;
+pub use crate::tracked::{OnMove, PositionMap, PositionTracker, TrackedBinaryHeap};
+pub use crate::tuple_cmp::{Asc, Desc, TupleCmp};
+pub use crate::weak::WeakHeap;
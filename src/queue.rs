@@ -0,0 +1,144 @@
+//! Traits for writing algorithms generically over the heap types.
+
+use crate::binary_heap::BinaryHeap;
+use compare::Compare;
+
+/// The operations shared by the heaps of this crate.
+///
+/// Write an algorithm against `PriorityQueue` to run it on any heap, for
+/// instance to benchmark [`BinaryHeap`] against [`BlockedBinaryHeap`] by
+/// swapping the type. Capabilities that only some heaps have are in the
+/// [`MeldablePriorityQueue`] and [`IndexedPriorityQueue`] extensions.
+///
+/// Heaps whose operations have other signatures do not implement this
+/// trait: [`ArrayHeap::push`] can fail, [`LazyBinaryHeap::peek`] needs
+/// `&mut self` and [`SpillingHeap`] does I/O.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::{BinaryHeap, HybridBinaryHeap, PriorityQueue};
+///
+/// fn k_smallest<Q: PriorityQueue<u32>>(mut queue: Q, items: &[u32], k: usize) -> Vec<u32> {
+///     for &x in items {
+///         queue.push(x);
+///         if queue.len() > k {
+///             queue.pop();
+///         }
+///     }
+///     let mut out: Vec<u32> = std::iter::from_fn(|| queue.pop()).collect();
+///     out.reverse();
+///     out
+/// }
+///
+/// let items = [5, 1, 4, 2, 3];
+/// assert_eq!(k_smallest(BinaryHeap::new(), &items, 3), [1, 2, 3]);
+/// assert_eq!(k_smallest(HybridBinaryHeap::new(), &items, 3), [1, 2, 3]);
+/// ```
+///
+/// [`BinaryHeap`]: struct.BinaryHeap.html
+/// [`BlockedBinaryHeap`]: struct.BlockedBinaryHeap.html
+/// [`MeldablePriorityQueue`]: trait.MeldablePriorityQueue.html
+/// [`IndexedPriorityQueue`]: trait.IndexedPriorityQueue.html
+/// [`ArrayHeap::push`]: struct.ArrayHeap.html#method.push
+/// [`LazyBinaryHeap::peek`]: struct.LazyBinaryHeap.html#method.peek
+/// [`SpillingHeap`]: struct.SpillingHeap.html
+pub trait PriorityQueue<T> {
+    /// Pushes an item onto the queue.
+    fn push(&mut self, item: T);
+
+    /// Removes the greatest item and returns it, or `None` if the queue is
+    /// empty.
+    fn pop(&mut self) -> Option<T>;
+
+    /// Returns the greatest item, or `None` if the queue is empty.
+    fn peek(&self) -> Option<&T>;
+
+    /// Returns the number of items in the queue.
+    fn len(&self) -> usize;
+
+    /// Checks if the queue is empty.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A [`PriorityQueue`] that can absorb another queue of the same type faster
+/// than by pushing its items one by one.
+///
+/// [`PriorityQueue`]: trait.PriorityQueue.html
+pub trait MeldablePriorityQueue<T>: PriorityQueue<T> {
+    /// Moves all the items of `other` into the queue, leaving `other` empty.
+    fn meld(&mut self, other: &mut Self);
+}
+
+/// A [`PriorityQueue`] whose items can be changed in place, given their
+/// index, which generalizes decrease-key.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::{BinaryHeap, IndexedPriorityQueue, PriorityQueue};
+///
+/// fn lower<Q: IndexedPriorityQueue<u32>>(queue: &mut Q, from: u32, to: u32) {
+///     if let Some(i) = queue.as_slice().iter().position(|&x| x == from) {
+///         queue.replace_at(i, to);
+///     }
+/// }
+///
+/// let mut heap = BinaryHeap::from(vec![7, 3, 5]);
+/// lower(&mut heap, 7, 1);
+/// assert_eq!(PriorityQueue::peek(&heap), Some(&5));
+/// ```
+///
+/// [`PriorityQueue`]: trait.PriorityQueue.html
+pub trait IndexedPriorityQueue<T>: PriorityQueue<T> {
+    /// Returns the items in the order of their indices.
+    fn as_slice(&self) -> &[T];
+
+    /// Replaces the item at `index`, moves the new one into place and returns
+    /// the old one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    fn replace_at(&mut self, index: usize, item: T) -> T;
+}
+
+impl<T, C: Compare<T>> PriorityQueue<T> for BinaryHeap<T, C> {
+    fn push(&mut self, item: T) {
+        BinaryHeap::push(self, item);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        BinaryHeap::pop(self)
+    }
+
+    fn peek(&self) -> Option<&T> {
+        BinaryHeap::peek(self)
+    }
+
+    fn len(&self) -> usize {
+        BinaryHeap::len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        BinaryHeap::is_empty(self)
+    }
+}
+
+impl<T, C: Compare<T>> MeldablePriorityQueue<T> for BinaryHeap<T, C> {
+    fn meld(&mut self, other: &mut Self) {
+        self.append(other);
+    }
+}
+
+impl<T, C: Compare<T>> IndexedPriorityQueue<T> for BinaryHeap<T, C> {
+    fn as_slice(&self) -> &[T] {
+        BinaryHeap::as_slice(self)
+    }
+
+    fn replace_at(&mut self, index: usize, item: T) -> T {
+        BinaryHeap::replace_at(self, index, item)
+    }
+}
@@ -0,0 +1,293 @@
+//! A retry queue with exponential backoff built on [`BinaryHeap`].
+//!
+//! [`BinaryHeap`]: struct.BinaryHeap.html
+
+use crate::binary_heap::BinaryHeap;
+use crate::util::splitmix64;
+use compare::Compare;
+use std::cmp::Ordering;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, Instant};
+
+/// Backoff and retry limits used by a [`RetryQueue`].
+///
+/// The delay before attempt *k* (counting the first retry as attempt 1) is
+/// `initial * multiplier^(k - 1)`, capped at `max_delay`. With a `jitter` of
+/// *j*, each delay is then scaled by a random factor in `(1 - j, 1]`.
+///
+/// [`RetryQueue`]: struct.RetryQueue.html
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RetryPolicy {
+    /// Delay before the first retry.
+    pub initial: Duration,
+    /// Growth factor of the delay between consecutive retries.
+    pub multiplier: u32,
+    /// Upper bound of the delay before jitter is applied.
+    pub max_delay: Duration,
+    /// Maximum number of retries of a single item.
+    pub max_retries: u32,
+    /// Fraction of each delay that is randomized, between `0.0` and `1.0`.
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    /// 100ms initial delay doubling up to 30s, 5 retries and 50% jitter.
+    fn default() -> Self {
+        RetryPolicy {
+            initial: Duration::from_millis(100),
+            multiplier: 2,
+            max_delay: Duration::from_secs(30),
+            max_retries: 5,
+            jitter: 0.5,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Returns the delay before retry `attempt`, without jitter.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use binary_heap_plus::RetryPolicy;
+    /// use std::time::Duration;
+    ///
+    /// let policy = RetryPolicy {
+    ///     initial: Duration::from_secs(1),
+    ///     max_delay: Duration::from_secs(5),
+    ///     ..RetryPolicy::default()
+    /// };
+    /// assert_eq!(policy.delay(1), Duration::from_secs(1));
+    /// assert_eq!(policy.delay(3), Duration::from_secs(4));
+    /// assert_eq!(policy.delay(4), Duration::from_secs(5));
+    /// ```
+    #[must_use]
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let mut delay = self.initial;
+        for _ in 1..attempt {
+            if delay >= self.max_delay {
+                break;
+            }
+            delay = delay.checked_mul(self.multiplier).unwrap_or(self.max_delay);
+        }
+        delay.min(self.max_delay)
+    }
+}
+
+/// An item handed out by a [`RetryQueue`], with the number of the retry it
+/// is due for.
+///
+/// [`RetryQueue`]: struct.RetryQueue.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Attempt<T> {
+    /// The payload being retried.
+    pub value: T,
+    attempt: u32,
+}
+
+impl<T> Attempt<T> {
+    /// Returns the retry number, starting at `1` for the first retry.
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    /// Consumes the attempt and returns its payload.
+    pub fn into_value(self) -> T {
+        self.value
+    }
+}
+
+struct RetryEntry<T> {
+    ready_at: Instant,
+    seq: u64,
+    attempt: Attempt<T>,
+}
+
+/// Orders entries by earliest ready time, then by insertion order.
+#[derive(Clone, Copy, Default, Debug)]
+struct ReadyOrder;
+
+impl<T> Compare<RetryEntry<T>> for ReadyOrder {
+    fn compare(&self, a: &RetryEntry<T>, b: &RetryEntry<T>) -> Ordering {
+        b.ready_at
+            .cmp(&a.ready_at)
+            .then_with(|| b.seq.cmp(&a.seq))
+    }
+}
+
+/// A queue of failed items waiting to be retried with exponential backoff.
+///
+/// Failed items are scheduled at `now + delay` according to the queue's
+/// [`RetryPolicy`] and become available from [`pop_ready`] once that instant
+/// has passed. An item that fails again is handed back with [`requeue`],
+/// which increments its attempt count; once the policy's retry limit is
+/// reached the item is returned to the caller instead of being scheduled.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::{RetryPolicy, RetryQueue};
+/// use std::time::{Duration, Instant};
+///
+/// let policy = RetryPolicy {
+///     initial: Duration::from_secs(1),
+///     max_retries: 2,
+///     jitter: 0.0,
+///     ..RetryPolicy::default()
+/// };
+/// let mut queue = RetryQueue::new(policy);
+/// let now = Instant::now();
+///
+/// let due = queue.push("job", now).unwrap();
+/// assert_eq!(due, now + Duration::from_secs(1));
+/// assert!(queue.pop_ready(now).is_none());
+///
+/// let attempt = queue.pop_ready(due).unwrap();
+/// assert_eq!(attempt.attempt(), 1);
+///
+/// // The job fails again and is retried after two seconds.
+/// let due = queue.requeue(attempt, due).unwrap();
+/// let attempt = queue.pop_ready(due).unwrap();
+/// assert_eq!(attempt.attempt(), 2);
+///
+/// // Out of retries: the job is handed back.
+/// let exhausted = queue.requeue(attempt, due).unwrap_err();
+/// assert_eq!(exhausted.into_value(), "job");
+/// ```
+///
+/// [`RetryPolicy`]: struct.RetryPolicy.html
+/// [`pop_ready`]: RetryQueue::pop_ready
+/// [`requeue`]: RetryQueue::requeue
+pub struct RetryQueue<T> {
+    heap: BinaryHeap<RetryEntry<T>, ReadyOrder>,
+    policy: RetryPolicy,
+    rng: u64,
+    next_seq: u64,
+}
+
+impl<T: fmt::Debug> fmt::Debug for RetryQueue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryQueue")
+            .field("policy", &self.policy)
+            .field(
+                "items",
+                &self
+                    .heap
+                    .iter()
+                    .map(|e| (&e.attempt, e.ready_at))
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl<T> RetryQueue<T> {
+    /// Creates an empty queue with the given policy and a randomly seeded
+    /// jitter source.
+    pub fn new(policy: RetryPolicy) -> Self {
+        RetryQueue::with_seed(policy, RandomState::new().build_hasher().finish())
+    }
+
+    /// Creates an empty queue whose jitter is derived from `seed`.
+    ///
+    /// Queues with the same seed and policy produce the same schedule, which
+    /// is useful in tests.
+    pub fn with_seed(policy: RetryPolicy, seed: u64) -> Self {
+        RetryQueue {
+            heap: BinaryHeap::from_vec(vec![]),
+            policy,
+            rng: seed,
+            next_seq: 0,
+        }
+    }
+
+    /// Returns the retry policy.
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+
+    /// Schedules the first retry of an item that failed at `now`.
+    ///
+    /// Returns the instant the item becomes ready, or gives the item back if
+    /// the policy allows no retries.
+    pub fn push(&mut self, value: T, now: Instant) -> Result<Instant, T> {
+        self.schedule(Attempt { value, attempt: 1 }, now)
+            .map_err(Attempt::into_value)
+    }
+
+    /// Schedules the next retry of an item that failed again at `now`.
+    ///
+    /// Returns the instant the item becomes ready, or gives the item back if
+    /// it has used up its retries.
+    pub fn requeue(&mut self, attempt: Attempt<T>, now: Instant) -> Result<Instant, Attempt<T>> {
+        let next = Attempt {
+            value: attempt.value,
+            attempt: attempt.attempt.saturating_add(1),
+        };
+        self.schedule(next, now).map_err(|mut a| {
+            a.attempt -= 1;
+            a
+        })
+    }
+
+    fn schedule(&mut self, attempt: Attempt<T>, now: Instant) -> Result<Instant, Attempt<T>> {
+        if attempt.attempt > self.policy.max_retries {
+            return Err(attempt);
+        }
+        let delay = self.jittered(self.policy.delay(attempt.attempt));
+        let ready_at = now + delay;
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.heap.push(RetryEntry {
+            ready_at,
+            seq,
+            attempt,
+        });
+        Ok(ready_at)
+    }
+
+    fn jittered(&mut self, delay: Duration) -> Duration {
+        let jitter = self.policy.jitter.clamp(0.0, 1.0);
+        if jitter.is_nan() || jitter == 0.0 {
+            return delay;
+        }
+        let z = splitmix64(&mut self.rng);
+        // A uniform float in [0, 1).
+        let unit = (z >> 11) as f64 / (1u64 << 53) as f64;
+        delay.mul_f64(1.0 - jitter * unit)
+    }
+
+    /// Removes and returns an item whose retry is due at `now`, or `None` if
+    /// no item is ready yet.
+    ///
+    /// Items are returned in order of their ready time.
+    pub fn pop_ready(&mut self, now: Instant) -> Option<Attempt<T>> {
+        if self.heap.peek()?.ready_at > now {
+            return None;
+        }
+        self.heap.pop().map(|e| e.attempt)
+    }
+
+    /// Returns the instant the next item becomes ready, or `None` if the
+    /// queue is empty.
+    #[must_use]
+    pub fn next_ready_at(&self) -> Option<Instant> {
+        self.heap.peek().map(|e| e.ready_at)
+    }
+
+    /// Returns the number of scheduled items.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Checks if the queue is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+}
@@ -0,0 +1,305 @@
+//! A heap stored in fixed-size chunks instead of one contiguous vector.
+
+use crate::array::{sift_down_slice, sift_up_slice};
+use crate::binary_heap::MaxComparator;
+use crate::queue::PriorityQueue;
+use compare::Compare;
+use std::fmt;
+use std::iter::Flatten;
+use std::mem;
+use std::slice;
+
+/// Number of elements per chunk used by [`SegmentedBinaryHeap::new`].
+///
+/// [`SegmentedBinaryHeap::new`]: struct.SegmentedBinaryHeap.html#method.new
+pub const DEFAULT_CHUNK_LEN: usize = 4096;
+
+/// A priority queue whose elements are stored in fixed-size chunks.
+///
+/// A [`BinaryHeap`] keeps its elements in one `Vec`, which doubles its
+/// allocation when it runs out of room: growing a very large heap copies
+/// every element and briefly needs room for both the old and the new buffer.
+/// `SegmentedBinaryHeap` instead allocates one chunk of `chunk_len` elements
+/// at a time and never moves existing elements, so memory grows smoothly and
+/// no contiguous region larger than a chunk is ever required. The price is an
+/// extra indirection on every access.
+///
+/// One emptied chunk is kept as a spare, so pushing and popping around a
+/// chunk boundary does not allocate repeatedly.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::{MinComparator, SegmentedBinaryHeap};
+///
+/// let mut heap = SegmentedBinaryHeap::with_chunk_len_cmp(4, MinComparator);
+/// heap.extend(vec![7, 3, 9, 1, 8, 2]);
+/// assert_eq!(heap.chunks(), 2);
+///
+/// assert_eq!(heap.pop(), Some(1));
+/// assert_eq!(heap.pop(), Some(2));
+/// assert_eq!(heap.peek(), Some(&3));
+/// ```
+///
+/// [`BinaryHeap`]: struct.BinaryHeap.html
+pub struct SegmentedBinaryHeap<T, C = MaxComparator> {
+    /// Every chunk but the last is full; none is empty.
+    chunks: Vec<Vec<T>>,
+    spare: Option<Vec<T>>,
+    shift: u32,
+    len: usize,
+    cmp: C,
+}
+
+impl<T: fmt::Debug, C> fmt::Debug for SegmentedBinaryHeap<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.chunks.iter().flatten())
+            .finish()
+    }
+}
+
+impl<T: Ord> Default for SegmentedBinaryHeap<T> {
+    fn default() -> Self {
+        SegmentedBinaryHeap::new()
+    }
+}
+
+impl<T: Ord> SegmentedBinaryHeap<T> {
+    /// Creates an empty max-heap with chunks of [`DEFAULT_CHUNK_LEN`]
+    /// elements.
+    ///
+    /// [`DEFAULT_CHUNK_LEN`]: constant.DEFAULT_CHUNK_LEN.html
+    #[must_use]
+    pub fn new() -> Self {
+        SegmentedBinaryHeap::with_chunk_len(DEFAULT_CHUNK_LEN)
+    }
+
+    /// Creates an empty max-heap with chunks of `chunk_len` elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_len` is not a power of two.
+    #[must_use]
+    pub fn with_chunk_len(chunk_len: usize) -> Self {
+        SegmentedBinaryHeap::with_chunk_len_cmp(chunk_len, MaxComparator)
+    }
+}
+
+impl<T, C: Compare<T>> SegmentedBinaryHeap<T, C> {
+    /// Creates an empty heap with chunks of `chunk_len` elements, ordered by
+    /// `cmp`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_len` is not a power of two.
+    pub fn with_chunk_len_cmp(chunk_len: usize, cmp: C) -> Self {
+        assert!(
+            chunk_len.is_power_of_two(),
+            "chunk length must be a power of two"
+        );
+        SegmentedBinaryHeap {
+            chunks: Vec::new(),
+            spare: None,
+            shift: chunk_len.trailing_zeros(),
+            len: 0,
+            cmp,
+        }
+    }
+
+    /// Returns the number of elements per chunk.
+    #[must_use]
+    pub fn chunk_len(&self) -> usize {
+        1 << self.shift
+    }
+
+    /// Returns the number of allocated chunks in use, not counting the
+    /// spare.
+    #[must_use]
+    pub fn chunks(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Returns the number of elements the heap can hold without allocating
+    /// another chunk, counting the spare.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        (self.chunks.len() + usize::from(self.spare.is_some())) << self.shift
+    }
+
+    /// Returns the number of bytes the heap has allocated for its chunks,
+    /// including the spare, and for the list of chunks.
+    ///
+    /// Memory owned by the elements themselves and the bookkeeping overhead
+    /// of the allocator are not included.
+    #[must_use]
+    pub fn memory_usage(&self) -> usize {
+        let slots: usize = self
+            .chunks
+            .iter()
+            .chain(&self.spare)
+            .map(Vec::capacity)
+            .sum();
+        slots * mem::size_of::<T>() + self.chunks.capacity() * mem::size_of::<Vec<T>>()
+    }
+
+    /// Returns the number of elements in the heap.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns an iterator visiting all elements in arbitrary order.
+    pub fn iter(&self) -> Flatten<slice::Iter<'_, Vec<T>>> {
+        self.chunks.iter().flatten()
+    }
+
+    /// Drops all elements and frees all chunks.
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+        self.spare = None;
+        self.len = 0;
+    }
+
+    /// Returns the greatest element, or `None` if the heap is empty.
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.chunks.first().and_then(|c| c.first())
+    }
+
+    /// Pushes an item onto the heap.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)). Allocates one chunk every `chunk_len` pushes.
+    pub fn push(&mut self, item: T) {
+        let chunk_len = self.chunk_len();
+        if self.chunks.last().map_or(true, |c| c.len() == chunk_len) {
+            let chunk = self
+                .spare
+                .take()
+                .unwrap_or_else(|| Vec::with_capacity(chunk_len));
+            self.chunks.push(chunk);
+        }
+        if let Some(last) = self.chunks.last_mut() {
+            last.push(item);
+        }
+        self.len += 1;
+        self.sift_up(self.len - 1);
+    }
+
+    /// Removes the greatest element and returns it, or `None` if the heap is
+    /// empty.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)).
+    pub fn pop(&mut self) -> Option<T> {
+        let last_chunk = self.chunks.last_mut()?;
+        let mut item = last_chunk.pop()?;
+        if last_chunk.is_empty() {
+            self.spare = self.chunks.pop();
+        }
+        self.len -= 1;
+        if self.len > 0 {
+            mem::swap(&mut item, &mut self.chunks[0][0]);
+            self.sift_down(0);
+        }
+        Some(item)
+    }
+
+    fn get(&self, index: usize) -> &T {
+        &self.chunks[index >> self.shift][index & (self.chunk_len() - 1)]
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        let mask = self.chunk_len() - 1;
+        let (ca, cb) = (a >> self.shift, b >> self.shift);
+        if ca == cb {
+            self.chunks[ca].swap(a & mask, b & mask);
+        } else {
+            let (lo, hi) = (ca.min(cb), ca.max(cb));
+            let (head, tail) = self.chunks.split_at_mut(hi);
+            let (x, y) = if ca < cb {
+                (a & mask, b & mask)
+            } else {
+                (b & mask, a & mask)
+            };
+            mem::swap(&mut head[lo][x], &mut tail[0][y]);
+        }
+    }
+
+    // The top levels of the tree live in the first chunk, so sifting runs on
+    // a plain slice there and only uses chunked indexing below it.
+
+    fn sift_up(&mut self, mut pos: usize) {
+        let chunk_len = self.chunk_len();
+        while pos >= chunk_len {
+            let parent = (pos - 1) / 2;
+            if self.cmp.compares_le(self.get(pos), self.get(parent)) {
+                return;
+            }
+            self.swap(pos, parent);
+            pos = parent;
+        }
+        sift_up_slice(&mut self.chunks[0], pos, &self.cmp);
+    }
+
+    fn sift_down(&mut self, mut pos: usize) {
+        let end = self.len;
+        if end <= self.chunk_len() {
+            sift_down_slice(&mut self.chunks[0], pos, &self.cmp);
+            return;
+        }
+        loop {
+            let mut child = 2 * pos + 1;
+            if child >= end {
+                return;
+            }
+            if child + 1 < end && self.cmp.compares_le(self.get(child), self.get(child + 1)) {
+                child += 1;
+            }
+            if self.cmp.compares_ge(self.get(pos), self.get(child)) {
+                return;
+            }
+            self.swap(pos, child);
+            pos = child;
+        }
+    }
+}
+
+impl<T, C: Compare<T>> Extend<T> for SegmentedBinaryHeap<T, C> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+impl<T, C: Compare<T>> PriorityQueue<T> for SegmentedBinaryHeap<T, C> {
+    fn push(&mut self, item: T) {
+        SegmentedBinaryHeap::push(self, item);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        SegmentedBinaryHeap::pop(self)
+    }
+
+    fn peek(&self) -> Option<&T> {
+        SegmentedBinaryHeap::peek(self)
+    }
+
+    fn len(&self) -> usize {
+        SegmentedBinaryHeap::len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        SegmentedBinaryHeap::is_empty(self)
+    }
+}
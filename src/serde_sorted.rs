@@ -0,0 +1,117 @@
+//! Serializing heaps with their elements in sorted order.
+//!
+//! A heap serializes its underlying vector as is, so the order of the
+//! elements depends on how the heap was built. The functions of this module,
+//! for use with `#[serde(with = "binary_heap_plus::serde_sorted")]`, write
+//! them in ascending order instead, so equal heaps give equal output, which
+//! suits snapshots kept under version control. The [`seq`] submodule writes
+//! only the sorted elements, without the comparator.
+//!
+//! Elements that compare equal keep the order they have in the heap, so
+//! they only serialize the same if they are indeed equal.
+//!
+//! Sorting takes *O*(*n* log(*n*)) time and allocates a vector of
+//! references. Deserializing accepts elements in any order and rebuilds the
+//! heap.
+//!
+//! Requires the `serde` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use binary_heap_plus::BinaryHeap;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Snapshot {
+//!     #[serde(with = "binary_heap_plus::serde_sorted::seq")]
+//!     queue: BinaryHeap<u32>,
+//! }
+//!
+//! let snapshot = Snapshot { queue: BinaryHeap::from(vec![2, 7, 1, 8]) };
+//! let json = serde_json::to_string(&snapshot).unwrap();
+//! assert_eq!(json, r#"{"queue":[1,2,7,8]}"#);
+//!
+//! let snapshot: Snapshot = serde_json::from_str(&json).unwrap();
+//! assert_eq!(snapshot.queue.peek(), Some(&8));
+//! ```
+//!
+//! [`seq`]: seq/index.html
+
+use crate::binary_heap::BinaryHeap;
+use compare::Compare;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// The elements of a heap, sorted in ascending order.
+fn sorted<T, C: Compare<T>>(heap: &BinaryHeap<T, C>) -> Vec<&T> {
+    let mut items: Vec<&T> = heap.iter().collect();
+    let cmp = heap.cmp();
+    items.sort_by(|a, b| cmp.compare(a, b));
+    items
+}
+
+#[derive(Serialize)]
+#[serde(rename = "BinaryHeap")]
+struct SortedHeap<'a, T, C> {
+    data: Vec<&'a T>,
+    cmp: &'a C,
+}
+
+/// Serializes the heap like its `Serialize` implementation does, but with
+/// the elements in ascending order.
+pub fn serialize<T, C, S>(heap: &BinaryHeap<T, C>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Serialize,
+    C: Compare<T> + Serialize,
+    S: Serializer,
+{
+    SortedHeap {
+        data: sorted(heap),
+        cmp: heap.cmp(),
+    }
+    .serialize(serializer)
+}
+
+/// Deserializes a heap written by [`serialize`] or by the `Serialize`
+/// implementation of `BinaryHeap`.
+///
+/// [`serialize`]: fn.serialize.html
+pub fn deserialize<'de, T, C, D>(deserializer: D) -> Result<BinaryHeap<T, C>, D::Error>
+where
+    T: Deserialize<'de>,
+    C: Compare<T> + Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    BinaryHeap::deserialize(deserializer)
+}
+
+/// Serializing heaps as a plain sorted sequence of their elements.
+///
+/// The comparator is not written; deserializing creates it with `Default`.
+pub mod seq {
+    use super::sorted;
+    use crate::binary_heap::BinaryHeap;
+    use compare::Compare;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    /// Serializes the elements of the heap in ascending order.
+    pub fn serialize<T, C, S>(heap: &BinaryHeap<T, C>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        C: Compare<T>,
+        S: Serializer,
+    {
+        serializer.collect_seq(sorted(heap))
+    }
+
+    /// Deserializes a sequence of elements, in any order, into a heap with
+    /// the default comparator.
+    pub fn deserialize<'de, T, C, D>(deserializer: D) -> Result<BinaryHeap<T, C>, D::Error>
+    where
+        T: Deserialize<'de>,
+        C: Compare<T> + Default,
+        D: Deserializer<'de>,
+    {
+        Vec::deserialize(deserializer).map(BinaryHeap::from_vec)
+    }
+}
@@ -0,0 +1,254 @@
+//! A heap checked against a sorted vector after every operation.
+//!
+//! Requires the `shadow` feature.
+
+use crate::binary_heap::{BinaryHeap, MaxComparator};
+use crate::queue::PriorityQueue;
+use compare::Compare;
+use std::fmt;
+use std::mem;
+
+/// A [`BinaryHeap`] that mirrors every operation on a trivially correct
+/// model, a sorted `Vec`, and panics as soon as the two disagree.
+///
+/// After each operation the lengths and greatest elements of the heap and
+/// the model are compared, and popped elements must be equal according to
+/// the comparator. The panic message lists every operation performed so far,
+/// rendered with `Debug`, so a failure can be replayed.
+///
+/// Every operation costs *O*(*n*) on top of the heap's own work, and the
+/// trace grows without bound: this type is meant for tests and debugging
+/// sessions, not production. It is available with the `shadow` feature.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::ShadowBinaryHeap;
+///
+/// let mut heap = ShadowBinaryHeap::new();
+/// heap.push(3);
+/// heap.push(5);
+/// assert_eq!(heap.pop(), Some(5));
+/// assert_eq!(heap.trace(), ["push(3)", "push(5)", "pop() -> Some(5)"]);
+/// ```
+///
+/// [`BinaryHeap`]: struct.BinaryHeap.html
+pub struct ShadowBinaryHeap<T, C = MaxComparator> {
+    heap: BinaryHeap<T, C>,
+    /// The same elements, sorted in ascending order.
+    model: Vec<T>,
+    cmp: C,
+    trace: Vec<String>,
+}
+
+impl<T: fmt::Debug, C> fmt::Debug for ShadowBinaryHeap<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShadowBinaryHeap")
+            .field("heap", &self.heap)
+            .field("model", &self.model)
+            .finish()
+    }
+}
+
+impl<T: Ord + Clone + fmt::Debug> Default for ShadowBinaryHeap<T> {
+    fn default() -> Self {
+        ShadowBinaryHeap::new()
+    }
+}
+
+impl<T: Ord + Clone + fmt::Debug> ShadowBinaryHeap<T> {
+    /// Creates an empty max-heap.
+    #[must_use]
+    pub fn new() -> Self {
+        ShadowBinaryHeap::with_cmp(MaxComparator)
+    }
+}
+
+impl<T, C> ShadowBinaryHeap<T, C> {
+    /// Returns the operations performed so far, one per entry.
+    #[must_use]
+    pub fn trace(&self) -> &[String] {
+        &self.trace
+    }
+
+    /// Returns the number of elements in the heap.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Returns the checked heap, dropping the model and the trace.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_inner(self) -> BinaryHeap<T, C> {
+        self.heap
+    }
+}
+
+impl<T: Clone + fmt::Debug, C: Compare<T> + Clone> ShadowBinaryHeap<T, C> {
+    /// Creates an empty heap ordered by `cmp`.
+    pub fn with_cmp(cmp: C) -> Self {
+        ShadowBinaryHeap {
+            heap: BinaryHeap::from_vec_cmp(Vec::new(), cmp.clone()),
+            model: Vec::new(),
+            cmp,
+            trace: Vec::new(),
+        }
+    }
+
+    /// Returns the greatest element, or `None` if the heap is empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the heap and the model disagree.
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.check();
+        self.heap.peek()
+    }
+
+    /// Pushes an item onto the heap and the model.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the heap and the model disagree afterwards.
+    pub fn push(&mut self, item: T) {
+        self.trace.push(format!("push({:?})", item));
+        self.insert_into_model(item.clone());
+        self.heap.push(item);
+        self.check();
+    }
+
+    /// Removes the greatest element from the heap and the model and returns
+    /// it, or `None` if the heap is empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the heap and the model disagree.
+    pub fn pop(&mut self) -> Option<T> {
+        let item = self.heap.pop();
+        let expected = self.model.pop();
+        self.trace.push(format!("pop() -> {:?}", item));
+        let same = match (&item, &expected) {
+            (Some(a), Some(b)) => self.cmp.compares_eq(a, b),
+            (a, b) => a.is_none() && b.is_none(),
+        };
+        if !same {
+            self.diverged(&format!("popped {:?}, expected {:?}", item, expected));
+        }
+        self.check();
+        item
+    }
+
+    /// Moves all the elements of `other` into the heap and the model,
+    /// leaving `other` empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the heap and the model disagree afterwards.
+    pub fn append(&mut self, other: &mut BinaryHeap<T, C>) {
+        self.trace.push(format!("append({:?})", other));
+        for item in other.iter() {
+            self.insert_into_model(item.clone());
+        }
+        self.heap.append(other);
+        self.check();
+    }
+
+    /// Drops all elements of the heap and the model.
+    pub fn clear(&mut self) {
+        self.trace.push("clear()".to_string());
+        self.heap.clear();
+        self.model.clear();
+    }
+
+    /// Consumes the heap and returns its elements in ascending order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the result differs from the model.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        self.trace.push("into_sorted_vec()".to_string());
+        let empty = BinaryHeap::from_vec_cmp(Vec::new(), self.cmp.clone());
+        let sorted = mem::replace(&mut self.heap, empty).into_sorted_vec();
+        let same = sorted.len() == self.model.len()
+            && sorted
+                .iter()
+                .zip(&self.model)
+                .all(|(a, b)| self.cmp.compares_eq(a, b));
+        if !same {
+            self.diverged(&format!(
+                "sorted to {:?}, expected {:?}",
+                sorted, self.model
+            ));
+        }
+        sorted
+    }
+
+    fn insert_into_model(&mut self, item: T) {
+        let cmp = &self.cmp;
+        let pos = self.model.partition_point(|x| cmp.compares_le(x, &item));
+        self.model.insert(pos, item);
+    }
+
+    /// Compares the lengths and greatest elements of the heap and the model.
+    fn check(&self) {
+        if self.heap.len() != self.model.len() {
+            self.diverged(&format!(
+                "heap has {} elements, expected {}",
+                self.heap.len(),
+                self.model.len()
+            ));
+        }
+        if let (Some(top), Some(greatest)) = (self.heap.peek(), self.model.last()) {
+            if !self.cmp.compares_eq(top, greatest) {
+                self.diverged(&format!("top is {:?}, expected {:?}", top, greatest));
+            }
+        }
+    }
+
+    #[cold]
+    fn diverged(&self, what: &str) -> ! {
+        panic!(
+            "heap diverged from its model: {}\noperations:\n  {}",
+            what,
+            self.trace.join("\n  ")
+        );
+    }
+}
+
+impl<T: Clone + fmt::Debug, C: Compare<T> + Clone> Extend<T> for ShadowBinaryHeap<T, C> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+impl<T: Clone + fmt::Debug, C: Compare<T> + Clone> PriorityQueue<T> for ShadowBinaryHeap<T, C> {
+    fn push(&mut self, item: T) {
+        ShadowBinaryHeap::push(self, item);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        ShadowBinaryHeap::pop(self)
+    }
+
+    fn peek(&self) -> Option<&T> {
+        ShadowBinaryHeap::peek(self)
+    }
+
+    fn len(&self) -> usize {
+        ShadowBinaryHeap::len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        ShadowBinaryHeap::is_empty(self)
+    }
+}
@@ -0,0 +1,229 @@
+//! A thread-safe heap split into independently locked shards.
+
+use crate::binary_heap::{BinaryHeap, MaxComparator};
+use crate::util::{lock, try_lock};
+use compare::Compare;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+const DEFAULT_SHARDS: usize = 8;
+
+/// A thread-safe priority queue made of several [`BinaryHeap`] shards, each
+/// guarded by its own lock.
+///
+/// `push` locks a single shard, so producers rarely contend with each other.
+/// `pop` visits the shards in order, holding at most two locks at a time,
+/// and removes the best of the shard tops it saw. Because shards are visited
+/// one after another, an element pushed concurrently into an already visited
+/// shard can be missed: `pop` returns *a* greatest element among those
+/// present during the scan, not necessarily the greatest at the instant it
+/// returns. Once producers are quiescent, elements come out in exact order.
+///
+/// A comparator that panics leaves the affected shard valid; later
+/// operations ignore the resulting lock poisoning.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::ConcurrentBinaryHeap;
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// let heap = Arc::new(ConcurrentBinaryHeap::new());
+/// let producers: Vec<_> = (0..4)
+///     .map(|t| {
+///         let heap = Arc::clone(&heap);
+///         thread::spawn(move || {
+///             for i in 0..100 {
+///                 heap.push(t * 100 + i);
+///             }
+///         })
+///     })
+///     .collect();
+/// for p in producers {
+///     p.join().unwrap();
+/// }
+///
+/// assert_eq!(heap.len(), 400);
+/// assert_eq!(heap.pop(), Some(399));
+/// assert_eq!(heap.pop(), Some(398));
+/// ```
+///
+/// [`BinaryHeap`]: struct.BinaryHeap.html
+pub struct ConcurrentBinaryHeap<T, C = MaxComparator> {
+    shards: Vec<Mutex<BinaryHeap<T, C>>>,
+    cmp: C,
+    len: AtomicUsize,
+    next_shard: AtomicUsize,
+}
+
+impl<T: fmt::Debug, C> fmt::Debug for ConcurrentBinaryHeap<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConcurrentBinaryHeap")
+            .field("shards", &self.shards)
+            .finish()
+    }
+}
+
+impl<T: Ord> Default for ConcurrentBinaryHeap<T> {
+    fn default() -> Self {
+        ConcurrentBinaryHeap::new()
+    }
+}
+
+impl<T: Ord> ConcurrentBinaryHeap<T> {
+    /// Creates an empty max-heap with the default number of shards.
+    #[must_use]
+    pub fn new() -> Self {
+        ConcurrentBinaryHeap::with_shards(DEFAULT_SHARDS)
+    }
+
+    /// Creates an empty max-heap with `shards` shards.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shards` is zero.
+    #[must_use]
+    pub fn with_shards(shards: usize) -> Self {
+        ConcurrentBinaryHeap::with_shards_cmp(shards, MaxComparator)
+    }
+}
+
+impl<T, C: Compare<T> + Clone> ConcurrentBinaryHeap<T, C> {
+    /// Creates an empty heap with `shards` shards ordered by `cmp`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shards` is zero.
+    pub fn with_shards_cmp(shards: usize, cmp: C) -> Self {
+        assert!(shards > 0, "a concurrent heap needs at least one shard");
+        ConcurrentBinaryHeap {
+            shards: (0..shards)
+                .map(|_| Mutex::new(BinaryHeap::from_vec_cmp(vec![], cmp.clone())))
+                .collect(),
+            cmp,
+            len: AtomicUsize::new(0),
+            next_shard: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl<T, C: Compare<T>> ConcurrentBinaryHeap<T, C> {
+    /// Pushes an item onto one of the shards.
+    ///
+    /// Shards are chosen round robin; a shard that is currently locked is
+    /// skipped in favor of the next free one.
+    pub fn push(&self, item: T) {
+        let n = self.shards.len();
+        let start = self.next_shard.fetch_add(1, Ordering::Relaxed) % n;
+        for i in 0..n {
+            if let Some(mut shard) = try_lock(&self.shards[(start + i) % n]) {
+                return self.push_locked(&mut shard, item);
+            }
+        }
+        self.push_locked(&mut lock(&self.shards[start]), item);
+    }
+
+    fn push_locked(&self, shard: &mut BinaryHeap<T, C>, item: T) {
+        shard.push(item);
+        self.len.fetch_add(1, Ordering::Release);
+    }
+
+    /// Removes the greatest item among the shard tops and returns it, or
+    /// `None` if every shard was empty when visited.
+    ///
+    /// Blocks while a shard is locked by another thread.
+    pub fn pop(&self) -> Option<T> {
+        self.pop_with(|shard| Some(lock(shard)))
+    }
+
+    /// Like [`pop`], but skips shards that are locked by another thread
+    /// instead of waiting for them.
+    ///
+    /// Returns `None` if no item was found in the shards that could be
+    /// locked without blocking.
+    ///
+    /// [`pop`]: ConcurrentBinaryHeap::pop
+    pub fn try_pop(&self) -> Option<T> {
+        self.pop_with(try_lock)
+    }
+
+    fn pop_with<'a, L>(&'a self, lock_shard: L) -> Option<T>
+    where
+        L: FnMut(&'a Mutex<BinaryHeap<T, C>>) -> Option<MutexGuard<'a, BinaryHeap<T, C>>>,
+    {
+        let item = lock_best(&self.shards, &self.cmp, lock_shard)?.pop();
+        if item.is_some() {
+            self.len.fetch_sub(1, Ordering::Release);
+        }
+        item
+    }
+
+    /// Returns the number of items in the heap.
+    ///
+    /// The value may be stale as soon as it is returned if other threads are
+    /// pushing or popping.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Acquire)
+    }
+
+    /// Checks if the heap is empty. See [`len`] for caveats.
+    ///
+    /// [`len`]: ConcurrentBinaryHeap::len
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of shards.
+    #[must_use]
+    pub fn shards(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Consumes the heap and merges the shards into a single `BinaryHeap`.
+    pub fn into_heap(self) -> BinaryHeap<T, C> {
+        let mut heap = BinaryHeap::from_vec_cmp(vec![], self.cmp);
+        for shard in self.shards {
+            let mut shard = shard.into_inner().unwrap_or_else(|e| e.into_inner());
+            heap.append(&mut shard);
+        }
+        heap
+    }
+}
+
+/// Locks the heaps in turn with `lock_heap`, skipping those it returns `None`
+/// for, and returns the guard of the one with the greatest top according to
+/// `cmp`, holding at most two locks at a time.
+pub(crate) fn lock_best<'a, T, C, I, L>(
+    heaps: I,
+    cmp: &C,
+    mut lock_heap: L,
+) -> Option<MutexGuard<'a, BinaryHeap<T, C>>>
+where
+    C: Compare<T>,
+    I: IntoIterator<Item = &'a Mutex<BinaryHeap<T, C>>>,
+    L: FnMut(&'a Mutex<BinaryHeap<T, C>>) -> Option<MutexGuard<'a, BinaryHeap<T, C>>>,
+{
+    let mut best: Option<MutexGuard<'a, BinaryHeap<T, C>>> = None;
+    for heap in heaps {
+        let guard = match lock_heap(heap) {
+            Some(guard) => guard,
+            None => continue,
+        };
+        let top = match guard.peek() {
+            Some(top) => top,
+            None => continue,
+        };
+        let better = match best.as_ref().and_then(|b| b.peek()) {
+            Some(current) => cmp.compares_gt(top, current),
+            None => true,
+        };
+        if better {
+            best = Some(guard);
+        }
+    }
+    best
+}
@@ -0,0 +1,204 @@
+//! A heap that stores its first elements inline.
+//!
+//! Requires the `smallvec` feature.
+
+use crate::array::{sift_down_slice, sift_up_slice};
+use crate::queue::PriorityQueue;
+use compare::Compare;
+use smallvec::SmallVec;
+use std::fmt;
+use std::mem;
+use std::slice;
+
+/// A priority queue that keeps up to `N` elements inline and only allocates
+/// once it grows beyond that.
+///
+/// For many small, short-lived queues this avoids an allocation per queue,
+/// while still accepting any number of elements. It is backed by a
+/// [`SmallVec`] and, like [`BinaryHeap`], pops the greatest element
+/// according to the comparator `C` first.
+///
+/// This type is available with the `smallvec` feature.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::{MaxComparator, SmallBinaryHeap};
+///
+/// let mut heap: SmallBinaryHeap<u32, MaxComparator, 4> = SmallBinaryHeap::new();
+/// heap.extend([3, 1, 4, 1]);
+/// assert!(!heap.spilled());
+///
+/// heap.push(5);
+/// assert!(heap.spilled());
+/// assert_eq!(heap.pop(), Some(5));
+/// assert_eq!(heap.pop(), Some(4));
+/// ```
+///
+/// [`SmallVec`]: https://docs.rs/smallvec/1/smallvec/struct.SmallVec.html
+/// [`BinaryHeap`]: struct.BinaryHeap.html
+pub struct SmallBinaryHeap<T, C, const N: usize> {
+    data: SmallVec<[T; N]>,
+    cmp: C,
+}
+
+impl<T: fmt::Debug, C, const N: usize> fmt::Debug for SmallBinaryHeap<T, C, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.data.iter()).finish()
+    }
+}
+
+impl<T: Clone, C: Clone, const N: usize> Clone for SmallBinaryHeap<T, C, N> {
+    fn clone(&self) -> Self {
+        SmallBinaryHeap {
+            data: self.data.clone(),
+            cmp: self.cmp.clone(),
+        }
+    }
+}
+
+impl<T, C: Compare<T> + Default, const N: usize> Default for SmallBinaryHeap<T, C, N> {
+    fn default() -> Self {
+        SmallBinaryHeap::new()
+    }
+}
+
+impl<T, C: Compare<T> + Default, const N: usize> SmallBinaryHeap<T, C, N> {
+    /// Creates an empty heap.
+    #[must_use]
+    pub fn new() -> Self {
+        SmallBinaryHeap::with_cmp(C::default())
+    }
+}
+
+impl<T, C, const N: usize> SmallBinaryHeap<T, C, N> {
+    /// Creates an empty heap ordered by `cmp`.
+    pub fn with_cmp(cmp: C) -> Self {
+        SmallBinaryHeap {
+            data: SmallVec::new(),
+            cmp,
+        }
+    }
+
+    /// Returns the number of elements that fit inline.
+    #[must_use]
+    pub fn inline_size(&self) -> usize {
+        N
+    }
+
+    /// Returns `true` if the elements have been moved to a heap allocation.
+    #[must_use]
+    pub fn spilled(&self) -> bool {
+        self.data.spilled()
+    }
+
+    /// Returns the number of bytes the heap has allocated outside of itself:
+    /// zero while the elements are inline, the capacity times the size of
+    /// `T` once they have spilled.
+    ///
+    /// Memory owned by the elements themselves and the bookkeeping overhead
+    /// of the allocator are not included.
+    #[must_use]
+    pub fn memory_usage(&self) -> usize {
+        if self.data.spilled() {
+            self.data.capacity() * mem::size_of::<T>()
+        } else {
+            0
+        }
+    }
+
+    /// Returns the number of elements in the heap.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns the elements in arbitrary order, as stored in the heap.
+    #[must_use]
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    /// Returns an iterator visiting all elements in arbitrary order.
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.data.iter()
+    }
+
+    /// Drops all elements, keeping any allocated capacity.
+    pub fn clear(&mut self) {
+        self.data.clear();
+    }
+}
+
+impl<T, C: Compare<T>, const N: usize> SmallBinaryHeap<T, C, N> {
+    /// Returns the greatest element, or `None` if the heap is empty.
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.data.first()
+    }
+
+    /// Pushes an item onto the heap.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)), plus an *O*(*n*) move the first time the heap grows
+    /// beyond `N` elements.
+    pub fn push(&mut self, item: T) {
+        let pos = self.data.len();
+        self.data.push(item);
+        sift_up_slice(&mut self.data, pos, &self.cmp);
+    }
+
+    /// Removes the greatest element and returns it, or `None` if the heap is
+    /// empty.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)).
+    pub fn pop(&mut self) -> Option<T> {
+        let item = self.data.pop().map(|mut item| {
+            if !self.data.is_empty() {
+                mem::swap(&mut item, &mut self.data[0]);
+            }
+            item
+        });
+        sift_down_slice(&mut self.data, 0, &self.cmp);
+        item
+    }
+}
+
+impl<T, C: Compare<T>, const N: usize> Extend<T> for SmallBinaryHeap<T, C, N> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+impl<T, C: Compare<T>, const N: usize> PriorityQueue<T> for SmallBinaryHeap<T, C, N> {
+    fn push(&mut self, item: T) {
+        SmallBinaryHeap::push(self, item);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        SmallBinaryHeap::pop(self)
+    }
+
+    fn peek(&self) -> Option<&T> {
+        SmallBinaryHeap::peek(self)
+    }
+
+    fn len(&self) -> usize {
+        SmallBinaryHeap::len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        SmallBinaryHeap::is_empty(self)
+    }
+}
@@ -0,0 +1,334 @@
+//! A heap that stores the priorities apart from the values.
+
+#[cfg(not(feature = "safe_only"))]
+use crate::binary_heap::Hole;
+use crate::binary_heap::MaxComparator;
+use compare::Compare;
+use std::fmt;
+use std::iter::FromIterator;
+use std::slice;
+
+/// A priority queue of `(priority, value)` pairs that stores the priorities
+/// in one vector and the values in another, in the same order.
+///
+/// Sifting compares only priorities, so with large values it reads far less
+/// memory than a [`BinaryHeap`] of pairs, where each comparison pulls the
+/// whole pair into the cache. Values are only moved, together with their
+/// priority, once per level they travel. The pair with the greatest priority
+/// according to `C` is popped first.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::{MinComparator, SoaBinaryHeap};
+///
+/// let mut heap = SoaBinaryHeap::with_cmp(MinComparator);
+/// heap.push(30, [3u8; 256]);
+/// heap.push(10, [1u8; 256]);
+/// heap.push(20, [2u8; 256]);
+///
+/// assert_eq!(heap.peek_priority(), Some(&10));
+/// let (priority, value) = heap.pop().unwrap();
+/// assert_eq!((priority, value[0]), (10, 1));
+/// assert_eq!(heap.priorities().len(), 2);
+/// ```
+///
+/// [`BinaryHeap`]: struct.BinaryHeap.html
+pub struct SoaBinaryHeap<P, V, C = MaxComparator> {
+    priorities: Vec<P>,
+    values: Vec<V>,
+    cmp: C,
+}
+
+impl<P: fmt::Debug, V: fmt::Debug, C> fmt::Debug for SoaBinaryHeap<P, V, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<P: Clone, V: Clone, C: Clone> Clone for SoaBinaryHeap<P, V, C> {
+    fn clone(&self) -> Self {
+        SoaBinaryHeap {
+            priorities: self.priorities.clone(),
+            values: self.values.clone(),
+            cmp: self.cmp.clone(),
+        }
+    }
+}
+
+impl<P: Ord, V> Default for SoaBinaryHeap<P, V> {
+    fn default() -> Self {
+        SoaBinaryHeap::new()
+    }
+}
+
+impl<P: Ord, V> SoaBinaryHeap<P, V> {
+    /// Creates an empty max-heap.
+    #[must_use]
+    pub fn new() -> Self {
+        SoaBinaryHeap::with_cmp(MaxComparator)
+    }
+}
+
+impl<P, V, C> SoaBinaryHeap<P, V, C> {
+    /// Creates an empty heap ordered by `cmp`.
+    pub fn with_cmp(cmp: C) -> Self {
+        SoaBinaryHeap::with_capacity_cmp(0, cmp)
+    }
+
+    /// Creates an empty heap ordered by `cmp`, with space for at least
+    /// `capacity` pairs.
+    pub fn with_capacity_cmp(capacity: usize, cmp: C) -> Self {
+        SoaBinaryHeap {
+            priorities: Vec::with_capacity(capacity),
+            values: Vec::with_capacity(capacity),
+            cmp,
+        }
+    }
+
+    /// Returns the number of pairs in the heap.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.priorities.len()
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.priorities.is_empty()
+    }
+
+    /// Returns the greatest priority and its value, or `None` if the heap is
+    /// empty.
+    #[must_use]
+    pub fn peek(&self) -> Option<(&P, &V)> {
+        Some((self.priorities.first()?, self.values.first()?))
+    }
+
+    /// Returns the greatest priority, or `None` if the heap is empty.
+    #[must_use]
+    pub fn peek_priority(&self) -> Option<&P> {
+        self.priorities.first()
+    }
+
+    /// Returns the priorities in heap layout, each at the same index as its
+    /// value in [`values`].
+    ///
+    /// [`values`]: SoaBinaryHeap::values
+    #[must_use]
+    pub fn priorities(&self) -> &[P] {
+        &self.priorities
+    }
+
+    /// Returns the values, each at the same index as its priority in
+    /// [`priorities`].
+    ///
+    /// [`priorities`]: SoaBinaryHeap::priorities
+    #[must_use]
+    pub fn values(&self) -> &[V] {
+        &self.values
+    }
+
+    /// Returns an iterator visiting all pairs in arbitrary order.
+    pub fn iter(&self) -> std::iter::Zip<slice::Iter<'_, P>, slice::Iter<'_, V>> {
+        self.priorities.iter().zip(self.values.iter())
+    }
+
+    /// Drops all pairs, keeping the allocated capacity.
+    pub fn clear(&mut self) {
+        self.priorities.clear();
+        self.values.clear();
+    }
+
+    /// Consumes the heap and returns the priorities and the values, in the
+    /// same arbitrary order.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_vecs(self) -> (Vec<P>, Vec<V>) {
+        (self.priorities, self.values)
+    }
+}
+
+impl<P, V, C: Compare<P>> SoaBinaryHeap<P, V, C> {
+    /// Creates a heap ordered by `cmp` from the priorities and the values
+    /// at the same indices.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the vectors have different lengths.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*n*).
+    pub fn from_vecs_cmp(priorities: Vec<P>, values: Vec<V>, cmp: C) -> Self {
+        assert_eq!(
+            priorities.len(),
+            values.len(),
+            "every priority needs a value"
+        );
+        let mut heap = SoaBinaryHeap {
+            priorities,
+            values,
+            cmp,
+        };
+        for pos in (0..heap.len() / 2).rev() {
+            heap.sift_down(pos);
+        }
+        heap
+    }
+
+    /// Pushes a value with its priority onto the heap.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)).
+    pub fn push(&mut self, priority: P, value: V) {
+        self.priorities.push(priority);
+        self.values.push(value);
+        self.sift_up(self.priorities.len() - 1);
+    }
+
+    /// Removes the greatest priority and its value and returns them, or
+    /// `None` if the heap is empty.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)).
+    pub fn pop(&mut self) -> Option<(P, V)> {
+        if self.is_empty() {
+            return None;
+        }
+        let priority = self.priorities.swap_remove(0);
+        let value = self.values.swap_remove(0);
+        if !self.is_empty() {
+            self.sift_down(0);
+        }
+        Some((priority, value))
+    }
+
+    // These move a hole through each vector, in step, so that every priority
+    // and value moves once per level. If the comparator panics, both holes
+    // are at the same index when they are filled back.
+
+    #[cfg(not(feature = "safe_only"))]
+    fn sift_up(&mut self, pos: usize) {
+        // SAFETY: callers pass an index in bounds of both vectors, which have
+        //  the same length.
+        let mut priority = unsafe { Hole::new(&mut self.priorities, pos) };
+        let mut value = unsafe { Hole::new(&mut self.values, pos) };
+        while priority.pos() > 0 {
+            let parent = (priority.pos() - 1) / 2;
+            // SAFETY: `parent` is below the hole, so in bounds and not the
+            //  hole.
+            if self
+                .cmp
+                .compares_le(priority.element(), unsafe { priority.get(parent) })
+            {
+                break;
+            }
+            unsafe {
+                priority.move_to(parent);
+                value.move_to(parent);
+            }
+        }
+    }
+
+    #[cfg(not(feature = "safe_only"))]
+    fn sift_down(&mut self, pos: usize) {
+        let end = self.priorities.len();
+        // SAFETY: as in `sift_up`.
+        let mut priority = unsafe { Hole::new(&mut self.priorities, pos) };
+        let mut value = unsafe { Hole::new(&mut self.values, pos) };
+        loop {
+            let mut child = 2 * priority.pos() + 1;
+            if child >= end {
+                break;
+            }
+            // SAFETY: `child` and `child + 1` are above the hole and checked
+            //  against `end`.
+            if child + 1 < end
+                && self
+                    .cmp
+                    .compares_le(unsafe { priority.get(child) }, unsafe {
+                        priority.get(child + 1)
+                    })
+            {
+                child += 1;
+            }
+            if self
+                .cmp
+                .compares_ge(priority.element(), unsafe { priority.get(child) })
+            {
+                break;
+            }
+            unsafe {
+                priority.move_to(child);
+                value.move_to(child);
+            }
+        }
+    }
+
+    // With `safe_only`, these swap in both vectors instead, which keeps them
+    // the same length and in step even if the comparator panics.
+
+    #[cfg(feature = "safe_only")]
+    fn sift_up(&mut self, mut pos: usize) {
+        while pos > 0 {
+            let parent = (pos - 1) / 2;
+            if self
+                .cmp
+                .compares_le(&self.priorities[pos], &self.priorities[parent])
+            {
+                break;
+            }
+            self.swap(pos, parent);
+            pos = parent;
+        }
+    }
+
+    #[cfg(feature = "safe_only")]
+    fn sift_down(&mut self, mut pos: usize) {
+        let end = self.priorities.len();
+        loop {
+            let mut child = 2 * pos + 1;
+            if child >= end {
+                break;
+            }
+            if child + 1 < end
+                && self
+                    .cmp
+                    .compares_le(&self.priorities[child], &self.priorities[child + 1])
+            {
+                child += 1;
+            }
+            if self
+                .cmp
+                .compares_ge(&self.priorities[pos], &self.priorities[child])
+            {
+                break;
+            }
+            self.swap(pos, child);
+            pos = child;
+        }
+    }
+
+    #[cfg(feature = "safe_only")]
+    fn swap(&mut self, a: usize, b: usize) {
+        self.priorities.swap(a, b);
+        self.values.swap(a, b);
+    }
+}
+
+impl<P, V, C: Compare<P>> Extend<(P, V)> for SoaBinaryHeap<P, V, C> {
+    fn extend<I: IntoIterator<Item = (P, V)>>(&mut self, iter: I) {
+        for (priority, value) in iter {
+            self.push(priority, value);
+        }
+    }
+}
+
+impl<P: Ord, V> FromIterator<(P, V)> for SoaBinaryHeap<P, V> {
+    fn from_iter<I: IntoIterator<Item = (P, V)>>(iter: I) -> Self {
+        let (priorities, values) = iter.into_iter().unzip();
+        SoaBinaryHeap::from_vecs_cmp(priorities, values, MaxComparator)
+    }
+}
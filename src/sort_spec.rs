@@ -0,0 +1,312 @@
+//! Comparators built at runtime from a list of sort keys.
+
+use compare::Compare;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+
+type KeyCmp<T> = Arc<dyn Fn(&T, &T) -> Ordering + Send + Sync>;
+
+struct SortKey<T> {
+    name: Option<Arc<str>>,
+    cmp: KeyCmp<T>,
+    /// Whether lesser values pop first.
+    ascending: bool,
+}
+
+impl<T> Clone for SortKey<T> {
+    fn clone(&self) -> Self {
+        SortKey {
+            name: self.name.clone(),
+            cmp: Arc::clone(&self.cmp),
+            ascending: self.ascending,
+        }
+    }
+}
+
+/// A comparator made of sort keys chosen at runtime, each ascending or
+/// descending.
+///
+/// Like [`TupleCmp`], the keys are compared in turn and the first that
+/// differs decides; a descending key pops greater values first and an
+/// ascending key lesser values first. Keys are added with the builder
+/// methods, or parsed from a string such as `"-priority,+created_at"` by
+/// [`SortKeys::parse`]. Each comparison calls the key functions through
+/// `dyn Fn`, so prefer [`TupleCmp`] or a closure when the order is known at
+/// compile time.
+///
+/// A spec without keys considers all elements equal.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::{BinaryHeap, SortSpec};
+///
+/// struct Task {
+///     priority: u8,
+///     created_at: u64,
+/// }
+///
+/// let spec = SortSpec::new()
+///     .desc(|t: &Task| t.priority)
+///     .asc(|t: &Task| t.created_at);
+/// let mut heap = BinaryHeap::from_vec_cmp(vec![], spec);
+/// heap.push(Task { priority: 1, created_at: 10 });
+/// heap.push(Task { priority: 3, created_at: 30 });
+/// heap.push(Task { priority: 3, created_at: 20 });
+///
+/// let top = heap.pop().unwrap();
+/// assert_eq!((top.priority, top.created_at), (3, 20));
+/// ```
+///
+/// [`TupleCmp`]: struct.TupleCmp.html
+/// [`SortKeys::parse`]: struct.SortKeys.html#method.parse
+pub struct SortSpec<T> {
+    keys: Vec<SortKey<T>>,
+}
+
+impl<T> Clone for SortSpec<T> {
+    fn clone(&self) -> Self {
+        SortSpec {
+            keys: self.keys.clone(),
+        }
+    }
+}
+
+impl<T> Default for SortSpec<T> {
+    fn default() -> Self {
+        SortSpec::new()
+    }
+}
+
+impl<T> fmt::Debug for SortSpec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut list = f.debug_list();
+        for key in &self.keys {
+            let sign = if key.ascending { '+' } else { '-' };
+            let name = key.name.as_deref().unwrap_or("<fn>");
+            list.entry(&format_args!("{}{}", sign, name));
+        }
+        list.finish()
+    }
+}
+
+impl<T> SortSpec<T> {
+    /// Creates a spec without keys.
+    #[must_use]
+    pub fn new() -> Self {
+        SortSpec { keys: Vec::new() }
+    }
+
+    /// Returns the number of keys.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Checks if the spec has no keys.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Adds a key, compared after the previous ones, whose lesser values pop
+    /// first.
+    #[must_use]
+    pub fn asc<K, F>(self, key: F) -> Self
+    where
+        K: Ord,
+        F: Fn(&T) -> K + Send + Sync + 'static,
+    {
+        self.asc_by(move |a: &T, b: &T| key(a).cmp(&key(b)))
+    }
+
+    /// Adds a key, compared after the previous ones, whose greater values
+    /// pop first.
+    #[must_use]
+    pub fn desc<K, F>(self, key: F) -> Self
+    where
+        K: Ord,
+        F: Fn(&T) -> K + Send + Sync + 'static,
+    {
+        self.desc_by(move |a: &T, b: &T| key(a).cmp(&key(b)))
+    }
+
+    /// Adds a key compared by `cmp`, after the previous ones, whose lesser
+    /// values pop first.
+    #[must_use]
+    pub fn asc_by<F>(self, cmp: F) -> Self
+    where
+        F: Fn(&T, &T) -> Ordering + Send + Sync + 'static,
+    {
+        self.with_key(None, Arc::new(cmp), true)
+    }
+
+    /// Adds a key compared by `cmp`, after the previous ones, whose greater
+    /// values pop first.
+    #[must_use]
+    pub fn desc_by<F>(self, cmp: F) -> Self
+    where
+        F: Fn(&T, &T) -> Ordering + Send + Sync + 'static,
+    {
+        self.with_key(None, Arc::new(cmp), false)
+    }
+
+    fn with_key(mut self, name: Option<Arc<str>>, cmp: KeyCmp<T>, ascending: bool) -> Self {
+        self.keys.push(SortKey {
+            name,
+            cmp,
+            ascending,
+        });
+        self
+    }
+}
+
+impl<T> Compare<T> for SortSpec<T> {
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        for key in &self.keys {
+            let order = if key.ascending {
+                (key.cmp)(b, a)
+            } else {
+                (key.cmp)(a, b)
+            };
+            if order != Ordering::Equal {
+                return order;
+            }
+        }
+        Ordering::Equal
+    }
+}
+
+/// A registry of named sort keys, from which [`parse`] builds a
+/// [`SortSpec`].
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::{BinaryHeap, SortKeys};
+///
+/// // (name, priority, created_at)
+/// type Row = (&'static str, u8, u64);
+///
+/// let mut keys = SortKeys::new();
+/// keys.register("name", |r: &Row| r.0);
+/// keys.register("priority", |r: &Row| r.1);
+/// keys.register("created_at", |r: &Row| r.2);
+///
+/// // For instance from a query string.
+/// let spec = keys.parse("-priority, +created_at").unwrap();
+/// let mut heap = BinaryHeap::from_vec_cmp(vec![], spec);
+/// heap.extend(vec![("a", 1, 5), ("b", 2, 9), ("c", 2, 7)]);
+/// assert_eq!(heap.pop(), Some(("c", 2, 7)));
+///
+/// assert!(keys.parse("-size").is_err());
+/// ```
+///
+/// [`parse`]: SortKeys::parse
+/// [`SortSpec`]: struct.SortSpec.html
+pub struct SortKeys<T> {
+    keys: HashMap<Arc<str>, KeyCmp<T>>,
+}
+
+impl<T> Clone for SortKeys<T> {
+    fn clone(&self) -> Self {
+        SortKeys {
+            keys: self.keys.clone(),
+        }
+    }
+}
+
+impl<T> Default for SortKeys<T> {
+    fn default() -> Self {
+        SortKeys::new()
+    }
+}
+
+impl<T> fmt::Debug for SortKeys<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.keys.keys()).finish()
+    }
+}
+
+impl<T> SortKeys<T> {
+    /// Creates an empty registry.
+    #[must_use]
+    pub fn new() -> Self {
+        SortKeys {
+            keys: HashMap::new(),
+        }
+    }
+
+    /// Registers the key `name`, extracted from the elements by `key`,
+    /// replacing any key registered under that name.
+    pub fn register<K, F>(&mut self, name: &str, key: F) -> &mut Self
+    where
+        K: Ord,
+        F: Fn(&T) -> K + Send + Sync + 'static,
+    {
+        self.register_by(name, move |a: &T, b: &T| key(a).cmp(&key(b)))
+    }
+
+    /// Registers the key `name`, which orders the elements by `cmp`,
+    /// replacing any key registered under that name.
+    pub fn register_by<F>(&mut self, name: &str, cmp: F) -> &mut Self
+    where
+        F: Fn(&T, &T) -> Ordering + Send + Sync + 'static,
+    {
+        self.keys.insert(Arc::from(name), Arc::new(cmp));
+        self
+    }
+
+    /// Builds a spec from a comma-separated list of registered key names,
+    /// each prefixed with `+` for ascending, which is the default, or `-` for
+    /// descending. Whitespace around the names and signs is ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a name is empty or was not registered.
+    pub fn parse(&self, spec: &str) -> Result<SortSpec<T>, SortSpecError> {
+        let mut parsed = SortSpec::new();
+        for term in spec.split(',') {
+            let term = term.trim();
+            let (ascending, name) = if let Some(name) = term.strip_prefix('-') {
+                (false, name.trim_start())
+            } else {
+                (true, term.strip_prefix('+').unwrap_or(term).trim_start())
+            };
+            if name.is_empty() {
+                return Err(SortSpecError::EmptyKey);
+            }
+            let (name, cmp) = self
+                .keys
+                .get_key_value(name)
+                .ok_or_else(|| SortSpecError::UnknownKey(name.to_string()))?;
+            parsed = parsed.with_key(Some(Arc::clone(name)), Arc::clone(cmp), ascending);
+        }
+        Ok(parsed)
+    }
+}
+
+/// The error returned by [`SortKeys::parse`].
+///
+/// [`SortKeys::parse`]: struct.SortKeys.html#method.parse
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SortSpecError {
+    /// A term of the spec has no key name.
+    EmptyKey,
+    /// No key is registered under this name.
+    UnknownKey(String),
+}
+
+impl fmt::Display for SortSpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SortSpecError::EmptyKey => write!(f, "sort spec has an empty key"),
+            SortSpecError::UnknownKey(name) => write!(f, "unknown sort key `{}`", name),
+        }
+    }
+}
+
+impl Error for SortSpecError {}
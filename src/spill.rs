@@ -0,0 +1,311 @@
+//! A heap that spills its least important elements to temporary files.
+//!
+//! Requires the `spill` feature.
+
+use crate::binary_heap::{BinaryHeap, MaxComparator};
+use compare::Compare;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Seek, SeekFrom};
+use std::mem;
+
+/// Number of elements kept in memory by [`SpillingHeap::new`].
+///
+/// [`SpillingHeap::new`]: struct.SpillingHeap.html#method.new
+pub const DEFAULT_SPILL_THRESHOLD: usize = 1 << 16;
+
+/// A sorted run of spilled elements, read back greatest first.
+struct Run<T> {
+    reader: BufReader<File>,
+    /// The greatest element of the run not yet popped.
+    head: T,
+    /// Number of elements left in the file after `head`.
+    remaining: usize,
+}
+
+/// A priority queue for more elements than fit in memory.
+///
+/// `SpillingHeap` keeps at most `threshold` elements in an in-memory
+/// [`BinaryHeap`]. When a push exceeds that, the heap sorts its elements,
+/// keeps the greater half in memory and writes the rest to an anonymous
+/// temporary file as a sorted run. [`pop`] merges the in-memory heap with the
+/// runs on demand, reading each run back one element at a time, so only the
+/// greatest element of every run is held in memory.
+///
+/// Elements are encoded with [`bincode`], so `T` must implement serde's
+/// `Serialize` and `DeserializeOwned`. Temporary files are deleted when their
+/// run is exhausted or the heap is dropped.
+///
+/// This type is available with the `spill` feature.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::{MinComparator, SpillingHeap};
+///
+/// # fn main() -> std::io::Result<()> {
+/// let mut heap = SpillingHeap::with_threshold_cmp(4, MinComparator);
+/// for x in [7, 3, 9, 1, 8, 2, 6] {
+///     heap.push(x)?;
+/// }
+/// assert!(heap.runs() > 0);
+/// assert_eq!(heap.len(), 7);
+///
+/// assert_eq!(heap.pop()?, Some(1));
+/// assert_eq!(heap.pop()?, Some(2));
+/// assert_eq!(heap.peek(), Some(&3));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`BinaryHeap`]: struct.BinaryHeap.html
+/// [`pop`]: SpillingHeap::pop
+/// [`bincode`]: https://docs.rs/bincode/1/bincode/
+pub struct SpillingHeap<T, C = MaxComparator> {
+    hot: BinaryHeap<T, C>,
+    runs: Vec<Run<T>>,
+    threshold: usize,
+    len: usize,
+    cmp: C,
+}
+
+impl<T, C> fmt::Debug for SpillingHeap<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SpillingHeap")
+            .field("len", &self.len)
+            .field("in_memory", &self.hot.len())
+            .field("runs", &self.runs.len())
+            .finish()
+    }
+}
+
+impl<T: Ord + Serialize + DeserializeOwned> Default for SpillingHeap<T> {
+    fn default() -> Self {
+        SpillingHeap::new()
+    }
+}
+
+impl<T: Ord + Serialize + DeserializeOwned> SpillingHeap<T> {
+    /// Creates an empty max-heap that keeps up to
+    /// [`DEFAULT_SPILL_THRESHOLD`] elements in memory.
+    ///
+    /// [`DEFAULT_SPILL_THRESHOLD`]: constant.DEFAULT_SPILL_THRESHOLD.html
+    #[must_use]
+    pub fn new() -> Self {
+        SpillingHeap::with_threshold(DEFAULT_SPILL_THRESHOLD)
+    }
+
+    /// Creates an empty max-heap that keeps up to `threshold` elements in
+    /// memory.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threshold` is less than 2.
+    #[must_use]
+    pub fn with_threshold(threshold: usize) -> Self {
+        SpillingHeap::with_threshold_cmp(threshold, MaxComparator)
+    }
+}
+
+impl<T, C: Compare<T> + Clone> SpillingHeap<T, C> {
+    /// Creates an empty heap ordered by `cmp` that keeps up to `threshold`
+    /// elements in memory.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threshold` is less than 2.
+    pub fn with_threshold_cmp(threshold: usize, cmp: C) -> Self {
+        assert!(threshold >= 2, "spill threshold must be at least 2");
+        SpillingHeap {
+            hot: BinaryHeap::from_vec_cmp(Vec::new(), cmp.clone()),
+            runs: Vec::new(),
+            threshold,
+            len: 0,
+            cmp,
+        }
+    }
+}
+
+impl<T, C> SpillingHeap<T, C> {
+    /// Returns the maximum number of elements kept in memory.
+    #[must_use]
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// Returns the number of spilled runs not yet exhausted.
+    #[must_use]
+    pub fn runs(&self) -> usize {
+        self.runs.len()
+    }
+
+    /// Returns the number of elements in the heap, including spilled ones.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of elements held in memory by the heap itself,
+    /// not counting the greatest element of each run.
+    #[must_use]
+    pub fn in_memory_len(&self) -> usize {
+        self.hot.len()
+    }
+
+    /// Drops all elements and deletes all temporary files.
+    pub fn clear(&mut self) {
+        self.hot.clear();
+        self.runs.clear();
+        self.len = 0;
+    }
+}
+
+impl<T: Serialize + DeserializeOwned, C: Compare<T>> SpillingHeap<T, C> {
+    /// Returns the greatest element, or `None` if the heap is empty.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*r*), where *r* is the number of [`runs`]. Never touches the disk.
+    ///
+    /// [`runs`]: SpillingHeap::runs
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        match (self.hot.peek(), self.best_run()) {
+            (Some(top), Some(i)) if self.cmp.compares_lt(top, &self.runs[i].head) => {
+                Some(&self.runs[i].head)
+            }
+            (None, Some(i)) => Some(&self.runs[i].head),
+            (top, _) => top,
+        }
+    }
+
+    /// Pushes an item onto the heap, spilling to a new temporary file if
+    /// the in-memory part grows beyond the threshold.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the temporary file cannot be created or written.
+    /// The item is pushed regardless, leaving the in-memory part above the
+    /// threshold until a later push spills successfully.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)), plus *O*(*t* log(*t*)) and *t*/2 writes every *t*/2
+    /// pushes, where *t* is the threshold.
+    pub fn push(&mut self, item: T) -> io::Result<()> {
+        self.hot.push(item);
+        self.len += 1;
+        if self.hot.len() > self.threshold {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    /// Removes the greatest element and returns it, or `None` if the heap is
+    /// empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the next element of a run cannot be read back.
+    /// The heap is unchanged in that case, but the run is likely unusable.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*) + *r*), where *r* is the number of [`runs`], plus one
+    /// buffered read if the element comes from a run.
+    ///
+    /// [`runs`]: SpillingHeap::runs
+    pub fn pop(&mut self) -> io::Result<Option<T>> {
+        let i = match self.best_run() {
+            Some(i)
+                if self
+                    .hot
+                    .peek()
+                    .map_or(true, |top| self.cmp.compares_lt(top, &self.runs[i].head)) =>
+            {
+                i
+            }
+            _ => {
+                let item = self.hot.pop();
+                if item.is_some() {
+                    self.len -= 1;
+                }
+                return Ok(item);
+            }
+        };
+        let run = &mut self.runs[i];
+        let item = if run.remaining > 0 {
+            let next = bincode::deserialize_from(&mut run.reader).map_err(|e| into_io(*e))?;
+            run.remaining -= 1;
+            mem::replace(&mut run.head, next)
+        } else {
+            self.runs.swap_remove(i).head
+        };
+        self.len -= 1;
+        Ok(Some(item))
+    }
+
+    /// Returns the index of the run with the greatest head.
+    fn best_run(&self) -> Option<usize> {
+        let mut best: Option<usize> = None;
+        for (i, run) in self.runs.iter().enumerate() {
+            if best.map_or(true, |b| {
+                self.cmp.compares_gt(&run.head, &self.runs[b].head)
+            }) {
+                best = Some(i);
+            }
+        }
+        best
+    }
+
+    /// Writes the lesser half of the in-memory elements to a new run.
+    fn spill(&mut self) -> io::Result<()> {
+        let mut data: Vec<T> = self.hot.drain().collect();
+        let cmp = &self.cmp;
+        data.sort_unstable_by(|a, b| cmp.compare(a, b));
+        // `data` is sorted in ascending order: the run is written from its
+        // greatest element down, and the greatest one stays in memory.
+        let cold = data.len() - self.threshold / 2;
+        let reader = match write_run(&data[..cold - 1]) {
+            Ok(reader) => reader,
+            Err(e) => {
+                self.hot.extend(data);
+                return Err(e);
+            }
+        };
+        self.hot.extend(data.drain(cold..));
+        let head = data.pop().expect("a spilled run is never empty");
+        self.runs.push(Run {
+            reader,
+            head,
+            remaining: cold - 1,
+        });
+        Ok(())
+    }
+}
+
+/// Writes `items` to a temporary file in reverse order and rewinds it.
+fn write_run<T: Serialize>(items: &[T]) -> io::Result<BufReader<File>> {
+    let mut writer = BufWriter::new(tempfile::tempfile()?);
+    for item in items.iter().rev() {
+        bincode::serialize_into(&mut writer, item).map_err(|e| into_io(*e))?;
+    }
+    let mut file = writer.into_inner().map_err(|e| e.into_error())?;
+    file.seek(SeekFrom::Start(0))?;
+    Ok(BufReader::new(file))
+}
+
+fn into_io(e: bincode::ErrorKind) -> io::Error {
+    match e {
+        bincode::ErrorKind::Io(e) => e,
+        e => io::Error::new(io::ErrorKind::InvalidData, e),
+    }
+}
@@ -0,0 +1,180 @@
+//! Per-worker priority queues with work stealing, built on [`BinaryHeap`].
+//!
+//! [`BinaryHeap`]: struct.BinaryHeap.html
+
+use crate::binary_heap::{BinaryHeap, MaxComparator};
+use crate::sharded::lock_best;
+use crate::util::{lock, try_lock};
+use compare::Compare;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// A set of per-worker priority queues where idle workers steal from busy
+/// ones.
+///
+/// Each worker, identified by its index, owns a local [`BinaryHeap`]. A
+/// worker pushes to and pops from its own heap, so workers only contend
+/// when one of them runs dry: [`pop`] then steals the best item among the
+/// tops of the other workers' heaps. Items therefore come out in priority
+/// order per worker, and only approximately in global priority order.
+///
+/// Stealing skips heaps that are locked at that moment rather than waiting
+/// for them. A comparator that panics leaves the affected heap valid; later
+/// operations ignore the resulting lock poisoning.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::WorkStealingPriorityPool;
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// let pool = Arc::new(WorkStealingPriorityPool::new(2));
+/// // Worker 0 gets all the work up front.
+/// for job in 0..100 {
+///     pool.push(0, job);
+/// }
+///
+/// let thief = {
+///     let pool = Arc::clone(&pool);
+///     thread::spawn(move || {
+///         let mut done = 0;
+///         while pool.pop(1).is_some() {
+///             done += 1;
+///         }
+///         done
+///     })
+/// };
+/// let mut done = 0;
+/// while pool.pop(0).is_some() {
+///     done += 1;
+/// }
+/// assert_eq!(done + thief.join().unwrap(), 100);
+/// ```
+///
+/// [`BinaryHeap`]: struct.BinaryHeap.html
+/// [`pop`]: WorkStealingPriorityPool::pop
+pub struct WorkStealingPriorityPool<T, C = MaxComparator> {
+    locals: Vec<Mutex<BinaryHeap<T, C>>>,
+    cmp: C,
+    len: AtomicUsize,
+}
+
+impl<T: fmt::Debug, C> fmt::Debug for WorkStealingPriorityPool<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WorkStealingPriorityPool")
+            .field("locals", &self.locals)
+            .finish()
+    }
+}
+
+impl<T: Ord> WorkStealingPriorityPool<T> {
+    /// Creates a pool of empty max-heaps for `workers` workers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `workers` is zero.
+    #[must_use]
+    pub fn new(workers: usize) -> Self {
+        WorkStealingPriorityPool::with_cmp(workers, MaxComparator)
+    }
+}
+
+impl<T, C: Compare<T> + Clone> WorkStealingPriorityPool<T, C> {
+    /// Creates a pool for `workers` workers whose heaps are ordered by `cmp`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `workers` is zero.
+    pub fn with_cmp(workers: usize, cmp: C) -> Self {
+        assert!(
+            workers > 0,
+            "a work-stealing pool needs at least one worker"
+        );
+        WorkStealingPriorityPool {
+            locals: (0..workers)
+                .map(|_| Mutex::new(BinaryHeap::from_vec_cmp(vec![], cmp.clone())))
+                .collect(),
+            cmp,
+            len: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl<T, C: Compare<T>> WorkStealingPriorityPool<T, C> {
+    /// Pushes an item onto the local heap of `worker`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `worker` is not less than [`workers`].
+    ///
+    /// [`workers`]: WorkStealingPriorityPool::workers
+    pub fn push(&self, worker: usize, item: T) {
+        let mut local = lock(&self.locals[worker]);
+        local.push(item);
+        self.len.fetch_add(1, Ordering::Release);
+    }
+
+    /// Removes the greatest item from the local heap of `worker`, or steals
+    /// one from another worker if the local heap is empty.
+    ///
+    /// Returns `None` if no item was found.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `worker` is not less than [`workers`].
+    ///
+    /// [`workers`]: WorkStealingPriorityPool::workers
+    pub fn pop(&self, worker: usize) -> Option<T> {
+        let local = lock(&self.locals[worker]).pop();
+        let item = local.or_else(|| self.steal(worker));
+        if item.is_some() {
+            self.len.fetch_sub(1, Ordering::Release);
+        }
+        item
+    }
+
+    /// Removes the best item among the tops of the other workers' heaps,
+    /// holding at most two locks at a time.
+    fn steal(&self, thief: usize) -> Option<T> {
+        let n = self.locals.len();
+        let others = (1..n).map(|i| &self.locals[(thief + i) % n]);
+        lock_best(others, &self.cmp, try_lock)?.pop()
+    }
+
+    /// Returns the number of items in the local heap of `worker`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `worker` is not less than [`workers`].
+    ///
+    /// [`workers`]: WorkStealingPriorityPool::workers
+    #[must_use]
+    pub fn local_len(&self, worker: usize) -> usize {
+        lock(&self.locals[worker]).len()
+    }
+
+    /// Returns the number of items in the pool.
+    ///
+    /// The value may be stale as soon as it is returned if other threads are
+    /// pushing or popping.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Acquire)
+    }
+
+    /// Checks if the pool is empty. See [`len`] for caveats.
+    ///
+    /// [`len`]: WorkStealingPriorityPool::len
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of workers.
+    #[must_use]
+    pub fn workers(&self) -> usize {
+        self.locals.len()
+    }
+}
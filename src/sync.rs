@@ -0,0 +1,156 @@
+//! A [`BinaryHeap`] that consumers can block on.
+//!
+//! [`BinaryHeap`]: struct.BinaryHeap.html
+
+use crate::binary_heap::{BinaryHeap, MaxComparator};
+use crate::util;
+use compare::Compare;
+use std::fmt;
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+
+/// A thread-safe [`BinaryHeap`] whose consumers can wait for items.
+///
+/// Every operation takes a single lock. [`push`] wakes one waiting consumer;
+/// [`pop_wait`] blocks until an item is available and [`pop_timeout`] gives up
+/// after a timeout. Share the heap between threads with an `Arc`.
+///
+/// A comparator that panics leaves the heap valid; later operations ignore
+/// the resulting lock poisoning.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::SyncBinaryHeap;
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// let heap = Arc::new(SyncBinaryHeap::new());
+/// let worker = {
+///     let heap = Arc::clone(&heap);
+///     thread::spawn(move || {
+///         let mut jobs = Vec::new();
+///         while let Some(job) = heap.pop_wait() {
+///             jobs.push(job);
+///         }
+///         jobs
+///     })
+/// };
+///
+/// heap.push(Some(1));
+/// heap.push(Some(3));
+/// // `None` sorts below every job, so it is only seen once the jobs are done.
+/// heap.push(None);
+/// let jobs = worker.join().unwrap();
+/// assert_eq!(jobs.len(), 2);
+/// ```
+///
+/// [`BinaryHeap`]: struct.BinaryHeap.html
+/// [`push`]: SyncBinaryHeap::push
+/// [`pop_wait`]: SyncBinaryHeap::pop_wait
+/// [`pop_timeout`]: SyncBinaryHeap::pop_timeout
+pub struct SyncBinaryHeap<T, C = MaxComparator> {
+    heap: Mutex<BinaryHeap<T, C>>,
+    available: Condvar,
+}
+
+impl<T: fmt::Debug, C> fmt::Debug for SyncBinaryHeap<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SyncBinaryHeap")
+            .field("heap", &self.heap)
+            .finish()
+    }
+}
+
+impl<T: Ord> Default for SyncBinaryHeap<T> {
+    fn default() -> Self {
+        SyncBinaryHeap::new()
+    }
+}
+
+impl<T: Ord> SyncBinaryHeap<T> {
+    /// Creates an empty max-heap.
+    #[must_use]
+    pub fn new() -> Self {
+        SyncBinaryHeap::from_heap(BinaryHeap::new())
+    }
+}
+
+impl<T, C: Compare<T>> From<BinaryHeap<T, C>> for SyncBinaryHeap<T, C> {
+    fn from(heap: BinaryHeap<T, C>) -> Self {
+        SyncBinaryHeap::from_heap(heap)
+    }
+}
+
+impl<T, C: Compare<T>> SyncBinaryHeap<T, C> {
+    /// Wraps an existing heap.
+    pub fn from_heap(heap: BinaryHeap<T, C>) -> Self {
+        SyncBinaryHeap {
+            heap: Mutex::new(heap),
+            available: Condvar::new(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BinaryHeap<T, C>> {
+        util::lock(&self.heap)
+    }
+
+    /// Pushes an item onto the heap and wakes one waiting consumer.
+    pub fn push(&self, item: T) {
+        self.lock().push(item);
+        self.available.notify_one();
+    }
+
+    /// Removes the greatest item and returns it, or `None` if the heap is
+    /// empty. Never waits for an item.
+    pub fn try_pop(&self) -> Option<T> {
+        self.lock().pop()
+    }
+
+    /// Removes the greatest item and returns it, blocking until the heap is
+    /// not empty.
+    pub fn pop_wait(&self) -> T {
+        let mut heap = self.lock();
+        loop {
+            if let Some(item) = heap.pop() {
+                return item;
+            }
+            heap = self.available.wait(heap).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    /// Removes the greatest item and returns it, blocking for at most
+    /// `timeout` until the heap is not empty.
+    ///
+    /// Returns `None` if no item arrived in time.
+    pub fn pop_timeout(&self, timeout: Duration) -> Option<T> {
+        let heap = self.lock();
+        let (mut heap, _) = self
+            .available
+            .wait_timeout_while(heap, timeout, |heap| heap.is_empty())
+            .unwrap_or_else(|e| e.into_inner());
+        heap.pop()
+    }
+
+    /// Returns the number of items in the heap.
+    ///
+    /// The value may be stale as soon as it is returned if other threads are
+    /// pushing or popping.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Checks if the heap is empty. See [`len`] for caveats.
+    ///
+    /// [`len`]: SyncBinaryHeap::len
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Consumes the wrapper and returns the heap.
+    pub fn into_inner(self) -> BinaryHeap<T, C> {
+        self.heap.into_inner().unwrap_or_else(|e| e.into_inner())
+    }
+}
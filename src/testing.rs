@@ -0,0 +1,158 @@
+//! Testing support.
+//!
+//! Requires the `testing` feature.
+
+use crate::binary_heap::{BinaryHeap, MaxComparator};
+use compare::Compare;
+use proptest::arbitrary::any_with;
+use proptest::collection::{vec, SizeRange, VecStrategy};
+use proptest::strategy::{Map, Strategy};
+use quickcheck::Gen;
+use std::cell::Cell;
+use std::cmp::Ordering;
+
+/// A proptest strategy generating a [`BinaryHeap`].
+///
+/// [`BinaryHeap`]: struct.BinaryHeap.html
+pub type HeapStrategy<S, C> =
+    Map<VecStrategy<S>, fn(Vec<<S as Strategy>::Value>) -> BinaryHeap<<S as Strategy>::Value, C>>;
+
+/// Returns a proptest strategy generating heaps whose elements are drawn
+/// from `element` and whose length lies in `size`.
+///
+/// Heaps shrink like the vector of their elements, by dropping elements and
+/// shrinking the rest, and every shrunk vector is heapified again, so each
+/// value tried is a valid heap.
+///
+/// This function is available with the `testing` feature. The
+/// [`proptest::arbitrary::Arbitrary`] implementation for `BinaryHeap` uses
+/// it too.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::{heap_strategy, BinaryHeap, MinComparator};
+/// use proptest::prelude::*;
+///
+/// proptest!(|(heap in heap_strategy::<_, MinComparator>(0..100u32, 0..20))| {
+///     let heap: BinaryHeap<u32, MinComparator> = heap;
+///     let sorted = heap.clone().into_sorted_vec();
+///     prop_assert_eq!(heap.peek(), sorted.last());
+/// });
+/// ```
+///
+/// [`proptest::arbitrary::Arbitrary`]: https://docs.rs/proptest/1/proptest/arbitrary/trait.Arbitrary.html
+pub fn heap_strategy<S, C>(element: S, size: impl Into<SizeRange>) -> HeapStrategy<S, C>
+where
+    S: Strategy,
+    C: Compare<S::Value> + Default,
+{
+    vec(element, size).prop_map(BinaryHeap::from_vec as fn(_) -> _)
+}
+
+/// Generates heaps of arbitrary elements with any comparator that has a
+/// `Default`, such as [`MaxComparator`] and [`MinComparator`].
+///
+/// [`MaxComparator`]: struct.MaxComparator.html
+/// [`MinComparator`]: struct.MinComparator.html
+impl<T, C> proptest::arbitrary::Arbitrary for BinaryHeap<T, C>
+where
+    T: proptest::arbitrary::Arbitrary,
+    C: Compare<T> + Default,
+{
+    type Parameters = (SizeRange, T::Parameters);
+    type Strategy = HeapStrategy<T::Strategy, C>;
+
+    fn arbitrary_with((size, args): Self::Parameters) -> Self::Strategy {
+        heap_strategy(any_with::<T>(args), size)
+    }
+}
+
+/// Generates heaps of arbitrary elements with any comparator that has a
+/// `Default`. Heaps shrink like the vector of their elements, heapified
+/// again, so every shrunk value is a valid heap.
+impl<T, C> quickcheck::Arbitrary for BinaryHeap<T, C>
+where
+    T: quickcheck::Arbitrary,
+    C: Compare<T> + Default + Clone + 'static,
+{
+    fn arbitrary(g: &mut Gen) -> Self {
+        BinaryHeap::from_vec(Vec::arbitrary(g))
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        Box::new(self.clone().into_vec().shrink().map(BinaryHeap::from_vec))
+    }
+}
+
+/// A comparator that panics on its `n`th comparison, for testing that code
+/// using a heap stays sound when a comparison panics.
+///
+/// Until then, and after it, it orders like the wrapped comparator. Clones
+/// share nothing: each counts its own comparisons from the count at the
+/// time it was cloned.
+///
+/// This type is available with the `testing` feature.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::{BinaryHeap, PanickingComparator};
+/// use std::panic::{catch_unwind, AssertUnwindSafe};
+///
+/// let mut heap = BinaryHeap::from_vec_cmp(vec![], PanickingComparator::new(3));
+/// let mut panics = 0;
+/// for i in 1..=4 {
+///     // One of these pushes makes the third comparison.
+///     if catch_unwind(AssertUnwindSafe(|| heap.push(i))).is_err() {
+///         panics += 1;
+///     }
+/// }
+/// assert!(panics > 0);
+///
+/// // Every element is still there, though maybe no longer in heap order.
+/// assert_eq!(heap.len(), 4);
+/// let mut items = heap.into_vec();
+/// items.sort();
+/// assert_eq!(items, [1, 2, 3, 4]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct PanickingComparator<C = MaxComparator> {
+    inner: C,
+    panic_at: usize,
+    count: Cell<usize>,
+}
+
+impl PanickingComparator {
+    /// Creates a comparator ordering like [`MaxComparator`] that panics on
+    /// its `n`th comparison, counting from 1.
+    ///
+    /// [`MaxComparator`]: struct.MaxComparator.html
+    #[must_use]
+    pub fn new(n: usize) -> Self {
+        PanickingComparator::with_cmp(n, MaxComparator)
+    }
+}
+
+impl<C> PanickingComparator<C> {
+    /// Creates a comparator ordering like `inner` that panics on its `n`th
+    /// comparison, counting from 1.
+    pub fn with_cmp(n: usize, inner: C) -> Self {
+        PanickingComparator {
+            inner,
+            panic_at: n,
+            count: Cell::new(0),
+        }
+    }
+}
+
+impl<T, C: Compare<T>> Compare<T> for PanickingComparator<C> {
+    fn compare(&self, l: &T, r: &T) -> Ordering {
+        let count = self.count.get() + 1;
+        self.count.set(count);
+        if count == self.panic_at {
+            panic!("PanickingComparator: comparison {} panics", count);
+        }
+        self.inner.compare(l, r)
+    }
+}
@@ -0,0 +1,413 @@
+//! A heap that reports where its elements move.
+
+use crate::binary_heap::MaxComparator;
+use crate::queue::{IndexedPriorityQueue, PriorityQueue};
+use compare::Compare;
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::slice;
+
+/// Receives the positions of the elements of a [`TrackedBinaryHeap`].
+///
+/// [`on_move`] is called with every element that lands at a new index,
+/// including newly pushed ones, once it is in place, so the last index
+/// reported for an element is always its current one. [`on_remove`] is
+/// called with every element that leaves the heap.
+///
+/// [`OnMove`] adapts a closure and [`PositionMap`] keeps an index map up to
+/// date.
+///
+/// [`TrackedBinaryHeap`]: struct.TrackedBinaryHeap.html
+/// [`on_move`]: PositionTracker::on_move
+/// [`on_remove`]: PositionTracker::on_remove
+/// [`OnMove`]: struct.OnMove.html
+/// [`PositionMap`]: struct.PositionMap.html
+pub trait PositionTracker<T> {
+    /// Called when `item` was moved to `index`.
+    fn on_move(&mut self, item: &T, index: usize);
+
+    /// Called when `item` was removed from the heap.
+    #[inline]
+    fn on_remove(&mut self, item: &T) {
+        let _ = item;
+    }
+}
+
+/// A [`PositionTracker`] that calls a closure with each element and its new
+/// index, or `None` when it was removed.
+///
+/// [`PositionTracker`]: trait.PositionTracker.html
+#[derive(Clone, Copy, Default, Debug)]
+pub struct OnMove<F>(pub F);
+
+impl<T, F: FnMut(&T, Option<usize>)> PositionTracker<T> for OnMove<F> {
+    fn on_move(&mut self, item: &T, index: usize) {
+        (self.0)(item, Some(index));
+    }
+
+    fn on_remove(&mut self, item: &T) {
+        (self.0)(item, None);
+    }
+}
+
+/// A [`PositionTracker`] that maps a key of each element, such as an id, to
+/// its index in the heap.
+///
+/// The keys of the elements in the heap must be distinct. To change the
+/// priority of an element, look up its index by key and pass an element
+/// with the same key to [`TrackedBinaryHeap::replace_at`].
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::{PositionMap, TrackedBinaryHeap};
+///
+/// // (priority, id)
+/// let mut heap = TrackedBinaryHeap::new(PositionMap::new(|&(_, id): &(u32, char)| id));
+/// heap.extend(vec![(3, 'a'), (8, 'b'), (5, 'c')]);
+///
+/// let i = heap.tracker().index_of(&'a').unwrap();
+/// heap.replace_at(i, (9, 'a'));
+/// assert_eq!(heap.tracker().index_of(&'a'), Some(0));
+///
+/// assert_eq!(heap.pop(), Some((9, 'a')));
+/// assert_eq!(heap.tracker().index_of(&'a'), None);
+/// ```
+///
+/// [`PositionTracker`]: trait.PositionTracker.html
+/// [`TrackedBinaryHeap::replace_at`]: struct.TrackedBinaryHeap.html#method.replace_at
+pub struct PositionMap<K, F, S = RandomState> {
+    key: F,
+    map: HashMap<K, usize, S>,
+}
+
+impl<K: fmt::Debug, F, S> fmt::Debug for PositionMap<K, F, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.map.iter()).finish()
+    }
+}
+
+impl<K, F> PositionMap<K, F> {
+    /// Creates an empty map keyed by what `key` returns for each element.
+    pub fn new(key: F) -> Self {
+        PositionMap::with_hasher(key, RandomState::new())
+    }
+}
+
+impl<K, F, S> PositionMap<K, F, S> {
+    /// Creates an empty map keyed by what `key` returns for each element,
+    /// which hashes the keys with `hasher`.
+    pub fn with_hasher(key: F, hasher: S) -> Self {
+        PositionMap {
+            key,
+            map: HashMap::with_hasher(hasher),
+        }
+    }
+
+    /// Returns the underlying map from keys to indices.
+    #[must_use]
+    pub fn map(&self) -> &HashMap<K, usize, S> {
+        &self.map
+    }
+}
+
+impl<K: Hash + Eq, F, S: BuildHasher> PositionMap<K, F, S> {
+    /// Returns the index of the element with key `key`, or `None` if there
+    /// is none in the heap.
+    #[must_use]
+    pub fn index_of(&self, key: &K) -> Option<usize> {
+        self.map.get(key).copied()
+    }
+}
+
+impl<T, K: Hash + Eq, F: Fn(&T) -> K, S: BuildHasher> PositionTracker<T> for PositionMap<K, F, S> {
+    fn on_move(&mut self, item: &T, index: usize) {
+        self.map.insert((self.key)(item), index);
+    }
+
+    fn on_remove(&mut self, item: &T) {
+        self.map.remove(&(self.key)(item));
+    }
+}
+
+/// A binary heap that reports every change of position of its elements to a
+/// [`PositionTracker`].
+///
+/// Knowing where each element is lets callers change its priority with
+/// [`replace_at`] or take it out with [`remove_at`] in *O*(log(*n*)),
+/// without a dedicated indexed heap. The heap sifts by swapping, and each
+/// displaced element is reported once per level it moves, so every
+/// operation makes *O*(log(*n*)) calls to the tracker.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::{OnMove, TrackedBinaryHeap};
+///
+/// let mut log = Vec::new();
+/// let mut heap = TrackedBinaryHeap::new(OnMove(|&x: &u32, i| log.push((x, i))));
+/// heap.push(1);
+/// heap.push(5);
+/// assert_eq!(heap.pop(), Some(5));
+/// drop(heap);
+///
+/// assert_eq!(
+///     log,
+///     [(1, Some(0)), (1, Some(1)), (5, Some(0)), (5, None), (1, Some(0))]
+/// );
+/// ```
+///
+/// [`PositionTracker`]: trait.PositionTracker.html
+/// [`replace_at`]: TrackedBinaryHeap::replace_at
+/// [`remove_at`]: TrackedBinaryHeap::remove_at
+pub struct TrackedBinaryHeap<T, P, C = MaxComparator> {
+    data: Vec<T>,
+    cmp: C,
+    tracker: P,
+}
+
+impl<T: fmt::Debug, P: fmt::Debug, C> fmt::Debug for TrackedBinaryHeap<T, P, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TrackedBinaryHeap")
+            .field("data", &self.data)
+            .field("tracker", &self.tracker)
+            .finish()
+    }
+}
+
+impl<T: Ord, P: PositionTracker<T>> TrackedBinaryHeap<T, P> {
+    /// Creates an empty max-heap that reports to `tracker`.
+    pub fn new(tracker: P) -> Self {
+        TrackedBinaryHeap::with_cmp(MaxComparator, tracker)
+    }
+}
+
+impl<T, P, C> TrackedBinaryHeap<T, P, C> {
+    /// Returns the number of elements in the heap.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns the element at `index`, or `None` if it is out of bounds.
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.data.get(index)
+    }
+
+    /// Returns the elements in the order of their indices.
+    #[must_use]
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    /// Returns an iterator visiting all elements in the order of their
+    /// indices.
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.data.iter()
+    }
+
+    /// Returns the tracker.
+    #[must_use]
+    pub fn tracker(&self) -> &P {
+        &self.tracker
+    }
+
+    /// Returns the tracker mutably.
+    ///
+    /// Resetting the positions it recorded leaves it out of date until the
+    /// elements are moved again.
+    pub fn tracker_mut(&mut self) -> &mut P {
+        &mut self.tracker
+    }
+
+    /// Consumes the heap and returns its elements in heap layout, without
+    /// reporting them, together with the tracker.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_parts(self) -> (Vec<T>, P) {
+        (self.data, self.tracker)
+    }
+}
+
+impl<T, P: PositionTracker<T>, C: Compare<T>> TrackedBinaryHeap<T, P, C> {
+    /// Creates an empty heap ordered by `cmp` that reports to `tracker`.
+    pub fn with_cmp(cmp: C, tracker: P) -> Self {
+        TrackedBinaryHeap {
+            data: Vec::new(),
+            cmp,
+            tracker,
+        }
+    }
+
+    /// Returns the greatest element, or `None` if the heap is empty.
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.data.first()
+    }
+
+    /// Pushes an item onto the heap.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)).
+    pub fn push(&mut self, item: T) {
+        self.data.push(item);
+        self.sift_up(self.data.len() - 1);
+    }
+
+    /// Removes the greatest element and returns it, or `None` if the heap is
+    /// empty.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)).
+    pub fn pop(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        Some(self.remove_at(0))
+    }
+
+    /// Removes the element at `index` and returns it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)).
+    pub fn remove_at(&mut self, index: usize) -> T {
+        let item = self.data.swap_remove(index);
+        self.tracker.on_remove(&item);
+        if index < self.data.len() {
+            self.sift(index);
+        }
+        item
+    }
+
+    /// Replaces the element at `index` with `item`, moves `item` to its
+    /// place in the heap, and returns the old element.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)).
+    pub fn replace_at(&mut self, index: usize, item: T) -> T {
+        let old = std::mem::replace(&mut self.data[index], item);
+        self.tracker.on_remove(&old);
+        self.sift(index);
+        old
+    }
+
+    /// Removes all elements, reporting each of them.
+    pub fn clear(&mut self) {
+        for item in self.data.drain(..) {
+            self.tracker.on_remove(&item);
+        }
+    }
+
+    /// Moves the element at `pos`, which was just put there, up or down to
+    /// its place.
+    fn sift(&mut self, pos: usize) {
+        if pos > 0
+            && self
+                .cmp
+                .compares_gt(&self.data[pos], &self.data[(pos - 1) / 2])
+        {
+            self.sift_up(pos);
+        } else {
+            self.sift_down(pos);
+        }
+    }
+
+    fn sift_up(&mut self, mut pos: usize) {
+        while pos > 0 {
+            let parent = (pos - 1) / 2;
+            if self.cmp.compares_le(&self.data[pos], &self.data[parent]) {
+                break;
+            }
+            self.data.swap(pos, parent);
+            self.tracker.on_move(&self.data[pos], pos);
+            pos = parent;
+        }
+        self.tracker.on_move(&self.data[pos], pos);
+    }
+
+    fn sift_down(&mut self, mut pos: usize) {
+        let end = self.data.len();
+        loop {
+            let mut child = 2 * pos + 1;
+            if child >= end {
+                break;
+            }
+            if child + 1 < end
+                && self
+                    .cmp
+                    .compares_le(&self.data[child], &self.data[child + 1])
+            {
+                child += 1;
+            }
+            if self.cmp.compares_ge(&self.data[pos], &self.data[child]) {
+                break;
+            }
+            self.data.swap(pos, child);
+            self.tracker.on_move(&self.data[pos], pos);
+            pos = child;
+        }
+        self.tracker.on_move(&self.data[pos], pos);
+    }
+}
+
+impl<T, P: PositionTracker<T>, C: Compare<T>> Extend<T> for TrackedBinaryHeap<T, P, C> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+impl<T, P: PositionTracker<T>, C: Compare<T>> PriorityQueue<T> for TrackedBinaryHeap<T, P, C> {
+    fn push(&mut self, item: T) {
+        TrackedBinaryHeap::push(self, item);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        TrackedBinaryHeap::pop(self)
+    }
+
+    fn peek(&self) -> Option<&T> {
+        TrackedBinaryHeap::peek(self)
+    }
+
+    fn len(&self) -> usize {
+        TrackedBinaryHeap::len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        TrackedBinaryHeap::is_empty(self)
+    }
+}
+
+impl<T, P: PositionTracker<T>, C: Compare<T>> IndexedPriorityQueue<T>
+    for TrackedBinaryHeap<T, P, C>
+{
+    fn as_slice(&self) -> &[T] {
+        TrackedBinaryHeap::as_slice(self)
+    }
+
+    fn replace_at(&mut self, index: usize, item: T) -> T {
+        TrackedBinaryHeap::replace_at(self, index, item)
+    }
+}
@@ -0,0 +1,98 @@
+//! A comparator for tuples with a direction per field.
+
+use compare::Compare;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::marker::PhantomData;
+
+/// A [`TupleCmp`] direction that pops greater values of its field first.
+///
+/// [`TupleCmp`]: struct.TupleCmp.html
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Desc;
+
+/// A [`TupleCmp`] direction that pops lesser values of its field first.
+///
+/// [`TupleCmp`]: struct.TupleCmp.html
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Asc;
+
+/// The direction of a field of a [`TupleCmp`], [`Asc`] or [`Desc`].
+///
+/// [`TupleCmp`]: struct.TupleCmp.html
+/// [`Asc`]: struct.Asc.html
+/// [`Desc`]: struct.Desc.html
+pub trait Direction {
+    /// Compares `a` and `b` so that the one to pop first is greater.
+    fn compare<T: Ord + ?Sized>(a: &T, b: &T) -> Ordering;
+}
+
+impl Direction for Desc {
+    fn compare<T: Ord + ?Sized>(a: &T, b: &T) -> Ordering {
+        a.cmp(b)
+    }
+}
+
+impl Direction for Asc {
+    fn compare<T: Ord + ?Sized>(a: &T, b: &T) -> Ordering {
+        b.cmp(a)
+    }
+}
+
+/// The comparator for tuples whose fields are compared in turn, each in the
+/// direction given by the matching element of `D`.
+///
+/// `D` is a tuple of [`Asc`] and [`Desc`] markers as long as the elements,
+/// which may have up to 8 fields. The first field that differs decides, so
+/// `TupleCmp<(Desc, Asc)>` pops the highest score first and, among equal
+/// scores, the earliest timestamp.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::{Asc, BinaryHeap, Desc, TupleCmp};
+///
+/// // (score, timestamp)
+/// let mut heap = BinaryHeap::from_vec_cmp(vec![], TupleCmp::<(Desc, Asc)>::new());
+/// heap.extend(vec![(5, 30), (9, 20), (5, 10), (9, 40)]);
+///
+/// assert_eq!(heap.pop(), Some((9, 20)));
+/// assert_eq!(heap.pop(), Some((9, 40)));
+/// assert_eq!(heap.pop(), Some((5, 10)));
+/// ```
+///
+/// [`Asc`]: struct.Asc.html
+/// [`Desc`]: struct.Desc.html
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct TupleCmp<D>(PhantomData<D>);
+
+impl<D> TupleCmp<D> {
+    /// Creates the comparator.
+    #[must_use]
+    pub const fn new() -> Self {
+        TupleCmp(PhantomData)
+    }
+}
+
+macro_rules! tuple_cmp {
+    ($($T:ident $D:ident $i:tt),+) => {
+        impl<$($T: Ord, $D: Direction),+> Compare<($($T,)+)> for TupleCmp<($($D,)+)> {
+            fn compare(&self, a: &($($T,)+), b: &($($T,)+)) -> Ordering {
+                Ordering::Equal $(.then_with(|| $D::compare(&a.$i, &b.$i)))+
+            }
+        }
+    };
+}
+
+tuple_cmp!(A DA 0);
+tuple_cmp!(A DA 0, B DB 1);
+tuple_cmp!(A DA 0, B DB 1, C DC 2);
+tuple_cmp!(A DA 0, B DB 1, C DC 2, E DE 3);
+tuple_cmp!(A DA 0, B DB 1, C DC 2, E DE 3, F DF 4);
+tuple_cmp!(A DA 0, B DB 1, C DC 2, E DE 3, F DF 4, G DG 5);
+tuple_cmp!(A DA 0, B DB 1, C DC 2, E DE 3, F DF 4, G DG 5, H DH 6);
+tuple_cmp!(A DA 0, B DB 1, C DC 2, E DE 3, F DF 4, G DG 5, H DH 6, I DI 7);
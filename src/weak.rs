@@ -0,0 +1,300 @@
+//! A heap of weak references that forgets the dropped ones.
+
+use crate::array::sift_down_slice;
+use crate::binary_heap::MaxComparator;
+use compare::Compare;
+use std::cmp;
+use std::fmt;
+use std::sync::{Arc, Weak};
+
+/// Number of entries below which [`WeakHeap::new`] never compacts on push.
+///
+/// [`WeakHeap::new`]: struct.WeakHeap.html#method.new
+pub const DEFAULT_WEAK_COMPACTION_THRESHOLD: usize = 64;
+
+/// A priority queue of [`Weak`] references, ordered by their targets, that
+/// skips and drops the entries whose targets were deallocated.
+///
+/// The heap does not keep its elements alive: [`push`] stores a `Weak`, and
+/// [`pop`] and [`peek`] return the greatest target that still has an
+/// [`Arc`] elsewhere, dropping dead entries on the way. This suits
+/// scheduling callbacks for subscribers that may go away before their turn.
+///
+/// A dead target can no longer be compared, so an operation that meets a
+/// dead entry while sifting compacts the heap instead: it drops every dead
+/// entry and rebuilds the rest in *O*(*n*). Dead entries buried in the heap
+/// are only found by compacting, so [`push`] also compacts once the heap
+/// holds twice the entries that were alive after the previous compaction,
+/// and at least `threshold`, which keeps the allocations that dead `Weak`s
+/// hold on to from piling up. [`purge`] compacts on demand.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::WeakHeap;
+/// use std::sync::Arc;
+///
+/// let a = Arc::new(3);
+/// let b = Arc::new(7);
+/// let c = Arc::new(5);
+///
+/// let mut heap = WeakHeap::new();
+/// heap.extend([&a, &b, &c]);
+///
+/// drop(b);
+/// assert_eq!(heap.pop().as_deref(), Some(&5));
+/// assert_eq!(heap.pop().as_deref(), Some(&3));
+/// assert_eq!(heap.pop(), None);
+/// ```
+///
+/// [`Weak`]: https://doc.rust-lang.org/std/sync/struct.Weak.html
+/// [`Arc`]: https://doc.rust-lang.org/std/sync/struct.Arc.html
+/// [`push`]: WeakHeap::push
+/// [`pop`]: WeakHeap::pop
+/// [`peek`]: WeakHeap::peek
+/// [`purge`]: WeakHeap::purge
+pub struct WeakHeap<T, C = MaxComparator> {
+    data: Vec<Weak<T>>,
+    cmp: C,
+    threshold: usize,
+    /// Length at which the next push compacts the heap.
+    compact_at: usize,
+}
+
+impl<T: fmt::Debug, C> fmt::Debug for WeakHeap<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Ord> Default for WeakHeap<T> {
+    fn default() -> Self {
+        WeakHeap::new()
+    }
+}
+
+impl<T: Ord> WeakHeap<T> {
+    /// Creates an empty max-heap that compacts on push from
+    /// [`DEFAULT_WEAK_COMPACTION_THRESHOLD`] entries.
+    ///
+    /// [`DEFAULT_WEAK_COMPACTION_THRESHOLD`]: constant.DEFAULT_WEAK_COMPACTION_THRESHOLD.html
+    #[must_use]
+    pub fn new() -> Self {
+        WeakHeap::with_threshold(DEFAULT_WEAK_COMPACTION_THRESHOLD)
+    }
+
+    /// Creates an empty max-heap that compacts on push from `threshold`
+    /// entries.
+    #[must_use]
+    pub fn with_threshold(threshold: usize) -> Self {
+        WeakHeap::with_threshold_cmp(threshold, MaxComparator)
+    }
+}
+
+impl<T, C> WeakHeap<T, C> {
+    /// Creates an empty heap ordered by `cmp` that compacts on push from
+    /// [`DEFAULT_WEAK_COMPACTION_THRESHOLD`] entries.
+    ///
+    /// [`DEFAULT_WEAK_COMPACTION_THRESHOLD`]: constant.DEFAULT_WEAK_COMPACTION_THRESHOLD.html
+    pub fn with_cmp(cmp: C) -> Self {
+        WeakHeap::with_threshold_cmp(DEFAULT_WEAK_COMPACTION_THRESHOLD, cmp)
+    }
+
+    /// Creates an empty heap ordered by `cmp` that compacts on push from
+    /// `threshold` entries.
+    pub fn with_threshold_cmp(threshold: usize, cmp: C) -> Self {
+        WeakHeap {
+            data: Vec::new(),
+            cmp,
+            threshold,
+            compact_at: threshold,
+        }
+    }
+
+    /// Returns the number of entries below which pushes never compact.
+    #[must_use]
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// Returns the number of entries, including those whose targets were
+    /// dropped since the last compaction.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Checks if the heap has no entries, dead or alive.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns the number of entries whose targets are alive.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*n*).
+    #[must_use]
+    pub fn live_len(&self) -> usize {
+        self.data.iter().filter(|w| w.strong_count() > 0).count()
+    }
+
+    /// Returns an iterator over the targets that are alive, in arbitrary
+    /// order.
+    pub fn iter(&self) -> impl Iterator<Item = Arc<T>> + '_ {
+        self.data.iter().filter_map(Weak::upgrade)
+    }
+
+    /// Drops all entries.
+    pub fn clear(&mut self) {
+        self.data.clear();
+        self.compact_at = self.threshold;
+    }
+}
+
+impl<T, C: Compare<T>> WeakHeap<T, C> {
+    /// Pushes a weak reference to `item`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)), or *O*(*n*) when the push compacts the heap.
+    pub fn push(&mut self, item: &Arc<T>) {
+        if self.data.len() >= self.compact_at {
+            self.compact();
+        }
+        self.data.push(Arc::downgrade(item));
+        if !self.sift_up(self.data.len() - 1, item) {
+            self.compact();
+        }
+    }
+
+    /// Removes the greatest target that is alive and returns it, or `None`
+    /// if there is none left.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)), or *O*(*n*) when it meets a dead entry.
+    pub fn pop(&mut self) -> Option<Arc<T>> {
+        loop {
+            let top = match self.data.first()?.upgrade() {
+                Some(top) => top,
+                None => {
+                    self.compact();
+                    continue;
+                }
+            };
+            let last = self.data.pop().unwrap();
+            if !self.data.is_empty() {
+                self.data[0] = last;
+                let sifted = match self.data[0].upgrade() {
+                    Some(item) => self.sift_down(0, &item),
+                    None => false,
+                };
+                if !sifted {
+                    self.compact();
+                }
+            }
+            return Some(top);
+        }
+    }
+
+    /// Returns the greatest target that is alive, or `None` if there is none
+    /// left.
+    ///
+    /// Takes `&mut self` to drop the dead entries at the top.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(1), or *O*(*n*) when the top entry is dead.
+    pub fn peek(&mut self) -> Option<Arc<T>> {
+        loop {
+            match self.data.first()?.upgrade() {
+                Some(top) => return Some(top),
+                None => self.compact(),
+            }
+        }
+    }
+
+    /// Drops the entries whose targets were deallocated and rebuilds the
+    /// heap from the others.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*n*).
+    pub fn purge(&mut self) {
+        self.compact();
+    }
+
+    fn compact(&mut self) {
+        // Holding the targets keeps them alive, hence comparable, while the
+        // heap is rebuilt.
+        let mut live: Vec<Arc<T>> = self.data.drain(..).filter_map(|w| w.upgrade()).collect();
+        let cmp = |a: &Arc<T>, b: &Arc<T>| self.cmp.compare(a, b);
+        for i in (0..live.len() / 2).rev() {
+            sift_down_slice(&mut live, i, &cmp);
+        }
+        self.data.extend(live.iter().map(Arc::downgrade));
+        self.compact_at = cmp::max(2 * self.data.len(), self.threshold);
+    }
+
+    /// Moves the entry at `pos`, whose target is `item`, up like
+    /// `sift_up_slice`. Returns `false`, leaving the heap to be compacted, if
+    /// it meets a dead entry.
+    fn sift_up(&mut self, mut pos: usize, item: &T) -> bool {
+        while pos > 0 {
+            let parent = (pos - 1) / 2;
+            let above = match self.data[parent].upgrade() {
+                Some(above) => above,
+                None => return false,
+            };
+            if self.cmp.compares_le(item, &above) {
+                break;
+            }
+            self.data.swap(pos, parent);
+            pos = parent;
+        }
+        true
+    }
+
+    /// Moves the entry at `pos`, whose target is `item`, down like
+    /// `sift_down_slice`. Returns `false`, leaving the heap to be compacted,
+    /// if it meets a dead entry.
+    fn sift_down(&mut self, mut pos: usize, item: &T) -> bool {
+        let end = self.data.len();
+        loop {
+            let mut child = 2 * pos + 1;
+            if child >= end {
+                break;
+            }
+            let mut below = match self.data[child].upgrade() {
+                Some(below) => below,
+                None => return false,
+            };
+            if child + 1 < end {
+                let right = match self.data[child + 1].upgrade() {
+                    Some(right) => right,
+                    None => return false,
+                };
+                if self.cmp.compares_le(&below, &right) {
+                    child += 1;
+                    below = right;
+                }
+            }
+            if self.cmp.compares_ge(item, &below) {
+                break;
+            }
+            self.data.swap(pos, child);
+            pos = child;
+        }
+        true
+    }
+}
+
+impl<'a, T: 'a, C: Compare<T>> Extend<&'a Arc<T>> for WeakHeap<T, C> {
+    fn extend<I: IntoIterator<Item = &'a Arc<T>>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
@@ -0,0 +1,217 @@
+//! A weighted fair queuing scheduler built on [`BinaryHeap`].
+//!
+//! [`BinaryHeap`]: struct.BinaryHeap.html
+
+use crate::binary_heap::BinaryHeap;
+use compare::Compare;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+/// Fixed-point scale of virtual time, so that `cost / weight` keeps
+/// precision for small costs and large weights.
+const VIRTUAL_TIME_SCALE: u128 = 1 << 32;
+
+struct WfqEntry<K, T> {
+    finish: u128,
+    seq: u64,
+    flow: K,
+    item: T,
+}
+
+/// Orders entries by earliest virtual finish time, then by arrival.
+#[derive(Clone, Copy, Default, Debug)]
+struct FinishOrder;
+
+impl<K, T> Compare<WfqEntry<K, T>> for FinishOrder {
+    fn compare(&self, a: &WfqEntry<K, T>, b: &WfqEntry<K, T>) -> Ordering {
+        b.finish.cmp(&a.finish).then_with(|| b.seq.cmp(&a.seq))
+    }
+}
+
+struct FlowState {
+    last_finish: u128,
+    queued: usize,
+}
+
+/// A weighted fair queuing (WFQ) scheduler over flows keyed by `K`.
+///
+/// Each item is pushed into a flow together with its cost (for example a
+/// packet length or an estimated run time). Every flow receives a share of
+/// the service proportional to its weight: an item's virtual finish time is
+/// `max(V, F) + cost / weight`, where `F` is the finish time of the previous
+/// item of the same flow, and items are popped in order of finish time.
+///
+/// The virtual time `V` is self-clocked: it is the finish time of the item
+/// popped last (SCFQ). This avoids simulating a fluid GPS server while keeping
+/// the fairness bounds of WFQ within one maximum item cost per flow.
+///
+/// Flows without an explicit weight use the default weight, which is `1`
+/// unless changed with [`set_default_weight`].
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::WfqScheduler;
+///
+/// let mut wfq = WfqScheduler::new();
+/// wfq.set_weight("gold", 3);
+/// for i in 0..6 {
+///     wfq.push("gold", i, 1);
+///     wfq.push("bronze", i, 1);
+/// }
+///
+/// // "gold" receives three slots for every slot of "bronze".
+/// let served: Vec<_> = (0..8).map(|_| wfq.pop().unwrap().0).collect();
+/// assert_eq!(served.iter().filter(|&&flow| flow == "gold").count(), 6);
+/// ```
+///
+/// [`set_default_weight`]: WfqScheduler::set_default_weight
+pub struct WfqScheduler<K, T> {
+    heap: BinaryHeap<WfqEntry<K, T>, FinishOrder>,
+    flows: HashMap<K, FlowState>,
+    weights: HashMap<K, u32>,
+    default_weight: u32,
+    virtual_time: u128,
+    next_seq: u64,
+}
+
+impl<K: fmt::Debug, T: fmt::Debug> fmt::Debug for WfqScheduler<K, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WfqScheduler")
+            .field("weights", &self.weights)
+            .field("default_weight", &self.default_weight)
+            .field(
+                "items",
+                &self
+                    .heap
+                    .iter()
+                    .map(|e| (&e.flow, &e.item))
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl<K: Hash + Eq + Clone, T> Default for WfqScheduler<K, T> {
+    fn default() -> Self {
+        WfqScheduler::new()
+    }
+}
+
+impl<K: Hash + Eq + Clone, T> WfqScheduler<K, T> {
+    /// Creates an empty scheduler in which every flow has weight `1`.
+    #[must_use]
+    pub fn new() -> Self {
+        WfqScheduler {
+            heap: BinaryHeap::from_vec(vec![]),
+            flows: HashMap::new(),
+            weights: HashMap::new(),
+            default_weight: 1,
+            virtual_time: 0,
+            next_seq: 0,
+        }
+    }
+
+    /// Sets the weight of `flow`.
+    ///
+    /// The new weight applies to items pushed afterwards; queued items keep
+    /// the finish times they were given.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `weight` is zero.
+    pub fn set_weight(&mut self, flow: K, weight: u32) {
+        assert!(weight > 0, "flow weight must be positive");
+        self.weights.insert(flow, weight);
+    }
+
+    /// Sets the weight of flows without an explicit weight.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `weight` is zero.
+    pub fn set_default_weight(&mut self, weight: u32) {
+        assert!(weight > 0, "flow weight must be positive");
+        self.default_weight = weight;
+    }
+
+    /// Returns the weight of `flow`.
+    #[must_use]
+    pub fn weight(&self, flow: &K) -> u32 {
+        self.weights.get(flow).copied().unwrap_or(self.default_weight)
+    }
+
+    /// Pushes `item` with the given `cost` into `flow`.
+    pub fn push(&mut self, flow: K, item: T, cost: u64) {
+        let weight = self.weight(&flow);
+        let virtual_time = self.virtual_time;
+        let state = self.flows.entry(flow.clone()).or_insert(FlowState {
+            last_finish: virtual_time,
+            queued: 0,
+        });
+        let start = state.last_finish.max(virtual_time);
+        let finish = start + u128::from(cost) * VIRTUAL_TIME_SCALE / u128::from(weight);
+        state.last_finish = finish;
+        state.queued += 1;
+
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.heap.push(WfqEntry {
+            finish,
+            seq,
+            flow,
+            item,
+        });
+    }
+
+    /// Removes the item with the earliest virtual finish time and returns it
+    /// with its flow, or `None` if the scheduler is empty.
+    pub fn pop(&mut self) -> Option<(K, T)> {
+        let entry = self.heap.pop()?;
+        self.virtual_time = entry.finish;
+        if let Some(state) = self.flows.get_mut(&entry.flow) {
+            state.queued -= 1;
+            if state.queued == 0 {
+                // An idle flow restarts at the virtual time, so its history
+                // can be forgotten.
+                self.flows.remove(&entry.flow);
+            }
+        }
+        Some((entry.flow, entry.item))
+    }
+
+    /// Returns the next item to be popped and its flow, or `None` if the
+    /// scheduler is empty.
+    #[must_use]
+    pub fn peek(&self) -> Option<(&K, &T)> {
+        self.heap.peek().map(|e| (&e.flow, &e.item))
+    }
+
+    /// Returns the number of items queued in `flow`.
+    #[must_use]
+    pub fn flow_len(&self, flow: &K) -> usize {
+        self.flows.get(flow).map_or(0, |state| state.queued)
+    }
+
+    /// Returns the number of flows with queued items.
+    #[must_use]
+    pub fn active_flows(&self) -> usize {
+        self.flows.len()
+    }
+}
+
+impl<K, T> WfqScheduler<K, T> {
+    /// Returns the total number of queued items.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Checks if the scheduler is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+}
@@ -0,0 +1,563 @@
+//! A timing wheel for near-term timers backed by [`BinaryHeap`] for
+//! far-future ones.
+//!
+//! [`BinaryHeap`]: struct.BinaryHeap.html
+
+use crate::tracked::{PositionMap, TrackedBinaryHeap};
+use compare::Compare;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::mem;
+use std::time::{Duration, Instant};
+
+/// Identifies a timer in a [`TimingWheel`], to cancel or reschedule it.
+///
+/// [`TimingWheel`]: struct.TimingWheel.html
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct TimerHandle(u64);
+
+/// What a repeating timer does when [`advance`] runs more than one of its
+/// intervals after its deadline.
+///
+/// [`advance`]: TimingWheel::advance
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MissedTicks {
+    /// Re-arms the timer one interval after its missed deadline, so that it
+    /// expires again on each following `advance` until it has caught up.
+    /// No expiry is lost and the schedule does not drift.
+    Burst,
+    /// Re-arms the timer one interval after the `advance` that expired it.
+    /// The schedule drifts by the lateness of each `advance`.
+    Delay,
+    /// Re-arms the timer at the first deadline of its schedule after the
+    /// `advance` that expired it, skipping the missed ones. The schedule does
+    /// not drift.
+    Skip,
+}
+
+impl Default for MissedTicks {
+    fn default() -> Self {
+        MissedTicks::Burst
+    }
+}
+
+struct Timer<T> {
+    key: u64,
+    value: T,
+    repeat: Option<Repeat<T>>,
+}
+
+/// The schedule of a repeating timer.
+struct Repeat<T> {
+    interval: Duration,
+    /// The deadline the timer is armed for.
+    deadline: Instant,
+    /// Clones the value, which stays in the wheel, for each expiry.
+    clone: fn(&T) -> T,
+}
+
+impl<T> Repeat<T> {
+    /// Returns the next deadline after an expiry at `now`, or `None` if it
+    /// cannot be represented.
+    fn next_deadline(&self, now: Instant, missed: MissedTicks) -> Option<Instant> {
+        match missed {
+            MissedTicks::Burst => self.deadline.checked_add(self.interval),
+            MissedTicks::Delay => now.checked_add(self.interval),
+            MissedTicks::Skip => {
+                let late = now.saturating_duration_since(self.deadline).as_nanos();
+                let nanos = self.interval.as_nanos() * (late / self.interval.as_nanos() + 1);
+                let secs = u64::try_from(nanos / 1_000_000_000).ok()?;
+                let subsec = (nanos % 1_000_000_000) as u32;
+                self.deadline.checked_add(Duration::new(secs, subsec))
+            }
+        }
+    }
+}
+
+struct OverflowEntry<T> {
+    tick: u64,
+    timer: Timer<T>,
+}
+
+fn key_of<T>(entry: &OverflowEntry<T>) -> u64 {
+    entry.timer.key
+}
+
+/// Orders entries by earliest tick, then by insertion order.
+#[derive(Clone, Copy, Default, Debug)]
+struct TickOrder;
+
+impl<T> Compare<OverflowEntry<T>> for TickOrder {
+    fn compare(&self, a: &OverflowEntry<T>, b: &OverflowEntry<T>) -> Ordering {
+        b.tick
+            .cmp(&a.tick)
+            .then_with(|| b.timer.key.cmp(&a.timer.key))
+    }
+}
+
+type OverflowHeap<T> =
+    TrackedBinaryHeap<OverflowEntry<T>, PositionMap<u64, fn(&OverflowEntry<T>) -> u64>, TickOrder>;
+
+/// Where a pending timer is held.
+#[derive(Clone, Copy, Debug)]
+enum Place {
+    /// In `overdue`, at this index.
+    Overdue(usize),
+    /// In this slot of the wheel, at this index.
+    Wheel(usize, usize),
+    /// In the overflow heap, which tracks its index.
+    Overflow,
+}
+
+/// A two-level timer structure: a timing wheel for deadlines within its
+/// horizon and a heap for deadlines beyond it.
+///
+/// The wheel is single-level, not a hierarchy of wheels with coarser ticks:
+/// every deadline past the horizon goes to the heap.
+///
+/// Time is divided into ticks of a fixed resolution. A timer due within
+/// `slots` ticks of the current tick goes into the wheel slot for its tick in
+/// *O*(1); a timer due later goes into an overflow [`BinaryHeap`] in
+/// *O*(log(*n*)) and is popped from there when it expires. Since most timers in
+/// high-rate workloads (connection timeouts, retransmissions) are short, the
+/// common case never touches the heap.
+///
+/// [`schedule`] returns a [`TimerHandle`], with which [`cancel`] and
+/// [`reschedule`] find the timer through a hash map and take it out of its
+/// slot in *O*(1), or out of the overflow heap, which tracks the position of
+/// each entry, in *O*(log(*n*)).
+///
+/// A timer added with [`schedule_repeating`] is re-armed one interval later
+/// each time it expires, until it is cancelled. How a late [`advance`]
+/// re-arms it is set with [`set_missed_ticks`].
+///
+/// Deadlines are rounded up to the next tick, so a timer never fires early
+/// but may fire up to one tick late.
+///
+/// # Examples
+///
+/// ```
+/// use binary_heap_plus::TimingWheel;
+/// use std::time::{Duration, Instant};
+///
+/// let start = Instant::now();
+/// let ms = Duration::from_millis;
+/// let mut wheel = TimingWheel::with_origin(start, ms(1), 64);
+///
+/// wheel.schedule(start + ms(10), "retransmit");
+/// wheel.schedule(start + ms(5_000), "idle timeout");
+/// let probe = wheel.schedule(start + ms(20), "keepalive probe");
+/// assert_eq!(wheel.overflow_len(), 1);
+///
+/// // Traffic arrived: the probe is no longer needed.
+/// assert_eq!(wheel.cancel(probe), Some("keepalive probe"));
+///
+/// assert!(wheel.advance(start + ms(9)).is_empty());
+/// assert_eq!(wheel.advance(start + ms(10)), ["retransmit"]);
+/// assert_eq!(wheel.next_expiration(), Some(start + ms(5_000)));
+/// assert_eq!(wheel.advance(start + ms(6_000)), ["idle timeout"]);
+/// assert!(wheel.is_empty());
+/// ```
+///
+/// [`BinaryHeap`]: struct.BinaryHeap.html
+/// [`TimerHandle`]: struct.TimerHandle.html
+/// [`schedule`]: TimingWheel::schedule
+/// [`cancel`]: TimingWheel::cancel
+/// [`reschedule`]: TimingWheel::reschedule
+/// [`schedule_repeating`]: TimingWheel::schedule_repeating
+/// [`advance`]: TimingWheel::advance
+/// [`set_missed_ticks`]: TimingWheel::set_missed_ticks
+pub struct TimingWheel<T> {
+    origin: Instant,
+    tick: Duration,
+    /// All ticks up to and including this one have been processed.
+    current: u64,
+    slots: Vec<Vec<Timer<T>>>,
+    wheel_len: usize,
+    /// Timers scheduled at or before the current tick.
+    overdue: Vec<Timer<T>>,
+    overflow: OverflowHeap<T>,
+    /// Where each pending timer is, by key.
+    places: HashMap<u64, Place>,
+    next_key: u64,
+    missed_ticks: MissedTicks,
+    /// Repeating timers that expired during the current `advance`.
+    rearm: Vec<Timer<T>>,
+}
+
+impl<T: fmt::Debug> fmt::Debug for TimingWheel<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TimingWheel")
+            .field("tick", &self.tick)
+            .field("current", &self.current)
+            .field("slots", &self.slots.len())
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl<T> TimingWheel<T> {
+    /// Creates an empty wheel starting now, with the given tick resolution
+    /// and number of slots.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tick` is zero or `slots` is zero.
+    pub fn new(tick: Duration, slots: usize) -> Self {
+        TimingWheel::with_origin(Instant::now(), tick, slots)
+    }
+
+    /// Creates an empty wheel whose tick `0` starts at `origin`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tick` is zero or `slots` is zero.
+    pub fn with_origin(origin: Instant, tick: Duration, slots: usize) -> Self {
+        assert!(tick > Duration::from_secs(0), "tick must be positive");
+        assert!(slots > 0, "a timing wheel needs at least one slot");
+        TimingWheel {
+            origin,
+            tick,
+            current: 0,
+            slots: (0..slots).map(|_| Vec::new()).collect(),
+            wheel_len: 0,
+            overdue: Vec::new(),
+            overflow: TrackedBinaryHeap::with_cmp(
+                TickOrder,
+                PositionMap::new(key_of::<T> as fn(&OverflowEntry<T>) -> u64),
+            ),
+            places: HashMap::new(),
+            next_key: 0,
+            missed_ticks: MissedTicks::default(),
+            rearm: Vec::new(),
+        }
+    }
+
+    /// Returns the span of time covered by the wheel itself, or
+    /// `Duration::MAX` if it is longer than that.
+    #[must_use]
+    pub fn horizon(&self) -> Duration {
+        u32::try_from(self.slots.len())
+            .ok()
+            .and_then(|slots| self.tick.checked_mul(slots))
+            .unwrap_or(Duration::MAX)
+    }
+
+    fn ticks_since_origin(&self, t: Instant, round_up: bool) -> u64 {
+        let nanos = t.saturating_duration_since(self.origin).as_nanos();
+        let tick = self.tick.as_nanos();
+        let ticks = if round_up {
+            (nanos + tick - 1) / tick
+        } else {
+            nanos / tick
+        };
+        ticks.min(u128::from(u64::MAX)) as u64
+    }
+
+    fn instant_of(&self, tick: u64) -> Instant {
+        let nanos = self.tick.as_nanos() * u128::from(tick);
+        let secs = (nanos / 1_000_000_000) as u64;
+        let subsec = (nanos % 1_000_000_000) as u32;
+        self.origin + Duration::new(secs, subsec)
+    }
+
+    /// Schedules `value` to expire at `deadline` and returns the handle of
+    /// the timer.
+    ///
+    /// A deadline that has already been processed expires on the next call
+    /// to [`advance`].
+    ///
+    /// [`advance`]: TimingWheel::advance
+    pub fn schedule(&mut self, deadline: Instant, value: T) -> TimerHandle {
+        let key = self.next_key;
+        self.next_key += 1;
+        self.insert(
+            Timer {
+                key,
+                value,
+                repeat: None,
+            },
+            deadline,
+        );
+        TimerHandle(key)
+    }
+
+    /// Schedules `value` to expire every `interval`, starting `interval`
+    /// after the current tick, and returns the handle of the timer.
+    ///
+    /// Each expiry yields a clone of `value`. The timer is re-armed after
+    /// each expiry according to [`missed_ticks`], until it is cancelled, or
+    /// its next deadline cannot be represented by `Instant`.
+    /// [`reschedule`] moves its next deadline, and the schedule continues
+    /// from there.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use binary_heap_plus::{MissedTicks, TimingWheel};
+    /// use std::time::{Duration, Instant};
+    ///
+    /// let start = Instant::now();
+    /// let ms = Duration::from_millis;
+    /// let mut wheel = TimingWheel::with_origin(start, ms(1), 64);
+    /// wheel.set_missed_ticks(MissedTicks::Skip);
+    ///
+    /// let tick = wheel.schedule_repeating(ms(10), "tick");
+    /// assert_eq!(wheel.advance(start + ms(10)), ["tick"]);
+    /// // Two deadlines passed; `Skip` fires once and keeps the schedule.
+    /// assert_eq!(wheel.advance(start + ms(35)), ["tick"]);
+    /// assert_eq!(wheel.next_expiration(), Some(start + ms(40)));
+    ///
+    /// assert_eq!(wheel.cancel(tick), Some("tick"));
+    /// assert!(wheel.is_empty());
+    /// ```
+    ///
+    /// [`missed_ticks`]: TimingWheel::missed_ticks
+    /// [`reschedule`]: TimingWheel::reschedule
+    pub fn schedule_repeating(&mut self, interval: Duration, value: T) -> TimerHandle
+    where
+        T: Clone,
+    {
+        assert!(
+            interval > Duration::from_secs(0),
+            "a repeating timer needs a positive interval"
+        );
+        let key = self.next_key;
+        self.next_key += 1;
+        let deadline = self.instant_of(self.current) + interval;
+        let repeat = Repeat {
+            interval,
+            deadline,
+            clone: T::clone,
+        };
+        self.insert(
+            Timer {
+                key,
+                value,
+                repeat: Some(repeat),
+            },
+            deadline,
+        );
+        TimerHandle(key)
+    }
+
+    /// Returns how repeating timers are re-armed after a late [`advance`].
+    ///
+    /// [`advance`]: TimingWheel::advance
+    #[must_use]
+    pub fn missed_ticks(&self) -> MissedTicks {
+        self.missed_ticks
+    }
+
+    /// Sets how repeating timers are re-armed after a late [`advance`]. The
+    /// default is [`MissedTicks::Burst`].
+    ///
+    /// [`advance`]: TimingWheel::advance
+    /// [`MissedTicks::Burst`]: enum.MissedTicks.html#variant.Burst
+    pub fn set_missed_ticks(&mut self, missed: MissedTicks) {
+        self.missed_ticks = missed;
+    }
+
+    /// Cancels the timer of `handle` and returns its value, or returns
+    /// `None` if it already expired or was cancelled.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(1) for a timer in the wheel, *O*(log(*n*)) for one in the
+    /// overflow heap.
+    pub fn cancel(&mut self, handle: TimerHandle) -> Option<T> {
+        self.take(handle.0).map(|timer| timer.value)
+    }
+
+    /// Moves the timer of `handle` to `deadline`, keeping its handle.
+    /// Returns `false` if it already expired or was cancelled.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(1) within the wheel, *O*(log(*n*)) if the timer leaves or enters
+    /// the overflow heap.
+    pub fn reschedule(&mut self, handle: TimerHandle, deadline: Instant) -> bool {
+        match self.take(handle.0) {
+            Some(timer) => {
+                self.insert(timer, deadline);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn insert(&mut self, mut timer: Timer<T>, deadline: Instant) {
+        if let Some(repeat) = &mut timer.repeat {
+            repeat.deadline = deadline;
+        }
+        let key = timer.key;
+        let tick = self.ticks_since_origin(deadline, true);
+        let slots = self.slots.len() as u64;
+        let place = if tick <= self.current {
+            self.overdue.push(timer);
+            Place::Overdue(self.overdue.len() - 1)
+        } else if tick - self.current <= slots {
+            let slot = (tick % slots) as usize;
+            self.slots[slot].push(timer);
+            self.wheel_len += 1;
+            Place::Wheel(slot, self.slots[slot].len() - 1)
+        } else {
+            self.overflow.push(OverflowEntry { tick, timer });
+            Place::Overflow
+        };
+        self.places.insert(key, place);
+    }
+
+    /// Removes the pending timer with key `key`, if any.
+    fn take(&mut self, key: u64) -> Option<Timer<T>> {
+        let timer = match self.places.remove(&key)? {
+            Place::Overdue(index) => swap_remove(&mut self.overdue, index, &mut self.places),
+            Place::Wheel(slot, index) => {
+                self.wheel_len -= 1;
+                swap_remove(&mut self.slots[slot], index, &mut self.places)
+            }
+            Place::Overflow => {
+                let index = self.overflow.tracker().index_of(&key)?;
+                self.overflow.remove_at(index).timer
+            }
+        };
+        Some(timer)
+    }
+
+    /// Advances the wheel to `now` and returns the timers that expired, in
+    /// order of their tick.
+    ///
+    /// Timers sharing a tick are returned in no particular order. Repeating
+    /// timers are re-armed once all timers due by `now` expired, so each
+    /// expires at most once per call.
+    pub fn advance(&mut self, now: Instant) -> Vec<T> {
+        let mut expired = Vec::new();
+        for timer in mem::take(&mut self.overdue) {
+            self.expire(timer, &mut expired);
+        }
+        let target = self.ticks_since_origin(now, false);
+        if target > self.current {
+            self.advance_to(target, &mut expired);
+        }
+        for mut timer in mem::take(&mut self.rearm) {
+            let repeat = timer
+                .repeat
+                .as_mut()
+                .expect("only repeating timers are re-armed");
+            if let Some(deadline) = repeat.next_deadline(now, self.missed_ticks) {
+                self.insert(timer, deadline);
+            }
+        }
+        expired
+    }
+
+    fn advance_to(&mut self, target: u64, expired: &mut Vec<T>) {
+        let slots = self.slots.len() as u64;
+        // Every wheel entry is due within `slots` ticks, so there is no need
+        // to walk further than that even after a long pause.
+        let last_wheel_tick = target.min(self.current + slots);
+        for tick in self.current + 1..=last_wheel_tick {
+            self.pop_overflow_until(tick, expired);
+            if self.wheel_len == 0 {
+                continue;
+            }
+            let index = (tick % slots) as usize;
+            let mut slot = mem::take(&mut self.slots[index]);
+            self.wheel_len -= slot.len();
+            for timer in slot.drain(..) {
+                self.expire(timer, expired);
+            }
+            // Keeps the allocation of the slot.
+            self.slots[index] = slot;
+        }
+        self.pop_overflow_until(target, expired);
+        self.current = target;
+    }
+
+    fn pop_overflow_until(&mut self, tick: u64, expired: &mut Vec<T>) {
+        while self.overflow.peek().map_or(false, |e| e.tick <= tick) {
+            if let Some(entry) = self.overflow.pop() {
+                self.expire(entry.timer, expired);
+            }
+        }
+    }
+
+    fn expire(&mut self, timer: Timer<T>, expired: &mut Vec<T>) {
+        self.places.remove(&timer.key);
+        match &timer.repeat {
+            Some(repeat) => {
+                expired.push((repeat.clone)(&timer.value));
+                self.rearm.push(timer);
+            }
+            None => expired.push(timer.value),
+        }
+    }
+
+    /// Returns the earliest instant at which a pending timer expires, or
+    /// `None` if no timer is pending.
+    ///
+    /// The instant is rounded to the tick of the timer. Overdue timers report
+    /// the current tick.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(slots) in the worst case.
+    #[must_use]
+    pub fn next_expiration(&self) -> Option<Instant> {
+        if !self.overdue.is_empty() {
+            return Some(self.instant_of(self.current));
+        }
+        let slots = self.slots.len() as u64;
+        let in_wheel = if self.wheel_len > 0 {
+            (self.current + 1..=self.current + slots)
+                .find(|tick| !self.slots[(tick % slots) as usize].is_empty())
+        } else {
+            None
+        };
+        let in_overflow = self.overflow.peek().map(|e| e.tick);
+        let tick = match (in_wheel, in_overflow) {
+            (Some(a), Some(b)) => a.min(b),
+            (a, b) => a.or(b)?,
+        };
+        Some(self.instant_of(tick))
+    }
+
+    /// Returns the number of pending timers.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.overdue.len() + self.wheel_len + self.overflow.len()
+    }
+
+    /// Checks if no timer is pending.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of pending timers held in the overflow heap.
+    #[must_use]
+    pub fn overflow_len(&self) -> usize {
+        self.overflow.len()
+    }
+}
+
+/// Removes the timer at `index` of `timers` and records the new index of the
+/// timer that takes its place.
+fn swap_remove<T>(
+    timers: &mut Vec<Timer<T>>,
+    index: usize,
+    places: &mut HashMap<u64, Place>,
+) -> Timer<T> {
+    let timer = timers.swap_remove(index);
+    if let Some(moved) = timers.get(index) {
+        if let Some(Place::Overdue(i) | Place::Wheel(_, i)) = places.get_mut(&moved.key) {
+            *i = index;
+        }
+    }
+    timer
+}
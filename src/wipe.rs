@@ -0,0 +1,173 @@
+//! Iterators that move the elements out of a vector and zero each slot they
+//! leave behind, for the `zeroize` feature.
+//!
+//! `vec::IntoIter` and `vec::Drain` give no access to the slots of the
+//! elements they have yielded, so these take the elements out of the length
+//! of the vector and read them from its spare capacity instead.
+
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::ptr::{self, NonNull};
+use core::slice;
+use zeroize::Zeroize;
+
+/// The vector that a [`Wiping`] iterator takes its elements out of.
+pub(crate) trait Buffer<T> {
+    fn vec(&self) -> &Vec<T>;
+    fn vec_mut(&mut self) -> &mut Vec<T>;
+}
+
+impl<T> Buffer<T> for Vec<T> {
+    fn vec(&self) -> &Vec<T> {
+        self
+    }
+
+    fn vec_mut(&mut self) -> &mut Vec<T> {
+        self
+    }
+}
+
+/// A vector borrowed for `'a`, held through a pointer so that the iterator
+/// stays covariant in `T`, like `vec::Drain`. Only zeroes are written to the
+/// vector, never a `T`, which makes this sound.
+pub(crate) struct Borrowed<'a, T> {
+    vec: NonNull<Vec<T>>,
+    _marker: PhantomData<&'a Vec<T>>,
+}
+
+// SAFETY: `Borrowed` acts as a `&'a mut Vec<T>`.
+unsafe impl<T: Send> Send for Borrowed<'_, T> {}
+unsafe impl<T: Sync> Sync for Borrowed<'_, T> {}
+
+impl<T> Buffer<T> for Borrowed<'_, T> {
+    fn vec(&self) -> &Vec<T> {
+        // SAFETY: the pointer comes from a `&'a mut Vec<T>`.
+        unsafe { self.vec.as_ref() }
+    }
+
+    fn vec_mut(&mut self) -> &mut Vec<T> {
+        // SAFETY: as in `vec`.
+        unsafe { self.vec.as_mut() }
+    }
+}
+
+/// Yields the elements of a vector, front to back, zeroing the slot of each
+/// element as it is moved out, and the slots of the elements not yielded
+/// when dropped.
+pub(crate) struct Wiping<T, V: Buffer<T>> {
+    /// A vector of length zero whose spare capacity holds the elements at
+    /// `start..end`.
+    buf: V,
+    start: usize,
+    end: usize,
+    _marker: PhantomData<T>,
+}
+
+/// Owns the buffer of a heap consumed by `into_iter`.
+pub(crate) type IntoIter<T> = Wiping<T, Vec<T>>;
+
+/// Borrows the buffer of a heap being drained, and leaves it empty.
+pub(crate) type Drain<'a, T> = Wiping<T, Borrowed<'a, T>>;
+
+impl<'a, T> Drain<'a, T> {
+    pub(crate) fn borrow(vec: &'a mut Vec<T>) -> Self {
+        Wiping::new(Borrowed {
+            vec: NonNull::from(vec),
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<T, V: Buffer<T>> Wiping<T, V> {
+    pub(crate) fn new(mut buf: V) -> Self {
+        let vec = buf.vec_mut();
+        let end = vec.len();
+        // SAFETY: shrinking the length only leaks the elements into the
+        //  spare capacity, where this iterator takes them over.
+        unsafe { vec.set_len(0) };
+        Wiping {
+            buf,
+            start: 0,
+            end,
+            _marker: PhantomData,
+        }
+    }
+
+    pub(crate) fn as_slice(&self) -> &[T] {
+        let data = self.buf.vec().as_ptr();
+        // SAFETY: the slots at `start..end` hold elements, within the
+        //  capacity of the vector.
+        unsafe { slice::from_raw_parts(data.add(self.start), self.end - self.start) }
+    }
+
+    pub(crate) fn as_mut_slice(&mut self) -> &mut [T] {
+        let data = self.buf.vec_mut().as_mut_ptr();
+        // SAFETY: as in `as_slice`.
+        unsafe { slice::from_raw_parts_mut(data.add(self.start), self.end - self.start) }
+    }
+
+    /// Moves the element out of the slot at `index`, which must hold one that
+    /// is not read again, and zeroes the slot.
+    fn take(&mut self, index: usize) -> T {
+        let slot = &mut self.buf.vec_mut().spare_capacity_mut()[index];
+        // SAFETY: guaranteed by the caller.
+        let item = unsafe { slot.as_ptr().read() };
+        slot.zeroize();
+        item
+    }
+}
+
+impl<T, V: Buffer<T>> Iterator for Wiping<T, V> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.start == self.end {
+            return None;
+        }
+        self.start += 1;
+        // The slot is now outside of `start..end`.
+        Some(self.take(self.start - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.start;
+        (len, Some(len))
+    }
+}
+
+impl<T, V: Buffer<T>> DoubleEndedIterator for Wiping<T, V> {
+    fn next_back(&mut self) -> Option<T> {
+        if self.start == self.end {
+            return None;
+        }
+        self.end -= 1;
+        Some(self.take(self.end))
+    }
+}
+
+impl<T, V: Buffer<T>> Drop for Wiping<T, V> {
+    fn drop(&mut self) {
+        let (start, end) = (self.start, self.end);
+        // Emptied first, so that a panicking destructor cannot cause a double
+        // drop.
+        self.start = end;
+        let rest: &mut [MaybeUninit<T>] = &mut self.buf.vec_mut().spare_capacity_mut()[start..end];
+        // SAFETY: the slots at `start..end` held elements that are no longer
+        //  reachable through `self`.
+        unsafe { ptr::drop_in_place(rest as *mut [MaybeUninit<T>] as *mut [T]) };
+        rest.zeroize();
+    }
+}
+
+impl<T: Clone> Clone for IntoIter<T> {
+    fn clone(&self) -> Self {
+        Wiping::new(self.as_slice().to_vec())
+    }
+}
+
+impl<T: fmt::Debug, V: Buffer<T>> fmt::Debug for Wiping<T, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}